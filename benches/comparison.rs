@@ -57,6 +57,82 @@ repeat!(
     smawk::recursive::column_minima
 );
 
+// Skewed matrices with 10 rows and many columns.
+macro_rules! skewed {
+    ([ $( ($row_bench:ident, $column_bench:ident, $size:expr) $(,)* )* ],
+     $row_func:path, $column_func:path) => {
+        $(
+            #[bench]
+            fn $row_bench(b: &mut Bencher) {
+                let mut rng = ChaCha20Rng::seed_from_u64(0);
+                let matrix: Array2<i32> = random_monge_matrix(10, $size, &mut rng);
+                b.iter(|| $row_func(&matrix));
+            }
+
+            #[bench]
+            fn $column_bench(b: &mut Bencher) {
+                let mut rng = ChaCha20Rng::seed_from_u64(0);
+                let matrix: Array2<i32> = random_monge_matrix(10, $size, &mut rng).reversed_axes();
+                b.iter(|| $column_func(&matrix));
+            }
+        )*
+    };
+}
+
+skewed!(
+    [
+        (
+            row_recursive_skewed_01000,
+            column_recursive_skewed_01000,
+            1_000
+        ),
+        (
+            row_recursive_skewed_10000,
+            column_recursive_skewed_10000,
+            10_000
+        ),
+        (
+            row_recursive_skewed_100000,
+            column_recursive_skewed_100000,
+            100_000
+        )
+    ],
+    smawk::recursive::row_minima,
+    smawk::recursive::column_minima
+);
+
+skewed!(
+    [
+        (
+            row_recursive_reduced_skewed_01000,
+            column_recursive_reduced_skewed_01000,
+            1_000
+        ),
+        (
+            row_recursive_reduced_skewed_10000,
+            column_recursive_reduced_skewed_10000,
+            10_000
+        ),
+        (
+            row_recursive_reduced_skewed_100000,
+            column_recursive_reduced_skewed_100000,
+            100_000
+        )
+    ],
+    smawk::recursive::row_minima_reduced,
+    smawk::recursive::column_minima_reduced
+);
+
+skewed!(
+    [
+        (row_smawk_skewed_01000, column_smawk_skewed_01000, 1_000),
+        (row_smawk_skewed_10000, column_smawk_skewed_10000, 10_000),
+        (row_smawk_skewed_100000, column_smawk_skewed_100000, 100_000)
+    ],
+    smawk::row_minima,
+    smawk::column_minima
+);
+
 repeat!(
     [
         (row_smawk_025, column_smawk_025, 25),
@@ -68,3 +144,78 @@ repeat!(
     smawk::row_minima,
     smawk::column_minima
 );
+
+// The functions below always use the SMAWK algorithm. Comparing them
+// with the brute force functions on small matrices is used to tune
+// smawk::BRUTE_FORCE_THRESHOLD.
+
+fn row_minima_no_fallback(matrix: &Array2<i32>) -> Vec<usize> {
+    smawk::row_minima_with_threshold(matrix, 0)
+}
+
+fn column_minima_no_fallback(matrix: &Array2<i32>) -> Vec<usize> {
+    smawk::column_minima_with_threshold(matrix, 0)
+}
+
+repeat!(
+    [
+        (row_brute_force_005, column_brute_force_005, 5),
+        (row_brute_force_010, column_brute_force_010, 10),
+        (row_brute_force_020, column_brute_force_020, 20),
+        (row_brute_force_040, column_brute_force_040, 40)
+    ],
+    smawk::brute_force::row_minima,
+    smawk::brute_force::column_minima
+);
+
+repeat!(
+    [
+        (row_no_fallback_005, column_no_fallback_005, 5),
+        (row_no_fallback_010, column_no_fallback_010, 10),
+        (row_no_fallback_020, column_no_fallback_020, 20),
+        (row_no_fallback_040, column_no_fallback_040, 40)
+    ],
+    row_minima_no_fallback,
+    column_minima_no_fallback
+);
+
+// Many calls on small matrices, where the allocations made by each
+// call weigh more than the work. The SMAWK functions allocate only a
+// scratch buffer per call, while column_minima_in_scratch reuses one
+// buffer and does not allocate at all.
+
+fn small_matrices() -> Vec<Array2<i32>> {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    (0..1000)
+        .map(|k| random_monge_matrix(2 + k % 7, 2 + k % 11, &mut rng))
+        .collect()
+}
+
+#[bench]
+fn column_many_small_no_fallback(b: &mut Bencher) {
+    let matrices = small_matrices();
+    b.iter(|| {
+        let mut total = 0;
+        for matrix in &matrices {
+            total += column_minima_no_fallback(matrix)[0];
+        }
+        total
+    });
+}
+
+#[bench]
+fn column_many_small_in_scratch(b: &mut Bencher) {
+    let matrices = small_matrices();
+    let mut buffer = vec![0; smawk::scratch_size(8, 12)];
+    let mut minima = vec![0; 12];
+    b.iter(|| {
+        let mut total = 0;
+        for matrix in &matrices {
+            let minima = &mut minima[..matrix.ncols()];
+            let mut scratch = smawk::Scratch::new(&mut buffer);
+            smawk::column_minima_in_scratch(matrix, minima, &mut scratch);
+            total += minima[0];
+        }
+        total
+    });
+}
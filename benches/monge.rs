@@ -0,0 +1,41 @@
+#![cfg(feature = "ndarray")]
+#![feature(test)]
+
+extern crate test;
+
+use ndarray::Array2;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use test::Bencher;
+
+#[path = "../tests/random_monge/mod.rs"]
+mod random_monge;
+use random_monge::random_monge_matrix;
+
+macro_rules! repeat {
+    ([ $( ($scan_bench:ident, $windows_bench:ident, $size:expr) $(,)* )* ]) => {
+        $(
+            #[bench]
+            fn $scan_bench(b: &mut Bencher) {
+                let mut rng = ChaCha20Rng::seed_from_u64(0);
+                let matrix: Array2<i32> = random_monge_matrix($size, $size, &mut rng);
+                b.iter(|| smawk::monge::is_monge(&matrix));
+            }
+
+            #[bench]
+            fn $windows_bench(b: &mut Bencher) {
+                let mut rng = ChaCha20Rng::seed_from_u64(0);
+                let matrix: Array2<i32> = random_monge_matrix($size, $size, &mut rng);
+                b.iter(|| smawk::monge::is_monge_by_windows(&matrix));
+            }
+        )*
+    };
+}
+
+repeat!([
+    (is_monge_0100, is_monge_by_windows_0100, 100),
+    (is_monge_0200, is_monge_by_windows_0200, 200),
+    (is_monge_0400, is_monge_by_windows_0400, 400),
+    (is_monge_0800, is_monge_by_windows_0800, 800),
+    (is_monge_1600, is_monge_by_windows_1600, 1600),
+]);
@@ -0,0 +1,78 @@
+#![cfg(feature = "ndarray")]
+#![feature(test)]
+
+extern crate test;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use test::Bencher;
+
+/// Break a paragraph with `size` words of random widths into lines
+/// of width 40, like a text wrapping library does.
+fn bench_line_breaking(b: &mut Bencher, size: usize) {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut offsets = vec![0];
+    for _ in 0..size {
+        let width: i64 = rng.gen_range(1..10);
+        offsets.push(offsets[offsets.len() - 1] + width + 1);
+    }
+    b.iter(|| {
+        smawk::online_column_minima(0, size, |minima: &[(usize, i64)], i, j| {
+            let gap = 40 - (offsets[j] - offsets[i] - 1);
+            minima[i].1 + if gap < 0 { 1000 * -gap } else { gap * gap }
+        })
+    });
+}
+
+#[bench]
+fn online_line_breaking_030(b: &mut Bencher) {
+    bench_line_breaking(b, 30);
+}
+
+#[bench]
+fn online_line_breaking_1000(b: &mut Bencher) {
+    bench_line_breaking(b, 1000);
+}
+
+/// Break many short paragraphs of random sizes, either with a fresh
+/// call per paragraph or with a runner which reuses its buffers.
+fn bench_many_paragraphs(b: &mut Bencher, reuse: bool) {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let paragraphs = (0..1000)
+        .map(|_| {
+            let mut offsets = vec![0];
+            for _ in 0..rng.gen_range(5..40) {
+                let width: i64 = rng.gen_range(1..10);
+                offsets.push(offsets[offsets.len() - 1] + width + 1);
+            }
+            offsets
+        })
+        .collect::<Vec<_>>();
+    let mut runner = smawk::OnlineMinima::new();
+    b.iter(|| {
+        let mut total = 0;
+        for offsets in &paragraphs {
+            let cost = |minima: &[(usize, i64)], i: usize, j: usize| {
+                let gap = 40 - (offsets[j] - offsets[i] - 1);
+                minima[i].1 + if gap < 0 { 1000 * -gap } else { gap * gap }
+            };
+            let size = offsets.len() - 1;
+            total += if reuse {
+                runner.run(0, size, cost)[size - 1].1
+            } else {
+                smawk::online_column_minima(0, size, cost)[size - 1].1
+            };
+        }
+        total
+    });
+}
+
+#[bench]
+fn online_many_paragraphs_fresh(b: &mut Bencher) {
+    bench_many_paragraphs(b, false);
+}
+
+#[bench]
+fn online_many_paragraphs_reused(b: &mut Bencher) {
+    bench_many_paragraphs(b, true);
+}
@@ -0,0 +1,1498 @@
+//! Ready-made solvers for problems which can be sped up with SMAWK.
+//!
+//! The functions here solve concrete optimization problems whose
+//! dynamic programming formulation has the Monge property. They
+//! double as examples of how to use [`online_column_minima`] and the
+//! other functions in this crate.
+
+use crate::dp::dp_step;
+use crate::dp::reconstruct::{path_from_parent_fn, path_from_parents, segments_from_breaks};
+use crate::{
+    column_minima, online_column_minima, row_minima, upper_triangular_column_minima, Matrix,
+};
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod reference;
+
+/// Schedule jobs in batches on a single machine to minimize the total
+/// weighted completion time.
+///
+/// The jobs must be processed in the given order, and they are
+/// partitioned into consecutive batches. Each batch starts with a
+/// `setup` time, after which all jobs in the batch are processed.
+/// All jobs in a batch complete together when the last job in the
+/// batch is done. The cost of a schedule is the sum of the completion
+/// times of the jobs, each multiplied by its weight. Without
+/// `weights`, every job has weight 1.
+///
+/// The function returns the optimal cost together with the start
+/// index of every batch. The first batch always starts at job 0.
+///
+/// A batch of jobs `i..j` delays all jobs from `i` onwards by the
+/// setup time plus `p[i] + ... + p[j - 1]`. With `W(i)` denoting the
+/// total weight of these jobs, the optimal cost `G(j)` of scheduling
+/// the first `j` jobs is
+///
+/// ```text
+/// G(j) = min { G(i) + (setup + p[i] + ... + p[j - 1]) * W(i) | i < j }
+/// ```
+///
+/// The processing times are non-negative and `W(i)` is non-increasing
+/// in `i`, which makes this a Monge matrix. The recurrence is thus
+/// solved with [`online_column_minima`].
+///
+/// Running time for *n* jobs: O(*n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::serial_batching;
+///
+/// // Batches 0..2 and 2..3 complete at times 3 and 5, which costs
+/// // 2 * 3 + 1 * 5 = 11. A single batch would cost 3 * 4 = 12.
+/// assert_eq!(serial_batching(&[1, 1, 1], 1, None), (11, vec![0, 2]));
+/// assert_eq!(serial_batching(&[1, 1, 1], 0, None), (6, vec![0, 1, 2]));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to pass `weights` of a different length than
+/// `process_times`. Overflow of the `u64` computations is an error.
+pub fn serial_batching(
+    process_times: &[u64],
+    setup: u64,
+    weights: Option<&[u64]>,
+) -> (u64, Vec<usize>) {
+    let n = process_times.len();
+    if n == 0 {
+        return (0, Vec::new());
+    }
+    if let Some(weights) = weights {
+        assert_eq!(weights.len(), n, "expected one weight per job");
+    }
+
+    // Prefix sums of processing times and suffix sums of weights.
+    let mut prefix = vec![0; n + 1];
+    for (i, &p) in process_times.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + p;
+    }
+    let mut suffix = vec![0; n + 1];
+    for i in (0..n).rev() {
+        suffix[i] = suffix[i + 1] + weights.map_or(1, |w| w[i]);
+    }
+
+    let minima = online_column_minima(0, n + 1, |result, i, j| {
+        result[i].1 + (setup + prefix[j] - prefix[i]) * suffix[i]
+    });
+
+    let parents = minima.iter().map(|&(i, _)| i).collect::<Vec<_>>();
+    let mut starts = path_from_parents(&parents, n);
+    starts.pop();
+    (minima[n].1, starts)
+}
+
+/// Place `p` centers among points on a line to minimize the largest
+/// distance from a point to its nearest center.
+///
+/// The `positions` must be sorted, and the centers are chosen among
+/// them. The function returns the optimal radius together with the
+/// indices of the chosen centers in increasing order. Fewer than `p`
+/// centers are returned when that is enough, for example when there
+/// are fewer than `p` points.
+///
+/// Every point is served by its nearest center, so the points served
+/// by a center form an interval `k..j`. The best center for the
+/// interval is the point closest to its midpoint. With `R(k, j)`
+/// denoting the resulting radius, the smallest radius `D(l, j)` for
+/// covering the first `j` points with at most `l` centers is
+///
+/// ```text
+/// D(l, j) = min { max(D(l - 1, k), R(k, j)) | k < j }
+/// ```
+///
+/// The interval radius is a bottleneck Monge matrix: the first term
+/// is non-decreasing and the second non-increasing in `k`. The
+/// minimum over `k` is therefore found where the two terms cross,
+/// which a binary search locates.
+///
+/// Running time for *n* points: O(*p* *n* log² *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::k_center_line;
+///
+/// let positions = [0.0, 1.0, 2.0, 10.0, 11.0];
+/// assert_eq!(k_center_line(&positions, 2), (1.0, vec![1, 3]));
+/// assert_eq!(k_center_line(&positions, 1), (9.0, vec![2]));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to pass unsorted positions or to ask for zero
+/// centers for a non-empty set of points.
+pub fn k_center_line(positions: &[f64], p: usize) -> (f64, Vec<usize>) {
+    let n = positions.len();
+    if n == 0 {
+        return (0.0, Vec::new());
+    }
+    assert!(p > 0, "cannot cover points with zero centers");
+    assert!(
+        positions.windows(2).all(|w| w[0] <= w[1]),
+        "positions must be sorted"
+    );
+
+    // Radius and center of a single center serving points k..j.
+    let interval = |k: usize, j: usize| {
+        let (left, right) = (positions[k], positions[j - 1]);
+        let mid = left + (right - left) / 2.0;
+        let c = k + positions[k..j].partition_point(|&x| x < mid);
+        let radius = |c: usize| f64::max(positions[c] - left, right - positions[c]);
+        if c > k && radius(c - 1) <= radius(c) {
+            (radius(c - 1), c - 1)
+        } else {
+            (radius(c), c)
+        }
+    };
+
+    let mut layer = vec![f64::INFINITY; n + 1];
+    layer[0] = 0.0;
+    let mut splits = Vec::with_capacity(p);
+    for _ in 0..p {
+        let mut next = vec![0.0; n + 1];
+        let mut split = vec![0; n + 1];
+        for j in 1..=n {
+            // The smallest k where the previous layer dominates.
+            let (mut lo, mut hi) = (0, j);
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                if layer[mid] >= interval(mid, j).0 {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+            let mut best = (f64::INFINITY, 0);
+            if lo > 0 {
+                best = (interval(lo - 1, j).0.max(layer[lo - 1]), lo - 1);
+            }
+            if lo < j && layer[lo] < best.0 {
+                best = (layer[lo], lo);
+            }
+            next[j] = best.0;
+            split[j] = best.1;
+        }
+        layer = next;
+        splits.push(split);
+    }
+
+    let mut breaks = Vec::with_capacity(p);
+    let mut j = n;
+    for split in splits.iter().rev() {
+        j = split[j];
+        breaks.push(j);
+    }
+    breaks.reverse();
+    let centers = segments_from_breaks(&breaks, n)
+        .into_iter()
+        .map(|segment| interval(segment.start, segment.end).1)
+        .collect();
+    (layer[n], centers)
+}
+
+/// Split an integer budget among activities with concave returns to
+/// maximize the total return.
+///
+/// Activity `k` returns `returns[k](x)` when it gets `x` units of the
+/// budget. The units are handed out as integers, the total may not
+/// exceed `budget`, and units which would only lower the total are
+/// left unused. The function returns the optimal total return together
+/// with the number of units given to every activity.
+///
+/// With `G(k, b)` denoting the best total return of the first `k`
+/// activities from at most `b` units, the recurrence is
+///
+/// ```text
+/// G(k, b) = max { G(k - 1, b') + returns[k - 1](b - b') | b' ≤ b }
+/// ```
+///
+/// Negating the terms gives a minimization over the upper triangle
+/// `b' ≤ b` of a matrix indexed by `(b', b)`. A concave function of `b
+/// - b'` becomes a convex one when negated, which makes the triangle
+/// Monge, so every activity is handled with
+/// [`upper_triangular_column_minima`].
+///
+/// The return functions are evaluated once for every `x` from 0 to
+/// `budget`. Debug builds check that the sampled returns are concave,
+/// that is, that the marginal returns `f(x + 1) - f(x)` never
+/// increase.
+///
+/// Running time for *n* activities: O(*n* · `budget`).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::concave_allocation;
+///
+/// let returns: [&dyn Fn(u64) -> i64; 2] = [
+///     &|x| 10 * x as i64 - (x * x) as i64,
+///     &|x| 4 * x as i64,
+/// ];
+/// // Units 1 to 3 are worth 9, 7 and 5 to the first activity, more
+/// // than the 4 they are worth to the second.
+/// assert_eq!(concave_allocation(5, &returns), (29, vec![3, 2]));
+/// ```
+///
+/// # Panics
+///
+/// Overflow of the `i64` computations is an error. In debug builds,
+/// it is an error to pass return functions which are not concave.
+pub fn concave_allocation<F: Fn(u64) -> i64>(budget: u64, returns: &[F]) -> (i64, Vec<u64>) {
+    let size = usize::try_from(budget).expect("budget does not fit in usize") + 1;
+    let mut best = vec![0; size];
+    let mut choices = Vec::with_capacity(returns.len());
+    for (k, f) in returns.iter().enumerate() {
+        let values = (0..=budget).map(f).collect::<Vec<_>>();
+        debug_assert!(
+            values
+                .windows(3)
+                .all(|w| w[0] as i128 + w[2] as i128 <= 2 * w[1] as i128),
+            "return function {} is not concave",
+            k
+        );
+        let matrix = Allocation {
+            best: &best,
+            values: &values,
+        };
+        let minima = upper_triangular_column_minima(&matrix);
+        best = (0..size).map(|b| -matrix.index(minima[b], b)).collect();
+        choices.push(minima);
+    }
+
+    // Budget b after k activities is numbered k * size + b, and the
+    // budgets before the first activity are the roots.
+    let parent = |id: usize| match id / size {
+        0 => id,
+        k => (k - 1) * size + choices[k - 1][id % size],
+    };
+    let path = path_from_parent_fn(returns.len() * size + size - 1, parent);
+    let allocation = path
+        .windows(2)
+        .map(|w| (w[1] % size - w[0] % size) as u64)
+        .collect();
+    (best[size - 1], allocation)
+}
+
+/// Implicit matrix with elements `-(best[b'] + values[b - b'])` on
+/// the upper triangle `b' ≤ b`.
+struct Allocation<'a> {
+    best: &'a [i64],
+    values: &'a [i64],
+}
+
+impl Matrix<i64> for Allocation<'_> {
+    fn nrows(&self) -> usize {
+        self.best.len()
+    }
+    fn ncols(&self) -> usize {
+        self.best.len()
+    }
+    fn index(&self, row: usize, column: usize) -> i64 {
+        -(self.best[row] + self.values[column - row])
+    }
+}
+
+/// Partition a sequence into every number of segments up to `max_k`
+/// and return the optimal cost for each.
+///
+/// The items `0..n` are split into exactly `k` non-empty segments of
+/// consecutive items, and putting the items `i..j` into one segment
+/// costs `w(i, j)`. The element at index `k - 1` of the result holds
+/// the optimal cost with `k` segments together with the start index
+/// of every segment. The first segment always starts at item 0. There
+/// is no partition into more than `n` segments, so the result holds
+/// `min(max_k, n)` elements.
+///
+/// With `D(k, j)` denoting the optimal cost of splitting the first `j`
+/// items into `k` segments, the recurrence is
+///
+/// ```text
+/// D(k, j) = min { D(k - 1, i) + w(i, j) | k - 1 ≤ i < j }
+/// ```
+///
+/// Every layer of the recurrence is an upper triangular matrix, which
+/// is solved with [`dp_step`] when `w` satisfies the quadrangle
+/// inequality
+///
+/// ```text
+/// w(a, c) + w(b, d) ≤ w(a, d) + w(b, c)  for all a ≤ b ≤ c ≤ d
+/// ```
+///
+/// The quadrangle inequality also makes the costs a convex function
+/// of `k`, which is what an elbow criterion looks for. All costs come
+/// from a single pass over the layers, where solving for one `k` at a
+/// time would repeat the layers before it. The split points of every
+/// layer are kept to reconstruct the partitions, which takes
+/// O(`max_k` · *n*) memory. Use [`all_k_partition_costs`] when only
+/// the costs are needed.
+///
+/// Running time for *n* items: O(`max_k` · *n*) evaluations of `w`.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::all_k_partitions;
+///
+/// // The cost of a segment is its squared length.
+/// let w = |i: usize, j: usize| ((j - i) * (j - i)) as i64;
+/// assert_eq!(all_k_partitions(4, 5, w),
+///            vec![(16, vec![0]), (8, vec![0, 2]), (6, vec![0, 1, 2]),
+///                 (4, vec![0, 1, 2, 3])]);
+/// ```
+///
+/// # Panics
+///
+/// Overflow of the `i64` computations is an error.
+pub fn all_k_partitions<W: Fn(usize, usize) -> i64>(
+    n: usize,
+    max_k: usize,
+    w: W,
+) -> Vec<(i64, Vec<usize>)> {
+    let mut splits = Vec::new();
+    let costs = partition_layers(n, max_k, &w, |split| splits.push(split));
+    costs
+        .into_iter()
+        .enumerate()
+        .map(|(last, cost)| {
+            // Segment k ending after the first j items is numbered
+            // k * (n + 1) + j, and its parent is segment k - 1 ending
+            // where segment k starts. The first segments are roots.
+            let parent = |id: usize| match (id / (n + 1), id % (n + 1)) {
+                (0, _) => id,
+                (k, j) => (k - 1) * (n + 1) + splits[k - 1][j - k - 1],
+            };
+            let path = path_from_parent_fn(last * (n + 1) + n, parent);
+            let ends = path[..last].iter().map(|&id| id % (n + 1));
+            (cost, std::iter::once(0).chain(ends).collect())
+        })
+        .collect()
+}
+
+/// Compute the optimal cost of partitioning a sequence into every
+/// number of segments up to `max_k`.
+///
+/// This returns the costs from [`all_k_partitions`] without the
+/// partitions. Only the latest layer of the recurrence is kept, so
+/// the memory use is O(*n*) instead of O(`max_k` · *n*).
+///
+/// Running time for *n* items: O(`max_k` · *n*) evaluations of `w`.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::all_k_partition_costs;
+///
+/// let w = |i: usize, j: usize| ((j - i) * (j - i)) as i64;
+/// assert_eq!(all_k_partition_costs(4, 5, w), vec![16, 8, 6, 4]);
+/// ```
+///
+/// # Panics
+///
+/// Overflow of the `i64` computations is an error.
+pub fn all_k_partition_costs<W: Fn(usize, usize) -> i64>(n: usize, max_k: usize, w: W) -> Vec<i64> {
+    partition_layers(n, max_k, &w, |_| {})
+}
+
+/// Compute the layers of the partition recurrence and return the cost
+/// of the whole sequence in every layer. The split points of every
+/// layer after the first are passed to `record`, where index `j - k`
+/// holds the start of the last segment for the first `j` items in
+/// layer `k`.
+fn partition_layers<W: Fn(usize, usize) -> i64>(
+    n: usize,
+    max_k: usize,
+    w: &W,
+    mut record: impl FnMut(Vec<usize>),
+) -> Vec<i64> {
+    let layers = std::cmp::min(max_k, n);
+    let mut costs = Vec::with_capacity(layers);
+    if layers == 0 {
+        return costs;
+    }
+    // Layer k holds the cost for the first j items at index j - k.
+    let mut layer = (1..=n).map(|j| w(0, j)).collect::<Vec<_>>();
+    costs.push(layer[n - 1]);
+    for k in 2..=layers {
+        // Split point i and end point j relative to k - 1 and k, with
+        // i ≤ j to leave the last segment non-empty.
+        let segment = |i: usize, j: usize| {
+            if i <= j {
+                w(k - 1 + i, k + j)
+            } else {
+                i64::MAX
+            }
+        };
+        let (next, minima) = dp_step(&layer[..=n - k], segment, n - k + 1);
+        layer = next;
+        costs.push(layer[n - k]);
+        record(minima.iter().map(|&i| i + k - 1).collect());
+    }
+    costs
+}
+
+/// Fold an RNA sequence into a secondary structure with single loops
+/// only.
+///
+/// The bases are numbered `0..n`, and `pairing(i, j)` tells if base
+/// `i` can pair with base `j > i`. A structure is a chain of nested
+/// pairs `(i₁, j₁), (i₂, j₂), ...` with `i₁ < i₂ < ... < j₂ < j₁`.
+/// Two consecutive pairs close a loop with `i₂ - i₁ - 1` unpaired
+/// bases on the left and `j₁ - j₂ - 1` on the right, which costs
+/// `loop_penalty` of the total number of unpaired bases. The innermost
+/// pair closes a hairpin, which costs `loop_penalty` of the number of
+/// bases inside of it. The bases outside of the outermost pair are
+/// free. Stacked pairs are loops of size zero, so `loop_penalty(0)`
+/// is typically negative.
+///
+/// The function returns the smallest energy together with the pairs
+/// of the structure from the outside in. No structure at all has
+/// energy zero and no pairs.
+///
+/// With `D(i, j)` denoting the smallest energy of a chain whose
+/// outermost pair is `(i, j)`, the recurrence is
+///
+/// ```text
+/// D(i, j) = min { loop_penalty(j - i - 1),
+///                 D(i', j') + loop_penalty((i' - i - 1) + (j - j' - 1))
+///                   | i < i' < j' < j }
+/// ```
+///
+/// Following Eppstein, Galil and Giancarlo, the penalty only depends
+/// on the difference of the spans `j - i` and `j' - i'`. The pairs are
+/// split in half by their columns and then by their rows, so that
+/// every pair in the left and lower halves lies inside of every pair
+/// in the right and upper halves. The best inner pair of each span is
+/// all that matters between the halves. For a concave penalty, the
+/// matrix from spans of inner pairs to spans of outer pairs is then
+/// inverse Monge, and its row minima are found with [`row_minima`]
+/// after reversing the columns.
+///
+/// The penalties are evaluated once for every loop size from 0 to
+/// `n - 1`. Debug builds check that they are concave.
+///
+/// Running time for *n* bases: O(*n*² log² *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::rna_single_loop;
+///
+/// let bases = b"GGGAAACCC";
+/// let pairing = |i: usize, j: usize| {
+///     j - i > 3 && matches!((bases[i], bases[j]), (b'G', b'C') | (b'C', b'G'))
+/// };
+/// // Stacking pays off, larger loops cost more.
+/// let penalty = |size: usize| if size == 0 { -3 } else { 1 + size as i64 };
+/// assert_eq!(
+///     rna_single_loop(pairing, penalty, bases.len()),
+///     (-2, vec![(0, 8), (1, 7), (2, 6)])
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Overflow of the `i64` computations is an error. In debug builds,
+/// it is an error to pass a loop penalty which is not concave.
+pub fn rna_single_loop<P, L>(pairing: P, loop_penalty: L, n: usize) -> (i64, Vec<(usize, usize)>)
+where
+    P: Fn(usize, usize) -> bool,
+    L: Fn(usize) -> i64,
+{
+    let penalty = (0..n).map(loop_penalty).collect::<Vec<_>>();
+    debug_assert!(
+        penalty
+            .windows(3)
+            .all(|w| w[0] as i128 + w[2] as i128 <= 2 * w[1] as i128),
+        "loop penalty is not concave"
+    );
+
+    // Start every pair out as a hairpin.
+    let mut fold = Fold {
+        n,
+        penalty: &penalty,
+        energy: vec![None; n * n],
+        inner: vec![None; n * n],
+    };
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if pairing(i, j) {
+                fold.energy[i * n + j] = Some(penalty[j - i - 1]);
+            }
+        }
+    }
+    fold.columns(0, n);
+
+    let mut best = (0, None);
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if let Some(energy) = fold.energy[i * n + j] {
+                if energy < best.0 {
+                    best = (energy, Some((i, j)));
+                }
+            }
+        }
+    }
+    let mut pairs = Vec::new();
+    let mut pair = best.1;
+    while let Some((i, j)) = pair {
+        pairs.push((i, j));
+        pair = fold.inner[i * n + j];
+    }
+    (best.0, pairs)
+}
+
+/// State of [`rna_single_loop`]: the energy of every pair `(i, j)`
+/// and its best inner pair, stored at `i * n + j`.
+struct Fold<'a> {
+    n: usize,
+    penalty: &'a [i64],
+    energy: Vec<Option<i64>>,
+    inner: Vec<Option<(usize, usize)>>,
+}
+
+impl Fold<'_> {
+    /// Finish the pairs in columns `lo..hi`, which have seen all inner
+    /// pairs in columns before `lo`.
+    fn columns(&mut self, lo: usize, hi: usize) {
+        if hi - lo <= 1 {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        self.columns(lo, mid);
+        self.rows(lo..mid, mid..hi, 0, self.n);
+        self.columns(mid, hi);
+    }
+
+    /// Relax the pairs in columns `outer` and rows `lo..hi` with the
+    /// inner pairs in columns `inner` and the same rows.
+    fn rows(
+        &mut self,
+        inner: std::ops::Range<usize>,
+        outer: std::ops::Range<usize>,
+        lo: usize,
+        hi: usize,
+    ) {
+        if hi - lo <= 1 {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        self.relax(inner.clone(), outer.clone(), mid..hi, lo..mid);
+        self.rows(inner.clone(), outer.clone(), lo, mid);
+        self.rows(inner, outer, mid, hi);
+    }
+
+    /// Relax every pair in `outer_columns` and `outer_rows` with every
+    /// pair in `inner_columns` and `inner_rows`. The inner pairs lie
+    /// strictly inside of the outer pairs.
+    fn relax(
+        &mut self,
+        inner_columns: std::ops::Range<usize>,
+        outer_columns: std::ops::Range<usize>,
+        inner_rows: std::ops::Range<usize>,
+        outer_rows: std::ops::Range<usize>,
+    ) {
+        let n = self.n;
+        // The best inner pair of every span, in order of span. The
+        // spans are bucketed from the smallest possible span.
+        let first = inner_columns.start.saturating_sub(inner_rows.end);
+        let mut buckets = vec![None; inner_columns.end - first];
+        for j in inner_columns {
+            for i in inner_rows.start..inner_rows.end.min(j) {
+                if let Some(energy) = self.energy[i * n + j] {
+                    let bucket = &mut buckets[j - i - first];
+                    if bucket.is_none_or(|(best, _)| energy < best) {
+                        *bucket = Some((energy, (i, j)));
+                    }
+                }
+            }
+        }
+        let spans = buckets
+            .into_iter()
+            .enumerate()
+            .filter_map(|(k, bucket)| bucket.map(|(energy, pair)| (first + k, energy, pair)))
+            .collect::<Vec<_>>();
+        if spans.is_empty() {
+            return;
+        }
+
+        // The spans of the outer pairs, and the row of each span.
+        let first = outer_columns.start.saturating_sub(outer_rows.end);
+        let mut rows = vec![None; outer_columns.end - first];
+        for j in outer_columns.clone() {
+            for i in outer_rows.start..outer_rows.end.min(j) {
+                if self.energy[i * n + j].is_some() {
+                    rows[j - i - first] = Some(0);
+                }
+            }
+        }
+        let mut targets = Vec::new();
+        for (k, row) in rows.iter_mut().enumerate() {
+            if row.is_some() {
+                *row = Some(targets.len());
+                targets.push(first + k);
+            }
+        }
+        if targets.is_empty() {
+            return;
+        }
+
+        let matrix = Loops {
+            spans: &spans,
+            targets: &targets,
+            penalty: self.penalty,
+        };
+        let minima = row_minima(&matrix);
+        for j in outer_columns {
+            for i in outer_rows.start..outer_rows.end.min(j) {
+                if let Some(energy) = self.energy[i * n + j] {
+                    let row = rows[j - i - first].unwrap();
+                    let value = matrix.index(row, minima[row]);
+                    if value < energy {
+                        self.energy[i * n + j] = Some(value);
+                        self.inner[i * n + j] = Some(spans[spans.len() - 1 - minima[row]].2);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Implicit matrix of loop energies from the inner pairs in `spans` to
+/// outer pairs with the spans in `targets`. The columns are in
+/// decreasing order of span, which makes the matrix Monge.
+struct Loops<'a> {
+    spans: &'a [(usize, i64, (usize, usize))],
+    targets: &'a [usize],
+    penalty: &'a [i64],
+}
+
+impl Matrix<i64> for Loops<'_> {
+    fn nrows(&self) -> usize {
+        self.targets.len()
+    }
+    fn ncols(&self) -> usize {
+        self.spans.len()
+    }
+    fn index(&self, row: usize, column: usize) -> i64 {
+        let (span, energy, _) = self.spans[self.spans.len() - 1 - column];
+        energy + self.penalty[self.targets[row] - span - 2]
+    }
+}
+
+/// Column of an alignment computed by [`align_with_concave_gaps`]:
+/// the indices of the aligned elements of the two sequences, where
+/// `None` stands for a gap.
+pub type AlignmentColumn = (Option<usize>, Option<usize>);
+
+/// Align two sequences with concave gap costs.
+///
+/// An alignment lines up the elements of `a` and `b` in order. Every
+/// column of the alignment holds an element of `a`, an element of
+/// `b`, or both. A column with both costs `substitution(a[i], b[j])`.
+/// A maximal run of `len` columns with elements of only one of the
+/// sequences is a gap, which costs `gap(len)`. The function returns
+/// the smallest total cost together with the columns of an optimal
+/// alignment, as pairs of indices into `a` and `b`.
+///
+/// With `M(i, j)`, `X(i, j)` and `Y(i, j)` denoting the smallest cost
+/// of aligning `a[..i]` with `b[..j]` so that the last column holds
+/// both elements, only an element of `a`, or only an element of `b`,
+/// the recurrences are
+///
+/// ```text
+/// M(i, j) = min { M, X, Y }(i - 1, j - 1) + substitution(a[i - 1], b[j - 1])
+/// X(i, j) = min { min { M, Y }(k, j) + gap(i - k) | k < i }
+/// Y(i, j) = min { min { M, X }(i, k) + gap(j - k) | k < j }
+/// ```
+///
+/// Computed directly, the gaps take O(*m* + *n*) time per entry.
+/// Following Eppstein, Galil and Giancarlo, the gap recurrences are
+/// instead solved by divide and conquer: the entries of one half are
+/// relaxed with all the entries of the other half at once. For a
+/// concave gap cost, the matrix from the first half to the second is
+/// inverse Monge, and its minima are found with [`column_minima`]
+/// after reversing the rows. Row `i` of `Y` only depends on `M` and
+/// `X` in the same row, so the rows are completed one at a time
+/// while `X` is relaxed over the rows.
+///
+/// The gap costs are evaluated once for every length from 1 to the
+/// length of the longer sequence. Debug builds check that they are
+/// concave.
+///
+/// Running time for sequences of lengths *m* and *n*: O(*mn* (log *m*
+/// + log *n*)).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::align_with_concave_gaps;
+///
+/// let substitution = |x: u8, y: u8| if x == y { 0 } else { 3 };
+/// // Opening a gap is expensive, extending it is cheap.
+/// let gap = |len: usize| 4 + len as i64;
+/// let (cost, columns) = align_with_concave_gaps(b"ACGT", b"AT", substitution, gap);
+/// assert_eq!(cost, 6);
+/// assert_eq!(
+///     columns,
+///     vec![(Some(0), Some(0)), (Some(1), None), (Some(2), None), (Some(3), Some(1))]
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Overflow of the `i64` computations is an error. In debug builds,
+/// it is an error to pass a gap cost which is not concave.
+pub fn align_with_concave_gaps<S, G>(
+    a: &[u8],
+    b: &[u8],
+    substitution: S,
+    gap: G,
+) -> (i64, Vec<AlignmentColumn>)
+where
+    S: Fn(u8, u8) -> i64,
+    G: Fn(usize) -> i64,
+{
+    let (m, n) = (a.len(), b.len());
+    // Lengths start at 1, the entry for 0 is never used.
+    let penalty = (0..=m.max(n))
+        .map(|len| if len == 0 { 0 } else { gap(len) })
+        .collect::<Vec<_>>();
+    debug_assert!(
+        penalty[1..]
+            .windows(3)
+            .all(|w| w[0] as i128 + w[2] as i128 <= 2 * w[1] as i128),
+        "gap cost is not concave"
+    );
+
+    let size = (m + 1) * (n + 1);
+    let mut alignment = Alignment {
+        a,
+        b,
+        substitution,
+        penalty: &penalty,
+        matched: vec![None; size],
+        deleted: vec![None; size],
+        inserted: vec![None; size],
+        deleted_from: vec![0; size],
+        inserted_from: vec![0; size],
+    };
+    alignment.rows(0, m + 1);
+    alignment.columns_from_end()
+}
+
+/// State of [`align_with_concave_gaps`]. Entry `(i, j)` is stored at
+/// `i * (n + 1) + j`, and `None` marks an impossible alignment. The
+/// `deleted` entries are `X`, the `inserted` entries are `Y`, and the
+/// start of the gap ending at every entry is stored next to them.
+struct Alignment<'a, S> {
+    a: &'a [u8],
+    b: &'a [u8],
+    substitution: S,
+    penalty: &'a [i64],
+    matched: Vec<Option<i64>>,
+    deleted: Vec<Option<i64>>,
+    inserted: Vec<Option<i64>>,
+    deleted_from: Vec<usize>,
+    inserted_from: Vec<usize>,
+}
+
+impl<S: Fn(u8, u8) -> i64> Alignment<'_, S> {
+    fn at(&self, i: usize, j: usize) -> usize {
+        i * (self.b.len() + 1) + j
+    }
+
+    /// Complete the rows `lo..hi`, which have seen the gaps from all
+    /// rows before `lo`.
+    fn rows(&mut self, lo: usize, hi: usize) {
+        if hi - lo == 1 {
+            self.complete_row(lo);
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        self.rows(lo, mid);
+        for j in 0..=self.b.len() {
+            let sources = (lo..mid)
+                .map(|k| {
+                    let idx = self.at(k, j);
+                    min_cost(self.matched[idx], self.inserted[idx])
+                })
+                .collect::<Vec<_>>();
+            for (i, best) in gap_minima(self.penalty, lo, &sources, mid..hi) {
+                let idx = self.at(i, j);
+                if let Some((cost, k)) = best {
+                    if self.deleted[idx].is_none_or(|old| cost < old) {
+                        self.deleted[idx] = Some(cost);
+                        self.deleted_from[idx] = k;
+                    }
+                }
+            }
+        }
+        self.rows(mid, hi);
+    }
+
+    /// Compute `M` and `Y` in row `i`, where `X` is complete.
+    fn complete_row(&mut self, i: usize) {
+        for j in 0..=self.b.len() {
+            let idx = self.at(i, j);
+            self.matched[idx] = match (i, j) {
+                (0, 0) => Some(0),
+                (0, _) | (_, 0) => None,
+                _ => {
+                    let prev = self.at(i - 1, j - 1);
+                    let best = min_cost(
+                        min_cost(self.matched[prev], self.deleted[prev]),
+                        self.inserted[prev],
+                    );
+                    best.map(|cost| cost + (self.substitution)(self.a[i - 1], self.b[j - 1]))
+                }
+            };
+        }
+        let sources = (0..=self.b.len())
+            .map(|k| {
+                let idx = self.at(i, k);
+                min_cost(self.matched[idx], self.deleted[idx])
+            })
+            .collect::<Vec<_>>();
+        self.columns(i, &sources, 0, sources.len());
+    }
+
+    /// Relax `Y` in row `i` and columns `lo..hi` with the gaps
+    /// starting in the same columns.
+    fn columns(&mut self, i: usize, sources: &[Option<i64>], lo: usize, hi: usize) {
+        if hi - lo <= 1 {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        for (j, best) in gap_minima(self.penalty, lo, &sources[lo..mid], mid..hi) {
+            let idx = self.at(i, j);
+            if let Some((cost, k)) = best {
+                if self.inserted[idx].is_none_or(|old| cost < old) {
+                    self.inserted[idx] = Some(cost);
+                    self.inserted_from[idx] = k;
+                }
+            }
+        }
+        self.columns(i, sources, lo, mid);
+        self.columns(i, sources, mid, hi);
+    }
+
+    /// Follow the optimal choices back from the end of both
+    /// sequences.
+    fn columns_from_end(&self) -> (i64, Vec<AlignmentColumn>) {
+        // State s of entry idx is numbered 3 * idx + s, and the state
+        // of every predecessor lies at an earlier entry.
+        const MATCHED: usize = 0;
+        const DELETED: usize = 1;
+        const INSERTED: usize = 2;
+        let cost = |state, idx: usize| match state {
+            MATCHED => self.matched[idx],
+            DELETED => self.deleted[idx],
+            _ => self.inserted[idx],
+        };
+        let best = |states: &[usize], idx| {
+            states
+                .iter()
+                .filter_map(|&state| cost(state, idx).map(|cost| (cost, 3 * idx + state)))
+                .min_by_key(|&(cost, _)| cost)
+                .unwrap()
+        };
+
+        let (m, n) = (self.a.len(), self.b.len());
+        let width = n + 1;
+        let parent = |id: usize| {
+            let (idx, state) = (id / 3, id % 3);
+            let (i, j) = (idx / width, idx % width);
+            if idx == 0 {
+                return id;
+            }
+            match state {
+                MATCHED => best(&[MATCHED, DELETED, INSERTED], self.at(i - 1, j - 1)).1,
+                DELETED => best(&[MATCHED, INSERTED], self.at(self.deleted_from[idx], j)).1,
+                _ => best(&[MATCHED, DELETED], self.at(i, self.inserted_from[idx])).1,
+            }
+        };
+        let (total, end) = best(&[MATCHED, DELETED, INSERTED], self.at(m, n));
+        let path = path_from_parent_fn(end, parent);
+
+        let mut columns = Vec::new();
+        for w in path.windows(2) {
+            let (from, to) = (w[0] / 3, w[1] / 3);
+            let (k, l) = (from / width, from % width);
+            let (i, j) = (to / width, to % width);
+            match w[1] % 3 {
+                MATCHED => columns.push((Some(i - 1), Some(j - 1))),
+                DELETED => columns.extend((k..i).map(|i| (Some(i), None))),
+                _ => columns.extend((l..j).map(|j| (None, Some(j)))),
+            }
+        }
+        (total, columns)
+    }
+}
+
+/// The smaller of two costs, where `None` is infinite.
+fn min_cost(a: Option<i64>, b: Option<i64>) -> Option<i64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Find the best gap into every target from the sources, which start
+/// at position `first`. All sources come before all targets. The
+/// result holds the cost and start of the best gap, if any.
+fn gap_minima(
+    penalty: &[i64],
+    first: usize,
+    sources: &[Option<i64>],
+    targets: std::ops::Range<usize>,
+) -> Vec<(usize, Option<(i64, usize)>)> {
+    let matrix = Gaps {
+        penalty,
+        first,
+        sources,
+        targets: targets.clone(),
+    };
+    let minima = column_minima(&matrix);
+    targets
+        .zip(minima)
+        .map(|(t, row)| {
+            let k = first + sources.len() - 1 - row;
+            (t, sources[k - first].map(|cost| (cost + penalty[t - k], k)))
+        })
+        .collect()
+}
+
+/// Implicit matrix of gap costs from the sources to the targets of
+/// [`gap_minima`]. The rows are in decreasing order of position,
+/// which makes the matrix Monge. Impossible sources give rows of
+/// infinite elements, which sort after all finite elements.
+struct Gaps<'a> {
+    penalty: &'a [i64],
+    first: usize,
+    sources: &'a [Option<i64>],
+    targets: std::ops::Range<usize>,
+}
+
+impl Matrix<(bool, i64)> for Gaps<'_> {
+    fn nrows(&self) -> usize {
+        self.sources.len()
+    }
+    fn ncols(&self) -> usize {
+        self.targets.len()
+    }
+    fn index(&self, row: usize, column: usize) -> (bool, i64) {
+        let k = self.sources.len() - 1 - row;
+        let t = self.targets.start + column;
+        match self.sources[k] {
+            Some(cost) => (false, cost + self.penalty[t - self.first - k]),
+            None => (true, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    /// Compute the weighted completion time of a schedule directly.
+    fn schedule_cost(process_times: &[u64], setup: u64, weights: &[u64], starts: &[usize]) -> u64 {
+        let mut time = 0;
+        let mut cost = 0;
+        for (b, &start) in starts.iter().enumerate() {
+            let end = starts.get(b + 1).copied().unwrap_or(process_times.len());
+            time += setup + process_times[start..end].iter().sum::<u64>();
+            cost += time * weights[start..end].iter().sum::<u64>();
+        }
+        cost
+    }
+
+    /// Largest distance from a point to its nearest center.
+    fn coverage_radius(positions: &[f64], centers: &[usize]) -> f64 {
+        positions
+            .iter()
+            .map(|&x| {
+                centers
+                    .iter()
+                    .map(|&c| (x - positions[c]).abs())
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Binary search over all candidate radii with a greedy check.
+    fn k_center_greedy(positions: &[f64], p: usize) -> f64 {
+        let centers_needed = |radius: f64| {
+            let mut count = 0;
+            let mut i = 0;
+            while i < positions.len() {
+                // Place the center at the last point within reach and
+                // skip all points covered by it.
+                let mut c = i;
+                while c + 1 < positions.len() && positions[c + 1] - positions[i] <= radius {
+                    c += 1;
+                }
+                while i < positions.len() && positions[i] - positions[c] <= radius {
+                    i += 1;
+                }
+                count += 1;
+            }
+            count
+        };
+        let mut radii = positions
+            .iter()
+            .flat_map(|&x| positions.iter().map(move |&y| (x - y).abs()))
+            .collect::<Vec<_>>();
+        radii.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let first = radii.partition_point(|&r| centers_needed(r) > p);
+        radii[first]
+    }
+
+    fn random_positions(n: usize, rng: &mut ChaCha20Rng) -> Vec<f64> {
+        let mut positions = (0..n)
+            .map(|_| rng.gen_range(0..100) as f64)
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        positions
+    }
+
+    #[test]
+    fn k_center_line_no_points() {
+        assert_eq!(k_center_line(&[], 3), (0.0, vec![]));
+    }
+
+    #[test]
+    fn k_center_line_more_centers_than_points() {
+        let (radius, centers) = k_center_line(&[1.0, 4.0, 4.0], 5);
+        assert_eq!(radius, 0.0);
+        assert_eq!(coverage_radius(&[1.0, 4.0, 4.0], &centers), 0.0);
+    }
+
+    #[test]
+    fn k_center_line_matches_reference() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let n = rng.gen_range(1..30);
+            let p = rng.gen_range(1..8);
+            let positions = random_positions(n, &mut rng);
+            let (radius, centers) = k_center_line(&positions, p);
+            let (expected, reference_centers) = reference::k_center_line(&positions, p);
+            assert_eq!(radius, expected, "{:?}", positions);
+            assert!(centers.len() <= p);
+            assert_eq!(coverage_radius(&positions, &centers), radius);
+            assert!(reference_centers.len() <= p);
+            assert_eq!(coverage_radius(&positions, &reference_centers), radius);
+        }
+    }
+
+    #[test]
+    fn k_center_line_matches_greedy() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for _ in 0..30 {
+            let n = rng.gen_range(10..150);
+            let p = rng.gen_range(1..12);
+            let positions = random_positions(n, &mut rng);
+            let (radius, centers) = k_center_line(&positions, p);
+            assert_eq!(radius, k_center_greedy(&positions, p), "{:?}", positions);
+            assert!(centers.len() <= p);
+            assert_eq!(coverage_radius(&positions, &centers), radius);
+        }
+    }
+
+    /// Random concave returns with marginal returns in `-20..50`.
+    fn random_returns(budget: u64, rng: &mut ChaCha20Rng) -> Vec<i64> {
+        let mut marginals = (0..budget)
+            .map(|_| rng.gen_range(-20..50))
+            .collect::<Vec<i64>>();
+        marginals.sort_unstable_by(|a, b| b.cmp(a));
+        let mut values = vec![rng.gen_range(-10..10)];
+        for m in marginals {
+            values.push(values[values.len() - 1] + m);
+        }
+        values
+    }
+
+    /// Hand out one unit at a time to the largest marginal return.
+    fn allocation_greedy(budget: u64, returns: &[Vec<i64>]) -> i64 {
+        let mut units = vec![0; returns.len()];
+        for _ in 0..budget {
+            let marginal = |k: usize| returns[k][units[k] + 1] - returns[k][units[k]];
+            match (0..returns.len()).max_by_key(|&k| marginal(k)) {
+                Some(k) if marginal(k) > 0 => units[k] += 1,
+                _ => break,
+            }
+        }
+        units.iter().zip(returns).map(|(&x, f)| f[x]).sum()
+    }
+
+    fn check_allocation(budget: u64, returns: &[Vec<i64>], expected: i64) {
+        let functions = returns
+            .iter()
+            .map(|values| move |x: u64| values[x as usize])
+            .collect::<Vec<_>>();
+        let (total, allocation) = concave_allocation(budget, &functions);
+        assert_eq!(total, expected, "{:?}", returns);
+        assert_eq!(allocation.len(), returns.len());
+        assert!(allocation.iter().sum::<u64>() <= budget);
+        let achieved = allocation
+            .iter()
+            .zip(returns)
+            .map(|(&x, f)| f[x as usize])
+            .sum::<i64>();
+        assert_eq!(achieved, total);
+    }
+
+    #[test]
+    fn concave_allocation_no_activities() {
+        let returns: [fn(u64) -> i64; 0] = [];
+        assert_eq!(concave_allocation(10, &returns), (0, vec![]));
+    }
+
+    #[test]
+    fn concave_allocation_zero_budget() {
+        let returns = [|x: u64| x as i64 + 3, |x: u64| 2 * x as i64 - 1];
+        assert_eq!(concave_allocation(0, &returns), (2, vec![0, 0]));
+    }
+
+    #[test]
+    fn concave_allocation_matches_reference() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let budget = rng.gen_range(0..30);
+            let n = rng.gen_range(1..6);
+            let returns = (0..n)
+                .map(|_| random_returns(budget, &mut rng))
+                .collect::<Vec<_>>();
+            let functions = returns
+                .iter()
+                .map(|values| move |x: u64| values[x as usize])
+                .collect::<Vec<_>>();
+            let (expected, allocation) = reference::concave_allocation(budget, &functions);
+            check_allocation(budget, &returns, expected);
+            assert!(allocation.iter().sum::<u64>() <= budget);
+            let achieved = allocation
+                .iter()
+                .zip(&returns)
+                .map(|(&x, f)| f[x as usize])
+                .sum::<i64>();
+            assert_eq!(achieved, expected);
+        }
+    }
+
+    #[test]
+    fn concave_allocation_matches_greedy() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for _ in 0..30 {
+            let budget = rng.gen_range(10..300);
+            let n = rng.gen_range(1..12);
+            let returns = (0..n)
+                .map(|_| random_returns(budget, &mut rng))
+                .collect::<Vec<_>>();
+            check_allocation(budget, &returns, allocation_greedy(budget, &returns));
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "return function 1 is not concave")]
+    fn concave_allocation_convex_returns() {
+        let returns = [|x: u64| x as i64, |x: u64| (x * x) as i64];
+        concave_allocation(3, &returns);
+    }
+
+    #[test]
+    fn serial_batching_no_jobs() {
+        assert_eq!(serial_batching(&[], 5, None), (0, vec![]));
+    }
+
+    #[test]
+    fn serial_batching_single_job() {
+        assert_eq!(serial_batching(&[7], 5, None), (12, vec![0]));
+        assert_eq!(serial_batching(&[7], 5, Some(&[3])), (36, vec![0]));
+    }
+
+    #[test]
+    fn serial_batching_zero_processing_times() {
+        // With nothing to process, a single batch only pays the setup
+        // time once for every job.
+        assert_eq!(serial_batching(&[0, 0, 0, 0], 3, None), (12, vec![0]));
+        assert_eq!(serial_batching(&[0, 0, 0], 0, None), (0, vec![0]));
+    }
+
+    #[test]
+    fn serial_batching_matches_reference() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let n = rng.gen_range(1..30);
+            let process_times = (0..n).map(|_| rng.gen_range(0..20)).collect::<Vec<_>>();
+            let weights = (0..n).map(|_| rng.gen_range(0..10)).collect::<Vec<_>>();
+            let setup = rng.gen_range(0..50);
+
+            for (weights, all_weights) in
+                [(Some(&weights[..]), weights.clone()), (None, vec![1; n])]
+            {
+                let (cost, starts) = serial_batching(&process_times, setup, weights);
+                let (expected, reference_starts) =
+                    reference::serial_batching(&process_times, setup, weights);
+                assert_eq!(cost, expected);
+                assert_eq!(
+                    cost,
+                    schedule_cost(&process_times, setup, &all_weights, &starts)
+                );
+                assert_eq!(
+                    cost,
+                    schedule_cost(&process_times, setup, &all_weights, &reference_starts)
+                );
+                assert_eq!(starts[0], 0);
+                assert!(starts.windows(2).all(|w| w[0] < w[1]));
+            }
+        }
+    }
+
+    /// Squared sums of random non-negative items, which satisfy the
+    /// quadrangle inequality and never increase when split.
+    fn squared_sums(n: usize, rng: &mut ChaCha20Rng) -> impl Fn(usize, usize) -> i64 {
+        let mut prefix = vec![0i64; n + 1];
+        for i in 0..n {
+            prefix[i + 1] = prefix[i] + rng.gen_range(0..10);
+        }
+        move |i, j| (prefix[j] - prefix[i]).pow(2)
+    }
+
+    #[test]
+    fn all_k_partitions_no_items() {
+        let w = |i: usize, j: usize| (j - i) as i64;
+        assert_eq!(all_k_partitions(0, 3, w), vec![]);
+        assert_eq!(all_k_partitions(3, 0, w), vec![]);
+        assert_eq!(all_k_partition_costs(0, 3, w), vec![]);
+    }
+
+    #[test]
+    fn all_k_partitions_matches_reference() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let n = rng.gen_range(1..25);
+            let max_k = rng.gen_range(1..30);
+            let w = squared_sums(n, &mut rng);
+
+            let partitions = all_k_partitions(n, max_k, &w);
+            let expected = reference::all_k_partitions(n, max_k, &w);
+            assert_eq!(partitions.len(), std::cmp::min(n, max_k));
+            assert_eq!(partitions.len(), expected.len());
+            for (k, ((cost, starts), (expected, _))) in (1..).zip(partitions.iter().zip(&expected))
+            {
+                assert_eq!(cost, expected);
+                assert_eq!(starts.len(), k);
+                assert_eq!(starts[0], 0);
+                assert!(starts.windows(2).all(|s| s[0] < s[1]));
+                let segments = segments_from_breaks(starts, n);
+                assert_eq!(*cost, segments.iter().map(|s| w(s.start, s.end)).sum());
+            }
+            let costs = partitions.iter().map(|&(cost, _)| cost).collect::<Vec<_>>();
+            assert_eq!(all_k_partition_costs(n, max_k, &w), costs);
+        }
+    }
+
+    #[test]
+    fn all_k_partitions_cost_curve() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let n = rng.gen_range(1..50);
+            let costs = all_k_partition_costs(n, n, squared_sums(n, &mut rng));
+            assert!(costs.windows(2).all(|c| c[0] >= c[1]), "{:?}", costs);
+            assert!(
+                costs.windows(3).all(|c| c[0] + c[2] >= 2 * c[1]),
+                "{:?}",
+                costs
+            );
+        }
+    }
+
+    /// Compute the energy of a structure directly.
+    fn rna_energy(pairs: &[(usize, usize)], penalty: &[i64]) -> i64 {
+        let mut energy = 0;
+        for w in pairs.windows(2) {
+            let ((i, j), (i2, j2)) = (w[0], w[1]);
+            assert!(i < i2 && i2 < j2 && j2 < j, "{:?} not nested", pairs);
+            energy += penalty[(i2 - i - 1) + (j - j2 - 1)];
+        }
+        if let Some(&(i, j)) = pairs.last() {
+            energy += penalty[j - i - 1];
+        }
+        energy
+    }
+
+    #[test]
+    fn rna_single_loop_empty() {
+        assert_eq!(rna_single_loop(|_, _| true, |_| -1, 0), (0, vec![]));
+        assert_eq!(rna_single_loop(|_, _| true, |_| -1, 1), (0, vec![]));
+        assert_eq!(rna_single_loop(|_, _| false, |_| -1, 10), (0, vec![]));
+        // Positive penalties make every structure worse than none.
+        assert_eq!(rna_single_loop(|_, _| true, |_| 1, 10), (0, vec![]));
+    }
+
+    #[test]
+    fn rna_single_loop_matches_reference() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let mut chains = 0;
+        for _ in 0..300 {
+            let n = rng.gen_range(1..=40);
+            // Half of the pairs can form, which gives many stems.
+            let table = (0..n * n).map(|_| rng.gen_bool(0.5)).collect::<Vec<_>>();
+            let pairing = |i: usize, j: usize| j - i > 3 && table[i * n + j];
+            // Concave penalties with decreasing increments.
+            let mut penalty = vec![-rng.gen_range(5..15)];
+            let mut step = rng.gen_range(0..4);
+            while penalty.len() < n {
+                penalty.push(penalty[penalty.len() - 1] + step);
+                step -= rng.gen_range(0..2);
+            }
+
+            let (energy, pairs) = rna_single_loop(pairing, |size| penalty[size], n);
+            let (expected, reference_pairs) =
+                reference::rna_single_loop(pairing, |size| penalty[size], n);
+            assert_eq!(energy, expected);
+            assert_eq!(energy, rna_energy(&reference_pairs, &penalty));
+            assert_eq!(energy, rna_energy(&pairs, &penalty));
+            assert!(pairs.iter().all(|&(i, j)| pairing(i, j)));
+            chains += usize::from(pairs.len() > 2);
+        }
+        // Many structures have loops between several pairs.
+        assert!(chains > 10, "{}", chains);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "loop penalty is not concave")]
+    fn rna_single_loop_convex_penalty() {
+        rna_single_loop(|_, _| true, |size| (size * size) as i64, 5);
+    }
+
+    /// Check that the columns form an alignment and compute its cost
+    /// directly.
+    fn alignment_cost(
+        a: &[u8],
+        b: &[u8],
+        substitution: &[[i64; 4]; 4],
+        gap: &[i64],
+        columns: &[AlignmentColumn],
+    ) -> i64 {
+        let a_indices = columns.iter().filter_map(|c| c.0).collect::<Vec<_>>();
+        let b_indices = columns.iter().filter_map(|c| c.1).collect::<Vec<_>>();
+        assert_eq!(a_indices, (0..a.len()).collect::<Vec<_>>());
+        assert_eq!(b_indices, (0..b.len()).collect::<Vec<_>>());
+
+        let mut cost = 0;
+        let mut k = 0;
+        while k < columns.len() {
+            match columns[k] {
+                (Some(i), Some(j)) => {
+                    cost += substitution[a[i] as usize][b[j] as usize];
+                    k += 1;
+                }
+                (x, y) => {
+                    assert!(x.is_some() != y.is_some(), "empty column");
+                    let len = columns[k..]
+                        .iter()
+                        .take_while(|c| {
+                            (c.0.is_some(), c.1.is_some()) == (x.is_some(), y.is_some())
+                        })
+                        .count();
+                    cost += gap[len];
+                    k += len;
+                }
+            }
+        }
+        cost
+    }
+
+    #[test]
+    fn align_with_concave_gaps_empty() {
+        let substitution = |_, _| 1;
+        let gap = |len: usize| 3 + len as i64;
+        assert_eq!(
+            align_with_concave_gaps(b"", b"", substitution, gap),
+            (0, vec![])
+        );
+        assert_eq!(
+            align_with_concave_gaps(b"", b"ab", substitution, gap),
+            (5, vec![(None, Some(0)), (None, Some(1))])
+        );
+        assert_eq!(
+            align_with_concave_gaps(b"a", b"", substitution, gap),
+            (4, vec![(Some(0), None)])
+        );
+    }
+
+    #[test]
+    fn align_with_concave_gaps_matches_reference() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let mut gaps = 0;
+        for _ in 0..300 {
+            let a = (0..rng.gen_range(0..15))
+                .map(|_| rng.gen_range(0..4))
+                .collect::<Vec<u8>>();
+            let b = (0..rng.gen_range(0..15))
+                .map(|_| rng.gen_range(0..4))
+                .collect::<Vec<u8>>();
+            let mut substitution = [[0; 4]; 4];
+            for (x, row) in substitution.iter_mut().enumerate() {
+                for (y, cost) in row.iter_mut().enumerate() {
+                    *cost = if x == y {
+                        -rng.gen_range(0..3)
+                    } else {
+                        rng.gen_range(0..8)
+                    };
+                }
+            }
+            // Concave gap costs with decreasing increments, which can
+            // become negative.
+            let mut gap = vec![0, rng.gen_range(-2..10)];
+            let mut step = rng.gen_range(-1..6);
+            while gap.len() <= a.len().max(b.len()) {
+                gap.push(gap[gap.len() - 1] + step);
+                step -= rng.gen_range(0..3);
+            }
+
+            let (cost, columns) = align_with_concave_gaps(
+                &a,
+                &b,
+                |x, y| substitution[x as usize][y as usize],
+                |len| gap[len],
+            );
+            let (expected, reference_columns) = reference::align_with_concave_gaps(
+                &a,
+                &b,
+                |x, y| substitution[x as usize][y as usize],
+                |len| gap[len],
+            );
+            assert_eq!(cost, expected);
+            assert_eq!(
+                cost,
+                alignment_cost(&a, &b, &substitution, &gap, &reference_columns)
+            );
+            assert_eq!(cost, alignment_cost(&a, &b, &substitution, &gap, &columns));
+            gaps += usize::from(columns.iter().any(|c| c.0.is_none() || c.1.is_none()));
+        }
+        assert!(gaps > 100, "{}", gaps);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "gap cost is not concave")]
+    fn align_with_concave_gaps_convex_gap() {
+        align_with_concave_gaps(b"abc", b"a", |_, _| 0, |len| (len * len) as i64);
+    }
+}
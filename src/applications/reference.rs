@@ -0,0 +1,422 @@
+//! Slow reference solvers for the applications.
+//!
+//! Every function here solves the same problem as the function of the
+//! same name in the [parent module](super), with the same arguments
+//! and the same result. Instead of SMAWK, the recurrence given in the
+//! documentation of the fast solver is evaluated directly, trying
+//! every choice. The functions are simple enough to be checked by
+//! reading them, which makes them useful for testing the fast solvers
+//! and for validating cost functions before handing them to one.
+//!
+//! Optimal solutions are not unique. When there are ties, a reference
+//! solver can return a different solution with the same cost, so
+//! tests should compare the costs and check the solutions on their
+//! own. The reference solvers also do not check that their inputs
+//! are Monge, concave or convex: they find the optimum regardless.
+//!
+//! **Note: this module is only available if you enable the
+//! `test-util` Cargo feature.**
+
+use super::AlignmentColumn;
+use crate::dp::reconstruct::path_from_parents;
+
+/// Reference solver for [`serial_batching`](super::serial_batching).
+///
+/// Running time for *n* jobs: O(*n*³).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::{reference, serial_batching};
+///
+/// let process_times = [4, 1, 3, 2];
+/// assert_eq!(reference::serial_batching(&process_times, 2, None).0,
+///            serial_batching(&process_times, 2, None).0);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to pass `weights` of a different length than
+/// `process_times`. Overflow of the `u64` computations is an error.
+pub fn serial_batching(
+    process_times: &[u64],
+    setup: u64,
+    weights: Option<&[u64]>,
+) -> (u64, Vec<usize>) {
+    let n = process_times.len();
+    if let Some(weights) = weights {
+        assert_eq!(weights.len(), n, "expected one weight per job");
+    }
+    let weight = |i: usize| weights.map_or(1, |w| w[i]);
+
+    // The best cost of the first j jobs and the start of their last
+    // batch.
+    let mut best = vec![0; n + 1];
+    let mut parents = vec![0; n + 1];
+    for j in 1..=n {
+        (best[j], parents[j]) = (0..j)
+            .map(|i| {
+                let batch = setup + process_times[i..j].iter().sum::<u64>();
+                (best[i] + batch * (i..n).map(weight).sum::<u64>(), i)
+            })
+            .min()
+            .expect("at least one batch");
+    }
+    let mut starts = path_from_parents(&parents, n);
+    starts.pop();
+    (best[n], starts)
+}
+
+/// Reference solver for [`k_center_line`](super::k_center_line).
+///
+/// The radius of every interval is found by trying every point in
+/// it as the center.
+///
+/// Running time for *n* points: O(*p* *n*³).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::{k_center_line, reference};
+///
+/// let positions = [0.0, 1.0, 2.0, 10.0, 11.0];
+/// assert_eq!(reference::k_center_line(&positions, 2).0,
+///            k_center_line(&positions, 2).0);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to pass unsorted positions or to ask for zero
+/// centers for a non-empty set of points.
+pub fn k_center_line(positions: &[f64], p: usize) -> (f64, Vec<usize>) {
+    let n = positions.len();
+    if n == 0 {
+        return (0.0, Vec::new());
+    }
+    assert!(p > 0, "cannot cover points with zero centers");
+    assert!(
+        positions.windows(2).all(|w| w[0] <= w[1]),
+        "positions must be sorted"
+    );
+
+    // Radius and center of a single center serving points k..j.
+    let interval = |k: usize, j: usize| {
+        (k..j)
+            .map(|c| {
+                let radius = f64::max(positions[c] - positions[k], positions[j - 1] - positions[c]);
+                (radius, c)
+            })
+            .min_by(|a, b| a.partial_cmp(b).expect("positions are not NaN"))
+            .expect("non-empty interval")
+    };
+
+    // The smallest radius for the first j points with at most l
+    // centers, and the first point served by the last center.
+    let mut radius = vec![vec![f64::INFINITY; n + 1]; p + 1];
+    let mut splits = vec![vec![0; n + 1]; p + 1];
+    radius[0][0] = 0.0;
+    for l in 1..=p {
+        radius[l][0] = 0.0;
+        for j in 1..=n {
+            for k in 0..j {
+                let candidate = f64::max(radius[l - 1][k], interval(k, j).0);
+                if candidate < radius[l][j] {
+                    radius[l][j] = candidate;
+                    splits[l][j] = k;
+                }
+            }
+        }
+    }
+
+    let mut centers = Vec::with_capacity(p);
+    let mut j = n;
+    for split in splits[1..].iter().rev() {
+        if j == 0 {
+            break;
+        }
+        centers.push(interval(split[j], j).1);
+        j = split[j];
+    }
+    centers.reverse();
+    (radius[p][n], centers)
+}
+
+/// Reference solver for
+/// [`concave_allocation`](super::concave_allocation).
+///
+/// Running time for *n* activities: O(*n* · `budget`²).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::{concave_allocation, reference};
+///
+/// let returns: [&dyn Fn(u64) -> i64; 2] = [
+///     &|x| 10 * x as i64 - (x * x) as i64,
+///     &|x| 4 * x as i64,
+/// ];
+/// assert_eq!(reference::concave_allocation(5, &returns),
+///            concave_allocation(5, &returns));
+/// ```
+///
+/// # Panics
+///
+/// Overflow of the `i64` computations is an error.
+pub fn concave_allocation<F: Fn(u64) -> i64>(budget: u64, returns: &[F]) -> (i64, Vec<u64>) {
+    let size = usize::try_from(budget).expect("budget does not fit in usize") + 1;
+
+    // The best total return of the first k activities from at most b
+    // units, and the units given to activity k - 1.
+    let mut best = vec![vec![0; size]];
+    let mut units = Vec::with_capacity(returns.len());
+    for f in returns {
+        let prev = &best[best.len() - 1];
+        let (next, chosen): (Vec<i64>, Vec<usize>) = (0..size)
+            .map(|b| {
+                (0..=b)
+                    .map(|x| (prev[b - x] + f(x as u64), x))
+                    .max_by_key(|&(value, _)| value)
+                    .expect("at least zero units")
+            })
+            .unzip();
+        best.push(next);
+        units.push(chosen);
+    }
+
+    let mut allocation = vec![0; returns.len()];
+    let mut b = size - 1;
+    for (x, chosen) in allocation.iter_mut().zip(&units).rev() {
+        *x = chosen[b] as u64;
+        b -= chosen[b];
+    }
+    (best[returns.len()][size - 1], allocation)
+}
+
+/// Reference solver for [`rna_single_loop`](super::rna_single_loop).
+///
+/// Running time for *n* bases: O(*n*⁴).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::{reference, rna_single_loop};
+///
+/// let bases = b"GGGAAACCC";
+/// let pairing = |i: usize, j: usize| {
+///     j - i > 3 && matches!((bases[i], bases[j]), (b'G', b'C') | (b'C', b'G'))
+/// };
+/// let penalty = |size: usize| if size == 0 { -3 } else { 1 + size as i64 };
+/// assert_eq!(reference::rna_single_loop(pairing, penalty, bases.len()),
+///            rna_single_loop(pairing, penalty, bases.len()));
+/// ```
+///
+/// # Panics
+///
+/// Overflow of the `i64` computations is an error.
+pub fn rna_single_loop<P, L>(pairing: P, loop_penalty: L, n: usize) -> (i64, Vec<(usize, usize)>)
+where
+    P: Fn(usize, usize) -> bool,
+    L: Fn(usize) -> i64,
+{
+    let penalty = (0..n).map(loop_penalty).collect::<Vec<_>>();
+
+    // The smallest energy of a chain with outermost pair (i, j), and
+    // the next pair of the chain, computed by increasing span.
+    let mut energy = vec![vec![None; n]; n];
+    let mut inner = vec![vec![None; n]; n];
+    let mut best = (0, None);
+    for span in 1..n {
+        for i in 0..n - span {
+            let j = i + span;
+            if !pairing(i, j) {
+                continue;
+            }
+            let mut value = (penalty[j - i - 1], None);
+            for i2 in i + 1..j {
+                for j2 in i2 + 1..j {
+                    if let Some(chain) = energy[i2][j2] {
+                        let candidate = chain + penalty[(i2 - i - 1) + (j - j2 - 1)];
+                        if candidate < value.0 {
+                            value = (candidate, Some((i2, j2)));
+                        }
+                    }
+                }
+            }
+            (energy[i][j], inner[i][j]) = (Some(value.0), value.1);
+            if value.0 < best.0 {
+                best = (value.0, Some((i, j)));
+            }
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let mut pair = best.1;
+    while let Some((i, j)) = pair {
+        pairs.push((i, j));
+        pair = inner[i][j];
+    }
+    (best.0, pairs)
+}
+
+/// Reference solver for
+/// [`align_with_concave_gaps`](super::align_with_concave_gaps).
+///
+/// Every gap is tried at every length.
+///
+/// Running time for sequences of lengths *m* and *n*: O(*mn* (*m* +
+/// *n*)).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::{align_with_concave_gaps, reference};
+///
+/// let substitution = |x: u8, y: u8| if x == y { 0 } else { 3 };
+/// let gap = |len: usize| 4 + len as i64;
+/// assert_eq!(reference::align_with_concave_gaps(b"ACGT", b"AT", substitution, gap),
+///            align_with_concave_gaps(b"ACGT", b"AT", substitution, gap));
+/// ```
+///
+/// # Panics
+///
+/// Overflow of the `i64` computations is an error.
+pub fn align_with_concave_gaps<S, G>(
+    a: &[u8],
+    b: &[u8],
+    substitution: S,
+    gap: G,
+) -> (i64, Vec<AlignmentColumn>)
+where
+    S: Fn(u8, u8) -> i64,
+    G: Fn(usize) -> i64,
+{
+    const MATCH: usize = 0;
+    const DELETE: usize = 1;
+    const INSERT: usize = 2;
+    let (m, n) = (a.len(), b.len());
+
+    // The smallest cost of aligning a[..i] with b[..j] when the last
+    // column is a match, a deletion or an insertion, together with the
+    // previous state and where the last gap starts.
+    let mut cost = vec![vec![vec![None; n + 1]; m + 1]; 3];
+    let mut from = vec![vec![vec![(MATCH, 0); n + 1]; m + 1]; 3];
+    let best_of = |cost: &[Vec<Vec<Option<i64>>>], states: &[usize], i: usize, j: usize| {
+        states
+            .iter()
+            .filter_map(|&state| Some((cost[state][i][j]?, state)))
+            .min()
+    };
+    let mut relax = |cost: &mut [Vec<Vec<Option<i64>>>],
+                     state: usize,
+                     i: usize,
+                     j: usize,
+                     value: i64,
+                     previous: (usize, usize)| {
+        if cost[state][i][j].is_none_or(|old| value < old) {
+            cost[state][i][j] = Some(value);
+            from[state][i][j] = previous;
+        }
+    };
+    cost[MATCH][0][0] = Some(0);
+    for i in 0..=m {
+        for j in 0..=n {
+            if i > 0 && j > 0 {
+                if let Some((value, state)) = best_of(&cost, &[MATCH, DELETE, INSERT], i - 1, j - 1)
+                {
+                    let value = value + substitution(a[i - 1], b[j - 1]);
+                    relax(&mut cost, MATCH, i, j, value, (state, 0));
+                }
+            }
+            for k in 0..i {
+                if let Some((value, state)) = best_of(&cost, &[MATCH, INSERT], k, j) {
+                    relax(&mut cost, DELETE, i, j, value + gap(i - k), (state, k));
+                }
+            }
+            for k in 0..j {
+                if let Some((value, state)) = best_of(&cost, &[MATCH, DELETE], i, k) {
+                    relax(&mut cost, INSERT, i, j, value + gap(j - k), (state, k));
+                }
+            }
+        }
+    }
+
+    let (total, mut state) =
+        best_of(&cost, &[MATCH, DELETE, INSERT], m, n).expect("every pair of sequences aligns");
+    let mut columns = Vec::with_capacity(m + n);
+    let (mut i, mut j) = (m, n);
+    while (i, j) != (0, 0) {
+        let (previous, start) = from[state][i][j];
+        match state {
+            MATCH => {
+                (i, j) = (i - 1, j - 1);
+                columns.push((Some(i), Some(j)));
+            }
+            DELETE => {
+                columns.extend((start..i).rev().map(|i| (Some(i), None)));
+                i = start;
+            }
+            _ => {
+                columns.extend((start..j).rev().map(|j| (None, Some(j))));
+                j = start;
+            }
+        }
+        state = previous;
+    }
+    columns.reverse();
+    (total, columns)
+}
+
+/// Reference solver for [`all_k_partitions`](super::all_k_partitions).
+///
+/// Running time for *n* items: O(`max_k` · *n*²).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::applications::{all_k_partitions, reference};
+///
+/// let w = |i: usize, j: usize| ((j - i) * (j - i)) as i64;
+/// assert_eq!(reference::all_k_partitions(4, 5, w), all_k_partitions(4, 5, w));
+/// ```
+///
+/// # Panics
+///
+/// Overflow of the `i64` computations is an error.
+pub fn all_k_partitions<W: Fn(usize, usize) -> i64>(
+    n: usize,
+    max_k: usize,
+    w: W,
+) -> Vec<(i64, Vec<usize>)> {
+    let layers = std::cmp::min(max_k, n);
+
+    // The best cost of the first j items in k segments, and the start
+    // of the last segment.
+    let mut cost = vec![vec![None; n + 1]; layers + 1];
+    let mut splits = vec![vec![0; n + 1]; layers + 1];
+    cost[0][0] = Some(0);
+    for k in 1..=layers {
+        for j in 1..=n {
+            for i in 0..j {
+                if let Some(prev) = cost[k - 1][i] {
+                    let value = prev + w(i, j);
+                    if cost[k][j].is_none_or(|old| value < old) {
+                        cost[k][j] = Some(value);
+                        splits[k][j] = i;
+                    }
+                }
+            }
+        }
+    }
+
+    (1..=layers)
+        .map(|k| {
+            let mut starts = vec![0; k];
+            let mut j = n;
+            for l in (1..=k).rev() {
+                j = splits[l][j];
+                starts[l - 1] = j;
+            }
+            (cost[k][n].expect("at least k items"), starts)
+        })
+        .collect()
+}
@@ -0,0 +1,194 @@
+//! Column minima for batches of matrices stored in one array.
+//!
+//! A stack of totally monotone matrices is often stored as a single
+//! array with more than two dimensions, such as an `Array3` of shape
+//! (batch, *m*, *n*). [`column_minima_batched`] computes the column
+//! minima of every matrix in such an array.
+//!
+//! **Note: this module is only available if you enable the `ndarray`
+//! Cargo feature.** Enable the `rayon` Cargo feature as well to
+//! process the matrices in parallel.
+
+use crate::Matrix;
+use ndarray::{ArrayD, ArrayView2, ArrayViewD, Axis, Dimension, IxDyn};
+
+/// Adapter for a matrix which is a view into a larger array.
+///
+/// The view can have arbitrary strides, so the matrix does not need
+/// to be contiguous in memory.
+struct View<'a, T>(ArrayView2<'a, T>);
+
+impl<T: Copy> Matrix<T> for View<'_, T> {
+    #[inline]
+    fn nrows(&self) -> usize {
+        self.0.nrows()
+    }
+    #[inline]
+    fn ncols(&self) -> usize {
+        self.0.ncols()
+    }
+    #[inline]
+    fn index(&self, row: usize, column: usize) -> T {
+        self.0[[row, column]]
+    }
+}
+
+/// Compute the column minima of the matrix at `index` among the
+/// batch axes.
+fn minima_at<T: PartialOrd + Copy>(array: &ArrayViewD<'_, T>, index: &IxDyn) -> Vec<usize> {
+    let mut matrix = array.view();
+    for &i in index.slice() {
+        matrix = matrix.index_axis_move(Axis(0), i);
+    }
+    let matrix = matrix
+        .into_dimensionality()
+        .expect("batch axes should leave a matrix");
+    crate::column_minima(&View(matrix))
+}
+
+/// Compute column minima for every matrix in a batch.
+///
+/// The two `matrix_axes` select the rows and columns of the matrices,
+/// and every matrix must be totally monotone. The remaining axes are
+/// batch axes. The result keeps the batch axes in their original
+/// order and adds a final axis with the minimum of every column. An
+/// array of shape (*b*, *m*, *n*) with `matrix_axes` equal to
+/// `(Axis(1), Axis(2))` thus gives a result of shape (*b*, *n*).
+///
+/// The matrix axes need not be the last axes, and the matrices need
+/// not be contiguous in memory. With the `rayon` Cargo feature
+/// enabled, the matrices are processed in parallel.
+///
+/// Running time on *b* matrices of size *m* ✕ *n*: O(*b* (*m* +
+/// *n*)).
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::{arr3, Axis};
+/// use smawk::batched::column_minima_batched;
+///
+/// let array = arr3(&[[[4, 2, 4, 3],
+///                     [5, 3, 5, 3],
+///                     [5, 3, 3, 1]],
+///                    [[1, 2, 3, 4],
+///                     [2, 1, 2, 3],
+///                     [3, 2, 1, 2]]]).into_dyn();
+/// let minima = column_minima_batched(&array, (Axis(1), Axis(2)));
+/// assert_eq!(minima.shape(), &[2, 4]);
+/// assert_eq!(minima.into_raw_vec(), vec![0, 0, 2, 2,
+///                                        0, 1, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to pass an array with fewer than two axes, to pass
+/// the same axis twice or an axis which is out of bounds, and to call
+/// this on a non-empty batch of matrices with zero rows.
+pub fn column_minima_batched<T>(array: &ArrayD<T>, matrix_axes: (Axis, Axis)) -> ArrayD<usize>
+where
+    T: PartialOrd + Copy + Send + Sync,
+{
+    let (rows, columns) = (matrix_axes.0.index(), matrix_axes.1.index());
+    let ndim = array.ndim();
+    assert!(ndim >= 2, "expected an array with at least two axes");
+    assert!(rows < ndim && columns < ndim, "matrix axis out of bounds");
+    assert_ne!(rows, columns, "expected two different matrix axes");
+
+    // Move the batch axes to the front, followed by the row and
+    // column axes. This only changes the strides of the view.
+    let mut axes = (0..ndim)
+        .filter(|&axis| axis != rows && axis != columns)
+        .collect::<Vec<_>>();
+    axes.extend([rows, columns]);
+    let view = array.view().permuted_axes(axes);
+
+    let batch_shape = &view.shape()[..ndim - 2];
+    let mut shape = batch_shape.to_vec();
+    shape.push(array.len_of(matrix_axes.1));
+
+    let indices = ndarray::indices(batch_shape).into_iter();
+    #[cfg(feature = "rayon")]
+    let minima = {
+        use rayon::prelude::*;
+        let indices = indices.collect::<Vec<_>>();
+        indices
+            .par_iter()
+            .map(|index| minima_at(&view, index))
+            .collect::<Vec<_>>()
+    };
+    #[cfg(not(feature = "rayon"))]
+    let minima = indices
+        .map(|index| minima_at(&view, &index))
+        .collect::<Vec<_>>();
+
+    ArrayD::from_shape_vec(shape, minima.concat()).expect("one minimum per column")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::distance_matrix;
+    use ndarray::{Array2, Array3, Array4};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /// Random [`distance_matrix`] as an `Array2`.
+    fn random_matrix(m: usize, n: usize, rng: &mut ChaCha20Rng) -> Array2<i64> {
+        let matrix = distance_matrix(m, n, 50, rng);
+        Array2::from_shape_fn((m, n), |(i, j)| matrix[i][j])
+    }
+
+    #[test]
+    fn empty_batch() {
+        let array = Array3::<i64>::zeros((0, 4, 5)).into_dyn();
+        let minima = column_minima_batched(&array, (Axis(1), Axis(2)));
+        assert_eq!(minima.shape(), &[0, 5]);
+    }
+
+    #[test]
+    fn single_matrix() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let matrix = random_matrix(7, 9, &mut rng);
+        let array = matrix.clone().insert_axis(Axis(0)).into_dyn();
+        let minima = column_minima_batched(&array, (Axis(1), Axis(2)));
+        assert_eq!(minima.shape(), &[1, 9]);
+        assert_eq!(minima.into_raw_vec(), crate::column_minima(&matrix));
+    }
+
+    #[test]
+    fn matrix_axes_in_any_position() {
+        // Matrices stored with the rows last and the columns first
+        // are not contiguous in memory.
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        let matrices = (0..3 * 4)
+            .map(|_| random_matrix(6, 5, &mut rng))
+            .collect::<Vec<_>>();
+        let array = Array4::from_shape_fn((5, 3, 4, 6), |(j, a, b, i)| matrices[a * 4 + b][[i, j]])
+            .into_dyn();
+
+        let minima = column_minima_batched(&array, (Axis(3), Axis(0)));
+        assert_eq!(minima.shape(), &[3, 4, 5]);
+        for a in 0..3 {
+            for b in 0..4 {
+                let expected = crate::column_minima(&matrices[a * 4 + b]);
+                let actual = minima.slice(ndarray::s![a, b, ..]).to_vec();
+                assert_eq!(actual, expected, "matrix ({}, {})", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn matrices_without_columns() {
+        let array = Array3::<i64>::zeros((2, 0, 0)).into_dyn();
+        let minima = column_minima_batched(&array, (Axis(1), Axis(2)));
+        assert_eq!(minima.shape(), &[2, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected two different matrix axes")]
+    fn same_axis_twice() {
+        let array = Array3::<i64>::zeros((2, 3, 3)).into_dyn();
+        column_minima_batched(&array, (Axis(1), Axis(1)));
+    }
+}
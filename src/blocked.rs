@@ -0,0 +1,150 @@
+//! SMAWK for matrices stored in blocks which are loaded on demand.
+//!
+//! Large matrices are sometimes tiled into rectangular blocks which
+//! are stored separately, for example on disk. Loading a block is
+//! expensive, but once loaded, all elements in the block are cheap to
+//! access. The functions here compute the same minima as the
+//! functions in the crate root, but access the matrix through
+//! [`BlockMatrix`] and keep recently used blocks in a cache.
+//!
+//! **Note: this module is only available if you enable the `ndarray`
+//! Cargo feature.**
+
+use ndarray::{s, Array2, ArrayViewMut2};
+use std::cell::RefCell;
+
+/// A matrix which is tiled into blocks.
+///
+/// The matrix is split into blocks of `block_size()` rows and columns,
+/// starting from the top-left corner. The blocks in the last block
+/// row and block column can be smaller when the block size does not
+/// divide the matrix size.
+pub trait BlockMatrix<T: Copy + Default> {
+    /// Return the number of rows.
+    fn nrows(&self) -> usize;
+    /// Return the number of columns.
+    fn ncols(&self) -> usize;
+    /// Return the number of rows and columns in a full block.
+    fn block_size(&self) -> (usize, usize);
+    /// Load the block at block row `bi` and block column `bj` into
+    /// `out`.
+    ///
+    /// The block holds the elements from row `bi * block_size().0`
+    /// and column `bj * block_size().1` onwards, and `out` has the
+    /// shape of the block. The buffer behind `out` is reused between
+    /// loads, so loading a block does not need to allocate. Its
+    /// previous contents are unspecified and must all be overwritten.
+    fn block(&self, bi: usize, bj: usize, out: ArrayViewMut2<'_, T>);
+}
+
+/// A block buffer together with the block column loaded into it, if
+/// any.
+type CachedBlock<T> = (Option<usize>, Array2<T>);
+
+/// Element access to a [`BlockMatrix`] through a cache of blocks.
+///
+/// The cache holds one block per block row. This matches the access
+/// pattern of SMAWK: it walks the rows of the matrix from top to
+/// bottom and moves from left to right within a row, so consecutive
+/// accesses to a block row tend to hit the same block. The buffer of a
+/// block row is allocated on the first load and reused afterwards.
+struct BlockCache<'a, T, B> {
+    matrix: &'a B,
+    block_size: (usize, usize),
+    blocks: RefCell<Vec<CachedBlock<T>>>,
+}
+
+impl<'a, T: Copy + Default, B: BlockMatrix<T>> BlockCache<'a, T, B> {
+    fn new(matrix: &'a B) -> Self {
+        let block_size = matrix.block_size();
+        assert!(
+            block_size.0 > 0 && block_size.1 > 0,
+            "block size must be positive"
+        );
+        let block_rows = matrix.nrows().div_ceil(block_size.0);
+        BlockCache {
+            matrix,
+            block_size,
+            blocks: RefCell::new(vec![(None, Array2::default((0, 0))); block_rows]),
+        }
+    }
+
+    fn index(&self, row: usize, column: usize) -> T {
+        let (bi, bj) = (row / self.block_size.0, column / self.block_size.1);
+        let (i, j) = (row % self.block_size.0, column % self.block_size.1);
+        let mut blocks = self.blocks.borrow_mut();
+        let (cached, buffer) = &mut blocks[bi];
+        if *cached != Some(bj) {
+            if buffer.is_empty() {
+                *buffer = Array2::default(self.block_size);
+            }
+            let rows = std::cmp::min(
+                self.block_size.0,
+                self.matrix.nrows() - bi * self.block_size.0,
+            );
+            let cols = std::cmp::min(
+                self.block_size.1,
+                self.matrix.ncols() - bj * self.block_size.1,
+            );
+            self.matrix
+                .block(bi, bj, buffer.slice_mut(s![..rows, ..cols]));
+            *cached = Some(bj);
+        }
+        buffer[[i, j]]
+    }
+}
+
+/// Compute column minima of a block matrix with SMAWK.
+///
+/// This gives the same result as [`crate::column_minima`], and the
+/// matrix must be totally monotone. The algorithm accesses O(*m* +
+/// *n*) elements, and the blocks holding them are loaded through a
+/// cache which keeps the most recently used block in each block row.
+///
+/// SMAWK makes O(log *n*) passes through the matrix, each on half as
+/// many columns as the previous one. A pass walks the rows from top
+/// to bottom, and the columns it inspects mostly increase with the
+/// rows: only the reduce step occasionally steps back to a column
+/// inspected earlier. Each block is therefore only loaded a small
+/// number of times per pass.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::{s, Array2, ArrayViewMut2};
+/// use smawk::blocked::BlockMatrix;
+///
+/// struct Tiled(Array2<i32>);
+///
+/// impl BlockMatrix<i32> for Tiled {
+///     fn nrows(&self) -> usize { self.0.nrows() }
+///     fn ncols(&self) -> usize { self.0.ncols() }
+///     fn block_size(&self) -> (usize, usize) { (2, 2) }
+///     fn block(&self, bi: usize, bj: usize, mut out: ArrayViewMut2<'_, i32>) {
+///         out.assign(&self.0.slice(s![2 * bi..(2 * bi + 2).min(self.0.nrows()),
+///                                     2 * bj..(2 * bj + 2).min(self.0.ncols())]));
+///     }
+/// }
+///
+/// let matrix = ndarray::arr2(&[[4, 2, 4, 3],
+///                              [5, 3, 5, 3],
+///                              [5, 3, 3, 1]]);
+/// assert_eq!(smawk::blocked::column_minima(&Tiled(matrix)),
+///            vec![0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows or with a
+/// block size of zero.
+pub fn column_minima<T: PartialOrd + Copy + Default, B: BlockMatrix<T>>(matrix: &B) -> Vec<usize> {
+    let cache = BlockCache::new(matrix);
+    let mut minima = vec![0; matrix.ncols()];
+    crate::smawk_inner(
+        &|i, j| cache.index(i, j),
+        &(0..matrix.nrows()).collect::<Vec<_>>(),
+        &(0..matrix.ncols()).collect::<Vec<_>>(),
+        &mut minima,
+    );
+    minima
+}
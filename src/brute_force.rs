@@ -3,14 +3,46 @@
 //! The functions here are mostly meant to be used for testing
 //! correctness of the SMAWK implementation.
 //!
-//! **Note: this module is only available if you enable the `ndarray`
-//! Cargo feature.**
+//! **Note: the functions on `ndarray` matrices are only available if
+//! you enable the `ndarray` Cargo feature.**
+//!
+//! # Compile-time minima
+//!
+//! The functions for fixed-size arrays, such as [`row_minima_i32`],
+//! are `const fn`. They can compute a lookup table at compile time,
+//! so that it is stored in the binary with no startup cost. A `const
+//! fn` cannot compare generic elements, so there is one function per
+//! element type: `i32`, `i64`, `u32` and `u64`.
+//!
+//! ```
+//! use smawk::brute_force::column_minima_i32;
+//!
+//! const COSTS: [[i32; 16]; 16] = {
+//!     let mut costs = [[0; 16]; 16];
+//!     let mut i = 0;
+//!     while i < 16 {
+//!         let mut j = 0;
+//!         while j < 16 {
+//!             let d = i as i32 - 2 * (j as i32) + 10;
+//!             costs[i][j] = d * d;
+//!             j += 1;
+//!         }
+//!         i += 1;
+//!     }
+//!     costs
+//! };
+//! const TABLE: [usize; 16] = column_minima_i32(&COSTS);
+//!
+//! assert_eq!(TABLE, [0, 0, 0, 0, 0, 0, 2, 4, 6, 8, 10, 12, 14, 15, 15, 15]);
+//! ```
 
+#[cfg(feature = "ndarray")]
 use ndarray::{Array2, ArrayView1};
 
 /// Compute lane minimum by brute force.
 ///
 /// This does a simple scan through the lane (row or column).
+#[cfg(feature = "ndarray")]
 #[inline]
 pub fn lane_minimum<T: Ord>(lane: ArrayView1<'_, T>) -> usize {
     lane.iter()
@@ -39,6 +71,7 @@ pub fn lane_minimum<T: Ord>(lane: ArrayView1<'_, T>) -> usize {
 /// # Panics
 ///
 /// It is an error to call this on a matrix with zero columns.
+#[cfg(feature = "ndarray")]
 pub fn row_minima<T: Ord>(matrix: &Array2<T>) -> Vec<usize> {
     matrix.rows().into_iter().map(lane_minimum).collect()
 }
@@ -62,11 +95,105 @@ pub fn row_minima<T: Ord>(matrix: &Array2<T>) -> Vec<usize> {
 /// # Panics
 ///
 /// It is an error to call this on a matrix with zero rows.
+#[cfg(feature = "ndarray")]
 pub fn column_minima<T: Ord>(matrix: &Array2<T>) -> Vec<usize> {
     matrix.columns().into_iter().map(lane_minimum).collect()
 }
 
-#[cfg(test)]
+macro_rules! const_minima {
+    ($($t:ty => $row_minima:ident, $column_minima:ident;)*) => {
+        $(
+            #[doc = concat!("Compute row minima of a fixed-size `", stringify!($t), "` matrix by")]
+            /// brute force in O(*mn*) time.
+            ///
+            /// This is like `row_minima`, but it is a `const fn` and can
+            /// run at compile time. Ties are broken in favor of the
+            /// smallest column.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            #[doc = concat!("const MATRIX: [[", stringify!($t), "; 4]; 3] = [[4, 2, 4, 3],")]
+            ///                                [5, 3, 5, 3],
+            ///                                [5, 3, 3, 1]];
+            #[doc = concat!("const MINIMA: [usize; 3] = smawk::brute_force::", stringify!($row_minima), "(&MATRIX);")]
+            /// assert_eq!(MINIMA, [1, 1, 3]);
+            /// ```
+            ///
+            /// # Panics
+            ///
+            /// It is an error to call this on a matrix with rows, but
+            /// zero columns.
+            pub const fn $row_minima<const M: usize, const N: usize>(
+                matrix: &[[$t; N]; M],
+            ) -> [usize; M] {
+                let mut minima = [0; M];
+                let mut i = 0;
+                while i < M {
+                    assert!(N > 0, "empty lane in matrix");
+                    let mut j = 1;
+                    while j < N {
+                        if matrix[i][j] < matrix[i][minima[i]] {
+                            minima[i] = j;
+                        }
+                        j += 1;
+                    }
+                    i += 1;
+                }
+                minima
+            }
+
+            #[doc = concat!("Compute column minima of a fixed-size `", stringify!($t), "` matrix by")]
+            /// brute force in O(*mn*) time.
+            ///
+            /// This is like `column_minima`, but it is a `const fn` and
+            /// can run at compile time. Ties are broken in favor of the
+            /// smallest row.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            #[doc = concat!("const MATRIX: [[", stringify!($t), "; 4]; 3] = [[4, 2, 4, 3],")]
+            ///                                [5, 3, 5, 3],
+            ///                                [5, 3, 3, 1]];
+            #[doc = concat!("const MINIMA: [usize; 4] = smawk::brute_force::", stringify!($column_minima), "(&MATRIX);")]
+            /// assert_eq!(MINIMA, [0, 0, 2, 2]);
+            /// ```
+            ///
+            /// # Panics
+            ///
+            /// It is an error to call this on a matrix with columns, but
+            /// zero rows.
+            pub const fn $column_minima<const M: usize, const N: usize>(
+                matrix: &[[$t; N]; M],
+            ) -> [usize; N] {
+                let mut minima = [0; N];
+                let mut j = 0;
+                while j < N {
+                    assert!(M > 0, "empty lane in matrix");
+                    let mut i = 1;
+                    while i < M {
+                        if matrix[i][j] < matrix[minima[j]][j] {
+                            minima[j] = i;
+                        }
+                        i += 1;
+                    }
+                    j += 1;
+                }
+                minima
+            }
+        )*
+    };
+}
+
+const_minima! {
+    i32 => row_minima_i32, column_minima_i32;
+    i64 => row_minima_i64, column_minima_i64;
+    u32 => row_minima_u32, column_minima_u32;
+    u64 => row_minima_u64, column_minima_u64;
+}
+
+#[cfg(all(test, feature = "ndarray"))]
 mod tests {
     use super::*;
     use ndarray::arr2;
@@ -147,4 +274,65 @@ mod tests {
         assert_eq!(row_minima(&matrix), minima);
         assert_eq!(column_minima(&matrix.reversed_axes()), minima);
     }
+
+    #[test]
+    fn const_minima_in_const_context() {
+        const MATRIX: [[i32; 5]; 5] = [
+            [3, 2, 4, 5, 6],
+            [2, 1, 3, 3, 4],
+            [2, 1, 3, 3, 4],
+            [3, 2, 4, 3, 4],
+            [4, 3, 2, 1, 1],
+        ];
+        const ROW_MINIMA: [usize; 5] = row_minima_i32(&MATRIX);
+        const COLUMN_MINIMA: [usize; 5] = column_minima_i32(&MATRIX);
+        assert_eq!(ROW_MINIMA.to_vec(), row_minima(&arr2(&MATRIX)));
+        assert_eq!(COLUMN_MINIMA.to_vec(), column_minima(&arr2(&MATRIX)));
+
+        const WIDE: [[u64; 3]; 2] = [[u64::MAX, 0, 0], [1, u64::MAX, 0]];
+        const WIDE_ROW_MINIMA: [usize; 2] = row_minima_u64(&WIDE);
+        const WIDE_COLUMN_MINIMA: [usize; 3] = column_minima_u64(&WIDE);
+        assert_eq!(WIDE_ROW_MINIMA, [1, 2]);
+        assert_eq!(WIDE_COLUMN_MINIMA, [1, 0, 0]);
+
+        const EMPTY: [[i64; 0]; 0] = [];
+        const EMPTY_ROW_MINIMA: [usize; 0] = row_minima_i64(&EMPTY);
+        assert_eq!(EMPTY_ROW_MINIMA, []);
+    }
+
+    #[test]
+    fn const_minima_agree_with_runtime() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            // Few distinct values give many ties.
+            let mut matrix = [[0i64; 16]; 16];
+            for row in matrix.iter_mut() {
+                for value in row.iter_mut() {
+                    *value = rng.gen_range(-3..3);
+                }
+            }
+            let expected_rows = row_minima(&arr2(&matrix));
+            let expected_columns = column_minima(&arr2(&matrix));
+
+            assert_eq!(row_minima_i64(&matrix).to_vec(), expected_rows);
+            assert_eq!(column_minima_i64(&matrix).to_vec(), expected_columns);
+            let matrix = matrix.map(|row| row.map(|value| value as i32));
+            assert_eq!(row_minima_i32(&matrix).to_vec(), expected_rows);
+            assert_eq!(column_minima_i32(&matrix).to_vec(), expected_columns);
+            let matrix = matrix.map(|row| row.map(|value| (value + 3) as u32));
+            assert_eq!(row_minima_u32(&matrix).to_vec(), expected_rows);
+            assert_eq!(column_minima_u32(&matrix).to_vec(), expected_columns);
+            let matrix = matrix.map(|row| row.map(u64::from));
+            assert_eq!(row_minima_u64(&matrix).to_vec(), expected_rows);
+            assert_eq!(column_minima_u64(&matrix).to_vec(), expected_columns);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "empty lane in matrix")]
+    fn const_row_minima_no_columns() {
+        row_minima_u32(&[[0u32; 0]; 2]);
+    }
 }
@@ -0,0 +1,510 @@
+//! Minima computations which can be cancelled while they run.
+//!
+//! Evaluating a matrix element can be expensive, for example when it
+//! involves network I/O. The functions here compute the same minima
+//! as the functions in the crate root, but they give up when a
+//! [`CancellationToken`] is cancelled, typically from another thread.
+//!
+//! The token is checked before the computation starts and then once
+//! every [`CHECK_INTERVAL`] evaluations. A check is a single atomic
+//! load, so it is cheap compared to evaluating an element. When the
+//! token is found to be cancelled, no further elements are evaluated
+//! and the function returns `Err(Cancelled)` without partial results.
+//! A cancelled computation thus evaluates at most `CHECK_INTERVAL`
+//! elements after the token is cancelled.
+//!
+//! [`column_minima_indexed`] instead checks the token once on every
+//! level of the SMAWK recursion, which never slows down the
+//! evaluation of an element.
+
+use crate::Matrix;
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Number of element evaluations between two checks of the
+/// cancellation token.
+pub const CHECK_INTERVAL: usize = 32;
+
+/// Flag used to request cancellation of a running computation.
+///
+/// The token is shared by reference between the computation and the
+/// code which cancels it. Once cancelled, a token stays cancelled.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::cancel::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// assert!(!token.is_cancelled());
+/// token.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Default)]
+pub struct CancellationToken {
+    cancelled: AtomicBool,
+}
+
+impl CancellationToken {
+    /// Create a token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of all computations using the token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Return `true` if cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Error returned when a computation is cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "computation was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Evaluate elements while checking a cancellation token.
+///
+/// After cancellation, the last value is returned instead of
+/// evaluating more elements. The algorithms only use the values to
+/// choose between rows, so they run to completion on these values
+/// without evaluating anything, after which the result is discarded.
+struct Checked<'a, T> {
+    token: &'a CancellationToken,
+    evaluations: Cell<usize>,
+    cancelled: Cell<bool>,
+    last: Cell<Option<T>>,
+}
+
+impl<'a, T: Copy> Checked<'a, T> {
+    fn new(token: &'a CancellationToken) -> Self {
+        Checked {
+            token,
+            evaluations: Cell::new(0),
+            cancelled: Cell::new(false),
+            last: Cell::new(None),
+        }
+    }
+
+    fn eval<F: FnOnce() -> T>(&self, f: F) -> T {
+        let evaluations = self.evaluations.get();
+        if evaluations.is_multiple_of(CHECK_INTERVAL) && self.token.is_cancelled() {
+            self.cancelled.set(true);
+        }
+        match self.last.get() {
+            Some(value) if self.cancelled.get() => value,
+            _ => {
+                let value = f();
+                self.evaluations.set(evaluations + 1);
+                self.last.set(Some(value));
+                value
+            }
+        }
+    }
+
+    fn finish<R>(&self, result: R) -> Result<R, Cancelled> {
+        if self.cancelled.get() {
+            Err(Cancelled)
+        } else {
+            Ok(result)
+        }
+    }
+}
+
+/// Adapter which evaluates the elements of a matrix through
+/// [`Checked`].
+struct CheckedMatrix<'a, T, M> {
+    matrix: &'a M,
+    checked: Checked<'a, T>,
+}
+
+impl<T: Copy, M: Matrix<T>> Matrix<T> for CheckedMatrix<'_, T, M> {
+    fn nrows(&self) -> usize {
+        self.matrix.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.matrix.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> T {
+        self.checked.eval(|| self.matrix.index(row, column))
+    }
+}
+
+/// Compute row minima unless the computation is cancelled.
+///
+/// This computes the same minima as [`crate::row_minima`], but
+/// returns `Err(Cancelled)` if `token` is cancelled before the
+/// computation is done. See the [module documentation](self) for
+/// how often the token is checked.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::cancel::{row_minima, CancellationToken, Cancelled};
+/// use smawk::Matrix;
+///
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let token = CancellationToken::new();
+/// assert_eq!(row_minima(&matrix, &token), Ok(vec![1, 1, 3]));
+/// token.cancel();
+/// assert_eq!(row_minima(&matrix, &token), Err(Cancelled));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero columns.
+pub fn row_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    token: &CancellationToken,
+) -> Result<Vec<usize>, Cancelled> {
+    if token.is_cancelled() {
+        return Err(Cancelled);
+    }
+    let matrix = CheckedMatrix {
+        matrix,
+        checked: Checked::new(token),
+    };
+    let minima = crate::row_minima(&matrix);
+    matrix.checked.finish(minima)
+}
+
+/// Compute column minima unless the computation is cancelled.
+///
+/// This computes the same minima as [`crate::column_minima`], but
+/// returns `Err(Cancelled)` if `token` is cancelled before the
+/// computation is done. See the [module documentation](self) for
+/// how often the token is checked.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::cancel::{column_minima, CancellationToken, Cancelled};
+/// use smawk::Matrix;
+///
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let token = CancellationToken::new();
+/// assert_eq!(column_minima(&matrix, &token), Ok(vec![0, 0, 2, 2]));
+/// token.cancel();
+/// assert_eq!(column_minima(&matrix, &token), Err(Cancelled));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    token: &CancellationToken,
+) -> Result<Vec<usize>, Cancelled> {
+    if token.is_cancelled() {
+        return Err(Cancelled);
+    }
+    let matrix = CheckedMatrix {
+        matrix,
+        checked: Checked::new(token),
+    };
+    let minima = crate::column_minima(&matrix);
+    matrix.checked.finish(minima)
+}
+
+/// Compute upper-right column minima unless the computation is
+/// cancelled.
+///
+/// This computes the same minima as
+/// [`crate::online_column_minima`], but returns `Err(Cancelled)` if
+/// `token` is cancelled before the computation is done. See the
+/// [module documentation](self) for how often the token is checked.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::cancel::{online_column_minima, CancellationToken};
+///
+/// let token = CancellationToken::new();
+/// let minima = online_column_minima(0, 4, |_, i, j| (j - i) * (j - i), &token);
+/// assert_eq!(minima, Ok(vec![(0, 0), (0, 1), (1, 1), (2, 1)]));
+/// ```
+pub fn online_column_minima<T, M>(
+    initial: T,
+    size: usize,
+    matrix: M,
+    token: &CancellationToken,
+) -> Result<Vec<(usize, T)>, Cancelled>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> T,
+{
+    if token.is_cancelled() {
+        return Err(Cancelled);
+    }
+    let checked = Checked::new(token);
+    let minima = crate::online_column_minima(initial, size, |result, i, j| {
+        checked.eval(|| matrix(result, i, j))
+    });
+    checked.finish(minima)
+}
+
+/// Compute column minima of a submatrix unless the computation is
+/// cancelled, checking the token on every level of the recursion.
+///
+/// The submatrix consists of the given `rows` and `cols` of `matrix`,
+/// which must be totally monotone. For every entry of `cols`, the
+/// function returns the entry of `rows` holding its minimum. Ties are
+/// broken in favor of the earliest row in `rows`.
+///
+/// Unlike [`column_minima`], the elements are not evaluated through a
+/// wrapper. The token is checked when a level of the recursion starts
+/// and again before the level computes its even columns on the way
+/// back up. That is about 2 log₂ *n* checks for *n* columns. A level
+/// evaluates O(*m* + *n*) elements between two checks, where *m* and
+/// *n* are the sizes of that level, so a cancellation takes effect
+/// within a constant number of passes over the rows and columns of
+/// the current level. Returns `Err(Cancelled)` without evaluating any
+/// element if the token is already cancelled.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::cancel::{column_minima_indexed, CancellationToken, Cancelled};
+///
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let token = CancellationToken::new();
+/// let minima = column_minima_indexed(&|i, j| matrix[i][j], &[1, 2], &[0, 2, 3], &token);
+/// assert_eq!(minima, Ok(vec![1, 2, 2]));
+/// token.cancel();
+/// let minima = column_minima_indexed(&|i, j| matrix[i][j], &[1, 2], &[0, 2, 3], &token);
+/// assert_eq!(minima, Err(Cancelled));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with no `rows` and a non-empty list of
+/// `cols`.
+pub fn column_minima_indexed<T, M>(
+    matrix: &M,
+    rows: &[usize],
+    cols: &[usize],
+    token: &CancellationToken,
+) -> Result<Vec<usize>, Cancelled>
+where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+{
+    if token.is_cancelled() {
+        return Err(Cancelled);
+    }
+    assert!(
+        !rows.is_empty() || cols.is_empty(),
+        "no rows to hold the minima"
+    );
+    // The recursion works with positions in `rows` and `cols`.
+    let positions = |r: usize, c: usize| matrix(rows[r], cols[c]);
+    let mut minima = vec![0; cols.len()];
+    checked_levels(
+        &positions,
+        &(0..rows.len()).collect::<Vec<_>>(),
+        &(0..cols.len()).collect::<Vec<_>>(),
+        &mut minima,
+        token,
+    )?;
+    Ok(minima.into_iter().map(|r| rows[r]).collect())
+}
+
+/// SMAWK which checks `token` when a level starts and before it
+/// computes its even columns.
+fn checked_levels<T, M>(
+    matrix: &M,
+    rows: &[usize],
+    cols: &[usize],
+    minima: &mut [usize],
+    token: &CancellationToken,
+) -> Result<(), Cancelled>
+where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+{
+    if token.is_cancelled() {
+        return Err(Cancelled);
+    }
+    if cols.is_empty() {
+        return Ok(());
+    }
+
+    let mut stack = vec![0; rows.len().min(cols.len())];
+    let len = crate::smawk_reduce(matrix, rows, cols, &mut stack, &mut Vec::new());
+    let rows = &stack[..len];
+    let odd_cols = cols.iter().skip(1).step_by(2).copied().collect::<Vec<_>>();
+    checked_levels(matrix, rows, &odd_cols, minima, token)?;
+
+    if token.is_cancelled() {
+        return Err(Cancelled);
+    }
+    let mut r = 0;
+    for (c, &col) in cols.iter().enumerate().step_by(2) {
+        let last_row = if c == cols.len() - 1 {
+            rows[rows.len() - 1]
+        } else {
+            minima[cols[c + 1]]
+        };
+        let mut pair = (matrix(rows[r], col), rows[r]);
+        while rows[r] != last_row {
+            r += 1;
+            let row = rows[r];
+            let value = matrix(row, col);
+            if (value, row) < pair {
+                pair = (value, row);
+            }
+        }
+        minima[col] = pair.1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::distance_matrix;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    /// Monge matrix whose elements are slow to evaluate. It counts
+    /// the evaluations made after the token is cancelled.
+    struct Slow<'a> {
+        size: usize,
+        token: &'a CancellationToken,
+        after_cancel: AtomicUsize,
+    }
+
+    impl Matrix<i64> for Slow<'_> {
+        fn nrows(&self) -> usize {
+            self.size
+        }
+        fn ncols(&self) -> usize {
+            self.size
+        }
+        fn index(&self, row: usize, column: usize) -> i64 {
+            if self.token.is_cancelled() {
+                self.after_cancel.fetch_add(1, Ordering::Relaxed);
+            }
+            std::thread::sleep(Duration::from_micros(100));
+            (row as i64 - column as i64).pow(2)
+        }
+    }
+
+    #[test]
+    fn not_cancelled() {
+        let token = CancellationToken::new();
+        let matrix = vec![vec![3, 2, 4], vec![2, 1, 3], vec![5, 3, 2]];
+        assert_eq!(
+            column_minima(&matrix, &token),
+            Ok(crate::column_minima(&matrix))
+        );
+        assert_eq!(row_minima(&matrix, &token), Ok(crate::row_minima(&matrix)));
+    }
+
+    #[test]
+    fn cancelled_before_start() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let minima = online_column_minima(0, 100, |_, _, _| -> i32 { unreachable!() }, &token);
+        assert_eq!(minima, Err(Cancelled));
+    }
+
+    #[test]
+    fn column_minima_cancelled_from_other_thread() {
+        let token = CancellationToken::new();
+        let matrix = Slow {
+            size: 5000,
+            token: &token,
+            after_cancel: AtomicUsize::new(0),
+        };
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                token.cancel();
+            });
+            column_minima(&matrix, &token)
+        });
+        assert_eq!(result, Err(Cancelled));
+        assert!(matrix.after_cancel.load(Ordering::Relaxed) <= CHECK_INTERVAL);
+    }
+
+    #[test]
+    fn online_column_minima_cancelled_from_other_thread() {
+        let token = CancellationToken::new();
+        let after_cancel = AtomicUsize::new(0);
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                token.cancel();
+            });
+            online_column_minima(
+                0,
+                5000,
+                |result: &[(usize, i64)], i, j| {
+                    if token.is_cancelled() {
+                        after_cancel.fetch_add(1, Ordering::Relaxed);
+                    }
+                    std::thread::sleep(Duration::from_micros(100));
+                    result[i].1 + (j - i).pow(2) as i64
+                },
+                &token,
+            )
+        });
+        assert_eq!(result, Err(Cancelled));
+        assert!(after_cancel.load(Ordering::Relaxed) <= CHECK_INTERVAL);
+    }
+
+    #[test]
+    fn column_minima_indexed_cancelled_before_start() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let rows = (0..1000).collect::<Vec<_>>();
+        let minima = column_minima_indexed(&|_, _| -> i32 { unreachable!() }, &rows, &rows, &token);
+        assert_eq!(minima, Err(Cancelled));
+    }
+
+    #[test]
+    fn column_minima_indexed_not_cancelled() {
+        use rand::{Rng, SeedableRng};
+
+        let token = CancellationToken::new();
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (m, n) = (rng.gen_range(1..60), rng.gen_range(0..60));
+            let matrix = distance_matrix(m, n, 50, &mut rng);
+
+            // Every other column of a random subset of the rows.
+            let rows = (0..m).filter(|_| rng.gen_bool(0.7)).collect::<Vec<_>>();
+            let cols = (0..n).step_by(2).collect::<Vec<_>>();
+            if rows.is_empty() {
+                continue;
+            }
+            let expected = cols
+                .iter()
+                .map(|&j| *rows.iter().min_by_key(|&&i| (matrix[i][j], i)).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                column_minima_indexed(&|i, j| matrix[i][j], &rows, &cols, &token),
+                Ok(expected)
+            );
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! Column minima with a certificate which can be checked
+//! independently.
+//!
+//! Checking that a matrix is totally monotone takes far longer than
+//! computing its minima with SMAWK. Once the matrix is known to be
+//! totally monotone, for example from how it is constructed, the
+//! minima can however be checked in O(*m* + *n*) time from a
+//! [`Certificate`] without trusting the implementation of SMAWK.
+//!
+//! The certificate holds the row and value of the minimum of every
+//! column. [`verify`] checks that the rows are non-decreasing, and
+//! compares the minimum of every column `j` with the rows from the
+//! minimum of column `j - 1` to the minimum of column `j + 1`. A row
+//! above the minimum must be strictly larger, and a row below must
+//! not be smaller.
+//!
+//! These comparisons prove that the minima are correct. Let `r[j]`
+//! denote the minimum of column `j`, and consider a row `i` above
+//! `r[j - 1]`. By induction from the left, `M[i, j - 1] > M[r[j - 1],
+//! j - 1]`. Total monotonicity, applied to the 2 ✕ 2 submatrix on
+//! rows `i` and `r[j - 1]` and columns `j - 1` and `j`, then gives
+//! `M[i, j] > M[r[j - 1], j]`, which is at least `M[r[j], j]` by the
+//! comparisons. The rows below `r[j + 1]` are handled by induction
+//! from the right in the same way. Total monotonicity is thus only
+//! used on 2 ✕ 2 submatrices spanning adjacent columns.
+
+use crate::Matrix;
+use std::cmp::Ordering::Greater;
+use std::fmt;
+
+/// Certificate for the column minima of a totally monotone matrix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate<T> {
+    minima: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T> Certificate<T> {
+    /// Create a certificate from the row and value of the minimum of
+    /// every column.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to pass slices of different lengths.
+    pub fn new(minima: Vec<usize>, values: Vec<T>) -> Self {
+        assert_eq!(minima.len(), values.len(), "expected a value per minimum");
+        Certificate { minima, values }
+    }
+
+    /// Return the row of the minimum of every column.
+    pub fn minima(&self) -> &[usize] {
+        &self.minima
+    }
+
+    /// Return the minimum value of every column.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+}
+
+/// Reason why [`verify`] rejected a certificate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertificateError {
+    /// The certificate does not have one minimum per column.
+    WrongLength,
+    /// The minimum of `column` is out of bounds.
+    OutOfBounds {
+        /// Column with the bad minimum.
+        column: usize,
+    },
+    /// The minimum of `column` lies below the minimum of `column + 1`.
+    NotMonotone {
+        /// Column whose minimum is too large.
+        column: usize,
+    },
+    /// The recorded value of `column` differs from the matrix.
+    WrongValue {
+        /// Column with the wrong value.
+        column: usize,
+    },
+    /// The element at `row` is smaller than the minimum of `column`,
+    /// or equal to it and above it.
+    Beaten {
+        /// Row which beats the minimum.
+        row: usize,
+        /// Column of the minimum.
+        column: usize,
+    },
+}
+
+impl fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CertificateError::WrongLength => write!(f, "certificate has the wrong length"),
+            CertificateError::OutOfBounds { column } => {
+                write!(f, "minimum of column {} is out of bounds", column)
+            }
+            CertificateError::NotMonotone { column } => write!(
+                f,
+                "minima of columns {} and {} are not monotone",
+                column,
+                column + 1
+            ),
+            CertificateError::WrongValue { column } => {
+                write!(f, "wrong value recorded for column {}", column)
+            }
+            CertificateError::Beaten { row, column } => {
+                write!(f, "row {} beats the minimum of column {}", row, column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CertificateError {}
+
+/// Compute column minima together with a certificate.
+///
+/// The minima are the same as for [`crate::column_minima`], and the
+/// matrix must be totally monotone. The certificate can be checked
+/// with [`verify`].
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::certificate::{column_minima, verify};
+/// use smawk::Matrix;
+///
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let (minima, certificate) = column_minima(&matrix);
+/// assert_eq!(minima, vec![0, 0, 2, 2]);
+/// assert_eq!(certificate.values(), &[4, 2, 3, 1]);
+/// assert_eq!(verify(&matrix, &certificate), Ok(()));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> (Vec<usize>, Certificate<T>) {
+    let minima = crate::column_minima(matrix);
+    let values = minima
+        .iter()
+        .enumerate()
+        .map(|(col, &row)| matrix.index(row, col))
+        .collect();
+    (minima.clone(), Certificate::new(minima, values))
+}
+
+/// Check a certificate for the column minima of a totally monotone
+/// matrix.
+///
+/// A certificate which passes proves that its minima are the column
+/// minima of the matrix, with ties broken in favor of the smallest
+/// row, provided that the matrix is totally monotone. See the
+/// [module documentation](self) for the argument. The matrix is not
+/// checked for total monotonicity.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::certificate::{verify, Certificate, CertificateError};
+/// use smawk::Matrix;
+///
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let forged = Certificate::new(vec![0, 1, 2, 2], vec![4, 3, 3, 1]);
+/// assert_eq!(verify(&matrix, &forged),
+///            Err(CertificateError::Beaten { row: 0, column: 1 }));
+/// ```
+pub fn verify<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    certificate: &Certificate<T>,
+) -> Result<(), CertificateError> {
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    let minima = &certificate.minima;
+    if minima.len() != n {
+        return Err(CertificateError::WrongLength);
+    }
+    if let Some(column) = minima.iter().position(|&row| row >= m) {
+        return Err(CertificateError::OutOfBounds { column });
+    }
+    if let Some(column) = minima.windows(2).position(|w| w[0] > w[1]) {
+        return Err(CertificateError::NotMonotone { column });
+    }
+
+    for (column, &minimum) in minima.iter().enumerate() {
+        let value = matrix.index(minimum, column);
+        if value != certificate.values[column] {
+            return Err(CertificateError::WrongValue { column });
+        }
+        let first = if column > 0 { minima[column - 1] } else { 0 };
+        let last = minima.get(column + 1).map_or(m - 1, |&row| row);
+        for row in first..=last {
+            let other = matrix.index(row, column);
+            let beaten = if row < minimum {
+                other.partial_cmp(&value) != Some(Greater)
+            } else {
+                other < value
+            };
+            if beaten {
+                return Err(CertificateError::Beaten { row, column });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::distance_matrix;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn genuine_certificates_pass() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..500 {
+            let (m, n) = (rng.gen_range(1..30), rng.gen_range(0..30));
+            let matrix = distance_matrix(m, n, 20, &mut rng);
+            let (minima, certificate) = column_minima(&matrix);
+            assert_eq!(minima, crate::column_minima(&matrix));
+            assert_eq!(verify(&matrix, &certificate), Ok(()));
+        }
+    }
+
+    #[test]
+    fn tampered_minima_fail() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for _ in 0..200 {
+            let (m, n) = (rng.gen_range(2..30), rng.gen_range(1..30));
+            let matrix = distance_matrix(m, n, 20, &mut rng);
+            let (minima, certificate) = column_minima(&matrix);
+            let column = rng.gen_range(0..n);
+            for row in (0..m).filter(|&row| row != minima[column]) {
+                let mut tampered = certificate.clone();
+                tampered.minima[column] = row;
+                // Also record the matching value, so only the
+                // position of the minimum is wrong.
+                tampered.values[column] = matrix[row][column];
+                assert!(verify(&matrix, &tampered).is_err(), "{:?}", tampered);
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_values_fail() {
+        let matrix = vec![vec![1, 2], vec![2, 1]];
+        let certificate = Certificate::new(vec![0, 1], vec![1, 0]);
+        assert_eq!(
+            verify(&matrix, &certificate),
+            Err(CertificateError::WrongValue { column: 1 })
+        );
+    }
+
+    #[test]
+    fn malformed_certificates_fail() {
+        let matrix = vec![vec![1, 2], vec![2, 1]];
+        assert_eq!(
+            verify(&matrix, &Certificate::new(vec![0], vec![1])),
+            Err(CertificateError::WrongLength)
+        );
+        assert_eq!(
+            verify(&matrix, &Certificate::new(vec![0, 2], vec![1, 1])),
+            Err(CertificateError::OutOfBounds { column: 1 })
+        );
+        assert_eq!(
+            verify(&matrix, &Certificate::new(vec![1, 0], vec![2, 2])),
+            Err(CertificateError::NotMonotone { column: 0 })
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Column minima of cost tables stored in Polars DataFrames.
+//!
+//! A [`DataFrame`] with one row per state and one column per decision
+//! is a matrix: element `M[i, j]` is row `i` of column `j`. The
+//! functions here read the columns in place through a matrix adapter,
+//! without copying them into an `ndarray` first. A column can consist
+//! of several chunks, such as after [`DataFrame::vstack`], and each
+//! element is looked up in the chunk which holds it.
+//!
+//! All columns must have the same dtype, which must be one of
+//! `Int32`, `Int64`, `UInt32`, `UInt64`, `Float32` or `Float64`. Null
+//! values are not supported and give an error.
+//!
+//! **Note: this module is only available if you enable the `polars`
+//! Cargo feature.**
+
+use crate::Matrix;
+use polars::prelude::*;
+
+/// Matrix adapter for the columns of a DataFrame.
+///
+/// Every column is stored as a list of chunks, together with the
+/// first row of each chunk.
+struct Columns<'a, N> {
+    nrows: usize,
+    columns: Vec<(Vec<&'a [N]>, Vec<usize>)>,
+}
+
+impl<'a, N: NumericNative> Columns<'a, N> {
+    fn new<T>(df: &'a DataFrame) -> PolarsResult<Self>
+    where
+        T: PolarsNumericType<Native = N>,
+    {
+        let mut columns = Vec::with_capacity(df.width());
+        for column in df.get_columns() {
+            let series = column.as_materialized_series();
+            polars_ensure!(
+                series.null_count() == 0,
+                ComputeError: "column {} contains null values", series.name()
+            );
+            let mut chunks = Vec::new();
+            let mut starts = Vec::new();
+            let mut start = 0;
+            for array in series.unpack::<T>()?.downcast_iter() {
+                chunks.push(&array.values()[..]);
+                starts.push(start);
+                start += array.len();
+            }
+            columns.push((chunks, starts));
+        }
+        Ok(Columns {
+            nrows: df.height(),
+            columns,
+        })
+    }
+}
+
+impl<N: Copy> Matrix<N> for Columns<'_, N> {
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+    fn ncols(&self) -> usize {
+        self.columns.len()
+    }
+    fn index(&self, row: usize, column: usize) -> N {
+        let (chunks, starts) = &self.columns[column];
+        if let [chunk] = &chunks[..] {
+            return chunk[row];
+        }
+        let k = starts.partition_point(|&start| start <= row) - 1;
+        chunks[k][row - starts[k]]
+    }
+}
+
+/// Return the dtype shared by all columns of `df`.
+fn common_dtype(df: &DataFrame) -> PolarsResult<Option<&DataType>> {
+    let mut columns = df.get_columns().iter();
+    let Some(first) = columns.next() else {
+        return Ok(None);
+    };
+    for column in columns {
+        polars_ensure!(
+            column.dtype() == first.dtype(),
+            SchemaMismatch: "column {} has dtype {}, but column {} has dtype {}",
+            column.name(), column.dtype(), first.name(), first.dtype()
+        );
+    }
+    Ok(Some(first.dtype()))
+}
+
+/// Compute the column minima of a DataFrame.
+///
+/// The DataFrame must be totally monotone when seen as a matrix with
+/// one row per DataFrame row. The result is a `UInt64` Series named
+/// `"row"` with the row of the minimum in every column. Ties are
+/// broken in favor of the smallest row, just like for
+/// [`column_minima`](crate::column_minima).
+///
+/// An error is returned if the columns have different or unsupported
+/// dtypes, if they contain null values, or if the DataFrame has
+/// columns but no rows.
+///
+/// # Examples
+///
+/// ```
+/// use polars::prelude::*;
+/// use smawk::dataframe::column_minima_df;
+///
+/// let df = df!("a" => [3, 2, 2],
+///              "b" => [2, 1, 1],
+///              "c" => [4, 3, 2]).unwrap();
+/// let minima = column_minima_df(&df).unwrap();
+/// let rows = minima.u64().unwrap().into_no_null_iter().collect::<Vec<_>>();
+/// assert_eq!(rows, vec![1, 1, 2]);
+/// ```
+pub fn column_minima_df(df: &DataFrame) -> PolarsResult<Series> {
+    polars_ensure!(
+        df.width() == 0 || df.height() > 0,
+        ComputeError: "cannot compute column minima of a DataFrame without rows"
+    );
+    let minima = match common_dtype(df)? {
+        None => Vec::new(),
+        Some(DataType::Int32) => crate::column_minima(&Columns::new::<Int32Type>(df)?),
+        Some(DataType::Int64) => crate::column_minima(&Columns::new::<Int64Type>(df)?),
+        Some(DataType::UInt32) => crate::column_minima(&Columns::new::<UInt32Type>(df)?),
+        Some(DataType::UInt64) => crate::column_minima(&Columns::new::<UInt64Type>(df)?),
+        Some(DataType::Float32) => crate::column_minima(&Columns::new::<Float32Type>(df)?),
+        Some(DataType::Float64) => crate::column_minima(&Columns::new::<Float64Type>(df)?),
+        Some(dtype) => polars_bail!(InvalidOperation: "unsupported dtype {}", dtype),
+    };
+    let minima = minima.into_iter().map(|row| row as u64).collect::<Vec<_>>();
+    Ok(Series::new("row".into(), minima))
+}
+
+/// Verify that a DataFrame is a Monge matrix.
+///
+/// This is [`is_monge`](crate::monge::is_monge) for a DataFrame seen
+/// as a matrix. The check needs exact arithmetic, so only integer
+/// dtypes are supported. An error is returned if the columns have
+/// different, floating point or unsupported dtypes, or if they contain
+/// null values.
+///
+/// # Examples
+///
+/// ```
+/// use polars::prelude::*;
+/// use smawk::dataframe::is_monge_df;
+///
+/// let df = df!("a" => [3, 2, 2],
+///              "b" => [2, 1, 1],
+///              "c" => [4, 3, 2]).unwrap();
+/// assert_eq!(is_monge_df(&df).unwrap(), true);
+/// ```
+pub fn is_monge_df(df: &DataFrame) -> PolarsResult<bool> {
+    use crate::monge::is_monge;
+    Ok(match common_dtype(df)? {
+        None => true,
+        Some(DataType::Int32) => is_monge(&Columns::new::<Int32Type>(df)?),
+        Some(DataType::Int64) => is_monge(&Columns::new::<Int64Type>(df)?),
+        Some(DataType::UInt32) => is_monge(&Columns::new::<UInt32Type>(df)?),
+        Some(DataType::UInt64) => is_monge(&Columns::new::<UInt64Type>(df)?),
+        Some(dtype) => polars_bail!(InvalidOperation: "unsupported dtype {}", dtype),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(minima: &Series) -> Vec<u64> {
+        minima.u64().unwrap().into_no_null_iter().collect()
+    }
+
+    #[test]
+    fn matches_column_minima() {
+        let matrix = [
+            vec![3, 2, 4, 5, 6],
+            vec![2, 1, 3, 3, 4],
+            vec![2, 1, 3, 3, 4],
+            vec![3, 2, 4, 3, 4],
+            vec![4, 3, 2, 1, 1],
+        ];
+        let df = DataFrame::new(
+            (0..5)
+                .map(|j| {
+                    let values = matrix.iter().map(|row| row[j] as f64).collect::<Vec<_>>();
+                    Column::new(format!("c{}", j).into(), values)
+                })
+                .collect(),
+        )
+        .unwrap();
+        let minima = column_minima_df(&df).unwrap();
+        assert_eq!(minima.dtype(), &DataType::UInt64);
+        assert_eq!(rows(&minima), vec![1, 1, 4, 4, 4]);
+    }
+
+    #[test]
+    fn multiple_chunks() {
+        let top = df!("a" => [4i64, 3, 3], "b" => [5i64, 4, 3]).unwrap();
+        let bottom = df!("a" => [2i64, 5], "b" => [2i64, 1]).unwrap();
+        let df = top.vstack(&bottom).unwrap();
+        let column = df.column("a").unwrap().as_materialized_series();
+        assert_eq!(column.n_chunks(), 2);
+
+        assert_eq!(rows(&column_minima_df(&df).unwrap()), vec![3, 4]);
+        assert!(is_monge_df(&df).unwrap());
+
+        let bottom = df!("a" => [2i64, 1], "b" => [2i64, 5]).unwrap();
+        assert!(!is_monge_df(&top.vstack(&bottom).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn negative_values() {
+        let df = df!("a" => [0i64, -5], "b" => [-5i64, 0]).unwrap();
+        assert!(!is_monge_df(&df).unwrap());
+        let df = df!("a" => [-5i32, 0], "b" => [0i32, -5]).unwrap();
+        assert!(is_monge_df(&df).unwrap());
+        let df = df!("a" => [i64::MIN, i64::MAX], "b" => [i64::MAX, i64::MIN]).unwrap();
+        assert!(is_monge_df(&df).unwrap());
+    }
+
+    #[test]
+    fn empty() {
+        let minima = column_minima_df(&DataFrame::empty()).unwrap();
+        assert_eq!(minima.len(), 0);
+        assert!(is_monge_df(&DataFrame::empty()).unwrap());
+
+        let df = df!("a" => Vec::<i32>::new()).unwrap();
+        assert!(column_minima_df(&df).is_err());
+    }
+
+    #[test]
+    fn mixed_dtypes() {
+        let df = df!("a" => [1i32, 2], "b" => [1i64, 2]).unwrap();
+        assert!(column_minima_df(&df).is_err());
+        assert!(is_monge_df(&df).is_err());
+    }
+
+    #[test]
+    fn unsupported_dtypes() {
+        let df = df!("a" => ["x", "y"]).unwrap();
+        assert!(column_minima_df(&df).is_err());
+        let df = df!("a" => [1.0, 2.0], "b" => [1.0, 2.0]).unwrap();
+        assert!(is_monge_df(&df).is_err());
+    }
+
+    #[test]
+    fn null_values() {
+        let df = df!("a" => [Some(1), None], "b" => [Some(1), Some(2)]).unwrap();
+        assert!(column_minima_df(&df).is_err());
+        assert!(is_monge_df(&df).is_err());
+    }
+}
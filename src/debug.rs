@@ -0,0 +1,436 @@
+//! Matrix adapter which checks how an algorithm accesses a matrix.
+//!
+//! The closure-based functions in this crate take elements as
+//! `(row, column)` pairs, and so do the closures they call. Swapping
+//! the two indices somewhere gives plausible minima which are simply
+//! wrong. Wrapping the closure in a [`DebugMatrix`] turns such a
+//! mistake into a panic: every access is checked against the declared
+//! size of the matrix, and optionally against the [`AccessOrder`]
+//! which the algorithm guarantees. The accesses are recorded, so they
+//! can be inspected afterwards, for example as a
+//! [`heatmap`](DebugMatrix::heatmap).
+//!
+//! Closures for the online algorithms can be validated on a small
+//! prefix with [`validate_online_closure`], before they are used on a
+//! large problem.
+//!
+//! **Note: this module is only available if you enable the
+//! `test-util` Cargo feature.**
+
+use crate::Matrix;
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering::{Equal, Greater};
+use std::fmt;
+
+/// Order in which an algorithm promises to access a matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessOrder {
+    /// Every element is above the main diagonal, that is, its row is
+    /// smaller than its column.
+    AboveDiagonal,
+    /// Every element is above the main diagonal, and its column comes
+    /// after every row accessed before it. This is how the online
+    /// algorithms, such as [`crate::online_column_minima`], only ask
+    /// for an element once the minimum of the column of its row is
+    /// known.
+    Online,
+}
+
+/// Matrix given by a closure which records and checks every access.
+///
+/// Element `(i, j)` is `matrix(i, j)`. Each access panics if it is
+/// outside the declared size, or if it breaks the [`AccessOrder`] set
+/// with [`DebugMatrix::with_order`]. Use the [`Matrix`] trait to pass
+/// it to the functions of this crate, or call [`Matrix::index`] from
+/// the closure given to a closure-based function.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::debug::DebugMatrix;
+/// use smawk::Matrix;
+///
+/// let matrix = DebugMatrix::new(2, 3, |i, j| (i as i32 - j as i32).pow(2));
+/// assert_eq!(smawk::column_minima(&matrix), vec![0, 1, 1]);
+/// assert_eq!(matrix.heatmap(), "111\n122\n");
+/// ```
+#[derive(Debug)]
+pub struct DebugMatrix<M> {
+    matrix: M,
+    nrows: usize,
+    ncols: usize,
+    order: Option<AccessOrder>,
+    accesses: RefCell<Vec<(usize, usize)>>,
+    last_row: Cell<Option<usize>>,
+}
+
+impl<M> DebugMatrix<M> {
+    /// Wrap `matrix`, which is declared to have `nrows` rows and
+    /// `ncols` columns.
+    pub fn new(nrows: usize, ncols: usize, matrix: M) -> Self {
+        DebugMatrix {
+            matrix,
+            nrows,
+            ncols,
+            order: None,
+            accesses: RefCell::new(Vec::new()),
+            last_row: Cell::new(None),
+        }
+    }
+
+    /// Check every access against `order`.
+    pub fn with_order(mut self, order: AccessOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Return the accesses so far, in the order they were made.
+    pub fn accesses(&self) -> Vec<(usize, usize)> {
+        self.accesses.borrow().clone()
+    }
+
+    /// Return how often every element has been accessed so far.
+    pub fn counts(&self) -> Vec<Vec<usize>> {
+        let mut counts = vec![vec![0; self.ncols]; self.nrows];
+        for &(row, column) in self.accesses.borrow().iter() {
+            counts[row][column] += 1;
+        }
+        counts
+    }
+
+    /// Render the access counts as text, one line per row.
+    ///
+    /// An element which was never accessed is shown as `.`, one
+    /// accessed up to nine times as the count, and one accessed more
+    /// often as `+`.
+    pub fn heatmap(&self) -> String {
+        let mut heatmap = String::with_capacity(self.nrows * (self.ncols + 1));
+        for row in self.counts() {
+            for count in row {
+                heatmap.push(match count {
+                    0 => '.',
+                    1..=9 => char::from(b'0' + count as u8),
+                    _ => '+',
+                });
+            }
+            heatmap.push('\n');
+        }
+        heatmap
+    }
+
+    /// Check an access and record it.
+    fn record(&self, row: usize, column: usize) {
+        assert!(
+            row < self.nrows && column < self.ncols,
+            "access ({}, {}) out of bounds for a {} x {} matrix",
+            row,
+            column,
+            self.nrows,
+            self.ncols
+        );
+        if let Some(order) = self.order {
+            assert!(
+                row < column,
+                "access ({}, {}) is not above the diagonal",
+                row,
+                column
+            );
+            if let (AccessOrder::Online, Some(last_row)) = (order, self.last_row.get()) {
+                assert!(
+                    last_row < column,
+                    "access ({}, {}) after an access to row {}",
+                    row,
+                    column,
+                    last_row
+                );
+            }
+        }
+        self.last_row
+            .set(Some(self.last_row.get().map_or(row, |last| last.max(row))));
+        self.accesses.borrow_mut().push((row, column));
+    }
+}
+
+impl<T: Copy, M: Fn(usize, usize) -> T> Matrix<T> for DebugMatrix<M> {
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+    fn index(&self, row: usize, column: usize) -> T {
+        self.record(row, column);
+        (self.matrix)(row, column)
+    }
+}
+
+/// Reason why [`validate_online_closure`] rejected a closure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnlineClosureIssue {
+    /// The extended matrix is not totally monotone: with rows
+    /// `[i, i']` and columns `[j, j']`, `M[i, j] > M[i', j]` but not
+    /// `M[i, j'] > M[i', j']`.
+    NotMonotone {
+        /// Rows `i < i'` of the witness.
+        rows: [usize; 2],
+        /// Columns `j < j'` of the witness, both after `i'`.
+        columns: [usize; 2],
+    },
+    /// [`crate::online_column_minima`] found a different minimum value
+    /// than a brute force search in `column`.
+    Mismatch {
+        /// First column with a different minimum value.
+        column: usize,
+    },
+}
+
+impl fmt::Display for OnlineClosureIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnlineClosureIssue::NotMonotone { rows, columns } => write!(
+                f,
+                "total monotonicity violated by rows {} and {} in columns {} and {}",
+                rows[0], rows[1], columns[0], columns[1]
+            ),
+            OnlineClosureIssue::Mismatch { column } => write!(
+                f,
+                "online minimum differs from brute force in column {}",
+                column
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OnlineClosureIssue {}
+
+/// Validate a closure for [`crate::online_column_minima`] on the
+/// first `n` columns.
+///
+/// The closure defines an extended matrix whose elements depend on
+/// the minima of earlier columns. This materializes the matrix above
+/// the diagonal by brute force: the minimum of column `j` is the
+/// smallest `M[i, j]` with `i < j`, and `matrix(result, i, j)` is
+/// called with exactly the `i + 1` minima up to column `i`, which is
+/// all the online algorithm guarantees to have computed. A closure
+/// which reads beyond `result[i]` thus panics with an index out of
+/// bounds.
+///
+/// Two things are then checked, in this order:
+///
+/// 1. The matrix is totally monotone for column minima: whenever
+///    `M[i, j] > M[i', j]` for rows `i < i' < j`, also `M[i, j'] >
+///    M[i', j']` for every later column `j'`. Otherwise the first such
+///    witness, in order of `i`, `i'` and `j'`, is returned as
+///    [`OnlineClosureIssue::NotMonotone`].
+///
+/// 2. The online algorithm finds the same minimum value as the brute
+///    force search in every column. This catches closures which give
+///    different elements depending on how many minima they are given,
+///    such as one which uses the last minimum instead of the minimum
+///    at row `i`. The first differing column is returned as
+///    [`OnlineClosureIssue::Mismatch`]. The rows of the minima are not
+///    compared, since ties can be broken differently.
+///
+/// Running time: O(*n*²) calls to `matrix` and O(*n*³) comparisons,
+/// so use this on a small prefix of a large problem.
+///
+/// **Note: this function is only available if you enable the
+/// `test-util` Cargo feature.**
+///
+/// # Examples
+///
+/// ```
+/// use smawk::debug::{validate_online_closure, OnlineClosureIssue};
+///
+/// // Break a line of words of widths 3, 1, 4, 1, 5 into lines of width
+/// // about 6, with a penalty for the squared amount of slack.
+/// let widths = [3, 1, 4, 1, 5];
+/// let cost = |minima: &[(usize, i64)], i: usize, j: usize| {
+///     let width: i64 = widths[i..j].iter().sum::<i64>() + (j - i - 1) as i64;
+///     minima[i].1 + (6 - width).pow(2)
+/// };
+/// assert_eq!(validate_online_closure(0, widths.len() + 1, cost), Ok(()));
+///
+/// // Using the latest minimum instead of the minimum at row i.
+/// let wrong = |minima: &[(usize, i64)], i: usize, j: usize| {
+///     cost(minima, i, j) - minima[i].1 + minima.last().unwrap().1
+/// };
+/// assert_eq!(
+///     validate_online_closure(0, widths.len() + 1, wrong),
+///     Err(OnlineClosureIssue::Mismatch { column: 2 })
+/// );
+/// ```
+pub fn validate_online_closure<T, M>(
+    initial: T,
+    n: usize,
+    matrix: M,
+) -> Result<(), OnlineClosureIssue>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> T,
+{
+    // Column j holds the elements M[i, j] for i < j.
+    let mut columns: Vec<Vec<T>> = Vec::with_capacity(n);
+    let mut minima = Vec::with_capacity(n);
+    if n > 0 {
+        columns.push(Vec::new());
+        minima.push((0, initial));
+    }
+    for j in 1..n {
+        let column = (0..j)
+            .map(|i| matrix(&minima[..=i], i, j))
+            .collect::<Vec<T>>();
+        let mut row = 0;
+        for i in 1..j {
+            if column[row] > column[i] {
+                row = i;
+            }
+        }
+        minima.push((row, column[row]));
+        columns.push(column);
+    }
+
+    for i in 0..n {
+        for i2 in i + 1..n {
+            // Both rows have elements from column i2 + 1 onwards.
+            let first = (i2 + 1..n).find(|&j| columns[j][i] > columns[j][i2]);
+            if let Some(j) = first {
+                if let Some(j2) = (j + 1..n)
+                    .find(|&j2| columns[j2][i].partial_cmp(&columns[j2][i2]) != Some(Greater))
+                {
+                    return Err(OnlineClosureIssue::NotMonotone {
+                        rows: [i, i2],
+                        columns: [j, j2],
+                    });
+                }
+            }
+        }
+    }
+
+    let online = crate::online_column_minima(initial, n, matrix);
+    match (0..n).find(|&j| online[j].1.partial_cmp(&minima[j].1) != Some(Equal)) {
+        Some(column) => Err(OnlineClosureIssue::Mismatch { column }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "out of bounds for a 3 x 5 matrix")]
+    fn transposed_closure_is_out_of_bounds() {
+        let matrix = DebugMatrix::new(3, 5, |i: usize, j: usize| (i as i64 - j as i64).pow(2));
+        // The indices are swapped by mistake. Without the adapter,
+        // this silently computes the minima of a different matrix.
+        crate::column_minima_by(3, 5, |i, j| matrix.index(j, i), Ord::cmp);
+    }
+
+    #[test]
+    fn records_every_access() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (m, n) = (rng.gen_range(1..50), rng.gen_range(1..50));
+            let x = (0..m).map(|_| rng.gen_range(0..100)).collect::<Vec<i64>>();
+            let cost = |i: usize, j: usize| (x[i] - 2 * j as i64).pow(2);
+            let dense = (0..m)
+                .map(|i| (0..n).map(|j| cost(i, j)).collect())
+                .collect::<Vec<Vec<_>>>();
+            let (expected, count) = crate::column_minima_counted(&dense);
+
+            let matrix = DebugMatrix::new(m, n, cost);
+            assert_eq!(crate::column_minima(&matrix), expected);
+            assert_eq!(matrix.accesses().len(), count);
+            let total: usize = matrix.counts().iter().flatten().sum();
+            assert_eq!(total, count);
+
+            // The row minima swap the indices internally, and still
+            // stay within bounds.
+            let matrix = DebugMatrix::new(m, n, cost);
+            assert_eq!(crate::row_minima(&matrix), crate::row_minima(&dense));
+        }
+    }
+
+    #[test]
+    fn online_access_order() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for size in 0..100 {
+            let offsets = (0..size)
+                .map(|_| rng.gen_range(0..10))
+                .collect::<Vec<i64>>();
+            let matrix = DebugMatrix::new(size, size, |i: usize, j: usize| {
+                offsets[i] + (j as i64 - i as i64 - 3).pow(2)
+            })
+            .with_order(AccessOrder::Online);
+            let minima = crate::online_column_minima(0, size, |_, i, j| matrix.index(i, j));
+            assert_eq!(minima.len(), size);
+            assert!(matrix.accesses().iter().all(|&(i, j)| i < j));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "access (0, 0) is not above the diagonal")]
+    fn above_diagonal_violation() {
+        let matrix = DebugMatrix::new(2, 2, |_, _| 0).with_order(AccessOrder::AboveDiagonal);
+        crate::column_minima(&matrix);
+    }
+
+    #[test]
+    #[should_panic(expected = "access (1, 2) after an access to row 2")]
+    fn online_violation() {
+        let matrix = DebugMatrix::new(4, 4, |_, _| 0).with_order(AccessOrder::Online);
+        matrix.index(2, 3);
+        matrix.index(1, 2);
+    }
+
+    #[test]
+    fn validate_online_closure_witness() {
+        // A concave penalty for the length of a piece is not totally
+        // monotone: long pieces get relatively cheaper.
+        let cost = |minima: &[(usize, i64)], i: usize, j: usize| {
+            minima[i].1 + 10 * ((j - i) as f64).sqrt() as i64
+        };
+        let issue = validate_online_closure(0, 10, cost).unwrap_err();
+        assert_eq!(
+            issue,
+            OnlineClosureIssue::NotMonotone {
+                rows: [1, 2],
+                columns: [5, 6]
+            }
+        );
+        assert_eq!(
+            issue.to_string(),
+            "total monotonicity violated by rows 1 and 2 in columns 5 and 6"
+        );
+
+        let convex =
+            |minima: &[(usize, i64)], i: usize, j: usize| minima[i].1 + 1 + ((j - i) as i64).pow(2);
+        for n in 0..30 {
+            assert_eq!(validate_online_closure(0, n, convex), Ok(()));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn validate_online_closure_reads_ahead() {
+        let cost = |minima: &[(usize, i64)], i: usize, j: usize| minima[i + 1].1 + (j - i) as i64;
+        let _ = validate_online_closure(0, 5, cost);
+    }
+
+    #[test]
+    fn heatmap_counts() {
+        let matrix = DebugMatrix::new(2, 3, |_, _| 0);
+        for _ in 0..12 {
+            matrix.index(0, 0);
+        }
+        for _ in 0..3 {
+            matrix.index(1, 2);
+        }
+        assert_eq!(matrix.heatmap(), "+..\n..3\n");
+    }
+}
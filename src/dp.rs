@@ -0,0 +1,728 @@
+//! Helpers for layered dynamic programming.
+//!
+//! Many dynamic programs compute a sequence of layers where each
+//! layer is derived from the previous one by a recurrence of the form
+//!
+//! ```text
+//! D[l, j] = min { D[l - 1, k] + cost(k, j) | k ≤ j }
+//! ```
+//!
+//! A typical example is partitioning a sequence of *n* items into *l*
+//! consecutive groups: `D[l, j]` is the cost of the best partition of
+//! the first `j` items into `l` groups and `cost(k, j)` is the cost
+//! of putting items `k..j` into a single group.
+//!
+//! When `cost` is a Monge matrix, the optimal split point `k` is a
+//! monotone function of `j` and a layer can be computed with far
+//! fewer evaluations than the O(*n*²) needed by a brute force scan.
+
+use crate::Matrix;
+use std::cell::RefCell;
+use std::ops::Add;
+
+pub mod reconstruct;
+
+/// Relax a layer through a totally monotone cost matrix with SMAWK.
+///
+/// This computes the column minima of the implicit *m* ✕ *n* matrix
+/// `prev[i] + cost(i, j)` where *m* = `prev.len()` and *n* = `ncols`.
+/// The tuple at index `j` of the result gives the row `i` of the
+/// minimum together with the relaxed value `prev[i] + cost(i, j)`.
+/// Ties are broken in favor of the smallest row index.
+///
+/// Adding `prev[i]` to every element of row `i` preserves the Monge
+/// property, so it is enough that `cost` is a Monge matrix.
+///
+/// Running time: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// let prev = vec![0, 1, 5];
+/// let next = smawk::dp::fused_row_relax(&prev, |i, j| (i as i32 - j as i32).pow(2), 4);
+/// assert_eq!(next, vec![(0, 0), (0, 1), (1, 2), (1, 5)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with an empty `prev` slice.
+pub fn fused_row_relax<T, C>(prev: &[T], cost: C, ncols: usize) -> Vec<(usize, T)>
+where
+    T: PartialOrd + Copy + Add<Output = T>,
+    C: Fn(usize, usize) -> T,
+{
+    assert!(!prev.is_empty(), "cannot relax an empty layer");
+    let matrix = Relaxed {
+        prev,
+        cost: &cost,
+        ncols,
+    };
+    crate::column_minima(&matrix)
+        .into_iter()
+        .enumerate()
+        .map(|(j, i)| (i, matrix.index(i, j)))
+        .collect()
+}
+
+/// Implicit matrix with elements `prev[i] + cost(i, j)`.
+struct Relaxed<'a, T, C> {
+    prev: &'a [T],
+    cost: &'a C,
+    ncols: usize,
+}
+
+impl<T, C> Matrix<T> for Relaxed<'_, T, C>
+where
+    T: Copy + Add<Output = T>,
+    C: Fn(usize, usize) -> T,
+{
+    fn nrows(&self) -> usize {
+        self.prev.len()
+    }
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+    fn index(&self, row: usize, column: usize) -> T {
+        self.prev[row] + (self.cost)(row, column)
+    }
+}
+
+/// Relax a layer of `i64` costs through a Monge cost matrix.
+///
+/// This computes `next[j] = min { prev[i] + w(i, j) | i < prev.len() }`
+/// for every `j < n_out` and returns `next` together with the row `i`
+/// of every minimum. Ties are broken in favor of the smallest row
+/// index, like in [`fused_row_relax`].
+///
+/// The value `i64::MAX` stands for infinity, both in `prev` and as a
+/// cost returned by `w`. A sum with an infinite term is infinite, and
+/// `w` is not evaluated for the rows where `prev` is infinite. Other
+/// sums saturate at `i64::MAX` instead of overflowing. A column
+/// without finite elements gets row 0 and the value `i64::MAX`.
+///
+/// The result is correct when `w` is a Monge matrix. Adding `prev[i]`
+/// to every element of row `i` preserves the Monge property, and
+/// infinite rows never win a column. Infinite costs are allowed below
+/// a staircase in the lower-left corner of `w`, such as `i > j` for
+/// the usual `k ≤ j` split points of a layered DP. Sums which
+/// saturate are not Monge, so the costs should stay far from
+/// `i64::MAX`.
+///
+/// Running time: O(*m* + *n*) calls to `w`, where *m* = `prev.len()`
+/// and *n* = `n_out`. Every minimum is evaluated once more at the end
+/// to return its value.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::dp::dp_step;
+/// let prev = vec![0, i64::MAX, 5];
+/// let w = |i: usize, j: usize| (i as i64 - j as i64).pow(2);
+/// assert_eq!(dp_step(&prev, w, 4), (vec![0, 1, 4, 6], vec![0, 0, 0, 2]));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with an empty `prev` slice.
+pub fn dp_step<W>(prev: &[i64], w: W, n_out: usize) -> (Vec<i64>, Vec<usize>)
+where
+    W: FnMut(usize, usize) -> i64,
+{
+    assert!(!prev.is_empty(), "cannot relax an empty layer");
+    let matrix = Step {
+        prev,
+        w: RefCell::new(w),
+        ncols: n_out,
+    };
+    let minima = crate::column_minima(&matrix);
+    let next = minima
+        .iter()
+        .enumerate()
+        .map(|(j, &i)| matrix.index(i, j))
+        .collect();
+    (next, minima)
+}
+
+/// Implicit matrix with elements `prev[i] + w(i, j)`, where
+/// `i64::MAX` is infinite.
+struct Step<'a, W> {
+    prev: &'a [i64],
+    w: RefCell<W>,
+    ncols: usize,
+}
+
+impl<W: FnMut(usize, usize) -> i64> Matrix<i64> for Step<'_, W> {
+    fn nrows(&self) -> usize {
+        self.prev.len()
+    }
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+    fn index(&self, row: usize, column: usize) -> i64 {
+        if self.prev[row] == i64::MAX {
+            return i64::MAX;
+        }
+        relax(self.prev[row], (self.w.borrow_mut())(row, column))
+    }
+}
+
+/// Compute `value + cost`, where `i64::MAX` is infinite, saturating
+/// at `i64::MAX` instead of overflowing.
+fn relax(value: i64, cost: i64) -> i64 {
+    if value == i64::MAX || cost == i64::MAX {
+        i64::MAX
+    } else {
+        value.saturating_add(cost)
+    }
+}
+
+/// Compute the next layer of a layered DP by divide and conquer.
+///
+/// The function computes `next[j] = min { prev[k] + cost(k, j) | k ≤
+/// j }` for every `j < prev.len()`. The `cost` function must be a
+/// Monge matrix on its upper triangle `k ≤ j`; the lower triangle is
+/// never evaluated.
+///
+/// The tuple at index `j` of the result gives the smallest optimal
+/// split point `k` together with the value `next[j]`.
+///
+/// Running time for *n* = `prev.len()`: O(*n* log *n*).
+///
+/// # Examples
+///
+/// ```
+/// // Split points 0, 1, 2, 3 with the cost of a group being the
+/// // squared distance between its end points.
+/// let prev = vec![0, 10, 10, 10];
+/// let next = smawk::dp::divide_and_conquer(&prev, |k, j| (j - k) * (j - k));
+/// assert_eq!(next, vec![(0, 0), (0, 1), (0, 4), (0, 9)]);
+/// ```
+pub fn divide_and_conquer<T, C>(prev: &[T], cost: C) -> Vec<(usize, T)>
+where
+    T: PartialOrd + Copy + Add<Output = T>,
+    C: Fn(usize, usize) -> T,
+{
+    // The initial values are placeholders which are all overwritten.
+    let mut next = prev.iter().copied().enumerate().collect::<Vec<_>>();
+    if !prev.is_empty() {
+        divide_and_conquer_inner(prev, &cost, 0, prev.len(), 0, prev.len() - 1, &mut next);
+    }
+    next
+}
+
+/// Compute all layers of a layered DP by divide and conquer and
+/// record every optimal split point.
+///
+/// Starting from `initial`, the function applies
+/// [`divide_and_conquer`] `layers` times. It returns the last layer
+/// together with a `layers` ✕ *n* matrix where the entry at `(l, j)`
+/// is the smallest optimal split point `k` chosen for `j` when
+/// computing layer `l + 1`.
+///
+/// For Monge costs the choice matrix forms a monotone staircase:
+/// each row is non-decreasing and each column is non-decreasing
+/// from one layer to the next. Following the choices backwards from
+/// the last layer reconstructs an optimal partition.
+///
+/// Running time for *n* = `initial.len()`: O(`layers` ✕ *n* log *n*).
+///
+/// **Note: this function is only available if you enable the
+/// `ndarray` Cargo feature.**
+///
+/// # Examples
+///
+/// ```
+/// let initial = vec![0, 100, 100, 100];
+/// let (last, choices) =
+///     smawk::dp::divide_and_conquer_with_choices(&initial, 2, |k, j| (j - k) * (j - k));
+/// assert_eq!(last, vec![0, 1, 2, 5]);
+/// assert_eq!(choices, ndarray::arr2(&[[0, 0, 0, 0],
+///                                     [0, 0, 1, 1]]));
+/// ```
+#[cfg(feature = "ndarray")]
+pub fn divide_and_conquer_with_choices<T, C>(
+    initial: &[T],
+    layers: usize,
+    cost: C,
+) -> (Vec<T>, ndarray::Array2<usize>)
+where
+    T: PartialOrd + Copy + Add<Output = T>,
+    C: Fn(usize, usize) -> T,
+{
+    let mut choices = ndarray::Array2::zeros((layers, initial.len()));
+    let mut layer = initial.to_vec();
+    for mut row in choices.rows_mut() {
+        let next = divide_and_conquer(&layer, &cost);
+        for (j, (k, value)) in next.into_iter().enumerate() {
+            row[j] = k;
+            layer[j] = value;
+        }
+    }
+    (layer, choices)
+}
+
+/// Shape of the weight function in [`monotone_decision_lws`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Convexity {
+    /// The weights satisfy the quadrangle inequality `w(a, c) + w(b,
+    /// d) ≤ w(a, d) + w(b, c)` for `a ≤ b ≤ c ≤ d`. This is the case
+    /// for `w(i, j) = f(j - i)` with a convex function `f`.
+    Convex,
+    /// The weights satisfy the inverse quadrangle inequality `w(a, c)
+    /// + w(b, d) ≥ w(a, d) + w(b, c)` for `a ≤ b ≤ c ≤ d`. This is
+    /// the case for `w(i, j) = f(j - i)` with a concave function `f`.
+    Concave,
+}
+
+/// Solve the least weight subsequence problem with a stack of
+/// candidate intervals.
+///
+/// The function computes `d[0] = 0` and `d[j] = min { d[i] + w(i, j)
+/// | i < j }` for `0 < j < n`. It returns `d` together with the
+/// smallest optimal predecessor of every `j`. The predecessor of `0`
+/// is `0` itself.
+///
+/// The weights must be convex or concave as described by
+/// [`Convexity`]. For convex weights, a later candidate `i` which
+/// beats an earlier one keeps doing so for all larger `j`. For concave
+/// weights it is the other way around. Either way, the optimal
+/// candidates form a sequence of intervals which is kept on a stack,
+/// and a binary search finds where a new candidate takes over.
+///
+/// Compared to [`online_column_minima`](crate::online_column_minima),
+/// this is a simpler algorithm with a small constant factor, which
+/// often makes it faster for small *n*. It also handles concave
+/// weights. Prefer the SMAWK-based solvers for large convex instances.
+///
+/// Like in [`dp_step`], `i64::MAX` is infinite and the sums saturate
+/// at `i64::MAX` instead of overflowing. Sums which saturate no longer
+/// satisfy the inequalities, so the weights should stay far from
+/// `i64::MAX`.
+///
+/// Running time: O(*n* log *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::dp::{monotone_decision_lws, Convexity};
+///
+/// // Jumps of length k cost 1 + k².
+/// let (d, parent) = monotone_decision_lws(5, |i, j| 1 + ((j - i) * (j - i)) as i64, Convexity::Convex);
+/// assert_eq!(d, vec![0, 2, 4, 6, 8]);
+/// assert_eq!(parent, vec![0, 0, 1, 2, 3]);
+/// ```
+pub fn monotone_decision_lws<W>(n: usize, w: W, convexity: Convexity) -> (Vec<i64>, Vec<usize>)
+where
+    W: Fn(usize, usize) -> i64,
+{
+    if n == 0 {
+        return (Vec::new(), Vec::new());
+    }
+    let mut d = vec![0; n];
+    let mut parent = vec![0; n];
+
+    // Candidate `b` strictly beats candidate `a < b` at column `j`.
+    let beats =
+        |d: &[i64], a: usize, b: usize, j: usize| relax(d[b], w(b, j)) < relax(d[a], w(a, j));
+
+    match convexity {
+        Convexity::Convex => {
+            // Candidates with the first column of their interval. The
+            // front of the deque covers the smallest columns.
+            let mut intervals: std::collections::VecDeque<(usize, usize)> =
+                std::collections::VecDeque::new();
+            intervals.push_back((0, 1));
+            for j in 1..n {
+                while intervals.len() > 1 && intervals[1].1 <= j {
+                    intervals.pop_front();
+                }
+                let i = intervals[0].0;
+                d[j] = relax(d[i], w(i, j));
+                parent[j] = i;
+
+                // Candidate `j` takes over a suffix of the columns.
+                let mut start = n;
+                while let Some(&(a, first)) = intervals.back() {
+                    let first = first.max(j + 1);
+                    if first < n && beats(&d, a, j, first) {
+                        intervals.pop_back();
+                        start = first;
+                        continue;
+                    }
+                    let (mut lo, mut hi) = (first, start);
+                    while lo < hi {
+                        let mid = lo + (hi - lo) / 2;
+                        if beats(&d, a, j, mid) {
+                            hi = mid;
+                        } else {
+                            lo = mid + 1;
+                        }
+                    }
+                    start = lo;
+                    break;
+                }
+                if start < n {
+                    intervals.push_back((j, start));
+                }
+            }
+        }
+        Convexity::Concave => {
+            // Candidates with the last column of their interval. The
+            // top of the stack covers the smallest columns.
+            let mut intervals = vec![(0, n - 1)];
+            for j in 1..n {
+                while intervals.last().is_some_and(|&(_, last)| last < j) {
+                    intervals.pop();
+                }
+                let i = intervals.last().unwrap().0;
+                d[j] = relax(d[i], w(i, j));
+                parent[j] = i;
+                if j + 1 == n {
+                    break;
+                }
+
+                // Candidate `j` takes over a prefix of the columns.
+                let mut end = None;
+                while let Some(&(a, last)) = intervals.last() {
+                    if beats(&d, a, j, last) {
+                        intervals.pop();
+                        end = Some(last);
+                        continue;
+                    }
+                    // Find the first column where `j` no longer wins.
+                    let (mut lo, mut hi) = (j + 1, last);
+                    while lo < hi {
+                        let mid = lo + (hi - lo) / 2;
+                        if beats(&d, a, j, mid) {
+                            lo = mid + 1;
+                        } else {
+                            hi = mid;
+                        }
+                    }
+                    if lo > j + 1 {
+                        end = Some(lo - 1);
+                    }
+                    break;
+                }
+                if let Some(end) = end {
+                    intervals.push((j, end));
+                }
+            }
+        }
+    }
+    (d, parent)
+}
+
+/// Fill `next[lo..hi]` knowing that the optimal split points are
+/// found in `opt_lo..=opt_hi`.
+fn divide_and_conquer_inner<T, C>(
+    prev: &[T],
+    cost: &C,
+    lo: usize,
+    hi: usize,
+    opt_lo: usize,
+    opt_hi: usize,
+    next: &mut [(usize, T)],
+) where
+    T: PartialOrd + Copy + Add<Output = T>,
+    C: Fn(usize, usize) -> T,
+{
+    if lo >= hi {
+        return;
+    }
+
+    let mid = lo + (hi - lo) / 2;
+    let mut best = (opt_lo, prev[opt_lo] + cost(opt_lo, mid));
+    let last = std::cmp::min(mid, opt_hi);
+    for (k, &p) in prev[..=last].iter().enumerate().skip(opt_lo + 1) {
+        let value = p + cost(k, mid);
+        if value < best.1 {
+            best = (k, value);
+        }
+    }
+    next[mid] = best;
+
+    divide_and_conquer_inner(prev, cost, lo, mid, opt_lo, best.0, next);
+    divide_and_conquer_inner(prev, cost, mid + 1, hi, best.0, opt_hi, next);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    /// Compute the next layer by trying all split points.
+    fn brute_force_layer(prev: &[u64], cost: impl Fn(usize, usize) -> u64) -> Vec<(usize, u64)> {
+        (0..prev.len())
+            .map(|j| {
+                (0..=j)
+                    .map(|k| (k, prev[k] + cost(k, j)))
+                    .min_by_key(|&(k, value)| (value, k))
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// Cost of a group is the square of the total weight. This is a
+    /// Monge matrix since the prefix sums are non-decreasing.
+    fn squared_weight(weights: &[u64]) -> impl Fn(usize, usize) -> u64 {
+        let mut prefix = vec![0];
+        for w in weights {
+            prefix.push(prefix[prefix.len() - 1] + w);
+        }
+        move |k, j| {
+            let sum = prefix[j] - prefix[k];
+            sum * sum
+        }
+    }
+
+    /// Solve the least weight subsequence problem in quadratic time.
+    fn quadratic_lws(n: usize, w: impl Fn(usize, usize) -> i64) -> (Vec<i64>, Vec<usize>) {
+        let mut d = vec![0; n];
+        let mut parent = vec![0; n];
+        for j in 1..n {
+            let (i, value) = (0..j)
+                .map(|i| (i, relax(d[i], w(i, j))))
+                .min_by_key(|&(i, value)| (value, i))
+                .unwrap();
+            d[j] = value;
+            parent[j] = i;
+        }
+        (d, parent)
+    }
+
+    /// Random weights `f(j - i) + a[i] + b[j]` with `f` convex or
+    /// concave.
+    fn random_weights(
+        n: usize,
+        convexity: Convexity,
+        rng: &mut impl Rng,
+    ) -> impl Fn(usize, usize) -> i64 {
+        let mut steps = (0..n).map(|_| rng.gen_range(-20..20)).collect::<Vec<i64>>();
+        steps.sort_unstable();
+        if convexity == Convexity::Concave {
+            steps.reverse();
+        }
+        let mut f = vec![0];
+        for step in steps {
+            f.push(f[f.len() - 1] + step);
+        }
+        let a = (0..n).map(|_| rng.gen_range(0..30)).collect::<Vec<i64>>();
+        let b = (0..n).map(|_| rng.gen_range(0..30)).collect::<Vec<i64>>();
+        move |i, j| f[j - i] + a[i] + b[j]
+    }
+
+    #[test]
+    fn monotone_decision_lws_empty() {
+        assert_eq!(
+            monotone_decision_lws(0, |_, _| 0, Convexity::Convex),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            monotone_decision_lws(1, |_, _| 0, Convexity::Concave),
+            (vec![0], vec![0])
+        );
+    }
+
+    #[test]
+    fn monotone_decision_lws_matches_quadratic() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let n = rng.gen_range(1..40);
+            for convexity in [Convexity::Convex, Convexity::Concave] {
+                let w = random_weights(n, convexity, &mut rng);
+                assert_eq!(
+                    monotone_decision_lws(n, &w, convexity),
+                    quadratic_lws(n, &w),
+                    "{:?} weights with n = {}",
+                    convexity,
+                    n
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn monotone_decision_lws_large_weights() {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        for _ in 0..100 {
+            let n = rng.gen_range(1..40);
+            for convexity in [Convexity::Convex, Convexity::Concave] {
+                // Most sums of two weights overflow i64, but the
+                // direct jump from 0 is always finite.
+                let w = random_weights(n, convexity, &mut rng);
+                let large = |i, j| i64::MAX / 2 + w(i, j);
+                assert_eq!(
+                    monotone_decision_lws(n, large, convexity),
+                    quadratic_lws(n, large)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn monotone_decision_lws_matches_online_column_minima() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for _ in 0..100 {
+            let n = rng.gen_range(1..100);
+            let w = random_weights(n, Convexity::Convex, &mut rng);
+            let minima = crate::online_column_minima(0, n, |result, i, j| result[i].1 + w(i, j));
+            let expected = (
+                minima.iter().map(|&(_, value)| value).collect::<Vec<_>>(),
+                minima.iter().map(|&(i, _)| i).collect::<Vec<_>>(),
+            );
+            assert_eq!(monotone_decision_lws(n, &w, Convexity::Convex), expected);
+        }
+    }
+
+    #[test]
+    fn fused_row_relax_matches_brute_force() {
+        // Squared differences of two non-decreasing sequences form a
+        // Monge matrix.
+        let xs = [0i64, 2, 3, 3, 7, 8, 12, 15, 15, 20];
+        let ys = [1i64, 1, 4, 6, 9, 10, 10, 13, 18, 21, 25, 30];
+        let cost = |i: usize, j: usize| (ys[j] - xs[i]).pow(2);
+        for seed in 1..20i64 {
+            let prev = (0..xs.len() as i64)
+                .map(|i| (i * i * seed + 7 * seed) % 97)
+                .collect::<Vec<_>>();
+            let expected = (0..ys.len())
+                .map(|j| {
+                    (0..xs.len())
+                        .map(|i| (i, prev[i] + cost(i, j)))
+                        .min_by_key(|&(i, value)| (value, i))
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(fused_row_relax(&prev, cost, ys.len()), expected);
+        }
+    }
+
+    #[test]
+    fn fused_row_relax_no_columns() {
+        let prev = vec![1, 2, 3];
+        assert_eq!(fused_row_relax(&prev, |_, _| 0, 0), vec![]);
+    }
+
+    #[test]
+    fn dp_step_matches_quadratic() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let (m, n) = (rng.gen_range(1..30), rng.gen_range(0..30));
+            let sorted = |len: usize, rng: &mut ChaCha20Rng| {
+                let mut values = (0..len).map(|_| rng.gen_range(0..50)).collect::<Vec<i64>>();
+                values.sort_unstable();
+                values
+            };
+            let (xs, ys) = (sorted(m, &mut rng), sorted(n, &mut rng));
+            // Squared differences with infinite costs below a staircase.
+            let offset = rng.gen_range(-3..=3);
+            let w = |i: usize, j: usize| {
+                if i as i64 > j as i64 + offset {
+                    i64::MAX
+                } else {
+                    (ys[j] - xs[i]).pow(2)
+                }
+            };
+            let prev = (0..m)
+                .map(|_| {
+                    if rng.gen_bool(0.3) {
+                        i64::MAX
+                    } else {
+                        rng.gen_range(0..100)
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let (expected_next, expected_rows): (Vec<_>, Vec<_>) = (0..n)
+                .map(|j| {
+                    (0..m)
+                        .map(|i| match (prev[i], w(i, j)) {
+                            (i64::MAX, _) | (_, i64::MAX) => (i64::MAX, i),
+                            (value, cost) => (value + cost, i),
+                        })
+                        .min()
+                        .unwrap()
+                })
+                .unzip();
+            assert_eq!(
+                dp_step(&prev, w, n),
+                (expected_next, expected_rows),
+                "prev = {:?}, offset = {}",
+                prev,
+                offset
+            );
+        }
+    }
+
+    #[test]
+    fn divide_and_conquer_empty() {
+        let prev: Vec<u64> = vec![];
+        assert_eq!(divide_and_conquer(&prev, |_, _| 0), vec![]);
+    }
+
+    #[test]
+    fn divide_and_conquer_matches_brute_force() {
+        let weights = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 8, 9, 7, 9];
+        let cost = squared_weight(&weights);
+        let mut prev = vec![u64::MAX / 4; weights.len() + 1];
+        prev[0] = 0;
+        for _ in 0..5 {
+            let next = divide_and_conquer(&prev, &cost);
+            assert_eq!(next, brute_force_layer(&prev, &cost));
+            prev = next.into_iter().map(|(_, value)| value).collect();
+        }
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn divide_and_conquer_choices_form_staircase() {
+        let weights = [2, 7, 1, 8, 2, 8, 1, 8, 2, 8, 4, 5, 9, 0, 4, 5];
+        let n = weights.len() + 1;
+        let cost = squared_weight(&weights);
+        let mut initial = vec![u64::MAX / 4; n];
+        initial[0] = 0;
+        let layers = 4;
+        let (last, choices) = divide_and_conquer_with_choices(&initial, layers, &cost);
+
+        for l in 0..layers {
+            for j in 1..n {
+                assert!(
+                    choices[[l, j - 1]] <= choices[[l, j]],
+                    "row {} not monotone",
+                    l
+                );
+                if l > 0 {
+                    assert!(
+                        choices[[l - 1, j]] <= choices[[l, j]],
+                        "column {} not monotone",
+                        j
+                    );
+                }
+            }
+        }
+
+        // Brute force DP over all layers.
+        let mut expected = initial.clone();
+        for _ in 0..layers {
+            expected = brute_force_layer(&expected, &cost)
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect();
+        }
+        assert_eq!(last, expected);
+
+        // Reconstruct the partition from the choices and check that
+        // it achieves the optimal cost.
+        let mut j = n - 1;
+        let mut total = 0;
+        for l in (0..layers).rev() {
+            let k = choices[[l, j]];
+            total += cost(k, j);
+            j = k;
+        }
+        assert_eq!(j, 0);
+        assert_eq!(total, last[n - 1]);
+    }
+}
@@ -0,0 +1,425 @@
+//! Reconstruct optimal solutions from the choices made by a dynamic
+//! program.
+//!
+//! The solvers in this crate return the optimal predecessor of every
+//! position, such as the row of each column minimum found by
+//! [`online_column_minima`](crate::online_column_minima). The
+//! functions here turn these choices into paths and partitions. When
+//! the choices of a layered DP are too large to keep in memory,
+//! [`hirschberg_reconstruct`] recomputes them on demand instead.
+
+use std::ops::Range;
+
+/// Follow parent pointers backwards from `end`.
+///
+/// The parent of position `i` is `parents[i]`, and the walk stops at
+/// a position which is its own parent. The positions visited are
+/// returned in increasing order, starting with this root and ending
+/// with `end`.
+///
+/// This is the path found by solvers such as
+/// [`online_column_minima`](crate::online_column_minima) and
+/// [`monotone_decision_lws`](super::monotone_decision_lws), where
+/// position 0 is the root.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::dp::reconstruct::path_from_parents;
+///
+/// let parents = [0, 0, 1, 1, 2, 3];
+/// assert_eq!(path_from_parents(&parents, 5), vec![0, 1, 3, 5]);
+/// assert_eq!(path_from_parents(&parents, 0), vec![0]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error if `end` is out of bounds or if a parent pointer
+/// points forward.
+pub fn path_from_parents(parents: &[usize], end: usize) -> Vec<usize> {
+    assert!(end < parents.len(), "end must be a valid position");
+    path_from_parent_fn(end, |i| parents[i])
+}
+
+/// Follow parent pointers computed by `parent` backwards from `end`.
+///
+/// This is [`path_from_parents`] for parents which are not stored in
+/// a single slice. A layered DP can number position `b` of layer `k`
+/// as `k * size + b`, and a DP with several states per position can
+/// number the states of each position consecutively. The parents are
+/// computed while walking, so only the positions on the path are
+/// visited.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::dp::reconstruct::path_from_parent_fn;
+///
+/// // Two layers of three positions, where position b of layer 1
+/// // comes from position b / 2 of layer 0.
+/// let parent = |id: usize| if id < 3 { id } else { (id - 3) / 2 };
+/// assert_eq!(path_from_parent_fn(5, parent), vec![1, 5]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error if a parent pointer points forward.
+pub fn path_from_parent_fn<P: Fn(usize) -> usize>(end: usize, parent: P) -> Vec<usize> {
+    let mut path = vec![end];
+    let mut i = end;
+    loop {
+        let p = parent(i);
+        if p == i {
+            break;
+        }
+        assert!(p < i, "parent pointers must point backwards");
+        i = p;
+        path.push(i);
+    }
+    path.reverse();
+    path
+}
+
+/// Split `0..n` into consecutive segments at the given breaks.
+///
+/// Each break starts a new segment. Breaks at 0 or `n` and repeated
+/// breaks would give empty segments, which are left out. The breaks
+/// can therefore be given either as the start of every segment or
+/// only as the boundaries between segments.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::dp::reconstruct::segments_from_breaks;
+///
+/// assert_eq!(segments_from_breaks(&[2, 3], 5), vec![0..2, 2..3, 3..5]);
+/// assert_eq!(segments_from_breaks(&[0, 2, 5], 5), vec![0..2, 2..5]);
+/// assert_eq!(segments_from_breaks(&[], 5), vec![0..5]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error if the breaks are not sorted or exceed `n`.
+pub fn segments_from_breaks(breaks: &[usize], n: usize) -> Vec<Range<usize>> {
+    assert!(
+        breaks.windows(2).all(|w| w[0] <= w[1]),
+        "breaks must be sorted"
+    );
+    assert!(
+        breaks.last().is_none_or(|&b| b <= n),
+        "breaks must not exceed n"
+    );
+    let mut segments = Vec::with_capacity(breaks.len() + 1);
+    let mut start = 0;
+    for &end in breaks.iter().chain(Some(&n)) {
+        if start < end {
+            segments.push(start..end);
+        }
+        start = end;
+    }
+    segments
+}
+
+/// Reconstruct an optimal path through a layered DP without storing
+/// the choices of every layer.
+///
+/// The DP starts from the `initial` layer and computes `layers` more
+/// layers, each from the previous one. Storing the split point of
+/// every position in every layer takes O(`layers` ✕ *n*) memory. This
+/// function only keeps O(*n*) values per level of recursion and
+/// recomputes layers as needed: it finds where the optimal path
+/// crosses the middle layer, and then solves the two halves
+/// independently, in the style of Hirschberg's algorithm.
+///
+/// The layers are computed by `solve_layer(l, offset, prev, ncols)`,
+/// which must compute layer `l` from layer `l - 1`. Positions are
+/// relative to `offset`: `prev[k]` is the value at position `offset +
+/// k`, and the result must hold `ncols` tuples with the optimal split
+/// point `k` for each position `j`, relative to `offset`, together with
+/// the new value. Only split points `k ≤ j` may be chosen, and `prev`
+/// can be shorter than `ncols`.
+///
+/// The result holds the position of the path in every layer, from
+/// layer 0 to layer `layers`, where it ends at the last position.
+/// The path is optimal, but when there are ties, it may differ from
+/// the path found by following the stored choices.
+///
+/// Running time: O(`layers` log `layers`) calls to `solve_layer`, on
+/// positions which shrink as the recursion gets deeper.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::dp::reconstruct::hirschberg_reconstruct;
+///
+/// // Split 0..6 into three groups, minimizing the sum of the
+/// // squared group sizes.
+/// let path = hirschberg_reconstruct(&[0, 100, 100, 100, 100, 100, 100], 3,
+///                                   |_, _, prev: &[i32], ncols| {
+///     (0..ncols)
+///         .map(|j| {
+///             (0..prev.len().min(j + 1))
+///                 .map(|k| (k, prev[k] + ((j - k) * (j - k)) as i32))
+///                 .min_by_key(|&(k, value)| (value, k))
+///                 .unwrap()
+///         })
+///         .collect()
+/// });
+/// assert_eq!(path, vec![0, 2, 4, 6]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with an empty `initial` layer, or if
+/// `solve_layer` returns the wrong number of tuples.
+pub fn hirschberg_reconstruct<T, S>(initial: &[T], layers: usize, solve_layer: S) -> Vec<usize>
+where
+    T: Copy,
+    S: Fn(usize, usize, &[T], usize) -> Vec<(usize, T)>,
+{
+    assert!(!initial.is_empty(), "expected a non-empty initial layer");
+    let mut path = vec![0; layers + 1];
+    path[layers] = initial.len() - 1;
+    hirschberg_inner(&solve_layer, 0, layers, 0, initial, &mut path);
+    path
+}
+
+/// Fill `path[first..last]` with an optimal path from layer `first`
+/// to position `path[last]` in layer `last`. The values in layer
+/// `first` are given by `prev` from position `offset` onwards.
+fn hirschberg_inner<T, S>(
+    solve_layer: &S,
+    first: usize,
+    last: usize,
+    offset: usize,
+    prev: &[T],
+    path: &mut [usize],
+) where
+    T: Copy,
+    S: Fn(usize, usize, &[T], usize) -> Vec<(usize, T)>,
+{
+    if first == last {
+        return;
+    }
+    let ncols = path[last] - offset + 1;
+    let solve = |l: usize, layer: &[T]| {
+        let next = solve_layer(l, offset, layer, ncols);
+        assert_eq!(next.len(), ncols, "expected one tuple per position");
+        next
+    };
+
+    if first + 1 == last {
+        path[first] = offset + solve(last, prev)[ncols - 1].0;
+        return;
+    }
+
+    // Compute the layers up to the middle, then keep track of where
+    // the optimal paths cross the middle layer.
+    let middle = first + (last - first) / 2;
+    let mut layer = prev.to_vec();
+    for l in first + 1..=middle {
+        layer = solve(l, &layer)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+    }
+    let middle_layer = layer.clone();
+    let mut crossings = (0..ncols).collect::<Vec<_>>();
+    for l in middle + 1..=last {
+        let next = solve(l, &layer);
+        crossings = next.iter().map(|&(k, _)| crossings[k]).collect();
+        layer = next.into_iter().map(|(_, value)| value).collect();
+    }
+
+    let crossing = crossings[ncols - 1];
+    path[middle] = offset + crossing;
+    let prefix = &prev[..prev.len().min(crossing + 1)];
+    hirschberg_inner(solve_layer, first, middle, offset, prefix, path);
+    let start = [middle_layer[crossing]];
+    hirschberg_inner(solve_layer, middle, last, offset + crossing, &start, path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn path_from_parents_single() {
+        assert_eq!(path_from_parents(&[0], 0), vec![0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "end must be a valid position")]
+    fn path_from_parents_empty() {
+        path_from_parents(&[], 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "parent pointers must point backwards")]
+    fn path_from_parents_forward() {
+        path_from_parents(&[0, 2, 1], 2);
+    }
+
+    #[test]
+    fn path_from_parents_other_root() {
+        // Positions which are their own parents are roots.
+        assert_eq!(path_from_parents(&[0, 1, 1, 2], 3), vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "parent pointers must point backwards")]
+    fn path_from_parent_fn_forward() {
+        path_from_parent_fn(1, |i| i + 1);
+    }
+
+    #[test]
+    fn segments_from_breaks_empty() {
+        assert_eq!(segments_from_breaks(&[], 0), vec![]);
+        assert_eq!(segments_from_breaks(&[0, 0], 0), vec![]);
+    }
+
+    #[test]
+    fn segments_from_breaks_single() {
+        assert_eq!(segments_from_breaks(&[], 1), vec![0..1]);
+        assert_eq!(segments_from_breaks(&[0], 1), vec![0..1]);
+        assert_eq!(segments_from_breaks(&[1], 1), vec![0..1]);
+    }
+
+    #[test]
+    fn segments_from_breaks_repeated() {
+        assert_eq!(
+            segments_from_breaks(&[1, 1, 3, 3], 4),
+            vec![0..1, 1..3, 3..4]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "breaks must be sorted")]
+    fn segments_from_breaks_unsorted() {
+        segments_from_breaks(&[2, 1], 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "breaks must not exceed n")]
+    fn segments_from_breaks_too_large() {
+        segments_from_breaks(&[4], 3);
+    }
+
+    /// Relax a layer by trying all split points `k ≤ j`.
+    fn brute_force_layer(
+        cost: &impl Fn(usize, usize, usize) -> u64,
+        l: usize,
+        offset: usize,
+        prev: &[u64],
+        ncols: usize,
+    ) -> Vec<(usize, u64)> {
+        (0..ncols)
+            .map(|j| {
+                (0..prev.len().min(j + 1))
+                    .map(|k| (k, prev[k] + cost(l, offset + k, offset + j)))
+                    .min_by_key(|&(k, value)| (value, k))
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// Compute all layers while storing every choice, and follow
+    /// the choices back from the last position.
+    fn stored_choices(
+        cost: &impl Fn(usize, usize, usize) -> u64,
+        initial: &[u64],
+        layers: usize,
+    ) -> (u64, Vec<usize>) {
+        let n = initial.len();
+        let mut layer = initial.to_vec();
+        let mut choices = Vec::new();
+        for l in 1..=layers {
+            let next = brute_force_layer(cost, l, 0, &layer, n);
+            choices.push(next.iter().map(|&(k, _)| k).collect::<Vec<_>>());
+            layer = next.into_iter().map(|(_, value)| value).collect();
+        }
+        let mut path = vec![n - 1];
+        for choice in choices.iter().rev() {
+            path.push(choice[path[path.len() - 1]]);
+        }
+        path.reverse();
+        (layer[n - 1], path)
+    }
+
+    /// Cost of following a path through the layers.
+    fn path_cost(
+        cost: &impl Fn(usize, usize, usize) -> u64,
+        initial: &[u64],
+        path: &[usize],
+    ) -> u64 {
+        let steps = path.windows(2).enumerate();
+        initial[path[0]] + steps.map(|(l, w)| cost(l + 1, w[0], w[1])).sum::<u64>()
+    }
+
+    #[test]
+    fn hirschberg_reconstruct_no_layers() {
+        let path = hirschberg_reconstruct(&[5, 3, 4], 0, |_, _, _: &[u64], _| unreachable!());
+        assert_eq!(path, vec![2]);
+    }
+
+    #[test]
+    fn hirschberg_reconstruct_single_position() {
+        let cost = |_: usize, _: usize, _: usize| 1;
+        let path = hirschberg_reconstruct(&[0], 5, |l, offset, prev, ncols| {
+            brute_force_layer(&cost, l, offset, prev, ncols)
+        });
+        assert_eq!(path, vec![0; 6]);
+    }
+
+    #[test]
+    fn hirschberg_reconstruct_matches_stored_choices() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..200 {
+            let n = rng.gen_range(1..20);
+            let layers = rng.gen_range(0..10);
+            let initial = (0..n).map(|_| rng.gen_range(0..50)).collect::<Vec<u64>>();
+            // Random costs which differ from layer to layer, with many
+            // ties between split points.
+            let table = (0..=layers)
+                .map(|_| {
+                    (0..n)
+                        .map(|_| (0..n).map(|_| rng.gen_range(0..10)).collect::<Vec<u64>>())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let cost = |l: usize, k: usize, j: usize| table[l][k][j];
+
+            let path = hirschberg_reconstruct(&initial, layers, |l, offset, prev, ncols| {
+                brute_force_layer(&cost, l, offset, prev, ncols)
+            });
+            let (best, stored) = stored_choices(&cost, &initial, layers);
+            assert_eq!(path.len(), layers + 1);
+            assert_eq!(path[layers], n - 1);
+            assert!(path.windows(2).all(|w| w[0] <= w[1]));
+            assert_eq!(path_cost(&cost, &initial, &path), best);
+            assert_eq!(path_cost(&cost, &initial, &stored), best);
+        }
+    }
+
+    #[test]
+    fn hirschberg_reconstruct_matches_without_ties() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for _ in 0..50 {
+            let n = rng.gen_range(1..30);
+            let layers = rng.gen_range(1..12);
+            let mut initial = vec![1_000_000; n];
+            initial[0] = 0;
+            // Ties are unlikely with costs from such a large range.
+            let weights = (0..(layers + 1) * n * n)
+                .map(|_| rng.gen_range(0..1 << 40))
+                .collect::<Vec<u64>>();
+            let cost = |l: usize, k: usize, j: usize| weights[(l * n + k) * n + j];
+            let path = hirschberg_reconstruct(&initial, layers, |l, offset, prev, ncols| {
+                brute_force_layer(&cost, l, offset, prev, ncols)
+            });
+            assert_eq!(path, stored_choices(&cost, &initial, layers).1);
+        }
+    }
+}
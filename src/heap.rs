@@ -0,0 +1,447 @@
+//! Priority queue over the entries of a Monge matrix.
+//!
+//! Shortest path algorithms on dense distance graphs, such as the
+//! Dijkstra variant of Fakcharoenphol and Rao, relax the edges of a
+//! complete bipartite graph whose lengths form a Monge matrix. The
+//! rows are the vertices whose distance is known, and the columns are
+//! the vertices they reach. A [`MongeHeap`] finds the closest column
+//! without looking at all rows for every column.
+//!
+//! Every active row `i` has an offset `d(i)`, and the entries of the
+//! heap are `d(i) + M[i, j]`. Adding a constant to a row keeps a
+//! matrix Monge, so the rows which give the column minima form a
+//! staircase: every active row owns an interval of columns, and the
+//! intervals are ordered like the rows. The heap keeps these
+//! intervals together with the smallest entry in each of them.
+
+use crate::Matrix;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::ops::{Add, Sub};
+
+/// Smallest entry of a row interval, ordered by value, row and
+/// column.
+#[derive(Debug, Clone, Copy)]
+struct Entry<T> {
+    value: T,
+    row: usize,
+    column: usize,
+    version: usize,
+}
+
+impl<T: PartialOrd> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: PartialOrd> Eq for Entry<T> {}
+
+impl<T: PartialOrd> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PartialOrd> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value
+            .partial_cmp(&other.value)
+            .expect("incomparable elements")
+            .then(self.row.cmp(&other.row))
+            .then(self.column.cmp(&other.column))
+    }
+}
+
+/// Priority queue over the entries of a Monge matrix with
+/// activated rows.
+///
+/// Rows are activated with an offset, after which
+/// [`extract_min`](Self::extract_min) repeatedly removes the column
+/// with the smallest entry `offset(i) + M[i, j]` over all active rows
+/// `i`. Every column is extracted at most once. The offset of an
+/// active row can be lowered with
+/// [`decrease_offset`](Self::decrease_offset), which is the decrease
+/// key operation of Dijkstra's algorithm.
+///
+/// The matrix must be Monge. Ties are broken in favor of the smallest
+/// row and then the smallest column.
+///
+/// # Complexity
+///
+/// Let *n* be the number of columns. Activating a row or decreasing
+/// its offset finds the interval of columns it owns with two binary
+/// searches, which evaluate O(log *n*) elements and take O(log² *n*)
+/// time. Every row keeps a segment tree over its columns, which is
+/// filled in lazily when the smallest entry of an interval is needed.
+/// Each element is thus evaluated at most once for this purpose, and
+/// only if the row owns its column at some point. An extracted column
+/// is removed from the tree of a row when it is found there, which
+/// takes O(log *n*) time once for every row and column. Apart from
+/// these one-time costs, [`extract_min`](Self::extract_min) takes
+/// amortized O(log *n*) time.
+///
+/// Memory is proportional to the number of evaluated elements plus
+/// the number of rows and columns.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::heap::MongeHeap;
+///
+/// let matrix = vec![vec![0, 1, 4, 9],
+///                   vec![4, 1, 0, 1]];
+/// let mut heap = MongeHeap::new(&matrix);
+/// heap.activate_row(0, 10);
+/// heap.activate_row(1, 0);
+/// assert_eq!(heap.extract_min(), Some((1, 2, 0)));
+/// assert_eq!(heap.extract_min(), Some((1, 1, 1)));
+/// heap.decrease_offset(0, 10);
+/// assert_eq!(heap.extract_min(), Some((0, 0, 0)));
+/// assert_eq!(heap.extract_min(), Some((1, 3, 1)));
+/// assert_eq!(heap.extract_min(), None);
+/// ```
+#[derive(Debug)]
+pub struct MongeHeap<'a, T, M> {
+    matrix: &'a M,
+    offsets: Vec<Option<T>>,
+    extracted: Vec<bool>,
+    /// Interval of columns owned by each row, by first column. The
+    /// intervals cover all columns once a row is active.
+    by_column: BTreeMap<usize, (usize, usize)>,
+    /// First column owned by each row which owns columns.
+    by_row: BTreeMap<usize, usize>,
+    /// Segment tree nodes of every row which have been computed.
+    trees: Vec<HashMap<usize, Option<(T, usize)>>>,
+    leaves: usize,
+    /// Only the latest entry of a row in the heap is valid.
+    versions: Vec<usize>,
+    heap: BinaryHeap<Reverse<Entry<T>>>,
+}
+
+impl<'a, T, M> MongeHeap<'a, T, M>
+where
+    T: PartialOrd + Copy + Add<Output = T> + Sub<Output = T>,
+    M: Matrix<T>,
+{
+    /// Create a heap without active rows.
+    pub fn new(matrix: &'a M) -> Self {
+        let (m, n) = (matrix.nrows(), matrix.ncols());
+        MongeHeap {
+            matrix,
+            offsets: vec![None; m],
+            extracted: vec![false; n],
+            by_column: BTreeMap::new(),
+            by_row: BTreeMap::new(),
+            trees: vec![HashMap::new(); m],
+            leaves: n.next_power_of_two(),
+            versions: vec![0; m],
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Activate a row with the given offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the row is already active.
+    pub fn activate_row(&mut self, row: usize, offset: T) {
+        assert!(self.offsets[row].is_none(), "row {} is already active", row);
+        self.offsets[row] = Some(offset);
+        self.insert(row);
+    }
+
+    /// Lower the offset of an active row by `delta`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the row is not active. In debug builds, panics if
+    /// `delta` would increase the offset.
+    pub fn decrease_offset(&mut self, row: usize, delta: T) {
+        let offset = self.offsets[row].unwrap_or_else(|| panic!("row {} is not active", row));
+        let lowered = offset - delta;
+        debug_assert!(
+            lowered.partial_cmp(&offset) != Some(Ordering::Greater),
+            "offset of row {} increased",
+            row
+        );
+        self.offsets[row] = Some(lowered);
+        self.insert(row);
+    }
+
+    /// Remove the column with the smallest entry among the active
+    /// rows and return its row, column and entry.
+    ///
+    /// Returns `None` when there are no active rows or when all
+    /// columns have been extracted.
+    pub fn extract_min(&mut self) -> Option<(usize, usize, T)> {
+        while let Some(Reverse(entry)) = self.heap.pop() {
+            if entry.version != self.versions[entry.row] {
+                continue;
+            }
+            let extracted = self.extracted[entry.column];
+            self.extracted[entry.column] = true;
+            self.remove(entry.row, entry.column);
+            self.push(entry.row);
+            if !extracted {
+                return Some((entry.row, entry.column, entry.value));
+            }
+        }
+        None
+    }
+
+    /// Entry of an active row.
+    fn value(&self, row: usize, column: usize) -> T {
+        self.offsets[row].unwrap() + self.matrix.index(row, column)
+    }
+
+    /// Return `true` if `row` gives a smaller entry in `column` than
+    /// the row which currently owns the column.
+    fn beats(&self, row: usize, column: usize) -> bool {
+        match self.by_column.range(..=column).next_back() {
+            Some((_, &(owner, _))) if owner != row => {
+                (self.value(row, column), row) < (self.value(owner, column), owner)
+            }
+            _ => true,
+        }
+    }
+
+    /// Give `row` the columns where it beats the current owners.
+    fn insert(&mut self, row: usize) {
+        let n = self.extracted.len();
+        // The rows before `row` own the columns before `p`, and the
+        // rows after it own the columns from `q`. The row wins a
+        // suffix of the former and a prefix of the latter.
+        let p = self.by_row.range(row..).next().map_or(n, |(_, &lo)| lo);
+        let q = self.by_row.range(row + 1..).next().map_or(n, |(_, &lo)| lo);
+        let (mut lo, mut hi) = (0, p);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.beats(row, mid) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        let start = lo;
+        let (mut lo, mut hi) = (q, n);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.beats(row, mid) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let end = lo;
+        if start == end {
+            return;
+        }
+
+        // Trim the intervals which overlap with [start, end).
+        let overlapping = self
+            .by_column
+            .range(..end)
+            .rev()
+            .take_while(|(_, &(_, hi))| hi > start)
+            .map(|(&lo, _)| lo)
+            .collect::<Vec<_>>();
+        let mut changed = vec![row];
+        for lo in overlapping {
+            let (owner, hi) = self.by_column.remove(&lo).unwrap();
+            self.by_row.remove(&owner);
+            if owner == row {
+                continue;
+            }
+            if lo < start {
+                self.by_column.insert(lo, (owner, start));
+                self.by_row.insert(owner, lo);
+                changed.push(owner);
+            } else if hi > end {
+                self.by_column.insert(end, (owner, hi));
+                self.by_row.insert(owner, end);
+                changed.push(owner);
+            } else {
+                self.versions[owner] += 1;
+            }
+        }
+        self.by_column.insert(start, (row, end));
+        self.by_row.insert(row, start);
+        for owner in changed {
+            self.push(owner);
+        }
+    }
+
+    /// Push the smallest entry in the interval of `row`, invalidating
+    /// its previous entries.
+    fn push(&mut self, row: usize) {
+        self.versions[row] += 1;
+        let Some(&lo) = self.by_row.get(&row) else {
+            return;
+        };
+        let hi = self.by_column[&lo].1;
+        if let Some((value, column)) = self.query(row, 1, 0, self.leaves, lo, hi) {
+            self.heap.push(Reverse(Entry {
+                value: self.offsets[row].unwrap() + value,
+                row,
+                column,
+                version: self.versions[row],
+            }));
+        }
+    }
+
+    /// Smallest element and its column in `node` of the tree of
+    /// `row`, which covers the columns `lo..hi`.
+    fn node(&mut self, row: usize, node: usize, lo: usize, hi: usize) -> Option<(T, usize)> {
+        if let Some(&min) = self.trees[row].get(&node) {
+            return min;
+        }
+        let min = if hi - lo == 1 {
+            if lo < self.extracted.len() && !self.extracted[lo] {
+                Some((self.matrix.index(row, lo), lo))
+            } else {
+                None
+            }
+        } else {
+            let mid = lo + (hi - lo) / 2;
+            let left = self.node(row, 2 * node, lo, mid);
+            let right = self.node(row, 2 * node + 1, mid, hi);
+            smaller(left, right)
+        };
+        self.trees[row].insert(node, min);
+        min
+    }
+
+    /// Smallest element and its column of `row` in the columns
+    /// `start..end`, where `node` covers `lo..hi`.
+    fn query(
+        &mut self,
+        row: usize,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        start: usize,
+        end: usize,
+    ) -> Option<(T, usize)> {
+        if end <= lo || hi <= start {
+            return None;
+        }
+        if start <= lo && hi <= end {
+            return self.node(row, node, lo, hi);
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = self.query(row, 2 * node, lo, mid, start, end);
+        let right = self.query(row, 2 * node + 1, mid, hi, start, end);
+        smaller(left, right)
+    }
+
+    /// Remove an extracted column from the tree of `row`.
+    fn remove(&mut self, row: usize, column: usize) {
+        let tree = &mut self.trees[row];
+        let mut node = self.leaves + column;
+        if tree.insert(node, None).is_none() {
+            // The leaf was never computed, so neither were its
+            // ancestors.
+            tree.remove(&node);
+            return;
+        }
+        while node > 1 {
+            node /= 2;
+            if !tree.contains_key(&node) {
+                break;
+            }
+            let min = smaller(tree[&(2 * node)], tree[&(2 * node + 1)]);
+            tree.insert(node, min);
+        }
+    }
+}
+
+/// The smaller of two optional elements with their columns. Ties go
+/// to the smaller column.
+fn smaller<T: PartialOrd>(a: Option<(T, usize)>, b: Option<(T, usize)>) -> Option<(T, usize)> {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            if b < a {
+                Some(b)
+            } else {
+                Some(a)
+            }
+        }
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::distance_matrix;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn monge_heap_empty() {
+        let matrix = vec![vec![1, 2], vec![3, 4]];
+        let mut heap = MongeHeap::new(&matrix);
+        assert_eq!(heap.extract_min(), None);
+        heap.activate_row(1, 0);
+        assert_eq!(heap.extract_min(), Some((1, 0, 3)));
+        assert_eq!(heap.extract_min(), Some((1, 1, 4)));
+        assert_eq!(heap.extract_min(), None);
+        heap.activate_row(0, 0);
+        assert_eq!(heap.extract_min(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "row 0 is already active")]
+    fn monge_heap_activate_twice() {
+        let matrix = vec![vec![1, 2]];
+        let mut heap = MongeHeap::new(&matrix);
+        heap.activate_row(0, 0);
+        heap.activate_row(0, 0);
+    }
+
+    #[test]
+    fn monge_heap_matches_naive() {
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..200 {
+            let (m, n) = (rng.gen_range(1..30), rng.gen_range(1..30));
+            let matrix = distance_matrix(m, n, 30, &mut rng);
+
+            let mut heap = MongeHeap::new(&matrix);
+            let mut offsets = vec![None; m];
+            let mut extracted = vec![false; n];
+            for _ in 0..(3 * (m + n)) {
+                let row = rng.gen_range(0..m);
+                match (rng.gen_range(0..3), offsets[row]) {
+                    (0, None) => {
+                        let offset = rng.gen_range(0..500);
+                        heap.activate_row(row, offset);
+                        offsets[row] = Some(offset);
+                    }
+                    (1, Some(offset)) => {
+                        let delta = rng.gen_range(0..100);
+                        heap.decrease_offset(row, delta);
+                        offsets[row] = Some(offset - delta);
+                    }
+                    _ => {
+                        let matrix = &matrix;
+                        let extracted_ref = &extracted;
+                        let expected = (0..m)
+                            .filter_map(|i| offsets[i].map(|offset| (i, offset)))
+                            .flat_map(|(i, offset)| {
+                                (0..n)
+                                    .filter(move |&j| !extracted_ref[j])
+                                    .map(move |j| (offset + matrix[i][j], i, j))
+                            })
+                            .min()
+                            .map(|(value, i, j)| (i, j, value));
+                        assert_eq!(heap.extract_min(), expected);
+                        if let Some((_, j, _)) = expected {
+                            extracted[j] = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -29,6 +29,31 @@
 //! column, so `minima[0] == 1` since the minimum value in the first
 //! column is 2 (row 1). Note that the smallest row index is returned.
 //!
+//! The matrix elements can be of any `Copy` type which can be
+//! compared, including your own types with a derived `Ord`
+//! implementation. Such types are compared lexicographically field by
+//! field, and the smallest index is only used as a tie-breaker when
+//! the full comparison finds two elements equal:
+//!
+//! ```
+//! use smawk::Matrix;
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+//! struct Cost {
+//!     primary: i64,
+//!     tiebreak: i64,
+//! }
+//!
+//! let cost = |primary, tiebreak| Cost { primary, tiebreak };
+//! let matrix = vec![
+//!     vec![cost(1, 5), cost(2, 0)],
+//!     vec![cost(1, 3), cost(2, 0)],
+//! ];
+//! // Column 0 is decided by the tiebreak field, column 1 by the
+//! // smallest row index since the two costs are equal.
+//! assert_eq!(smawk::column_minima(&matrix), vec![1, 0]);
+//! ```
+//!
 //! # Definitions
 //!
 //! Some of the functions in this crate only work on matrices that are
@@ -91,11 +116,30 @@
 // forbid unsafe code when building with the default features.
 #![cfg_attr(not(feature = "ndarray"), forbid(unsafe_code))]
 
+pub mod applications;
+#[cfg(feature = "ndarray")]
+pub mod batched;
 #[cfg(feature = "ndarray")]
+pub mod blocked;
 pub mod brute_force;
+pub mod cancel;
+pub mod certificate;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+#[cfg(any(test, feature = "test-util"))]
+pub mod debug;
+pub mod dp;
+pub mod heap;
 pub mod monge;
+#[cfg(feature = "rayon")]
+pub mod prefetch;
 #[cfg(feature = "ndarray")]
 pub mod recursive;
+pub mod sparse;
+pub mod unit_monge;
+
+#[cfg(test)]
+mod testing;
 
 /// Minimal matrix trait for two-dimensional arrays.
 ///
@@ -113,6 +157,23 @@ pub trait Matrix<T: Copy> {
     fn ncols(&self) -> usize;
     /// Return a matrix element.
     fn index(&self, row: usize, column: usize) -> T;
+
+    /// Evaluate a batch of matrix elements.
+    ///
+    /// Element `k` of `out` must be set to the element at
+    /// `queries[k]`, given as a `(row, column)` pair. The two slices
+    /// have the same length.
+    ///
+    /// The default implementation calls [`Matrix::index`] once per
+    /// query. Override this if your matrix is cheaper to evaluate many
+    /// elements at a time, for example with SIMD instructions or on a
+    /// GPU. [`row_minima`] and [`column_minima`] use this to evaluate
+    /// the candidates of many columns in one go.
+    fn eval_batch(&self, queries: &[(usize, usize)], out: &mut [T]) {
+        for (value, &(row, column)) in out.iter_mut().zip(queries) {
+            *value = self.index(row, column);
+        }
+    }
 }
 
 /// Simple and inefficient matrix representation used for doctest
@@ -153,11 +214,78 @@ impl<T: Copy> Matrix<T> for ndarray::Array2<T> {
     }
 }
 
+/// Adapter which gives access to the elements of a matrix by
+/// reference.
+///
+/// The functions in this crate need matrix elements which are `Copy`.
+/// Elements such as `num_bigint::BigInt` are not, and cloning them is
+/// expensive. References are `Copy` and compare like the elements
+/// they point to, so a `ByRef` matrix works with all functions which
+/// accept a [`Matrix`] without cloning a single element.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::ByRef;
+/// let matrix = vec![vec![String::from("d"), String::from("b")],
+///                   vec![String::from("c"), String::from("a")]];
+/// assert_eq!(smawk::column_minima(&ByRef(&matrix)), vec![1, 1]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ByRef<'a, S>(pub &'a S);
+
+impl<'a, T> Matrix<&'a T> for ByRef<'a, Vec<Vec<T>>> {
+    fn nrows(&self) -> usize {
+        self.0.len()
+    }
+    fn ncols(&self) -> usize {
+        self.0[0].len()
+    }
+    fn index(&self, row: usize, column: usize) -> &'a T {
+        &self.0[row][column]
+    }
+}
+
+/// Adapting a borrowed [`ndarray::Array2`] to the `Matrix` trait.
+///
+/// **Note: this implementation is only available if you enable the
+/// `ndarray` Cargo feature.**
+#[cfg(feature = "ndarray")]
+impl<'a, T> Matrix<&'a T> for ByRef<'a, ndarray::Array2<T>> {
+    #[inline]
+    fn nrows(&self) -> usize {
+        self.0.nrows()
+    }
+    #[inline]
+    fn ncols(&self) -> usize {
+        self.0.ncols()
+    }
+    #[inline]
+    fn index(&self, row: usize, column: usize) -> &'a T {
+        &self.0[[row, column]]
+    }
+}
+
+/// Matrix size (number of elements) below which [`row_minima`] and
+/// [`column_minima`] use a brute force scan instead of SMAWK.
+///
+/// The SMAWK algorithm has a larger constant overhead than a simple
+/// scan of the matrix. Benchmarks on square matrices show that the
+/// brute force scan is faster up to around 20 ✕ 20 elements.
+///
+/// Use [`row_minima_with_threshold`] and
+/// [`column_minima_with_threshold`] to pick a different threshold.
+pub const BRUTE_FORCE_THRESHOLD: usize = 400;
+
 /// Compute row minima in O(*m* + *n*) time.
 ///
 /// This implements the [SMAWK algorithm] for efficiently finding row
 /// minima in a totally monotone matrix.
 ///
+/// Matrices with fewer than [`BRUTE_FORCE_THRESHOLD`] elements are
+/// scanned by brute force, which is faster for small matrices. The
+/// result is the same in either case.
+///
 /// The SMAWK algorithm is from Agarwal, Klawe, Moran, Shor, and
 /// Wilbur, *Geometric applications of a matrix searching algorithm*,
 /// Algorithmica 2, pp. 195-208 (1987) and the code here is a
@@ -183,14 +311,53 @@ impl<T: Copy> Matrix<T> for ndarray::Array2<T> {
 /// [pads]: https://github.com/jfinkels/PADS/blob/master/pads/smawk.py
 /// [SMAWK algorithm]: https://en.wikipedia.org/wiki/SMAWK_algorithm
 pub fn row_minima<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> Vec<usize> {
+    row_minima_with_threshold(matrix, BRUTE_FORCE_THRESHOLD)
+}
+
+/// Compute row minima with a custom brute force threshold.
+///
+/// This is like [`row_minima`], except that matrices with fewer than
+/// `threshold` elements are scanned by brute force. Use a threshold
+/// of zero to always use the SMAWK algorithm.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::row_minima_with_threshold(&matrix, 0),
+///            vec![1, 1, 3]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero columns.
+pub fn row_minima_with_threshold<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    threshold: usize,
+) -> Vec<usize> {
+    let mut minima = vec![0; matrix.nrows()];
+    if matrix.nrows().saturating_mul(matrix.ncols()) < threshold {
+        brute_force_inner(&|j, i| matrix.index(i, j), matrix.ncols(), &mut minima);
+        return minima;
+    }
     // Benchmarking shows that SMAWK performs roughly the same on row-
     // and column-major matrices.
-    let mut minima = vec![0; matrix.nrows()];
-    smawk_inner(
+    let transposed = std::cell::RefCell::new(Vec::with_capacity(matrix.nrows() + matrix.ncols()));
+    smawk_full_with_sink(
         &|j, i| matrix.index(i, j),
-        &(0..matrix.ncols()).collect::<Vec<_>>(),
-        &(0..matrix.nrows()).collect::<Vec<_>>(),
+        &|queries: &[(usize, usize)], out: &mut [T]| {
+            let mut transposed = transposed.borrow_mut();
+            transposed.clear();
+            transposed.extend(queries.iter().map(|&(j, i)| (i, j)));
+            matrix.eval_batch(&transposed, out);
+        },
+        matrix.ncols(),
+        matrix.nrows(),
         &mut minima,
+        &mut |_, _| {},
     );
     minima
 }
@@ -205,6 +372,10 @@ pub fn smawk_row_minima<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> Vec<u
 /// This implements the [SMAWK algorithm] for efficiently finding
 /// column minima in a totally monotone matrix.
 ///
+/// Matrices with fewer than [`BRUTE_FORCE_THRESHOLD`] elements are
+/// scanned by brute force, which is faster for small matrices. The
+/// result is the same in either case.
+///
 /// The SMAWK algorithm is from Agarwal, Klawe, Moran, Shor, and
 /// Wilbur, *Geometric applications of a matrix searching algorithm*,
 /// Algorithmica 2, pp. 195-208 (1987) and the code here is a
@@ -230,14 +401,57 @@ pub fn smawk_row_minima<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> Vec<u
 /// [SMAWK algorithm]: https://en.wikipedia.org/wiki/SMAWK_algorithm
 /// [pads]: https://github.com/jfinkels/PADS/blob/master/pads/smawk.py
 pub fn column_minima<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> Vec<usize> {
+    column_minima_with_threshold(matrix, BRUTE_FORCE_THRESHOLD)
+}
+
+/// Compute column minima with a custom brute force threshold.
+///
+/// This is like [`column_minima`], except that matrices with fewer
+/// than `threshold` elements are scanned by brute force. Use a
+/// threshold of zero to always use the SMAWK algorithm.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::column_minima_with_threshold(&matrix, 0),
+///            vec![0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_with_threshold<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    threshold: usize,
+) -> Vec<usize> {
     let mut minima = vec![0; matrix.ncols()];
-    smawk_inner(
+    column_minima_into_slice(matrix, threshold, &mut minima);
+    minima
+}
+
+/// Like [`column_minima_with_threshold`], but write the minima to
+/// `minima`, which must have one slot per column.
+fn column_minima_into_slice<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    threshold: usize,
+    minima: &mut [usize],
+) {
+    if matrix.nrows().saturating_mul(matrix.ncols()) < threshold {
+        brute_force_inner(&|i, j| matrix.index(i, j), matrix.nrows(), minima);
+        return;
+    }
+    smawk_full_with_sink(
         &|i, j| matrix.index(i, j),
-        &(0..matrix.nrows()).collect::<Vec<_>>(),
-        &(0..matrix.ncols()).collect::<Vec<_>>(),
-        &mut minima,
+        &|queries: &[(usize, usize)], out: &mut [T]| matrix.eval_batch(queries, out),
+        matrix.nrows(),
+        matrix.ncols(),
+        minima,
+        &mut |_, _| {},
     );
-    minima
 }
 
 #[deprecated(since = "0.3.2", note = "Please use `column_minima` instead.")]
@@ -245,166 +459,4134 @@ pub fn smawk_column_minima<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> Ve
     column_minima(matrix)
 }
 
-/// Compute column minima in the given area of the matrix. The
-/// `minima` slice is updated inplace.
-fn smawk_inner<T: PartialOrd + Copy, M: Fn(usize, usize) -> T>(
-    matrix: &M,
-    rows: &[usize],
-    cols: &[usize],
-    minima: &mut [usize],
-) {
-    if cols.is_empty() {
-        return;
-    }
+/// Adapter which counts the elements read from a matrix.
+struct Counted<'a, M> {
+    matrix: &'a M,
+    count: std::cell::Cell<usize>,
+}
 
-    let mut stack = Vec::with_capacity(cols.len());
-    for r in rows {
-        // TODO: use stack.last() instead of stack.is_empty() etc
-        while !stack.is_empty()
-            && matrix(stack[stack.len() - 1], cols[stack.len() - 1])
-                > matrix(*r, cols[stack.len() - 1])
-        {
-            stack.pop();
-        }
-        if stack.len() != cols.len() {
-            stack.push(*r);
-        }
+impl<T: Copy, M: Matrix<T>> Matrix<T> for Counted<'_, M> {
+    fn nrows(&self) -> usize {
+        self.matrix.nrows()
     }
-    let rows = &stack;
-
-    let mut odd_cols = Vec::with_capacity(1 + cols.len() / 2);
-    for (idx, c) in cols.iter().enumerate() {
-        if idx % 2 == 1 {
-            odd_cols.push(*c);
-        }
+    fn ncols(&self) -> usize {
+        self.matrix.ncols()
     }
-
-    smawk_inner(matrix, rows, &odd_cols, minima);
-
-    let mut r = 0;
-    for (c, &col) in cols.iter().enumerate().filter(|(c, _)| c % 2 == 0) {
-        let mut row = rows[r];
-        let last_row = if c == cols.len() - 1 {
-            rows[rows.len() - 1]
-        } else {
-            minima[cols[c + 1]]
-        };
-        let mut pair = (matrix(row, col), row);
-        while row != last_row {
-            r += 1;
-            row = rows[r];
-            if (matrix(row, col), row) < pair {
-                pair = (matrix(row, col), row);
-            }
-        }
-        minima[col] = pair.1;
+    fn index(&self, row: usize, column: usize) -> T {
+        self.count.set(self.count.get() + 1);
+        self.matrix.index(row, column)
+    }
+    fn eval_batch(&self, queries: &[(usize, usize)], out: &mut [T]) {
+        self.count.set(self.count.get() + queries.len());
+        self.matrix.eval_batch(queries, out);
     }
 }
 
-/// Compute upper-right column minima in O(*m* + *n*) time.
+/// Compute column minima and count the matrix elements read.
 ///
-/// The input matrix must be totally monotone.
+/// This returns the same minima as [`column_minima`] together with
+/// the number of elements it read, whether one at a time with
+/// [`Matrix::index`] or in batches with [`Matrix::eval_batch`]. An
+/// element which is read twice is counted twice. This makes the
+/// O(*m* + *n*) bound directly observable without wrapping the matrix
+/// in a counter yourself. Matrices with fewer than
+/// [`BRUTE_FORCE_THRESHOLD`] elements are scanned by brute force,
+/// which reads every element at least once.
 ///
-/// The function returns a vector of `(usize, T)`. The `usize` in the
-/// tuple at index `j` tells you the row of the minimum value in
-/// column `j` and the `T` value is minimum value itself.
+/// # Examples
 ///
-/// The algorithm only considers values above the main diagonal, which
-/// means that it computes values `v(j)` where:
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::column_minima_counted(&matrix),
+///            (vec![0, 0, 2, 2], 14));
+/// ```
 ///
-/// ```text
-/// v(0) = initial
-/// v(j) = min { M[i, j] | i < j } for j > 0
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_counted<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> (Vec<usize>, usize) {
+    let counted = Counted {
+        matrix,
+        count: std::cell::Cell::new(0),
+    };
+    let minima = column_minima(&counted);
+    (minima, counted.count.get())
+}
+
+/// Compute column minima with a floor which competes in every column.
+///
+/// This models an extra row which holds the value `floor` in every
+/// column, for example the cost of a fallback option which is always
+/// available. The result holds the row and value of the minimum of
+/// every column. When the minimum of the column in `matrix` is larger
+/// than `floor`, the floor wins and the entry is `(usize::MAX,
+/// floor)`. The sentinel row `usize::MAX` can never be a real row
+/// index. Ties go to the matrix, so the floor only wins when it is
+/// strictly smaller.
+///
+/// The floor row is not given to SMAWK, so it does not need to keep
+/// the matrix totally monotone. A matrix with zero rows is allowed,
+/// in which case the floor wins every column, provided that the
+/// matrix knows its number of columns.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::column_minima_with_floor(&matrix, 3),
+///            vec![(usize::MAX, 3), (0, 2), (2, 3), (2, 1)]);
 /// ```
 ///
-/// If we let `r(j)` denote the row index of the minimum value in
-/// column `j`, the tuples in the result vector become `(r(j), M[r(j),
-/// j])`.
+/// # Panics
 ///
-/// The algorithm is an *online* algorithm, in the sense that `matrix`
-/// function can refer back to previously computed column minima when
-/// determining an entry in the matrix. The guarantee is that we only
-/// call `matrix(i, j)` after having computed `v(i)`. This is
-/// reflected in the `&[(usize, T)]` argument to `matrix`, which grows
-/// as more and more values are computed.
-pub fn online_column_minima<T: Copy + PartialOrd, M: Fn(&[(usize, T)], usize, usize) -> T>(
-    initial: T,
-    size: usize,
-    matrix: M,
+/// A `Vec<Vec<T>>` with zero rows has no row to take the number of
+/// columns from, and its [`Matrix::ncols`] panics. Use a matrix type
+/// which stores its shape, such as an `ndarray::Array2`, for
+/// matrices which can have zero rows.
+pub fn column_minima_with_floor<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    floor: T,
 ) -> Vec<(usize, T)> {
-    let mut result = vec![(0, initial)];
-
-    // State used by the algorithm.
-    let mut finished = 0;
-    let mut base = 0;
-    let mut tentative = 0;
-
-    // Shorthand for evaluating the matrix. We need a macro here since
-    // we don't want to borrow the result vector.
-    macro_rules! m {
-        ($i:expr, $j:expr) => {{
-            assert!($i < $j, "(i, j) not above diagonal: ({}, {})", $i, $j);
-            assert!(
-                $i < size && $j < size,
-                "(i, j) out of bounds: ({}, {}), size: {}",
-                $i,
-                $j,
-                size
-            );
-            matrix(&result[..finished + 1], $i, $j)
-        }};
+    if matrix.nrows() == 0 {
+        return vec![(usize::MAX, floor); matrix.ncols()];
     }
-
-    // Keep going until we have finished all size columns. Since the
-    // columns are zero-indexed, we're done when finished == size - 1.
-    while finished < size - 1 {
-        // First case: we have already advanced past the previous
-        // tentative value. We make a new tentative value by applying
-        // smawk_inner to the largest square submatrix that fits under
-        // the base.
-        let i = finished + 1;
-        if i > tentative {
-            let rows = (base..finished + 1).collect::<Vec<_>>();
-            tentative = std::cmp::min(finished + rows.len(), size - 1);
-            let cols = (finished + 1..tentative + 1).collect::<Vec<_>>();
-            let mut minima = vec![0; tentative + 1];
-            smawk_inner(&|i, j| m![i, j], &rows, &cols, &mut minima);
-            for col in cols {
-                let row = minima[col];
-                let v = m![row, col];
-                if col >= result.len() {
-                    result.push((row, v));
-                } else if v < result[col].1 {
-                    result[col] = (row, v);
-                }
+    column_minima(matrix)
+        .into_iter()
+        .enumerate()
+        .map(|(col, row)| {
+            let value = matrix.index(row, col);
+            if floor < value {
+                (usize::MAX, floor)
+            } else {
+                (row, value)
             }
-            finished = i;
-            continue;
-        }
+        })
+        .collect()
+}
 
-        // Second case: the new column minimum is on the diagonal. All
-        // subsequent ones will be at least as low, so we can clear
-        // out all our work from higher rows. As in the fourth case,
-        // the loss of tentative is amortized against the increase in
-        // base.
-        let diag = m![i - 1, i];
-        if diag < result[i].1 {
-            result[i] = (i - 1, diag);
-            base = i - 1;
-            tentative = i;
-            finished = i;
-            continue;
+/// Compute column minima of a matrix whose entries come from a small
+/// palette of values.
+///
+/// Every entry of `matrix` must be one of the values in `palette`,
+/// which is not checked. The smallest palette value is then a lower
+/// bound for every entry, and an entry which reaches it cannot lose
+/// to a later row. SMAWK uses this to skip evaluations which cannot
+/// change the result: a column scan stops as soon as it finds the
+/// smallest palette value, and when reducing the rows, a row with the
+/// smallest palette value is kept without evaluating the row below.
+///
+/// The result is identical to [`column_minima`], including ties,
+/// which are broken in favor of the smallest row.
+///
+/// This pays off when entries are expensive to compute and many
+/// columns reach the smallest palette value, such as a cost matrix
+/// where most transitions are free. With a large palette the bounds
+/// are rarely hit, and the extra comparisons make this slower than
+/// [`column_minima`].
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![2, 0, 2, 1],
+///                   vec![2, 1, 2, 1],
+///                   vec![2, 1, 1, 0]];
+/// assert_eq!(smawk::column_minima_palette(&matrix, &[0, 1, 2]),
+///            vec![0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to pass an empty palette, a palette with
+/// incomparable values, or to call this on a matrix with zero rows
+/// and a non-zero number of columns.
+pub fn column_minima_palette<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    palette: &[T],
+) -> Vec<usize> {
+    let (&first, rest) = palette.split_first().expect("palette must not be empty");
+    let low = rest.iter().fold(first, |low, &value| {
+        match value
+            .partial_cmp(&low)
+            .expect("incomparable palette values")
+        {
+            std::cmp::Ordering::Less => value,
+            _ => low,
         }
+    });
 
-        // Third case: row i-1 does not supply a column minimum in any
-        // column up to tentative. We simply advance finished while
-        // maintaining the invariant.
-        if m![i - 1, tentative] >= result[tentative].1 {
-            finished = i;
-            continue;
-        }
+    // Nothing is smaller than `low`, so it bounds every row.
+    row_bound_minima(matrix, &|_, _| low, &mut |_, _| {})
+}
 
-        // Fourth and final case: a new column minimum at tentative.
+/// Compute column minima with a lower bound for the elements of every
+/// row.
+///
+/// `row_bound(i, j)` must be a lower bound for `M[i, j]`, for example
+/// the best value which a relaxation allows row `i` to reach in any
+/// column from `j` on. Such a bound is often much cheaper than the
+/// element itself. SMAWK consults it before evaluating an element
+/// which only matters if it beats a value it already knows. When
+/// reducing the rows, a row whose bound is not below the top of the
+/// stack cannot pop it. When interpolating an even column, a
+/// candidate row whose bound is not below the best value found between
+/// the bracketing odd columns is skipped.
+///
+/// The result is identical to [`column_minima`], including ties,
+/// which are broken in favor of the smallest row. A bound which is
+/// larger than its element can give wrong minima, use
+/// [`column_minima_with_row_bound_checked`] to find it.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*) evaluations of
+/// both the matrix and the bound.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// // Row 1 never gets below 3.
+/// let bound = |i, j| if i == 1 { 3 } else { 0 };
+/// assert_eq!(smawk::column_minima_with_row_bound(&matrix, bound),
+///            vec![0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows and a
+/// non-zero number of columns.
+pub fn column_minima_with_row_bound<T, M, B>(matrix: &M, row_bound: B) -> Vec<usize>
+where
+    T: PartialOrd + Copy,
+    M: Matrix<T>,
+    B: Fn(usize, usize) -> T,
+{
+    row_bound_minima(matrix, &row_bound, &mut |_, _| {})
+}
+
+/// Compute column minima with a lower bound for every row and verify
+/// the bounds.
+///
+/// This computes the same minima as [`column_minima_with_row_bound`].
+/// Afterwards, every element which was skipped because of its bound
+/// is evaluated and compared against the bound. This evaluates the
+/// skipped elements after all, so it is meant as a debug mode while
+/// developing the bounds.
+///
+/// # Panics
+///
+/// Panics if a bound which caused an element to be skipped is larger
+/// than the element.
+pub fn column_minima_with_row_bound_checked<T, M, B>(matrix: &M, row_bound: B) -> Vec<usize>
+where
+    T: PartialOrd + Copy,
+    M: Matrix<T>,
+    B: Fn(usize, usize) -> T,
+{
+    let mut skipped = Vec::new();
+    let minima = row_bound_minima(matrix, &row_bound, &mut |i, j| skipped.push((i, j)));
+    for (row, column) in skipped {
+        assert!(
+            row_bound(row, column) <= matrix.index(row, column),
+            "wrong bound: row_bound({}, {}) is larger than the element",
+            row,
+            column
+        );
+    }
+    minima
+}
+
+/// Column minima with a row bound, calling `skipped(i, j)` for every
+/// element which the bound saved.
+fn row_bound_minima<T, M, B, S>(matrix: &M, row_bound: &B, skipped: &mut S) -> Vec<usize>
+where
+    T: PartialOrd + Copy,
+    M: Matrix<T>,
+    B: Fn(usize, usize) -> T,
+    S: FnMut(usize, usize),
+{
+    let mut minima = vec![0; matrix.ncols()];
+    if matrix.ncols() == 0 {
+        return minima;
+    }
+    assert!(matrix.nrows() > 0, "cannot compute minima with zero rows");
+    smawk_full_with_skip(
+        &|i, j| matrix.index(i, j),
+        matrix.nrows(),
+        matrix.ncols(),
+        &mut minima,
+        &mut RowBoundSkip { row_bound, skipped },
+    );
+    minima
+}
+
+/// Lower bound which lets SMAWK skip an element that cannot beat a
+/// value it already knows.
+trait Skip<T> {
+    /// Whether the bound is consulted at all. Without a bound, the
+    /// interpolation step evaluates its candidates in one batch.
+    const ACTIVE: bool = true;
+
+    /// Return whether `M[row, col]` cannot get below `best`, the value
+    /// of an earlier row in the same column.
+    fn skip(&mut self, best: T, row: usize, col: usize) -> bool;
+}
+
+/// The plain SMAWK algorithm, which evaluates every element it
+/// compares.
+struct NoSkip;
+
+impl<T> Skip<T> for NoSkip {
+    const ACTIVE: bool = false;
+
+    #[inline]
+    fn skip(&mut self, _: T, _: usize, _: usize) -> bool {
+        false
+    }
+}
+
+/// Skip the elements whose `row_bound` is not below the best value,
+/// calling `skipped(row, col)` for each of them.
+struct RowBoundSkip<'a, B, S> {
+    row_bound: &'a B,
+    skipped: &'a mut S,
+}
+
+impl<T, B, S> Skip<T> for RowBoundSkip<'_, B, S>
+where
+    T: PartialOrd,
+    B: Fn(usize, usize) -> T,
+    S: FnMut(usize, usize),
+{
+    fn skip(&mut self, best: T, row: usize, col: usize) -> bool {
+        if best <= (self.row_bound)(row, col) {
+            (self.skipped)(row, col);
+            return true;
+        }
+        false
+    }
+}
+
+/// Element wrapper which panics when two elements are incomparable.
+#[derive(Debug, Clone, Copy)]
+struct Comparable<T>(T);
+
+impl<T: PartialOrd> PartialEq for Comparable<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(std::cmp::Ordering::Equal)
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for Comparable<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.0.partial_cmp(&other.0).expect("incomparable elements"))
+    }
+}
+
+/// Adapter which wraps every element of a matrix in [`Comparable`].
+struct ComparableMatrix<'a, M>(&'a M);
+
+impl<T: Copy, M: Matrix<T>> Matrix<Comparable<T>> for ComparableMatrix<'_, M> {
+    fn nrows(&self) -> usize {
+        self.0.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.0.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> Comparable<T> {
+        Comparable(self.0.index(row, column))
+    }
+}
+
+/// Element of a band matrix where entries outside the band are
+/// infinite.
+///
+/// Variants are ordered by declaration. An entry above the band of
+/// its column loses to every present entry, and to entries further
+/// down. An entry below the band loses to everything, and to entries
+/// further up. When the first and last columns of the band are
+/// non-decreasing from row to row, this ordering makes the band
+/// matrix totally monotone if the present entries are Monge.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) enum BandKey<T> {
+    Present(T),
+    Above(std::cmp::Reverse<usize>),
+    Below(usize),
+}
+
+/// Compute row minima and panic on incomparable elements.
+///
+/// This is like [`row_minima`], but every comparison between two
+/// elements must succeed. The functions in this crate otherwise
+/// treat incomparable elements, such as a floating point NaN, as
+/// not smaller than each other, which silently gives arbitrary
+/// minima. Use this function for a type which only implements
+/// `PartialOrd`, but whose values are known to be totally ordered.
+/// A violation of this assumption is then reported loudly.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4.0, 2.0, 4.0, 3.0],
+///                   vec![5.0, 3.0, 5.0, 3.0],
+///                   vec![5.0, 3.0, 3.0, 1.0]];
+/// assert_eq!(smawk::row_minima_partial(&matrix), vec![1, 1, 3]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero columns. The
+/// function panics with the message "incomparable elements" if
+/// `partial_cmp` returns `None` for two elements it compares.
+pub fn row_minima_partial<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> Vec<usize> {
+    row_minima(&ComparableMatrix(matrix))
+}
+
+/// Compute column minima and panic on incomparable elements.
+///
+/// This is like [`column_minima`], but every comparison between two
+/// elements must succeed. See [`row_minima_partial`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4.0, 2.0, 4.0, 3.0],
+///                   vec![5.0, 3.0, 5.0, 3.0],
+///                   vec![5.0, 3.0, 3.0, 1.0]];
+/// assert_eq!(smawk::column_minima_partial(&matrix), vec![0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows. The
+/// function panics with the message "incomparable elements" if
+/// `partial_cmp` returns `None` for two elements it compares.
+pub fn column_minima_partial<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> Vec<usize> {
+    column_minima(&ComparableMatrix(matrix))
+}
+
+/// Total order key for `f32` elements.
+///
+/// Keys are ordered by [`f32::total_cmp`] after replacing every NaN
+/// with positive infinity.
+#[derive(Debug, Clone, Copy)]
+struct TotalF32(f32);
+
+impl TotalF32 {
+    fn key(self) -> f32 {
+        if self.0.is_nan() {
+            f32::INFINITY
+        } else {
+            self.0
+        }
+    }
+}
+
+impl PartialEq for TotalF32 {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for TotalF32 {}
+
+impl PartialOrd for TotalF32 {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TotalF32 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().total_cmp(&other.key())
+    }
+}
+
+/// Adapter which wraps every element of an `f32` matrix in
+/// [`TotalF32`].
+struct TotalF32Matrix<'a, M>(&'a M);
+
+impl<M: Matrix<f32>> Matrix<TotalF32> for TotalF32Matrix<'_, M> {
+    fn nrows(&self) -> usize {
+        self.0.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.0.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> TotalF32 {
+        TotalF32(self.0.index(row, column))
+    }
+}
+
+/// Compute row minima of an `f32` matrix with a total order.
+///
+/// This is like [`row_minima`], but the elements are compared with
+/// [`f32::total_cmp`], and every NaN is treated as positive infinity.
+/// A NaN thus never wins against a number, and ties with infinity and
+/// other NaNs like equal elements do: the smallest index wins. The
+/// total order puts `-0.0` before `0.0`, so `-0.0` wins against an
+/// earlier `0.0`.
+///
+/// With [`row_minima`], a NaN compares as neither smaller nor larger
+/// than anything, which makes the result depend on where it is
+/// compared. Here the result is deterministic. The matrix must still
+/// be totally monotone when NaN is replaced by infinity.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![f32::NAN, 2.0, 4.0, 3.0],
+///                   vec![5.0, 3.0, 5.0, 3.0],
+///                   vec![5.0, 3.0, 3.0, -0.0]];
+/// assert_eq!(smawk::row_minima_f32(&matrix), vec![1, 1, 3]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero columns.
+pub fn row_minima_f32<M: Matrix<f32>>(matrix: &M) -> Vec<usize> {
+    row_minima(&TotalF32Matrix(matrix))
+}
+
+/// Compute column minima of an `f32` matrix with a total order.
+///
+/// This is like [`column_minima`], but the elements are compared with
+/// [`f32::total_cmp`] and NaN is treated as positive infinity. See
+/// [`row_minima_f32`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![f32::NAN, 2.0, 0.0, 3.0],
+///                   vec![5.0, 3.0, -0.0, 3.0],
+///                   vec![5.0, 3.0, 3.0, 1.0]];
+/// assert_eq!(smawk::column_minima_f32(&matrix), vec![1, 0, 1, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_f32<M: Matrix<f32>>(matrix: &M) -> Vec<usize> {
+    column_minima(&TotalF32Matrix(matrix))
+}
+
+/// Comparator together with the results of its calls.
+///
+/// The results are keyed by the positions of the two elements, with
+/// the smaller position first.
+struct CmpCache<C> {
+    cmp: C,
+    results: std::cell::RefCell<std::collections::HashMap<[(usize, usize); 2], std::cmp::Ordering>>,
+}
+
+/// Element wrapper which compares elements with a [`CmpCache`].
+struct CachedCmp<'a, T, C> {
+    position: (usize, usize),
+    value: T,
+    cache: &'a CmpCache<C>,
+}
+
+impl<T: Copy, C> Clone for CachedCmp<'_, T, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Copy, C> Copy for CachedCmp<'_, T, C> {}
+
+impl<T, C: Fn(&T, &T) -> std::cmp::Ordering> PartialEq for CachedCmp<'_, T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(std::cmp::Ordering::Equal)
+    }
+}
+
+impl<T, C: Fn(&T, &T) -> std::cmp::Ordering> PartialOrd for CachedCmp<'_, T, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        if self.position == other.position {
+            return Some(std::cmp::Ordering::Equal);
+        }
+        let (first, second) = match self.position < other.position {
+            true => (self, other),
+            false => (other, self),
+        };
+        let key = [first.position, second.position];
+        let cached = self.cache.results.borrow().get(&key).copied();
+        let ordering = cached.unwrap_or_else(|| {
+            let ordering = (self.cache.cmp)(&first.value, &second.value);
+            self.cache.results.borrow_mut().insert(key, ordering);
+            ordering
+        });
+        match self.position < other.position {
+            true => Some(ordering),
+            false => Some(ordering.reverse()),
+        }
+    }
+}
+
+/// Adapter which wraps every element of a matrix in [`CachedCmp`].
+struct CachedCmpMatrix<'a, M, C> {
+    matrix: &'a M,
+    cache: &'a CmpCache<C>,
+}
+
+impl<'a, T: Copy, M: Matrix<T>, C> Matrix<CachedCmp<'a, T, C>> for CachedCmpMatrix<'a, M, C> {
+    fn nrows(&self) -> usize {
+        self.matrix.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.matrix.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> CachedCmp<'a, T, C> {
+        CachedCmp {
+            position: (row, column),
+            value: self.matrix.index(row, column),
+            cache: self.cache,
+        }
+    }
+}
+
+/// Compute row minima with a comparator whose results are cached.
+///
+/// This is like [`row_minima`], except that elements are compared
+/// with `cmp` instead of `PartialOrd`. The SMAWK algorithm can compare
+/// the same two elements more than once, in different steps of the
+/// recursion. Here the result of every call to `cmp` is stored under
+/// the positions of the two elements and reused when they are
+/// compared again, so `cmp` is called at most once per pair.
+///
+/// This trades memory for fewer comparator calls: the cache holds one
+/// entry for every distinct pair, which is O(*m* + *n*) entries for an
+/// *m* ✕ *n* matrix, and every comparison costs a hash table lookup.
+/// It pays off when `cmp` is expensive, such as when it compares long
+/// strings or runs a simulation. The comparator must be consistent,
+/// otherwise the cache hides the inconsistency.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::row_minima_cached_cmp(&matrix, |a: &i32, b: &i32| a.cmp(b)),
+///            vec![1, 1, 3]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero columns.
+pub fn row_minima_cached_cmp<T, M, C>(matrix: &M, cmp: C) -> Vec<usize>
+where
+    T: Copy,
+    M: Matrix<T>,
+    C: Fn(&T, &T) -> std::cmp::Ordering,
+{
+    let cache = CmpCache {
+        cmp,
+        results: Default::default(),
+    };
+    row_minima(&CachedCmpMatrix {
+        matrix,
+        cache: &cache,
+    })
+}
+
+/// Compute column minima with a comparator whose results are cached.
+///
+/// This is like [`column_minima`], except that elements are compared
+/// with `cmp` instead of `PartialOrd`. See [`row_minima_cached_cmp`]
+/// for details on the cache.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec!["dd", "b", "dd", "c"],
+///                   vec!["eee", "c", "eee", "c"],
+///                   vec!["eee", "c", "c", "a"]];
+/// let by_length = |a: &&str, b: &&str| a.len().cmp(&b.len()).then(a.cmp(b));
+/// assert_eq!(smawk::column_minima_cached_cmp(&matrix, by_length),
+///            vec![0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_cached_cmp<T, M, C>(matrix: &M, cmp: C) -> Vec<usize>
+where
+    T: Copy,
+    M: Matrix<T>,
+    C: Fn(&T, &T) -> std::cmp::Ordering,
+{
+    let cache = CmpCache {
+        cmp,
+        results: Default::default(),
+    };
+    column_minima(&CachedCmpMatrix {
+        matrix,
+        cache: &cache,
+    })
+}
+
+/// Element wrapper which compares elements with a comparator.
+struct ByCmp<'a, T, C> {
+    value: T,
+    cmp: &'a C,
+}
+
+impl<T: Copy, C> Clone for ByCmp<'_, T, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Copy, C> Copy for ByCmp<'_, T, C> {}
+
+impl<T, C: Fn(&T, &T) -> std::cmp::Ordering> PartialEq for ByCmp<'_, T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.cmp)(&self.value, &other.value) == std::cmp::Ordering::Equal
+    }
+}
+
+impl<T, C: Fn(&T, &T) -> std::cmp::Ordering> PartialOrd for ByCmp<'_, T, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some((self.cmp)(&self.value, &other.value))
+    }
+}
+
+/// Implicit matrix whose elements are compared with a comparator.
+struct ByCmpMatrix<'a, E, C> {
+    nrows: usize,
+    ncols: usize,
+    eval: &'a E,
+    cmp: &'a C,
+}
+
+impl<'a, T: Copy, E: Fn(usize, usize) -> T, C> Matrix<ByCmp<'a, T, C>> for ByCmpMatrix<'a, E, C> {
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+    fn index(&self, row: usize, column: usize) -> ByCmp<'a, T, C> {
+        ByCmp {
+            value: (self.eval)(row, column),
+            cmp: self.cmp,
+        }
+    }
+}
+
+/// Compute column minima and their values with a comparator.
+///
+/// The elements of the implicit *m* ✕ *n* matrix are computed with
+/// `eval` and compared with `cmp`. For every column, the row of the
+/// smallest element according to `cmp` is returned together with the
+/// element. Ties go to the smallest row, like in [`column_minima`].
+///
+/// This covers the other variants of the minima functions: a reversed
+/// comparator gives column maxima, `f64::total_cmp` gives minima of
+/// floating point numbers, and comparing by a key gives minima by
+/// that key. The element type can also hold more than the compared
+/// value, such as the state of a dynamic program over a semiring.
+///
+/// The matrix must be totally monotone with respect to `cmp`: for all
+/// `i < i'` and `j < j'`, if `cmp(M[i, j], M[i', j])` is `Greater`,
+/// then `cmp(M[i, j'], M[i', j'])` must be `Greater` too. The
+/// comparator must be a total order.
+///
+/// Running time: O(*m* + *n*) calls to `eval` and `cmp`. Every column
+/// minimum is evaluated once more at the end to return its value.
+///
+/// # Examples
+///
+/// ```
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let eval = |i: usize, j: usize| matrix[i][j];
+/// assert_eq!(smawk::column_minima_by(3, 4, eval, |a, b| a.cmp(b)),
+///            vec![(0, 4), (0, 2), (2, 3), (2, 1)]);
+///
+/// // Column maxima of the negated matrix.
+/// let negated = |i: usize, j: usize| -matrix[i][j];
+/// assert_eq!(smawk::column_minima_by(3, 4, negated, |a, b| b.cmp(a)),
+///            vec![(0, -4), (0, -2), (2, -3), (2, -1)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with zero rows and a non-zero number
+/// of columns.
+pub fn column_minima_by<T, E, C>(nrows: usize, ncols: usize, eval: E, cmp: C) -> Vec<(usize, T)>
+where
+    T: Copy,
+    E: Fn(usize, usize) -> T,
+    C: Fn(&T, &T) -> std::cmp::Ordering,
+{
+    let matrix = ByCmpMatrix {
+        nrows,
+        ncols,
+        eval: &eval,
+        cmp: &cmp,
+    };
+    column_minima(&matrix)
+        .into_iter()
+        .enumerate()
+        .map(|(col, row)| (row, eval(row, col)))
+        .collect()
+}
+
+/// Find the first column in every row where the row drops to or
+/// below a threshold.
+///
+/// The result holds, for every row `i`, the smallest column `j` with
+/// `M[i, j] <= threshold`, or `None` if the row stays above the
+/// threshold.
+///
+/// The matrix must be totally monotone, so that [`row_minima`] finds
+/// the minimum of every row, and every row must be non-increasing from
+/// its first column up to its minimum. What happens after the minimum
+/// does not matter. This holds for rows which are unimodal around
+/// their minimum, such as the rows of `(x[i] - y[j])²` for sorted `x`
+/// and `y`. The first crossing then lies on the descending flank of
+/// the row, where it is found by binary search, and rows whose
+/// minimum is above the threshold have no crossing.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* log *n* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 3, 5],
+///                   vec![6, 4, 3, 4],
+///                   vec![9, 7, 5, 2]];
+/// assert_eq!(smawk::row_threshold_crossings(&matrix, 3),
+///            vec![Some(1), Some(2), Some(3)]);
+/// assert_eq!(smawk::row_threshold_crossings(&matrix, 2),
+///            vec![Some(1), None, Some(3)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with rows, but zero
+/// columns.
+pub fn row_threshold_crossings<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    threshold: T,
+) -> Vec<Option<usize>> {
+    if matrix.nrows() == 0 {
+        return vec![];
+    }
+    row_minima(matrix)
+        .into_iter()
+        .enumerate()
+        .map(|(row, minimum)| {
+            (matrix.index(row, minimum) <= threshold).then(|| {
+                // The row is non-increasing up to its minimum, so the
+                // columns at or below the threshold form a suffix there.
+                let (mut lo, mut hi) = (0, minimum);
+                while lo < hi {
+                    let mid = lo + (hi - lo) / 2;
+                    if matrix.index(row, mid) <= threshold {
+                        hi = mid;
+                    } else {
+                        lo = mid + 1;
+                    }
+                }
+                lo
+            })
+        })
+        .collect()
+}
+
+/// Adapter which breaks ties in favor of the largest row.
+struct LastTie<'a, M>(&'a M);
+
+impl<T: Copy, M: Matrix<T>> Matrix<(T, std::cmp::Reverse<usize>)> for LastTie<'_, M> {
+    fn nrows(&self) -> usize {
+        self.0.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.0.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> (T, std::cmp::Reverse<usize>) {
+        (self.0.index(row, column), std::cmp::Reverse(row))
+    }
+}
+
+/// Add two counts, modulo `modulus` if given.
+fn add_count(count: u64, other: u64, modulus: Option<u64>) -> u64 {
+    match modulus {
+        Some(modulus) => ((count as u128 + other as u128) % modulus as u128) as u64,
+        None => count
+            .checked_add(other)
+            .expect("count overflows u64, use a modulus"),
+    }
+}
+
+/// Compute column minima and count the rows which attain them.
+///
+/// For every column, the result holds the first row of the minimum,
+/// the minimum itself, and the number of rows with an element equal
+/// to the minimum, reduced modulo `modulus` if given. This counts the
+/// optimal predecessor choices of a dynamic program.
+///
+/// The rows attaining a minimum need not be contiguous: a single
+/// column can hold the values 0, 1, 0. They do lie between the first
+/// and the last row of the minimum, which both form monotone
+/// staircases in a Monge matrix. The function finds the two
+/// staircases with SMAWK, ordering ties by increasing and by
+/// decreasing row, and then compares the rows between them with the
+/// minimum. The matrix must be Monge, or at least satisfy `M[i, j] >=
+/// M[i', j]` ⇒ `M[i, j'] >= M[i', j']` for `i < i'` and `j < j'` in
+/// addition to being totally monotone.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n* + *w*), where
+/// *w* is the total number of rows between the first and the last row
+/// of the minima. This is linear when the ties are few, and O(*mn*)
+/// for a constant matrix.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![1, 2, 4],
+///                   vec![1, 1, 2],
+///                   vec![2, 1, 1]];
+/// assert_eq!(smawk::column_minima_counting(&matrix, None),
+///            vec![(0, 1, 2), (1, 1, 2), (2, 1, 1)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows, or with a
+/// modulus of zero.
+pub fn column_minima_counting<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    modulus: Option<u64>,
+) -> Vec<(usize, T, u64)> {
+    assert_ne!(modulus, Some(0), "modulus must not be zero");
+    let last = column_minima(&LastTie(matrix));
+    column_minima(matrix)
+        .into_iter()
+        .zip(last)
+        .enumerate()
+        .map(|(col, (first, last))| {
+            let value = matrix.index(first, col);
+            let count = (first..=last)
+                .filter(|&row| row == first || matrix.index(row, col) == value)
+                .fold(0, |count, _| add_count(count, 1, modulus));
+            (first, value, count)
+        })
+        .collect()
+}
+
+/// Compute both row and column minima with shared evaluations.
+///
+/// This returns the same as `(row_minima(matrix),
+/// column_minima(matrix))`, but evaluates each matrix element at most
+/// once. This is useful when the elements are expensive to compute.
+///
+/// The two runs of SMAWK share a cache of the evaluated elements.
+/// Both tend to evaluate elements close to the staircase of minima,
+/// so a good fraction of the evaluations are shared. Matrices with
+/// fewer than [`BRUTE_FORCE_THRESHOLD`] elements are scanned once by
+/// brute force, which gives both the row and column minima.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*) evaluations and
+/// expected O(*m* + *n*) time for the cache.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::all_minima(&matrix),
+///            (vec![1, 1, 3], vec![0, 0, 2, 2]));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows or zero
+/// columns.
+pub fn all_minima<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> (Vec<usize>, Vec<usize>) {
+    let (nrows, ncols) = (matrix.nrows(), matrix.ncols());
+    assert!(
+        nrows > 0 && ncols > 0,
+        "cannot compute minima of an empty matrix"
+    );
+
+    let mut row_minima = vec![0; nrows];
+    let mut column_minima = vec![0; ncols];
+    if nrows.saturating_mul(ncols) < BRUTE_FORCE_THRESHOLD {
+        let mut row_values = vec![None; nrows];
+        for (j, column_minimum) in column_minima.iter_mut().enumerate() {
+            let mut column_value = None;
+            for (i, row_value) in row_values.iter_mut().enumerate() {
+                let value = matrix.index(i, j);
+                // Strict comparisons keep the smallest index on ties.
+                if column_value.is_none_or(|v| value < v) {
+                    column_value = Some(value);
+                    *column_minimum = i;
+                }
+                if row_value.is_none_or(|v| value < v) {
+                    *row_value = Some(value);
+                    row_minima[i] = j;
+                }
+            }
+        }
+        return (row_minima, column_minima);
+    }
+
+    let cache = std::cell::RefCell::new(std::collections::HashMap::new());
+    let lookup = |i: usize, j: usize| {
+        *cache
+            .borrow_mut()
+            .entry((i, j))
+            .or_insert_with(|| matrix.index(i, j))
+    };
+    let batch = |queries: &[(usize, usize)], out: &mut [T]| {
+        let mut missing = Vec::new();
+        {
+            let cache = cache.borrow();
+            for (k, (value, query)) in out.iter_mut().zip(queries).enumerate() {
+                match cache.get(query) {
+                    Some(&v) => *value = v,
+                    None => missing.push(k),
+                }
+            }
+        }
+        if missing.is_empty() {
+            return;
+        }
+        let missing_queries = missing.iter().map(|&k| queries[k]).collect::<Vec<_>>();
+        let mut values = missing.iter().map(|&k| out[k]).collect::<Vec<_>>();
+        matrix.eval_batch(&missing_queries, &mut values);
+        let mut cache = cache.borrow_mut();
+        for ((k, query), value) in missing.into_iter().zip(missing_queries).zip(values) {
+            out[k] = value;
+            cache.insert(query, value);
+        }
+    };
+
+    let rows = (0..nrows).collect::<Vec<_>>();
+    let cols = (0..ncols).collect::<Vec<_>>();
+    smawk_inner_batched(
+        &|j, i| lookup(i, j),
+        &|queries: &[(usize, usize)], out: &mut [T]| {
+            let transposed = queries.iter().map(|&(j, i)| (i, j)).collect::<Vec<_>>();
+            batch(&transposed, out);
+        },
+        &cols,
+        &rows,
+        &mut row_minima,
+    );
+    smawk_inner_batched(&lookup, &batch, &rows, &cols, &mut column_minima);
+    (row_minima, column_minima)
+}
+
+/// Return the number of scratch slots needed by
+/// [`column_minima_in_scratch`] for a matrix of the given size.
+///
+/// SMAWK needs lists of row and column indices on every level of its
+/// recursion. The lists on the first level hold all `rows` and `cols`
+/// indices. On each level, the rows are reduced to at most as many as
+/// there are columns, and the odd columns are passed to the next
+/// level. No scratch space is needed for matrix elements.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(smawk::scratch_size(3, 4), 16);
+/// ```
+pub fn scratch_size(rows: usize, cols: usize) -> usize {
+    checked_scratch_size(rows, cols).expect("scratch size overflows usize")
+}
+
+/// Like [`scratch_size`], but return `None` on overflow.
+fn checked_scratch_size(rows: usize, cols: usize) -> Option<usize> {
+    let mut size = rows.checked_add(cols)?;
+    let mut cols = cols;
+    while cols > 0 {
+        size = size.checked_add(std::cmp::min(rows, cols) + cols / 2)?;
+        cols /= 2;
+    }
+    Some(size)
+}
+
+/// Caller-provided scratch memory for [`column_minima_in_scratch`].
+///
+/// This wraps a buffer of at least [`scratch_size`] slots. The buffer
+/// can be a static array or a stack array, so that no heap memory is
+/// needed.
+#[derive(Debug)]
+pub struct Scratch<'a> {
+    buffer: &'a mut [usize],
+}
+
+impl<'a> Scratch<'a> {
+    /// Wrap a buffer for use as scratch memory.
+    pub fn new(buffer: &'a mut [usize]) -> Self {
+        Scratch { buffer }
+    }
+
+    /// Return the number of slots in the scratch memory.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Return `true` if the scratch memory has no slots.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+/// Compute column minima without allocating memory.
+///
+/// This computes the same minima as [`column_minima`] and stores them
+/// in `out`, but never allocates: all intermediate index lists are
+/// carved out of the `scratch` memory. This makes the function
+/// suitable for real-time code and for targets without a heap
+/// allocator. Unlike [`column_minima`], small matrices are not
+/// scanned by brute force.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::{Matrix, Scratch};
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let mut buffer = [0; 16];
+/// let mut minima = [0; 4];
+/// smawk::column_minima_in_scratch(&matrix, &mut minima, &mut Scratch::new(&mut buffer));
+/// assert_eq!(minima, [0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows, with an
+/// `out` slice of a different length than the number of columns, or
+/// with less than [`scratch_size`] slots of scratch memory.
+pub fn column_minima_in_scratch<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    out: &mut [usize],
+    scratch: &mut Scratch<'_>,
+) {
+    let (nrows, ncols) = (matrix.nrows(), matrix.ncols());
+    assert!(nrows > 0, "cannot compute minima with zero rows");
+    assert_eq!(out.len(), ncols, "expected one output slot per column");
+    assert!(
+        scratch.len() >= scratch_size(nrows, ncols),
+        "need {} slots of scratch memory, got {}",
+        scratch_size(nrows, ncols),
+        scratch.len()
+    );
+
+    let (rows, rest) = scratch.buffer.split_at_mut(nrows);
+    let (cols, rest) = rest.split_at_mut(ncols);
+    for (i, row) in rows.iter_mut().enumerate() {
+        *row = i;
+    }
+    for (j, col) in cols.iter_mut().enumerate() {
+        *col = j;
+    }
+    smawk_in_scratch(&|i, j| matrix.index(i, j), rows, cols, out, rest);
+}
+
+/// Compute column minima into a one-dimensional array view.
+///
+/// This computes the same minima as [`column_minima`], but writes
+/// them to `out` instead of returning a new vector. This is useful
+/// when the minima of many layers of a dynamic program are kept as
+/// the rows of a preallocated `Array2<usize>`: row `l` of it can be
+/// passed as `out`, which avoids allocating a vector per layer and
+/// copying it into the array. A view whose elements are not
+/// contiguous in memory, such as a column of a row-major array, is
+/// filled from a temporary vector instead.
+///
+/// **Note: this function is only available if you enable the
+/// `ndarray` Cargo feature.**
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::Array2;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let mut layers = Array2::zeros((2, 4));
+/// smawk::column_minima_into_view(&matrix, layers.row_mut(1));
+/// assert_eq!(layers.row(1).to_vec(), vec![0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows, or with a
+/// view whose length differs from the number of columns.
+#[cfg(feature = "ndarray")]
+pub fn column_minima_into_view<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    mut out: ndarray::ArrayViewMut1<'_, usize>,
+) {
+    assert_eq!(
+        out.len(),
+        matrix.ncols(),
+        "expected one output slot per column"
+    );
+    match out.as_slice_mut() {
+        Some(out) => column_minima_into_slice(matrix, BRUTE_FORCE_THRESHOLD, out),
+        None => out.assign(&ndarray::Array1::from(column_minima(matrix))),
+    }
+}
+
+/// Like [`smawk_inner`], but with the index lists of every level
+/// taken from `scratch`.
+fn smawk_in_scratch<T: PartialOrd + Copy, M: Fn(usize, usize) -> T>(
+    matrix: &M,
+    rows: &[usize],
+    cols: &[usize],
+    minima: &mut [usize],
+    scratch: &mut [usize],
+) {
+    if cols.is_empty() {
+        return;
+    }
+
+    let (stack, rest) = scratch.split_at_mut(std::cmp::min(rows.len(), cols.len()));
+    let (odd_cols, rest) = rest.split_at_mut(cols.len() / 2);
+
+    let mut len = 0;
+    for &r in rows {
+        while len > 0 && matrix(stack[len - 1], cols[len - 1]) > matrix(r, cols[len - 1]) {
+            len -= 1;
+        }
+        if len != cols.len() {
+            stack[len] = r;
+            len += 1;
+        }
+    }
+    let rows = &stack[..len];
+
+    for (odd, &c) in odd_cols.iter_mut().zip(cols.iter().skip(1).step_by(2)) {
+        *odd = c;
+    }
+
+    smawk_in_scratch(matrix, rows, odd_cols, minima, rest);
+
+    let mut r = 0;
+    for (c, &col) in cols.iter().enumerate().step_by(2) {
+        let mut row = rows[r];
+        let last_row = if c == cols.len() - 1 {
+            rows[rows.len() - 1]
+        } else {
+            minima[cols[c + 1]]
+        };
+        let mut pair = (matrix(row, col), row);
+        while row != last_row {
+            r += 1;
+            row = rows[r];
+            if (matrix(row, col), row) < pair {
+                pair = (matrix(row, col), row);
+            }
+        }
+        minima[col] = pair.1;
+    }
+}
+
+/// Error returned by the `try_*` functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SmawkError {
+    /// A buffer could not be allocated.
+    Alloc,
+}
+
+impl std::fmt::Display for SmawkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmawkError::Alloc => write!(f, "memory allocation failed"),
+        }
+    }
+}
+
+impl std::error::Error for SmawkError {}
+
+/// Source of the buffers allocated up front by the `try_*` functions.
+/// Tests use a source which fails on purpose.
+pub(crate) trait BufferSource {
+    /// Return an empty vector with room for `len` elements.
+    fn with_capacity<U>(&self, len: usize) -> Result<Vec<U>, SmawkError>;
+
+    /// Return a vector with `len` copies of `value`.
+    fn filled<U: Clone>(&self, len: usize, value: U) -> Result<Vec<U>, SmawkError> {
+        let mut buffer = self.with_capacity(len)?;
+        buffer.resize(len, value);
+        Ok(buffer)
+    }
+}
+
+/// Buffers allocated with `Vec::with_capacity`, which aborts when
+/// the allocation fails.
+pub(crate) struct Infallible;
+
+impl BufferSource for Infallible {
+    fn with_capacity<U>(&self, len: usize) -> Result<Vec<U>, SmawkError> {
+        Ok(Vec::with_capacity(len))
+    }
+}
+
+/// Buffers allocated with `Vec::try_reserve_exact`.
+struct Fallible;
+
+impl BufferSource for Fallible {
+    fn with_capacity<U>(&self, len: usize) -> Result<Vec<U>, SmawkError> {
+        let mut buffer = Vec::new();
+        buffer
+            .try_reserve_exact(len)
+            .map_err(|_| SmawkError::Alloc)?;
+        Ok(buffer)
+    }
+}
+
+/// Compute column minima and report allocation failure as an error.
+///
+/// This computes the same minima as [`column_minima`], but the result
+/// and the index lists used by SMAWK are allocated up front with
+/// `try_reserve`. When the allocation fails, for example for an
+/// implicit matrix with billions of columns, `Err(SmawkError::Alloc)`
+/// is returned instead of aborting the process. No other memory is
+/// allocated, see [`column_minima_in_scratch`].
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::try_column_minima(&matrix), Ok(vec![0, 0, 2, 2]));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn try_column_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> Result<Vec<usize>, SmawkError> {
+    try_minima_from(
+        &|i, j| matrix.index(i, j),
+        matrix.nrows(),
+        matrix.ncols(),
+        &Fallible,
+    )
+}
+
+/// Compute row minima and report allocation failure as an error.
+///
+/// This computes the same minima as [`row_minima`], with allocations
+/// handled like in [`try_column_minima`].
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::try_row_minima(&matrix), Ok(vec![1, 1, 3]));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero columns.
+pub fn try_row_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> Result<Vec<usize>, SmawkError> {
+    try_minima_from(
+        &|i, j| matrix.index(j, i),
+        matrix.ncols(),
+        matrix.nrows(),
+        &Fallible,
+    )
+}
+
+/// Compute column minima with the result and scratch memory taken
+/// from `source`.
+fn try_minima_from<T, M, S>(
+    matrix: &M,
+    nrows: usize,
+    ncols: usize,
+    source: &S,
+) -> Result<Vec<usize>, SmawkError>
+where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+    S: BufferSource,
+{
+    assert!(nrows > 0, "cannot compute minima with zero rows");
+    let size = checked_scratch_size(nrows, ncols).ok_or(SmawkError::Alloc)?;
+    let mut minima = source.filled(ncols, 0)?;
+    let mut scratch = source.with_capacity(size)?;
+    scratch.extend(0..nrows);
+    scratch.extend(0..ncols);
+    scratch.resize(size, 0);
+
+    let (rows, rest) = scratch.split_at_mut(nrows);
+    let (cols, rest) = rest.split_at_mut(ncols);
+    smawk_in_scratch(matrix, rows, cols, &mut minima, rest);
+    Ok(minima)
+}
+
+/// Compute column minima of a matrix with labeled rows and columns.
+///
+/// The matrix is given implicitly by `cost(row_label, column_label)`
+/// for all labels in `row_labels` and `column_labels`. The labels can
+/// be arbitrary keys, e.g., timestamps, as long as the matrix is
+/// totally monotone when the rows and columns are ordered as in the
+/// slices. The result holds the row label of the minimum for each
+/// column label.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// let row_labels = [10, 20, 40];
+/// let column_labels = [15, 25, 33];
+/// let minima = smawk::column_minima_labeled(&row_labels, &column_labels, |r: i32, c: i32| {
+///     (r - c).abs()
+/// });
+/// assert_eq!(minima, vec![10, 20, 40]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with no row labels.
+pub fn column_minima_labeled<L: Copy, T: PartialOrd + Copy, C: Fn(L, L) -> T>(
+    row_labels: &[L],
+    column_labels: &[L],
+    cost: C,
+) -> Vec<L> {
+    assert!(!row_labels.is_empty(), "no row labels given");
+    let mut minima = vec![0; column_labels.len()];
+    smawk_inner(
+        &|i, j| cost(row_labels[i], column_labels[j]),
+        &(0..row_labels.len()).collect::<Vec<_>>(),
+        &(0..column_labels.len()).collect::<Vec<_>>(),
+        &mut minima,
+    );
+    minima.into_iter().map(|i| row_labels[i]).collect()
+}
+
+/// Compute the smallest element of every group of columns.
+///
+/// Every column `j` belongs to the group `group(j)`. The result maps
+/// each group to the `(row, column, value)` of its smallest element.
+/// The column minima are computed with SMAWK first and then reduced
+/// within each group, so only whole columns take part: there is no
+/// way to restrict a group to some of the rows. On ties, the smallest
+/// column wins, and within it the smallest row.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*) plus *n* calls
+/// to `group` and hash map operations.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let best = smawk::column_minima_grouped(&matrix, |j| j % 2);
+/// assert_eq!(best[&0], (2, 2, 3));
+/// assert_eq!(best[&1], (2, 3, 1));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_grouped<T, M, G, F>(
+    matrix: &M,
+    group: F,
+) -> std::collections::HashMap<G, (usize, usize, T)>
+where
+    T: PartialOrd + Copy,
+    M: Matrix<T>,
+    G: Eq + std::hash::Hash,
+    F: Fn(usize) -> G,
+{
+    let mut best = std::collections::HashMap::new();
+    for (column, row) in column_minima(matrix).into_iter().enumerate() {
+        let value = matrix.index(row, column);
+        best.entry(group(column))
+            .and_modify(|cell: &mut (usize, usize, T)| {
+                if value < cell.2 {
+                    *cell = (row, column, value);
+                }
+            })
+            .or_insert((row, column, value));
+    }
+    best
+}
+
+/// Compute column minima by a coarse key with ties broken by a fine
+/// key.
+///
+/// The result holds, for each column `j`, the row `i` which minimizes
+/// `(coarse(i, j), fine(i, j), i)` lexicographically. The matrix
+/// given by `coarse` must be totally monotone (it could be a Monge
+/// matrix) with `nrows` rows and `ncols` columns. No such requirement
+/// is placed on `fine`.
+///
+/// The fine key is evaluated lazily: the smallest and largest rows
+/// with a minimal coarse key are found for every column using SMAWK.
+/// Both are non-decreasing from column to column. Only the rows in
+/// between are inspected further, and `fine` is only evaluated on the
+/// rows which tie with the minimum. Columns without ties never
+/// evaluate `fine` at all.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*) plus the number
+/// of rows inspected between the smallest and largest minimum in each
+/// column.
+///
+/// # Examples
+///
+/// ```
+/// let coarse = vec![vec![1, 2, 4],
+///                   vec![1, 2, 3],
+///                   vec![2, 1, 3]];
+/// let fine = vec![vec![5, 5, 5],
+///                 vec![4, 4, 4],
+///                 vec![3, 3, 3]];
+/// let minima = smawk::column_minima_refine(3, 3, |i, j| coarse[i][j], |i, j| fine[i][j]);
+/// assert_eq!(minima, vec![1, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with zero rows.
+pub fn column_minima_refine<K1, K2, C, F>(
+    nrows: usize,
+    ncols: usize,
+    coarse: C,
+    fine: F,
+) -> Vec<usize>
+where
+    K1: PartialOrd + Copy,
+    K2: PartialOrd + Copy,
+    C: Fn(usize, usize) -> K1,
+    F: Fn(usize, usize) -> K2,
+{
+    assert!(nrows > 0, "cannot compute minima with zero rows");
+    let (smallest, largest) = minimal_row_spans(&coarse, nrows, ncols);
+
+    let mut minima = smallest;
+    for (j, minimum) in minima.iter_mut().enumerate() {
+        let first = *minimum;
+        let last = largest[j];
+        let value = coarse(first, j);
+        let mut best: Option<K2> = None;
+        for i in first + 1..=last {
+            if coarse(i, j) == value {
+                let best_fine = *best.get_or_insert_with(|| fine(first, j));
+                let candidate = fine(i, j);
+                if candidate < best_fine {
+                    best = Some(candidate);
+                    *minimum = i;
+                }
+            }
+        }
+    }
+    minima
+}
+
+/// Compute column minima of a matrix which is only nearly totally
+/// monotone.
+///
+/// Matrices built from measurements can violate total monotonicity
+/// locally because of noise. SMAWK then returns rows which are not
+/// always minimal, but which tend to be close to the true minima.
+/// This function runs [`column_minima`] and then scans the rows near
+/// the result for every column: the true minima form a monotone path
+/// when the noise is small, so the rows between the minima of the
+/// neighboring columns, extended by `slack` rows in both directions,
+/// are scanned. A scanned row replaces the minimum only if it is
+/// strictly smaller.
+///
+/// The function returns the minima together with a flag per column
+/// which tells if the scan changed the minimum. When the matrix is
+/// totally monotone, the result is exact and no flags are set.
+/// Otherwise this is a heuristic: it improves on [`column_minima`]
+/// but minima outside the scanned rows are not found. Use
+/// `brute_force::column_minima` for exact results on arbitrary
+/// matrices.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n* ✕ `slack`) when
+/// the minima returned by SMAWK are monotone, and O(*mn*) in the
+/// worst case.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::robust_column_minima(&matrix, 1),
+///            (vec![0, 0, 2, 2], vec![false; 4]));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn robust_column_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    slack: usize,
+) -> (Vec<usize>, Vec<bool>) {
+    let minima = column_minima(matrix);
+    let last_row = matrix.nrows() - 1;
+    let mut refined = minima.clone();
+    let mut changed = vec![false; minima.len()];
+    for (col, &row) in minima.iter().enumerate() {
+        let before = if col > 0 { minima[col - 1] } else { row };
+        let after = minima.get(col + 1).copied().unwrap_or(row);
+        let first = before.min(row).saturating_sub(slack);
+        let last = after.max(row).saturating_add(slack).min(last_row);
+        let mut best = matrix.index(row, col);
+        for candidate in first..=last {
+            let value = matrix.index(candidate, col);
+            if value < best {
+                best = value;
+                refined[col] = candidate;
+                changed[col] = true;
+            }
+        }
+    }
+    (refined, changed)
+}
+
+/// Statistics reported by [`column_minima_or_fallback`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FallbackStats {
+    /// Number of subproblems solved by SMAWK.
+    pub subproblems: usize,
+    /// Number of subproblems which failed the consistency checks and
+    /// were solved again by brute force.
+    pub fallbacks: usize,
+}
+
+/// Compute column minima with SMAWK, but fall back to brute force
+/// where the matrix turns out not to be totally monotone.
+///
+/// SMAWK silently returns wrong minima when the matrix is not totally
+/// monotone. This function checks every subproblem solved in the
+/// recursion: the minima must be non-decreasing, and no element may
+/// be smaller than the minimum of its column. Every row is compared
+/// with a few columns near the staircase of minima, and every column
+/// with a few rows. A subproblem which fails the checks is solved
+/// again by scanning all of its rows.
+///
+/// The first subproblem is the whole matrix, so its check validates
+/// the final minima in O(*m* + *n*) time. It includes the comparisons
+/// made by [`certificate::verify`]: the minimum of every column is
+/// compared with the rows between the minima of the neighboring
+/// columns. The minima thus either pass the checks of a
+/// [`certificate::Certificate`], or are computed by brute force.
+///
+/// The result is exact on a totally monotone matrix, where there are
+/// no fallbacks. On other matrices, the checks are local: they catch
+/// matrices which are far from totally monotone, such as matrices of
+/// random numbers, but a Monge matrix with a single lowered element
+/// far from the staircase of minima can pass them with wrong minima. Only a scan of every element can rule this
+/// out, since any element which is not read could be the minimum of
+/// its column.
+///
+/// Matrices with fewer than [`BRUTE_FORCE_THRESHOLD`] elements are
+/// scanned by brute force and report no subproblems.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*) on a totally
+/// monotone matrix, and O(*mn*) in the worst case.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::{FallbackStats, Matrix};
+/// let monge = (0..30)
+///     .map(|i| (0..30).map(|j| (i - j) * (i - j)).collect())
+///     .collect::<Vec<Vec<i32>>>();
+/// let (minima, stats) = smawk::column_minima_or_fallback(&monge);
+/// assert_eq!(minima, (0..30).collect::<Vec<_>>());
+/// assert_eq!(stats.fallbacks, 0);
+///
+/// // Reversing the rows breaks total monotonicity.
+/// let reversed = monge.iter().rev().cloned().collect::<Vec<_>>();
+/// let (minima, stats) = smawk::column_minima_or_fallback(&reversed);
+/// assert_eq!(minima, (0..30).rev().collect::<Vec<_>>());
+/// assert!(stats.fallbacks > 0);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_or_fallback<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> (Vec<usize>, FallbackStats) {
+    let mut minima = vec![0; matrix.ncols()];
+    let mut stats = FallbackStats::default();
+    if matrix.nrows().saturating_mul(matrix.ncols()) < BRUTE_FORCE_THRESHOLD {
+        brute_force_inner(&|i, j| matrix.index(i, j), matrix.nrows(), &mut minima);
+        return (minima, stats);
+    }
+    smawk_or_fallback_inner(
+        &|i, j| matrix.index(i, j),
+        &(0..matrix.nrows()).collect::<Vec<_>>(),
+        &(0..matrix.ncols()).collect::<Vec<_>>(),
+        &mut minima,
+        &mut stats,
+    );
+    (minima, stats)
+}
+
+/// Compute column minima and report each one as soon as it is known.
+///
+/// This computes the same minima as [`column_minima`], but instead of
+/// returning them, `sink(col, row)` is called exactly once for every
+/// column as soon as SMAWK has determined its minimum. This allows a
+/// consumer to start working on the results while the computation is
+/// still in progress.
+///
+/// The order of the callbacks is defined by the algorithm and is not
+/// sorted by column: SMAWK recursively determines the minima of the
+/// odd columns first, so the columns with an even index are reported
+/// last. Small matrices are not scanned by brute force.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let mut reported = Vec::new();
+/// smawk::column_minima_as_computed(&matrix, |col, row| reported.push((col, row)));
+/// assert_eq!(reported, vec![(3, 2), (1, 0), (0, 0), (2, 2)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_as_computed<T, M, S>(matrix: &M, mut sink: S)
+where
+    T: PartialOrd + Copy,
+    M: Matrix<T>,
+    S: FnMut(usize, usize),
+{
+    let mut minima = vec![0; matrix.ncols()];
+    smawk_full_with_sink(
+        &|i, j| matrix.index(i, j),
+        &|queries: &[(usize, usize)], out: &mut [T]| matrix.eval_batch(queries, out),
+        matrix.nrows(),
+        matrix.ncols(),
+        &mut minima,
+        &mut sink,
+    );
+}
+
+/// Compute column minima and the set of rows holding a minimum.
+///
+/// This computes the same minima as [`column_minima`] and returns
+/// them together with a bitset of the matrix rows. Bit `i` is set if
+/// row `i` is the minimum of at least one column. The bits are set
+/// as the minima are found, so no second pass over the minima is
+/// needed. When the matrix has many columns but few distinct rows are
+/// chosen, the bitset answers "is row `i` used?" in constant time.
+///
+/// **Note: this function is only available if you enable the
+/// `fixedbitset` Cargo feature.**
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let (minima, chosen) = smawk::column_minima_chosen_rows(&matrix);
+/// assert_eq!(minima, vec![0, 0, 2, 2]);
+/// assert_eq!(chosen.ones().collect::<Vec<_>>(), vec![0, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+#[cfg(feature = "fixedbitset")]
+pub fn column_minima_chosen_rows<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> (Vec<usize>, fixedbitset::FixedBitSet) {
+    let mut minima = vec![0; matrix.ncols()];
+    let mut chosen = fixedbitset::FixedBitSet::with_capacity(matrix.nrows());
+    smawk_full_with_sink(
+        &|i, j| matrix.index(i, j),
+        &|queries: &[(usize, usize)], out: &mut [T]| matrix.eval_batch(queries, out),
+        matrix.nrows(),
+        matrix.ncols(),
+        &mut minima,
+        &mut |_, row| chosen.insert(row),
+    );
+    (minima, chosen)
+}
+
+/// Compute column minima with ties broken toward target rows.
+///
+/// The minimum values are the same as for [`column_minima`], only the
+/// tie-breaking differs: when several rows share the minimum value of
+/// column `j`, the row closest to `target[j]` is returned. If two such
+/// rows are equally close, the smaller one is returned. The target
+/// rows need not be monotone, and they may lie outside of the matrix.
+///
+/// The rows sharing the minimum value need not be consecutive. The
+/// smallest and largest of them are found with SMAWK, after which
+/// the rows in between are searched outwards from the target until a
+/// minimum is found.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*) plus the
+/// distance from each target to the nearest minimum.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![1, 3, 2],
+///                   vec![1, 2, 1],
+///                   vec![1, 2, 1]];
+/// assert_eq!(smawk::column_minima(&matrix), vec![0, 1, 1]);
+/// assert_eq!(smawk::column_minima_closest_to(&matrix, &[2, 2, 0]),
+///            vec![2, 2, 1]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows or with a
+/// `target` slice of a different length than the number of columns.
+pub fn column_minima_closest_to<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    target: &[usize],
+) -> Vec<usize> {
+    let (nrows, ncols) = (matrix.nrows(), matrix.ncols());
+    assert!(nrows > 0, "cannot compute minima with zero rows");
+    assert_eq!(target.len(), ncols, "expected one target per column");
+    let (smallest, largest) = minimal_row_spans(&|i, j| matrix.index(i, j), nrows, ncols);
+
+    let mut minima = smallest;
+    for (j, minimum) in minima.iter_mut().enumerate() {
+        let (first, last) = (*minimum, largest[j]);
+        let value = matrix.index(first, j);
+        let t = target[j].clamp(first, last);
+        // Both first and last are minima, so this ends before the
+        // distance exceeds last - first.
+        for distance in 0.. {
+            if t - first >= distance && matrix.index(t - distance, j) == value {
+                *minimum = t - distance;
+                break;
+            }
+            if last - t >= distance && distance > 0 && matrix.index(t + distance, j) == value {
+                *minimum = t + distance;
+                break;
+            }
+        }
+    }
+    minima
+}
+
+/// Find the smallest and largest rows with a minimal value in every
+/// column of a totally monotone matrix.
+fn minimal_row_spans<T: PartialOrd + Copy, M: Fn(usize, usize) -> T>(
+    matrix: &M,
+    nrows: usize,
+    ncols: usize,
+) -> (Vec<usize>, Vec<usize>) {
+    let rows = (0..nrows).collect::<Vec<_>>();
+    let cols = (0..ncols).collect::<Vec<_>>();
+
+    let mut smallest = vec![0; ncols];
+    smawk_inner(matrix, &rows, &cols, &mut smallest);
+
+    // Reversing both rows and columns preserves total monotonicity
+    // and turns the largest minimal row into the smallest.
+    let mut reversed = vec![0; ncols];
+    smawk_inner(
+        &|i, j| matrix(nrows - 1 - i, ncols - 1 - j),
+        &rows,
+        &cols,
+        &mut reversed,
+    );
+    let largest = (0..ncols)
+        .map(|j| nrows - 1 - reversed[ncols - 1 - j])
+        .collect();
+    (smallest, largest)
+}
+
+/// Answer column minima queries one column at a time.
+///
+/// The oracle computes the minimum of a single column when it is
+/// queried and caches the answer. Since the column minima of a
+/// totally monotone matrix are monotone, the minima of the nearest
+/// cached columns to the left and right bound the rows which must be
+/// scanned. To keep these bounds tight, a query for column `j` first
+/// answers the columns visited by a binary search for `j` over all
+/// columns, starting with the middle column.
+///
+/// A single query evaluates O(*m* log *n*) elements of an *m* ✕ *n*
+/// matrix in the worst case. The columns answered along the way are
+/// shared between queries: answering all *n* columns, in any order,
+/// evaluates O((*m* + *n*) log *n*) elements in total. This is an
+/// amortized cost of O((*m* / *n* + 1) log *n*) per query, and a
+/// repeated query is answered without evaluating any elements. Use
+/// [`column_minima`] when all columns are needed up front.
+///
+/// Ties are broken in favor of the smallest row index, which gives
+/// the same answers as [`column_minima`].
+///
+/// # Examples
+///
+/// ```
+/// use smawk::{ColumnMinimaOracle, Matrix};
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let mut oracle = ColumnMinimaOracle::new(&matrix);
+/// assert_eq!(oracle.query(3), 2);
+/// assert_eq!(oracle.query(0), 0);
+/// assert_eq!(oracle.query(2), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ColumnMinimaOracle<'a, M> {
+    matrix: &'a M,
+    minima: std::collections::BTreeMap<usize, usize>,
+}
+
+impl<'a, M> ColumnMinimaOracle<'a, M> {
+    /// Create an oracle for a totally monotone matrix. No elements
+    /// are evaluated until the first query.
+    pub fn new(matrix: &'a M) -> Self {
+        ColumnMinimaOracle {
+            matrix,
+            minima: std::collections::BTreeMap::new(),
+        }
+    }
+
+    /// Return the row of the minimum in column `col`.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to query a column which is out of bounds or to
+    /// query a matrix with zero rows.
+    pub fn query<T: PartialOrd + Copy>(&mut self, col: usize) -> usize
+    where
+        M: Matrix<T>,
+    {
+        let ncols = self.matrix.ncols();
+        assert!(col < ncols, "column {} out of bounds", col);
+        let (mut lo, mut hi) = (0, ncols);
+        loop {
+            let mid = lo + (hi - lo) / 2;
+            let row = self.solve(mid);
+            match col.cmp(&mid) {
+                std::cmp::Ordering::Less => hi = mid,
+                std::cmp::Ordering::Equal => return row,
+                std::cmp::Ordering::Greater => lo = mid + 1,
+            }
+        }
+    }
+
+    /// Return the number of columns answered so far, including the
+    /// columns answered along the way to a query.
+    pub fn cached(&self) -> usize {
+        self.minima.len()
+    }
+
+    /// Find the minimum of a single column, scanning only the rows
+    /// between the minima of the nearest cached columns.
+    fn solve<T: PartialOrd + Copy>(&mut self, col: usize) -> usize
+    where
+        M: Matrix<T>,
+    {
+        if let Some(&row) = self.minima.get(&col) {
+            return row;
+        }
+        let first = self
+            .minima
+            .range(..col)
+            .next_back()
+            .map_or(0, |(_, &row)| row);
+        let last = match self.minima.range(col + 1..).next() {
+            Some((_, &row)) => row,
+            None => self.matrix.nrows() - 1,
+        };
+        let mut pair = (self.matrix.index(first, col), first);
+        for row in first + 1..=last {
+            let value = self.matrix.index(row, col);
+            if value < pair.0 {
+                pair = (value, row);
+            }
+        }
+        self.minima.insert(col, pair.1);
+        pair.1
+    }
+}
+
+/// Compute column minima as runs of columns sharing the same row.
+///
+/// The column minima of a totally monotone matrix form a monotone
+/// staircase: the row index of the minimum is non-decreasing from
+/// left to right. This function returns the staircase as a list of
+/// `(row, columns)` pairs, one for each maximal range of consecutive
+/// columns with their minimum in `row`. The rows are strictly
+/// increasing from one run to the next.
+///
+/// This is more compact than the vector returned by
+/// [`column_minima`] when few rows contain column minima.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::column_minima_runs(&matrix),
+///            vec![(0, 0..2), (2, 2..4)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_runs<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> Vec<(usize, std::ops::Range<usize>)> {
+    column_minima_run_iter(matrix).collect()
+}
+
+/// Iterate over the column minima as runs of columns sharing the same
+/// row.
+///
+/// This yields the same `(row, columns)` pairs as
+/// [`column_minima_runs`], but one at a time. This allows a streaming
+/// consumer to write out the staircase without collecting the runs.
+///
+/// The column minima are computed eagerly with SMAWK when this
+/// function is called, which takes O(*n*) memory for the minima of
+/// the *n* columns. Only the grouping of the minima into runs happens
+/// lazily as the iterator is advanced.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let mut runs = smawk::column_minima_run_iter(&matrix);
+/// assert_eq!(runs.next(), Some((0, 0..2)));
+/// assert_eq!(runs.next(), Some((2, 2..4)));
+/// assert_eq!(runs.next(), None);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_run_iter<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> impl Iterator<Item = (usize, std::ops::Range<usize>)> {
+    let minima = column_minima(matrix);
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        let row = *minima.get(start)?;
+        let end = start + minima[start..].iter().take_while(|&&r| r == row).count();
+        let run = (row, start..end);
+        start = end;
+        Some(run)
+    })
+}
+
+/// Compute column minima as run-length encoded `(row, length)` pairs.
+///
+/// This is a compact form of the staircase for storage: each pair
+/// holds the row of a run of column minima and the number of columns
+/// in the run, see [`column_minima_runs`]. The rows are strictly
+/// increasing, so a matrix with *m* rows and *n* columns gives at
+/// most min(*m*, *n*) pairs instead of *n* indices. Structured
+/// matrices often have far fewer runs: an *n* ✕ *n* matrix with its
+/// minima in √*n* rows compresses the minima by a factor of √*n* / 2.
+/// Use [`decode_column_minima_rle`] to expand the pairs again.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::column_minima_rle(&matrix), vec![(0, 2), (2, 2)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_rle<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> Vec<(usize, usize)> {
+    column_minima_run_iter(matrix)
+        .map(|(row, columns)| (row, columns.len()))
+        .collect()
+}
+
+/// Expand run-length encoded column minima.
+///
+/// This is the inverse of [`column_minima_rle`]. The number of columns
+/// is passed in to catch truncated or corrupted input.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(smawk::decode_column_minima_rle(&[(0, 2), (2, 2)], 4),
+///            vec![0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the run lengths do not add up to `cols`.
+pub fn decode_column_minima_rle(encoded: &[(usize, usize)], cols: usize) -> Vec<usize> {
+    // Check the total before allocating, so huge or corrupted run
+    // lengths cannot trigger a huge allocation.
+    let total = encoded
+        .iter()
+        .try_fold(0usize, |total, &(_, len)| total.checked_add(len));
+    assert_eq!(
+        total,
+        Some(cols),
+        "run lengths must add up to the number of columns"
+    );
+    let mut minima = Vec::with_capacity(cols);
+    for &(row, len) in encoded {
+        minima.extend(std::iter::repeat_n(row, len));
+    }
+    minima
+}
+
+/// Merge the column minima of two disjoint sets of rows.
+///
+/// The runs in `a` and `b` describe the column minima of two disjoint
+/// sets of rows of the same matrix, in the format returned by
+/// [`column_minima_runs`]. The result describes the column minima
+/// over the union of the two sets in the same format.
+///
+/// Splitting the columns at the ends of the runs in both inputs gives
+/// segments where the minima of `a` and `b` lie in fixed rows. Two
+/// rows of a totally monotone matrix form a totally monotone matrix,
+/// so the lower row wins a suffix of each segment. The start of this
+/// suffix is found by binary search, which evaluates the matrix in
+/// O(log *n*) columns near the crossover. This makes it possible to
+/// maintain the minima of a dynamic set of rows, such as the nodes of
+/// a segment tree over the rows, without rescanning every column.
+///
+/// Running time: O((*r* + *s*) log *n*) for *r* and *s* runs in `a`
+/// and `b` and *n* columns.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// // Minima of the first row and of the last two rows.
+/// let a = vec![(0, 0..4)];
+/// let b = vec![(1, 0..2), (2, 2..4)];
+/// assert_eq!(smawk::merge_column_minima(&matrix, &a, &b),
+///            vec![(0, 0..2), (2, 2..4)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with runs which do not cover the same
+/// columns from left to right.
+pub fn merge_column_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    a: &[(usize, std::ops::Range<usize>)],
+    b: &[(usize, std::ops::Range<usize>)],
+) -> Vec<(usize, std::ops::Range<usize>)> {
+    assert_eq!(
+        a.last().map_or(0, |(_, columns)| columns.end),
+        b.last().map_or(0, |(_, columns)| columns.end),
+        "runs must cover the same columns"
+    );
+    let mut merged: Vec<(usize, std::ops::Range<usize>)> = Vec::new();
+    let mut push = |row: usize, columns: std::ops::Range<usize>| {
+        if columns.is_empty() {
+            return;
+        }
+        match merged.last_mut() {
+            Some((last, previous)) if *last == row => previous.end = columns.end,
+            _ => merged.push((row, columns)),
+        }
+    };
+
+    let (mut a, mut b) = (a.iter(), b.iter());
+    let (mut run_a, mut run_b) = (a.next(), b.next());
+    let mut start = 0;
+    while let (Some((row_a, columns_a)), Some((row_b, columns_b))) = (run_a, run_b) {
+        assert!(
+            columns_a.start <= start && columns_b.start <= start,
+            "runs must be contiguous"
+        );
+        let end = std::cmp::min(columns_a.end, columns_b.end);
+        let (upper, lower) = (std::cmp::min(*row_a, *row_b), std::cmp::max(*row_a, *row_b));
+        let (mut lo, mut hi) = (start, end);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if matrix.index(lower, mid) < matrix.index(upper, mid) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        push(upper, start..lo);
+        push(lower, lo..end);
+
+        start = end;
+        if columns_a.end == end {
+            run_a = a.next();
+        }
+        if columns_b.end == end {
+            run_b = b.next();
+        }
+    }
+    assert!(
+        run_a.is_none() && run_b.is_none(),
+        "runs must cover the same columns"
+    );
+    merged
+}
+
+/// Compute column minima with each row left out in turn.
+///
+/// The vector at index `r` of the result holds the column minima of
+/// the matrix with row `r` deleted. The returned indices refer to
+/// rows of the full matrix.
+///
+/// Deleting row `r` only changes the minima of the columns which had
+/// their minimum in row `r`. These columns form a contiguous range,
+/// and since the matrix with row `r` deleted is still totally
+/// monotone, their new minima must lie between the rows of the
+/// minima to the left and right of the range. The function thus runs
+/// SMAWK once on the full matrix and then once on a small submatrix
+/// per row. Each row and column takes part in at most a constant
+/// number of these submatrices.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*) plus O(*mn*)
+/// to copy the result vectors.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::leave_one_out_column_minima(&matrix),
+///            vec![vec![1, 1, 2, 2],
+///                 vec![0, 0, 2, 2],
+///                 vec![0, 0, 0, 0]]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with fewer than two rows.
+pub fn leave_one_out_column_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> Vec<Vec<usize>> {
+    assert!(matrix.nrows() >= 2, "cannot leave out the only row");
+    let (minima, runners_up) =
+        column_runners_up(&|i, j| matrix.index(i, j), matrix.nrows(), matrix.ncols());
+
+    let mut result = vec![minima.clone(); matrix.nrows()];
+    for (j, &row) in minima.iter().enumerate() {
+        result[row][j] = runners_up[j];
+    }
+    result
+}
+
+/// Find the column minima, and the minima with the row of the
+/// minimum left out, of a totally monotone matrix with at least two
+/// rows.
+///
+/// See [`leave_one_out_column_minima`] for how this is done in
+/// O(*m* + *n*) time.
+fn column_runners_up<T: PartialOrd + Copy, M: Fn(usize, usize) -> T>(
+    matrix: &M,
+    nrows: usize,
+    ncols: usize,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut minima = vec![0; ncols];
+    smawk_inner(
+        matrix,
+        &(0..nrows).collect::<Vec<_>>(),
+        &(0..ncols).collect::<Vec<_>>(),
+        &mut minima,
+    );
+
+    let mut runners_up = minima.clone();
+    let mut start = 0;
+    while start < ncols {
+        let row = minima[start];
+        let end = start + minima[start..].iter().take_while(|&&r| r == row).count();
+        let lo = if start > 0 { minima[start - 1] } else { 0 };
+        let hi = if end < ncols { minima[end] } else { nrows - 1 };
+        let rows = (lo..=hi).filter(|&r| r != row).collect::<Vec<_>>();
+        smawk_inner(
+            matrix,
+            &rows,
+            &(start..end).collect::<Vec<_>>(),
+            &mut runners_up,
+        );
+        start = end;
+    }
+    (minima, runners_up)
+}
+
+/// A value or positive infinity.
+///
+/// Matrices often need forbidden elements, for example outside of a
+/// band or below a staircase. A large sentinel value such as
+/// `i64::MAX` overflows as soon as something is added to it, while
+/// `PosInf` is simply larger than every finite value. Since the
+/// minima functions only compare elements, they work on matrices of
+/// `Infinitary` values like on any other ordered type.
+///
+/// A column where every element is `PosInf` has a tie between all
+/// its rows, so its minimum is row 0 like for any other tie. The
+/// forbidden elements must keep the matrix totally monotone, also
+/// when infinite elements tie. For column minima of a Monge matrix,
+/// this is the case when the forbidden elements form a staircase in
+/// the lower-left corner: the first allowed column of every row is
+/// non-decreasing. For row minima, the staircase must be in the
+/// upper-right corner instead. Use
+/// [`is_monge_infinitary`](crate::monge::is_monge_infinitary) to
+/// check the allowed elements.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Infinitary::{self, Finite, PosInf};
+/// // Row i may only be used in columns j >= i.
+/// let matrix = (0..3)
+///     .map(|i| (0..4).map(|j| if j >= i { Finite(j - i) } else { PosInf }).collect())
+///     .collect::<Vec<Vec<Infinitary<usize>>>>();
+/// assert_eq!(smawk::column_minima(&matrix), vec![0, 1, 2, 2]);
+/// assert!(PosInf > Finite(u64::MAX));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Infinitary<T> {
+    /// A finite value.
+    Finite(T),
+    /// Positive infinity, larger than every finite value.
+    PosInf,
+}
+
+impl<T> Infinitary<T> {
+    /// Return the finite value, or `None` for `PosInf`.
+    pub fn finite(self) -> Option<T> {
+        match self {
+            Infinitary::Finite(value) => Some(value),
+            Infinitary::PosInf => None,
+        }
+    }
+}
+
+impl<T> From<T> for Infinitary<T> {
+    fn from(value: T) -> Self {
+        Infinitary::Finite(value)
+    }
+}
+
+/// An interval `[lo, hi]` of possible values for a matrix element.
+///
+/// See [`column_minima_interval`] for finding column minima of a
+/// matrix of intervals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval<T> {
+    /// Smallest possible value.
+    pub lo: T,
+    /// Largest possible value.
+    pub hi: T,
+}
+
+/// Column minimum of a matrix of intervals.
+///
+/// See [`column_minima_interval`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertifiedMin {
+    /// The row holds the column minimum for every choice of values
+    /// within the intervals.
+    Certified(usize),
+    /// The rows, in increasing order, which can hold the column
+    /// minimum for some choice of values within the intervals.
+    Ambiguous(Vec<usize>),
+}
+
+/// Compute column minima of a matrix whose elements are only known
+/// to lie within intervals.
+///
+/// For every column, let `i` be the row with the smallest upper
+/// bound `hi`, using the smallest row on ties. The row is
+/// [`CertifiedMin::Certified`] when no other row in the column has a
+/// lower bound `lo` below this upper bound: whatever the true values
+/// are, row `i` holds a minimum. Otherwise the column is
+/// [`CertifiedMin::Ambiguous`] and the candidates are all rows whose
+/// lower bound is at most the upper bound of row `i`. These rows need
+/// not be contiguous.
+///
+/// When every interval has width zero, every column is certified
+/// and the rows are those found by [`column_minima`].
+///
+/// The matrix of lower bounds and the matrix of upper bounds must
+/// both be totally monotone. This is the case when they are Monge,
+/// which can be checked with
+/// [`is_monge_interval`](crate::monge::is_monge_interval).
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*) plus O(*m*) per
+/// ambiguous column to collect its candidates.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::{CertifiedMin, Interval};
+/// let lo = vec![vec![4, 2, 4, 3],
+///               vec![5, 3, 5, 3],
+///               vec![5, 3, 3, 1]];
+/// let widths = [0, 2, 1];
+/// let matrix = lo
+///     .iter()
+///     .zip(widths)
+///     .map(|(row, w)| row.iter().map(|&x| Interval { lo: x, hi: x + w }).collect())
+///     .collect::<Vec<Vec<_>>>();
+/// assert_eq!(smawk::column_minima_interval(&matrix),
+///            vec![CertifiedMin::Certified(0),
+///                 CertifiedMin::Certified(0),
+///                 CertifiedMin::Ambiguous(vec![0, 2]),
+///                 CertifiedMin::Certified(2)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_interval<T: PartialOrd + Copy, M: Matrix<Interval<T>>>(
+    matrix: &M,
+) -> Vec<CertifiedMin> {
+    let (nrows, ncols) = (matrix.nrows(), matrix.ncols());
+    assert!(nrows > 0, "cannot compute minima with zero rows");
+    let lower = |i, j| matrix.index(i, j).lo;
+    let upper = |i, j| matrix.index(i, j).hi;
+    let mut best = vec![0; ncols];
+    smawk_inner(
+        &upper,
+        &(0..nrows).collect::<Vec<_>>(),
+        &(0..ncols).collect::<Vec<_>>(),
+        &mut best,
+    );
+    if nrows == 1 {
+        return best.into_iter().map(CertifiedMin::Certified).collect();
+    }
+
+    // The smallest lower bound outside row i is the smallest lower
+    // bound in the column, unless that lies in row i itself.
+    let (lowest, runners_up) = column_runners_up(&lower, nrows, ncols);
+    best.into_iter()
+        .enumerate()
+        .map(|(j, i)| {
+            let bound = upper(i, j);
+            let other = if lowest[j] == i {
+                runners_up[j]
+            } else {
+                lowest[j]
+            };
+            if bound <= lower(other, j) {
+                CertifiedMin::Certified(i)
+            } else {
+                CertifiedMin::Ambiguous((0..nrows).filter(|&k| lower(k, j) <= bound).collect())
+            }
+        })
+        .collect()
+}
+
+/// Compute column minima over a subset of the rows in O(*m* + *n*)
+/// time.
+///
+/// Only rows `i` where `enabled[i]` is `true` are considered. The
+/// returned indices refer to rows of the full matrix. This is useful
+/// when rows are toggled on and off between calls since no filtered
+/// copy of the matrix is needed.
+///
+/// Removing rows from a totally monotone matrix leaves a matrix which
+/// is still totally monotone: every submatrix of the smaller matrix
+/// is also a submatrix of the original matrix. The same holds for
+/// Monge matrices. The SMAWK algorithm can thus be applied directly
+/// to the enabled rows.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// assert_eq!(smawk::column_minima_enabled(&matrix, &[false, true, true]),
+///            vec![1, 1, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with no enabled rows or when
+/// `enabled.len()` differs from the number of rows.
+pub fn column_minima_enabled<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    enabled: &[bool],
+) -> Vec<usize> {
+    assert_eq!(
+        enabled.len(),
+        matrix.nrows(),
+        "enabled must have one entry per row"
+    );
+    let rows = (0..matrix.nrows())
+        .filter(|&i| enabled[i])
+        .collect::<Vec<_>>();
+    assert!(!rows.is_empty(), "no rows are enabled");
+    let mut minima = vec![0; matrix.ncols()];
+    smawk_inner(
+        &|i, j| matrix.index(i, j),
+        &rows,
+        &(0..matrix.ncols()).collect::<Vec<_>>(),
+        &mut minima,
+    );
+    minima
+}
+
+/// Compute column minima of a matrix whose elements are futures.
+///
+/// This computes the same minima as [`column_minima`] for an `nrows`
+/// ✕ `ncols` matrix, but the element in row `i` and column `j` is
+/// produced by awaiting `matrix(i, j)`. This is useful when the
+/// elements come from I/O, such as network calls. Only the elements
+/// needed by SMAWK are awaited, so O(*m* + *n*) futures are created.
+///
+/// The futures are awaited one at a time. Which element SMAWK needs
+/// next depends on the elements seen so far, so there is no
+/// concurrency between the awaits to exploit.
+///
+/// **Note: this function is only available if you enable the `async`
+/// Cargo feature.**
+///
+/// # Examples
+///
+/// ```
+/// use std::future::Future;
+/// use std::task::{Context, Poll, Waker};
+///
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let future = smawk::column_minima_async(3, 4, |i, j| {
+///     let value = matrix[i][j];
+///     async move { value }
+/// });
+/// // The futures are always ready here, so a single poll is enough
+/// // instead of an executor.
+/// let mut future = std::pin::pin!(future);
+/// let mut cx = Context::from_waker(Waker::noop());
+/// assert_eq!(future.as_mut().poll(&mut cx), Poll::Ready(vec![0, 0, 2, 2]));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with zero rows and a non-zero number
+/// of columns.
+#[cfg(feature = "async")]
+pub async fn column_minima_async<T, M, F>(nrows: usize, ncols: usize, matrix: M) -> Vec<usize>
+where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> F,
+    F: std::future::Future<Output = T>,
+{
+    let mut minima = vec![0; ncols];
+    if ncols == 0 {
+        return minima;
+    }
+    assert!(nrows > 0, "expected a matrix with at least one row");
+
+    // Recursive async functions must be boxed, so the recursion of
+    // `smawk_inner` is unrolled: the reduce step runs on the way down
+    // and the rows and columns of every level are kept for the
+    // interpolation step on the way up.
+    let mut levels: Vec<(Vec<usize>, Vec<usize>)> = Vec::new();
+    let mut rows = (0..nrows).collect::<Vec<_>>();
+    let mut cols = (0..ncols).collect::<Vec<_>>();
+    while !cols.is_empty() {
+        let mut stack: Vec<usize> = Vec::with_capacity(cols.len());
+        for &r in &rows {
+            while let Some(&top) = stack.last() {
+                let col = cols[stack.len() - 1];
+                if matrix(top, col).await > matrix(r, col).await {
+                    stack.pop();
+                } else {
+                    break;
+                }
+            }
+            if stack.len() != cols.len() {
+                stack.push(r);
+            }
+        }
+        let odd_cols = cols.iter().copied().skip(1).step_by(2).collect();
+        rows = stack.clone();
+        levels.push((stack, cols));
+        cols = odd_cols;
+    }
+
+    for (rows, cols) in levels.iter().rev() {
+        let mut r = 0;
+        for (c, &col) in cols.iter().enumerate().step_by(2) {
+            let last_row = match cols.get(c + 1) {
+                Some(&next) => minima[next],
+                None => rows[rows.len() - 1],
+            };
+            let mut pair = (matrix(rows[r], col).await, rows[r]);
+            while rows[r] != last_row {
+                r += 1;
+                let value = matrix(rows[r], col).await;
+                if value < pair.0 {
+                    pair = (value, rows[r]);
+                }
+            }
+            minima[col] = pair.1;
+        }
+    }
+    minima
+}
+
+/// Adapter which unrolls a cyclic matrix into a band matrix. Row `i`
+/// covers the unrolled columns `starts[i]..starts[i] + n`, counted
+/// from `starts[0]`, and unrolled column `k` is column `k mod n` of
+/// `matrix`.
+struct Unrolled<'a, M> {
+    matrix: &'a M,
+    starts: &'a [usize],
+}
+
+impl<T: Copy, M: Matrix<T>> Matrix<BandKey<T>> for Unrolled<'_, M> {
+    fn nrows(&self) -> usize {
+        self.starts.len()
+    }
+    fn ncols(&self) -> usize {
+        self.starts[self.starts.len() - 1] - self.starts[0] + self.matrix.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> BandKey<T> {
+        let n = self.matrix.ncols();
+        let column = self.starts[0] + column;
+        if column < self.starts[row] {
+            BandKey::Below(row)
+        } else if column >= self.starts[row] + n {
+            BandKey::Above(std::cmp::Reverse(row))
+        } else {
+            BandKey::Present(self.matrix.index(row, column % n))
+        }
+    }
+}
+
+/// Compute column minima of a cyclically Monge matrix.
+///
+/// The columns of an *m* ✕ *n* matrix are sometimes arranged on a
+/// circle, so that column `n - 1` is followed by column `0` again.
+/// An example is the cost `f(d(x[i], y[j]))` for points `x` and `y`
+/// sorted by angle on a circle, where `d` is the distance along the
+/// circle and `f` is convex. Such a matrix is rarely Monge, but it
+/// becomes Monge when every row is read in a window of *n*
+/// consecutive columns which starts at the right place.
+///
+/// Precisely, unroll the columns by letting column `k` for any `k ≥
+/// 0` denote column `k mod n`. Row `i` is read in the window of
+/// unrolled columns `starts[i]..starts[i] + n`, which holds every
+/// column exactly once. The matrix is *cyclically Monge* with respect
+/// to `starts` if
+///
+/// ```text
+/// M[i, k] + M[i', k'] ≤ M[i, k'] + M[i', k]
+/// ```
+///
+/// for all rows `i < i'` and unrolled columns `k < k'` where all four
+/// unrolled columns lie in the windows of their rows. For the points
+/// on a circle, the window of `x[i]` starts at the first column `y[j]`
+/// which is at most half a turn behind `x[i]`.
+///
+/// The windows are given by the caller since they cannot be derived
+/// from the matrix within the time bound. A cyclic rotation of a row
+/// looks no different from any other row, so finding a valid set of
+/// windows means checking the Monge inequality over candidate starts,
+/// which takes Ω(*mn*) evaluations. The windows also need not be
+/// unique: a circulant matrix such as the example below is cyclically
+/// Monge for several choices of `starts`, and they all give the same
+/// minima. The windows usually come for free from the geometry which
+/// produced the matrix, as for the points on a circle.
+///
+/// The unrolled matrix with infinity outside of the windows is
+/// totally monotone. The function runs SMAWK on it, which doubles the
+/// number of columns at most, and combines the two copies of every
+/// column. The result holds the row of the minimum in every column.
+/// Ties are broken in favor of the smallest row.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// // Eight points on a circle with squared circular distance as cost.
+/// let n = 8;
+/// let rows: [usize; 3] = [1, 4, 6];
+/// let matrix = rows
+///     .iter()
+///     .map(|&x| {
+///         (0..n).map(|y: usize| {
+///             let d = x.abs_diff(y);
+///             d.min(n - d).pow(2)
+///         }).collect()
+///     })
+///     .collect::<Vec<Vec<usize>>>();
+/// // Each window starts half a turn behind its row.
+/// let starts = rows.iter().map(|&x| x + n / 2).collect::<Vec<_>>();
+/// assert_eq!(smawk::cyclic_column_minima(&matrix, &starts),
+///            vec![0, 0, 0, 1, 1, 1, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows, or with
+/// `starts` which do not have one entry per row, are not
+/// non-decreasing, or span more than *n* columns.
+pub fn cyclic_column_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    starts: &[usize],
+) -> Vec<usize> {
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    assert!(m > 0, "expected a matrix with at least one row");
+    assert_eq!(starts.len(), m, "starts must have one entry per row");
+    assert!(
+        starts.windows(2).all(|w| w[0] <= w[1]),
+        "starts must be non-decreasing"
+    );
+    assert!(
+        starts[m - 1] - starts[0] <= n,
+        "starts must not span more than n columns"
+    );
+
+    let unrolled = Unrolled { matrix, starts };
+    let mut best: Vec<Option<(T, usize)>> = vec![None; n];
+    for (k, row) in column_minima(&unrolled).into_iter().enumerate() {
+        if let BandKey::Present(value) = unrolled.index(row, k) {
+            let column = (starts[0] + k) % n;
+            match best[column] {
+                Some(pair) if pair <= (value, row) => {}
+                _ => best[column] = Some((value, row)),
+            }
+        }
+    }
+    best.into_iter()
+        .map(|pair| pair.expect("every row covers every column").1)
+        .collect()
+}
+
+/// Adapter which hides the lower triangle of a matrix. Element `(i,
+/// j)` with `i > j` is infinite and never evaluated.
+struct UpperTriangle<'a, M>(&'a M);
+
+impl<T: Copy, M: Matrix<T>> Matrix<BandKey<T>> for UpperTriangle<'_, M> {
+    fn nrows(&self) -> usize {
+        self.0.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.0.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> BandKey<T> {
+        if row > column {
+            BandKey::Below(row)
+        } else {
+            BandKey::Present(self.0.index(row, column))
+        }
+    }
+}
+
+/// Compute column minima of an upper triangular Monge matrix.
+///
+/// Only the elements `M[i, j]` with `i ≤ j` are used, so column `j`
+/// has the candidate rows `0..=j`. The elements below the diagonal
+/// are never evaluated and can hold anything. Unlike
+/// [`online_column_minima`], which only uses the elements strictly
+/// above the diagonal, the diagonal is included here since all
+/// elements are known up front.
+///
+/// The upper triangle must be Monge, meaning that
+///
+/// ```text
+/// M[i, j] + M[i', j'] ≤ M[i, j'] + M[i', j]
+/// ```
+///
+/// for all `i < i'` and `j < j'` where all four elements lie in the
+/// upper triangle. With the lower triangle treated as infinite, the
+/// matrix is then totally monotone and SMAWK finds the minima. Ties
+/// are broken in favor of the smallest row.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// // The lower triangle holds garbage which would win every column.
+/// let matrix = vec![vec![3, 4, 5, 6],
+///                   vec![0, 2, 3, 3],
+///                   vec![0, 0, 4, 3]];
+/// assert_eq!(smawk::upper_triangular_column_minima(&matrix),
+///            vec![0, 1, 1, 1]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows and a
+/// non-zero number of columns.
+pub fn upper_triangular_column_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+) -> Vec<usize> {
+    column_minima(&UpperTriangle(matrix))
+}
+
+/// Adapter which turns a matrix upside down and hides the entries
+/// below a decreasing staircase. Row `r` of the adapter is row
+/// `m - 1 - r` of the matrix, which becomes an entry above the band if
+/// it is below `upper[j]`. Present entries are paired with their
+/// original row to break ties in favor of it.
+struct DecreasingStaircase<'a, M> {
+    matrix: &'a M,
+    upper: &'a [usize],
+}
+
+impl<T: Copy, M: Matrix<T>> Matrix<BandKey<(T, usize)>> for DecreasingStaircase<'_, M> {
+    fn nrows(&self) -> usize {
+        self.matrix.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.matrix.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> BandKey<(T, usize)> {
+        let i = self.matrix.nrows() - 1 - row;
+        if i > self.upper[column] {
+            BandKey::Above(std::cmp::Reverse(row))
+        } else {
+            BandKey::Present((self.matrix.index(i, column), i))
+        }
+    }
+}
+
+/// Compute column minima below a decreasing staircase.
+///
+/// Only the elements `M[i, j]` with `i ≤ upper[j]` are used, where
+/// `upper` is non-increasing. The elements below the staircase are
+/// never evaluated and can hold anything. Such staircases show up in
+/// dynamic programs for longest common subsequences and edit
+/// distances, where the valid region shrinks as the columns advance.
+///
+/// The minima of such a matrix cannot move down from left to right:
+/// column `j` only reaches row `upper[j]`. The elements inside the
+/// staircase must therefore be *inverse* Monge, meaning that
+///
+/// ```text
+/// M[i, j] + M[i', j'] ≥ M[i, j'] + M[i', j]
+/// ```
+///
+/// for all `i < i'` and `j < j'` where all four elements lie inside
+/// the staircase. Turned upside down, the matrix is then Monge with
+/// the excluded elements in an increasing staircase at the top, which
+/// is totally monotone when they are treated as infinite. A Monge
+/// matrix does not work here: a row which beats a lower row in one
+/// column can be cut off in a later column where the lower row is
+/// still a candidate. Ties are broken in favor of the smallest row.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*m* + *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Matrix;
+/// // The elements below the staircase would win every column.
+/// let matrix = vec![vec![6, 5, 4, 3],
+///                   vec![2, 2, 0, 0],
+///                   vec![1, 0, 0, 0]];
+/// assert_eq!(smawk::decreasing_staircase_column_minima(&matrix, &[2, 1, 0, 0]),
+///            vec![2, 1, 0, 0]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `upper` does not have one entry per column, if it is not
+/// non-increasing, or if an entry is not a row of the matrix.
+pub fn decreasing_staircase_column_minima<T: PartialOrd + Copy, M: Matrix<T>>(
+    matrix: &M,
+    upper: &[usize],
+) -> Vec<usize> {
+    assert_eq!(
+        upper.len(),
+        matrix.ncols(),
+        "upper must have one entry per column"
+    );
+    assert!(
+        upper.windows(2).all(|w| w[0] >= w[1]),
+        "upper must be non-increasing"
+    );
+    assert!(
+        upper.iter().all(|&i| i < matrix.nrows()),
+        "upper must only contain rows of the matrix"
+    );
+    let m = matrix.nrows();
+    column_minima(&DecreasingStaircase { matrix, upper })
+        .into_iter()
+        .map(|row| m - 1 - row)
+        .collect()
+}
+
+/// Compute column minima by scanning all `nrows` rows of each
+/// column. Ties are broken in favor of the smallest row index, just
+/// like in `smawk_inner`.
+fn brute_force_inner<T: PartialOrd + Copy, M: Fn(usize, usize) -> T>(
+    matrix: &M,
+    nrows: usize,
+    minima: &mut [usize],
+) {
+    for (col, minimum) in minima.iter_mut().enumerate() {
+        let mut pair = (matrix(0, col), 0);
+        for row in 1..nrows {
+            if (matrix(row, col), row) < pair {
+                pair = (matrix(row, col), row);
+            }
+        }
+        *minimum = pair.1;
+    }
+}
+
+/// Like `smawk_inner`, but check the minima of every subproblem with
+/// `is_staircase` and solve it by brute force if the check fails.
+fn smawk_or_fallback_inner<T: PartialOrd + Copy, M: Fn(usize, usize) -> T>(
+    matrix: &M,
+    rows: &[usize],
+    cols: &[usize],
+    minima: &mut [usize],
+    stats: &mut FallbackStats,
+) {
+    if cols.is_empty() {
+        return;
+    }
+    stats.subproblems += 1;
+
+    let mut stack: Vec<usize> = Vec::with_capacity(cols.len());
+    for &r in rows {
+        while let Some(&top) = stack.last() {
+            let col = cols[stack.len() - 1];
+            if matrix(top, col) > matrix(r, col) {
+                stack.pop();
+            } else {
+                break;
+            }
+        }
+        if stack.len() != cols.len() {
+            stack.push(r);
+        }
+    }
+
+    let odd_cols = cols.iter().copied().skip(1).step_by(2).collect::<Vec<_>>();
+    smawk_or_fallback_inner(matrix, &stack, &odd_cols, minima, stats);
+
+    // The minima of the odd columns bound the rows to scan for the
+    // even columns, unless they are out of order.
+    let mut consistent = true;
+    let mut r = 0;
+    for (c, &col) in cols.iter().enumerate().step_by(2) {
+        let last_row = match cols.get(c + 1) {
+            Some(&next) => minima[next],
+            None => stack[stack.len() - 1],
+        };
+        if last_row < stack[r] {
+            consistent = false;
+            break;
+        }
+        let mut pair = (matrix(stack[r], col), stack[r]);
+        while stack[r] != last_row {
+            r += 1;
+            if matrix(stack[r], col) < pair.0 {
+                pair = (matrix(stack[r], col), stack[r]);
+            }
+        }
+        minima[col] = pair.1;
+    }
+
+    if !consistent || !is_staircase(matrix, rows, cols, minima) {
+        stats.fallbacks += 1;
+        for &col in cols {
+            let mut pair = (matrix(rows[0], col), rows[0]);
+            for &row in &rows[1..] {
+                if matrix(row, col) < pair.0 {
+                    pair = (matrix(row, col), row);
+                }
+            }
+            minima[col] = pair.1;
+        }
+    }
+}
+
+/// Number of rows and columns in the bands compared by `is_staircase`.
+const STAIRCASE_BAND: usize = 4;
+
+/// Check that the minima of `cols` are non-decreasing, and that no
+/// row is smaller than the minimum of a column it is compared with,
+/// or equal to it with a smaller row index.
+/// Every row is compared with the first and last column, the columns
+/// where the row lies between the minima of the neighboring columns,
+/// and a band of `STAIRCASE_BAND` columns, and every column with a
+/// band of rows. This takes O(*m* + *n*) time.
+fn is_staircase<T: PartialOrd + Copy, M: Fn(usize, usize) -> T>(
+    matrix: &M,
+    rows: &[usize],
+    cols: &[usize],
+    minima: &[usize],
+) -> bool {
+    if cols.windows(2).any(|w| minima[w[0]] > minima[w[1]]) {
+        return false;
+    }
+    let values = cols
+        .iter()
+        .map(|&col| (matrix(minima[col], col), minima[col]))
+        .collect::<Vec<_>>();
+    // Column k covers the rows from the minimum of column k - 1 to
+    // the minimum of column k + 1.
+    // Compare every row with a band of columns and every column with
+    // a band of rows. The bands cover the whole matrix when it has few
+    // rows or few columns.
+    let (p, q) = (rows.len(), cols.len());
+    for (t, &row) in rows.iter().enumerate() {
+        for i in 0..STAIRCASE_BAND.min(q) {
+            let k = (t + i) % q;
+            if (matrix(row, cols[k]), row) < values[k] {
+                return false;
+            }
+        }
+    }
+    for (k, &col) in cols.iter().enumerate() {
+        for i in 0..STAIRCASE_BAND.min(p) {
+            let row = rows[(k + i) % p];
+            if (matrix(row, col), row) < values[k] {
+                return false;
+            }
+        }
+    }
+    let last = cols.len() - 1;
+    let mut first = 0;
+    for &row in rows {
+        if (matrix(row, cols[0]), row) < values[0] || (matrix(row, cols[last]), row) < values[last]
+        {
+            return false;
+        }
+        while first + 1 < cols.len() && minima[cols[first + 1]] < row {
+            first += 1;
+        }
+        let mut k = first;
+        while k < cols.len() && (k == 0 || minima[cols[k - 1]] <= row) {
+            if (matrix(row, cols[k]), row) < values[k] {
+                return false;
+            }
+            k += 1;
+        }
+    }
+    true
+}
+
+/// Compute column minima in the given area of the matrix. The
+/// `minima` slice is updated inplace.
+fn smawk_inner<T: PartialOrd + Copy, M: Fn(usize, usize) -> T>(
+    matrix: &M,
+    rows: &[usize],
+    cols: &[usize],
+    minima: &mut [usize],
+) {
+    smawk_inner_batched(
+        matrix,
+        &|queries: &[(usize, usize)], out: &mut [T]| {
+            for (value, &(row, col)) in out.iter_mut().zip(queries) {
+                *value = matrix(row, col);
+            }
+        },
+        rows,
+        cols,
+        minima,
+    );
+}
+
+/// SMAWK with the interpolation step evaluated in batches.
+///
+/// The reduce step compares elements one pair at a time, and every
+/// comparison depends on the outcome of the previous one, so it uses
+/// `matrix`. Once the minima of the odd columns are known, the
+/// candidate rows of all even columns are known too. These are
+/// gathered and evaluated with a single call to `batch`.
+fn smawk_inner_batched<T, M, B>(
+    matrix: &M,
+    batch: &B,
+    rows: &[usize],
+    cols: &[usize],
+    minima: &mut [usize],
+) where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+    B: Fn(&[(usize, usize)], &mut [T]),
+{
+    smawk_inner_with_sink(matrix, batch, rows, cols, minima, &mut |_, _| {});
+}
+
+/// Like [`smawk_inner_batched`], but call `sink(col, row)` as soon as
+/// the minimum of a column is final. Each column is finalized once,
+/// on the level of the recursion where it is an even column.
+fn smawk_inner_with_sink<T, M, B, S>(
+    matrix: &M,
+    batch: &B,
+    rows: &[usize],
+    cols: &[usize],
+    minima: &mut [usize],
+    sink: &mut S,
+) where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+    B: Fn(&[(usize, usize)], &mut [T]),
+    S: FnMut(usize, usize),
+{
+    let size = scratch_size(rows.len(), cols.len()) - rows.len() - cols.len();
+    let mut scratch = vec![0; size];
+    let mut queries = Vec::with_capacity(rows.len() + cols.len());
+    let mut values = Vec::with_capacity(rows.len() + cols.len());
+    smawk_levels(
+        matrix,
+        batch,
+        rows,
+        cols,
+        minima,
+        sink,
+        &mut NoSkip,
+        &mut scratch,
+        &mut queries,
+        &mut values,
+    );
+}
+
+/// Like [`smawk_inner_with_sink`] on all rows and columns of an
+/// *m* ✕ *n* matrix.
+///
+/// The index lists of the first level are written to the front of the
+/// scratch buffer of the lower levels, so a call allocates only this
+/// buffer, the `queries` and the `values`. This matters for many calls
+/// on small matrices, where the allocations dominate.
+fn smawk_full_with_sink<T, M, B, S>(
+    matrix: &M,
+    batch: &B,
+    nrows: usize,
+    ncols: usize,
+    minima: &mut [usize],
+    sink: &mut S,
+) where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+    B: Fn(&[(usize, usize)], &mut [T]),
+    S: FnMut(usize, usize),
+{
+    smawk_full(matrix, batch, nrows, ncols, minima, sink, &mut NoSkip);
+}
+
+/// Like [`smawk_full_with_sink`], but skip the elements which `skip`
+/// rules out. The interpolation step then evaluates one element at a
+/// time.
+fn smawk_full_with_skip<T, M, K>(
+    matrix: &M,
+    nrows: usize,
+    ncols: usize,
+    minima: &mut [usize],
+    skip: &mut K,
+) where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+    K: Skip<T>,
+{
+    let batch = |queries: &[(usize, usize)], out: &mut [T]| {
+        for (value, &(row, col)) in out.iter_mut().zip(queries) {
+            *value = matrix(row, col);
+        }
+    };
+    smawk_full(matrix, &batch, nrows, ncols, minima, &mut |_, _| {}, skip);
+}
+
+/// Shared body of [`smawk_full_with_sink`] and
+/// [`smawk_full_with_skip`].
+fn smawk_full<T, M, B, S, K>(
+    matrix: &M,
+    batch: &B,
+    nrows: usize,
+    ncols: usize,
+    minima: &mut [usize],
+    sink: &mut S,
+    skip: &mut K,
+) where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+    B: Fn(&[(usize, usize)], &mut [T]),
+    S: FnMut(usize, usize),
+    K: Skip<T>,
+{
+    let mut scratch = vec![0; scratch_size(nrows, ncols)];
+    let (rows, rest) = scratch.split_at_mut(nrows);
+    let (cols, rest) = rest.split_at_mut(ncols);
+    for (i, row) in rows.iter_mut().enumerate() {
+        *row = i;
+    }
+    for (j, col) in cols.iter_mut().enumerate() {
+        *col = j;
+    }
+    let mut queries = Vec::with_capacity(nrows + ncols);
+    let mut values = Vec::with_capacity(nrows + ncols);
+    smawk_levels(
+        matrix,
+        batch,
+        rows,
+        cols,
+        minima,
+        sink,
+        skip,
+        rest,
+        &mut queries,
+        &mut values,
+    );
+}
+
+/// Like [`smawk_inner_with_sink`], but without allocating.
+///
+/// The index lists of every level of the recursion are carved out of
+/// `scratch` like in [`smawk_in_scratch`], which needs
+/// `scratch_size(m, n) - m - n` slots for an *m* ✕ *n* matrix. The
+/// `queries` and `values` of the interpolation steps are reused by
+/// every level. They never grow beyond *m* + *n* elements, so they
+/// are not reallocated if they have that much capacity.
+///
+/// Elements which `skip` rules out are not evaluated. With an active
+/// [`Skip`], the interpolation step scans the candidates of every
+/// column one at a time instead of evaluating them with `batch`.
+#[allow(clippy::too_many_arguments)]
+fn smawk_levels<T, M, B, S, K>(
+    matrix: &M,
+    batch: &B,
+    rows: &[usize],
+    cols: &[usize],
+    minima: &mut [usize],
+    sink: &mut S,
+    skip: &mut K,
+    scratch: &mut [usize],
+    queries: &mut Vec<(usize, usize)>,
+    values: &mut Vec<T>,
+) where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+    B: Fn(&[(usize, usize)], &mut [T]),
+    S: FnMut(usize, usize),
+    K: Skip<T>,
+{
+    if cols.is_empty() {
+        return;
+    }
+
+    let (stack, rest) = scratch.split_at_mut(std::cmp::min(rows.len(), cols.len()));
+    let (odd_cols, rest) = rest.split_at_mut(cols.len() / 2);
+
+    // The interpolation step below overwrites `values`, so it is free
+    // to use here.
+    let len = smawk_reduce_with_skip(matrix, rows, cols, stack, values, skip);
+    let rows = &stack[..len];
+
+    for (odd, &c) in odd_cols.iter_mut().zip(cols.iter().skip(1).step_by(2)) {
+        *odd = c;
+    }
+
+    smawk_levels(
+        matrix, batch, rows, odd_cols, minima, sink, skip, rest, queries, values,
+    );
+
+    if K::ACTIVE {
+        // Later rows lose ties, so a row which cannot get below the
+        // best value so far is skipped.
+        let mut r = 0;
+        for (c, &col) in cols.iter().enumerate().step_by(2) {
+            let last_row = if c == cols.len() - 1 {
+                rows[rows.len() - 1]
+            } else {
+                minima[cols[c + 1]]
+            };
+            let mut pair = (matrix(rows[r], col), rows[r]);
+            while rows[r] != last_row {
+                r += 1;
+                if skip.skip(pair.0, rows[r], col) {
+                    continue;
+                }
+                let value = matrix(rows[r], col);
+                if value < pair.0 {
+                    pair = (value, rows[r]);
+                }
+            }
+            minima[col] = pair.1;
+            sink(col, pair.1);
+        }
+        return;
+    }
+
+    // Gather the candidate rows of every even column. The candidates
+    // for column cols[c] are the rows from the minimum of the
+    // previous odd column to the minimum of the next odd column.
+    queries.clear();
+    let mut r = 0;
+    for (c, &col) in cols.iter().enumerate().filter(|(c, _)| c % 2 == 0) {
+        let last_row = if c == cols.len() - 1 {
+            rows[rows.len() - 1]
+        } else {
+            minima[cols[c + 1]]
+        };
+        queries.push((rows[r], col));
+        while rows[r] != last_row {
+            r += 1;
+            queries.push((rows[r], col));
+        }
+    }
+
+    // The first value doubles as a placeholder for the rest.
+    let (row, col) = queries[0];
+    values.clear();
+    values.resize(queries.len(), matrix(row, col));
+    batch(&queries[1..], &mut values[1..]);
+
+    let mut k = 0;
+    while k < queries.len() {
+        let col = queries[k].1;
+        let mut pair = (values[k], queries[k].0);
+        k += 1;
+        while k < queries.len() && queries[k].1 == col {
+            if (values[k], queries[k].0) < pair {
+                pair = (values[k], queries[k].0);
+            }
+            k += 1;
+        }
+        minima[col] = pair.1;
+        sink(col, pair.1);
+    }
+}
+
+/// The reduce step of SMAWK: push the `rows` which can hold a column
+/// minimum onto `stack` and return how many there are.
+///
+/// A stack entry at position *k* is compared at column `cols[k]`. Its
+/// value there is kept in `values`, so popping an entry compares the
+/// new top without evaluating it again. A row which pops entries
+/// keeps its value at the column it is pushed to. Every entry below
+/// the top has been compared at its column, so only the value of the
+/// top can be missing. Each (row, column) pair is thus evaluated at
+/// most once.
+fn smawk_reduce<T: PartialOrd + Copy, M: Fn(usize, usize) -> T>(
+    matrix: &M,
+    rows: &[usize],
+    cols: &[usize],
+    stack: &mut [usize],
+    values: &mut Vec<T>,
+) -> usize {
+    smawk_reduce_with_skip(matrix, rows, cols, stack, values, &mut NoSkip)
+}
+
+/// Like [`smawk_reduce`], but a row which `skip` rules out at the
+/// column of the top of the stack cannot pop it, and is pushed
+/// without being evaluated.
+fn smawk_reduce_with_skip<T, M, K>(
+    matrix: &M,
+    rows: &[usize],
+    cols: &[usize],
+    stack: &mut [usize],
+    values: &mut Vec<T>,
+    skip: &mut K,
+) -> usize
+where
+    T: PartialOrd + Copy,
+    M: Fn(usize, usize) -> T,
+    K: Skip<T>,
+{
+    values.clear();
+    let mut len = 0;
+    for &r in rows {
+        // The value of `r` at the column of the last entry it popped,
+        // which is where it will be pushed.
+        let mut value = None;
+        while len > 0 {
+            if values.len() < len {
+                values.push(matrix(stack[len - 1], cols[len - 1]));
+            }
+            if skip.skip(values[len - 1], r, cols[len - 1]) {
+                break;
+            }
+            let v = matrix(r, cols[len - 1]);
+            if values[len - 1] > v {
+                len -= 1;
+                values.truncate(len);
+                value = Some(v);
+            } else {
+                break;
+            }
+        }
+        if len != cols.len() {
+            stack[len] = r;
+            len += 1;
+            values.extend(value);
+        }
+    }
+    len
+}
+
+/// Compute upper-right column minima in O(*m* + *n*) time.
+///
+/// The input matrix must be totally monotone.
+///
+/// The function returns a vector of `(usize, T)`. The `usize` in the
+/// tuple at index `j` tells you the row of the minimum value in
+/// column `j` and the `T` value is minimum value itself.
+///
+/// The algorithm only considers values above the main diagonal, which
+/// means that it computes values `v(j)` where:
+///
+/// ```text
+/// v(0) = initial
+/// v(j) = min { M[i, j] | i < j } for j > 0
+/// ```
+///
+/// If we let `r(j)` denote the row index of the minimum value in
+/// column `j`, the tuples in the result vector become `(r(j), M[r(j),
+/// j])`.
+///
+/// The algorithm is an *online* algorithm, in the sense that `matrix`
+/// function can refer back to previously computed column minima when
+/// determining an entry in the matrix. The guarantee is that we only
+/// call `matrix(i, j)` after having computed `v(i)`. This is
+/// reflected in the `&[(usize, T)]` argument to `matrix`, which grows
+/// as more and more values are computed.
+///
+/// An empty vector is returned when `size` is zero.
+///
+/// # Column 0
+///
+/// Column 0 has no elements above the diagonal, so the matrix is
+/// never evaluated there and the result always starts with `(0,
+/// initial)`. The row index 0 is a placeholder. This cannot be
+/// computed from the matrix instead: element `M[i, j]` may depend on
+/// `v(i)`, so `v(0)` must be known before any element is evaluated.
+/// If your recurrence has a genuine minimum in its first column,
+/// compute it yourself and pass it as `initial`.
+///
+/// The `initial` value takes part in comparisons when `matrix` uses
+/// it to build later elements, so it must be comparable to itself.
+/// Debug builds check this, which catches a NaN `initial` value.
+///
+/// # Panics
+///
+/// In debug builds, panics if `initial` is not comparable to itself.
+pub fn online_column_minima<T: Copy + PartialOrd, M: Fn(&[(usize, T)], usize, usize) -> T>(
+    initial: T,
+    size: usize,
+    matrix: M,
+) -> Vec<(usize, T)> {
+    online_column_minima_batched(initial, size, |result, queries, out| {
+        for (value, &(i, j)) in out.iter_mut().zip(queries) {
+            *value = matrix(result, i, j);
+        }
+    })
+}
+
+/// Compute upper-right column minima and count the optimal solutions.
+///
+/// This is like [`online_column_minima`], but every column also gets
+/// the number of optimal solutions of the dynamic program: column 0
+/// has one solution, and column `j` has the sum of the counts of all
+/// rows `i < j` with `M[i, j]` equal to the minimum `v(j)`. The counts
+/// are reduced modulo `modulus` if given.
+///
+/// The minima are computed online first. The matrix is then fully
+/// known, and the last rows of the minima are found with SMAWK like in
+/// [`column_minima_counting`], which has the details on the
+/// requirements and the running time.
+///
+/// # Examples
+///
+/// ```
+/// // Split 0..4 into pieces with a cost of 1 for pieces of length 1
+/// // or 2 and a convex penalty for longer pieces.
+/// let cost = |minima: &[(usize, u32)], i: usize, j: usize| {
+///     let long = (j - i).saturating_sub(2) as u32;
+///     minima[i].1 + 1 + 100 * long * long
+/// };
+/// let counted = smawk::online_column_minima_counting(0, 5, cost, None);
+/// assert_eq!(counted.iter().map(|&(_, v, _)| v).collect::<Vec<_>>(),
+///            vec![0, 1, 1, 2, 2]);
+/// assert_eq!(counted.iter().map(|&(_, _, c)| c).collect::<Vec<_>>(),
+///            vec![1, 1, 1, 2, 1]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `modulus` is zero, or if a count overflows `u64` when
+/// `modulus` is `None`.
+pub fn online_column_minima_counting<T, M>(
+    initial: T,
+    size: usize,
+    matrix: M,
+    modulus: Option<u64>,
+) -> Vec<(usize, T, u64)>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> T,
+{
+    assert_ne!(modulus, Some(0), "modulus must not be zero");
+    let minima = online_column_minima(initial, size, &matrix);
+    let mut last = vec![0; size];
+    if size > 1 {
+        smawk_inner(
+            &|i, j| {
+                if i >= j {
+                    BandKey::Below(i)
+                } else {
+                    BandKey::Present((matrix(&minima, i, j), std::cmp::Reverse(i)))
+                }
+            },
+            &(0..size).collect::<Vec<_>>(),
+            &(1..size).collect::<Vec<_>>(),
+            &mut last,
+        );
+    }
+
+    let mut counts = Vec::with_capacity(size);
+    for (col, &(first, value)) in minima.iter().enumerate() {
+        let count = if col == 0 {
+            add_count(0, 1, modulus)
+        } else {
+            (first..=last[col])
+                .filter(|&row| row == first || matrix(&minima, row, col) == value)
+                .fold(0, |count, row| add_count(count, counts[row], modulus))
+        };
+        counts.push(count);
+    }
+    minima
+        .into_iter()
+        .zip(counts)
+        .map(|((row, value), count)| (row, value, count))
+        .collect()
+}
+
+/// Compute upper-right column minima with a worst-case bound on the
+/// work per column.
+///
+/// This computes the same minima as [`online_column_minima`], which
+/// takes O(*n*) time in total, but occasionally runs SMAWK on a large
+/// submatrix before it can finish the next column. Here every column
+/// is finished after at most 4 ⌈log₂(*n* + 1)⌉ + 1 matrix
+/// evaluations, at the price of O(*n* log *n*) evaluations in total.
+/// This suits pipelines where each column must be finished within a
+/// fixed time.
+///
+/// The algorithm keeps the lower envelope of the finished rows: a
+/// list of rows, each owning an interval of the unfinished columns
+/// where it gives the minimum so far. Since the matrix is totally
+/// monotone, a new row beats the current owners on a suffix of the
+/// columns. Once column `j` is finished, row `j` is added by finding
+/// the start of its suffix with one binary search over the intervals
+/// and one over the columns of an interval. The envelope is cut at
+/// that point, which takes constant time, unlike popping the replaced
+/// intervals one at a time. Ties are broken in favor of the smallest
+/// row.
+///
+/// # Examples
+///
+/// ```
+/// let matrix = vec![vec![0, 2, 4],
+///                   vec![0, 0, 1],
+///                   vec![0, 0, 0]];
+/// let minima = smawk::online_column_minima_deamortized(0, 3, |_, i, j| matrix[i][j]);
+/// assert_eq!(minima, vec![(0, 0), (0, 2), (1, 1)]);
+/// ```
+pub fn online_column_minima_deamortized<T, M>(initial: T, size: usize, matrix: M) -> Vec<(usize, T)>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> T,
+{
+    if size == 0 {
+        return Vec::new();
+    }
+    let mut result = Vec::with_capacity(size);
+    result.push((0, initial));
+    // The envelope holds (row, first column) pairs from `head` on. The
+    // interval of a row ends where the next one starts.
+    let mut envelope = vec![(0, 1)];
+    let mut head = 0;
+
+    for col in 1..size {
+        while envelope
+            .get(head + 1)
+            .is_some_and(|&(_, start)| start <= col)
+        {
+            head += 1;
+        }
+        let row = envelope[head].0;
+        result.push((row, matrix(&result, row, col)));
+        if col == size - 1 {
+            break;
+        }
+
+        // Row `col` beats the owner of column `c` if it is strictly
+        // smaller there.
+        let beats = |owner: usize, c: usize| matrix(&result, col, c) < matrix(&result, owner, c);
+        let start_of = |k: usize| std::cmp::max(envelope[k].1, col + 1);
+        let (mut lo, mut hi) = (head, envelope.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if beats(envelope[mid].0, start_of(mid)) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        // Row `col` wins all intervals from `lo` on, and a suffix of
+        // the interval before it.
+        let mut start = if lo < envelope.len() {
+            start_of(lo)
+        } else {
+            size
+        };
+        if lo > head {
+            let owner = envelope[lo - 1].0;
+            let (mut first, mut last) = (start_of(lo - 1), start);
+            while first < last {
+                let mid = first + (last - first) / 2;
+                if beats(owner, mid) {
+                    last = mid;
+                } else {
+                    first = mid + 1;
+                }
+            }
+            start = first;
+        }
+        envelope.truncate(lo);
+        if start < size {
+            envelope.push((col, start));
+        }
+    }
+    result
+}
+
+/// Compute upper-right column minima with batched matrix evaluations.
+///
+/// This is like [`online_column_minima`], except that `matrix` is
+/// asked for many elements at once. It must set element `k` of the
+/// output slice to `M[i, j]` where `(i, j)` is element `k` of the
+/// query slice. All queried rows have been finished, so the same
+/// guarantee as for [`online_column_minima`] holds.
+///
+/// Whenever the algorithm computes new tentative column minima, it
+/// evaluates a whole square submatrix with SMAWK. The candidates found
+/// by the interpolation steps of SMAWK are evaluated in batches, as
+/// are the minimum values of the new tentative columns. The remaining
+/// evaluations depend on each other and are done one at a time.
+///
+/// # Examples
+///
+/// ```
+/// let matrix = vec![vec![0, 2, 4],
+///                   vec![0, 0, 1],
+///                   vec![0, 0, 0]];
+/// let minima = smawk::online_column_minima_batched(0, 3, |_, queries, out| {
+///     for (value, &(i, j)) in out.iter_mut().zip(queries) {
+///         *value = matrix[i][j];
+///     }
+/// });
+/// assert_eq!(minima, vec![(0, 0), (0, 2), (1, 1)]);
+/// ```
+pub fn online_column_minima_batched<T, M>(initial: T, size: usize, matrix: M) -> Vec<(usize, T)>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], &[(usize, usize)], &mut [T]),
+{
+    online_inner(initial, size, matrix, &NoHooks, &Infallible).expect("allocation is infallible")
+}
+
+/// Runner for [`online_column_minima`] which keeps its memory
+/// between calls.
+///
+/// Every call to [`online_column_minima`] allocates the result vector
+/// and the buffers used by the algorithm. When the function is called
+/// many times, for example once per paragraph when wrapping text,
+/// these allocations can take a noticeable part of the time. The
+/// runner instead keeps the buffers and clears them at the start of
+/// every [`run`](OnlineMinima::run). Once a run has been as large as
+/// the following ones, the runs allocate no memory at all.
+///
+/// The buffers grow to fit the largest run and are not shrunk again.
+/// Use [`reset`](OnlineMinima::reset) to release them, for example
+/// after an unusually large run.
+///
+/// # Examples
+///
+/// ```
+/// let mut runner = smawk::OnlineMinima::new();
+/// for size in [5, 3, 4] {
+///     let cost = |minima: &[(usize, i32)], i: usize, j: usize| {
+///         minima[i].1 + ((j - i) as i32 - 2).pow(2)
+///     };
+///     let minima = runner.run(0, size, cost);
+///     assert_eq!(minima, &smawk::online_column_minima(0, size, cost)[..]);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct OnlineMinima<T> {
+    buffers: OnlineBuffers<T>,
+}
+
+impl<T: Copy + PartialOrd> OnlineMinima<T> {
+    /// Create a runner without any buffers.
+    pub fn new() -> Self {
+        OnlineMinima {
+            buffers: OnlineBuffers::new(),
+        }
+    }
+
+    /// Compute upper-right column minima with the buffers of the
+    /// runner.
+    ///
+    /// This returns the same minima as [`online_column_minima`] with
+    /// the same arguments. The minima are valid until the next call.
+    pub fn run<M: Fn(&[(usize, T)], usize, usize) -> T>(
+        &mut self,
+        initial: T,
+        size: usize,
+        matrix: M,
+    ) -> &[(usize, T)] {
+        online_in_buffers(
+            initial,
+            size,
+            |result, queries, out| {
+                for (value, &(i, j)) in out.iter_mut().zip(queries) {
+                    *value = matrix(result, i, j);
+                }
+            },
+            &NoHooks,
+            &Infallible,
+            &mut self.buffers,
+        )
+        .expect("allocation is infallible");
+        &self.buffers.result
+    }
+
+    /// Release the memory held by the runner.
+    pub fn reset(&mut self) {
+        self.buffers = OnlineBuffers::new();
+    }
+}
+
+impl<T: Copy + PartialOrd> Default for OnlineMinima<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Number of rows up to which the online algorithm scans a new
+/// tentative submatrix directly instead of running SMAWK on it.
+const ONLINE_SCAN_ROWS: usize = 4;
+
+/// Compute upper-right column minima and report allocation failure
+/// as an error.
+///
+/// This computes the same minima as [`online_column_minima`]. The
+/// result vector and the index lists used by the algorithm, which
+/// all grow with `size`, are allocated up front with `try_reserve`.
+/// When the allocation fails, `Err(SmawkError::Alloc)` is returned
+/// instead of aborting the process. The short-lived vectors used by
+/// each SMAWK step are still allocated normally.
+///
+/// # Examples
+///
+/// ```
+/// let matrix = vec![vec![0, 2, 4],
+///                   vec![0, 0, 1],
+///                   vec![0, 0, 0]];
+/// let minima = smawk::try_online_column_minima(0, 3, |_, i, j| matrix[i][j]);
+/// assert_eq!(minima, Ok(vec![(0, 0), (0, 2), (1, 1)]));
+/// ```
+pub fn try_online_column_minima<T, M>(
+    initial: T,
+    size: usize,
+    matrix: M,
+) -> Result<Vec<(usize, T)>, SmawkError>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> T,
+{
+    online_inner(
+        initial,
+        size,
+        |result, queries, out| {
+            for (value, &(i, j)) in out.iter_mut().zip(queries) {
+                *value = matrix(result, i, j);
+            }
+        },
+        &NoHooks,
+        &Fallible,
+    )
+}
+
+/// Hooks which let the callers of [`online_inner`] observe and steer
+/// the online algorithm.
+pub(crate) trait OnlineHooks<T> {
+    /// Called before the elements of a step are evaluated one at a
+    /// time, with the finished prefix of the result and the elements
+    /// which the step is likely to need. All of these elements are in
+    /// finished rows, so they can be evaluated ahead of time.
+    fn prefetch(&self, _result: &[(usize, T)], _queries: &[(usize, usize)]) {}
+
+    /// Return `true` if `row` is known not to hold the minimum of any
+    /// column after `column`.
+    fn dominated_beyond(&self, _row: usize, _column: usize) -> bool {
+        false
+    }
+
+    /// Return `true` if the element `(row, column)` is known to be at
+    /// least `value` without evaluating it. The finished prefix of
+    /// the result is given like for [`OnlineHooks::prefetch`].
+    fn at_least(&self, _result: &[(usize, T)], _row: usize, _column: usize, _value: T) -> bool {
+        false
+    }
+}
+
+/// Hooks for [`online_inner`] which do nothing.
+struct NoHooks;
+
+impl<T> OnlineHooks<T> for NoHooks {}
+
+/// The online algorithm with the vectors which grow with `size`
+/// taken from `source`.
+pub(crate) fn online_inner<T, M, H, S>(
+    initial: T,
+    size: usize,
+    matrix: M,
+    hooks: &H,
+    source: &S,
+) -> Result<Vec<(usize, T)>, SmawkError>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], &[(usize, usize)], &mut [T]),
+    H: OnlineHooks<T>,
+    S: BufferSource,
+{
+    let mut buffers = OnlineBuffers::new();
+    online_in_buffers(initial, size, matrix, hooks, source, &mut buffers)?;
+    Ok(buffers.result)
+}
+
+/// The result of the online algorithm and the buffers used by its
+/// SMAWK steps.
+#[derive(Debug)]
+struct OnlineBuffers<T> {
+    result: Vec<(usize, T)>,
+    rows: Vec<usize>,
+    cols: Vec<usize>,
+    minima: Vec<usize>,
+    scratch: Vec<usize>,
+    queries: Vec<(usize, usize)>,
+    values: Vec<T>,
+}
+
+impl<T> OnlineBuffers<T> {
+    fn new() -> Self {
+        OnlineBuffers {
+            result: Vec::new(),
+            rows: Vec::new(),
+            cols: Vec::new(),
+            minima: Vec::new(),
+            scratch: Vec::new(),
+            queries: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+/// Clear `buffer` and make room for `len` elements, replacing it with
+/// a buffer from `source` if it is too small.
+fn reuse_buffer<U, S: BufferSource>(
+    buffer: &mut Vec<U>,
+    len: usize,
+    source: &S,
+) -> Result<(), SmawkError> {
+    buffer.clear();
+    if buffer.capacity() < len {
+        *buffer = source.with_capacity(len)?;
+    }
+    Ok(())
+}
+
+/// The online algorithm with the result written to `buffers`. The
+/// buffers are cleared first, and only those which are too small for
+/// `size` are replaced with new ones from `source`.
+fn online_in_buffers<T, M, H, S>(
+    initial: T,
+    size: usize,
+    matrix: M,
+    hooks: &H,
+    source: &S,
+    buffers: &mut OnlineBuffers<T>,
+) -> Result<(), SmawkError>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], &[(usize, usize)], &mut [T]),
+    H: OnlineHooks<T>,
+    S: BufferSource,
+{
+    debug_assert!(
+        initial.partial_cmp(&initial).is_some(),
+        "initial value is not comparable to itself"
+    );
+    buffers.result.clear();
+    if size == 0 {
+        return Ok(());
+    }
+    // The buffers are moved out while the algorithm runs and put back
+    // at the end. They are dropped on errors.
+    let mut result = std::mem::take(&mut buffers.result);
+    reuse_buffer(&mut result, size, source)?;
+    result.push((0, initial));
+
+    // Index lists, minima, scratch memory, and queries for the SMAWK
+    // steps. Their sizes are bounded by the size of the largest step,
+    // so they are allocated once.
+    let mut rows = std::mem::take(&mut buffers.rows);
+    reuse_buffer(&mut rows, size, source)?;
+    let mut cols = std::mem::take(&mut buffers.cols);
+    reuse_buffer(&mut cols, size, source)?;
+    let mut minima = std::mem::take(&mut buffers.minima);
+    reuse_buffer(&mut minima, size, source)?;
+    minima.resize(size, 0);
+    let scratch_size = checked_scratch_size(size, size).ok_or(SmawkError::Alloc)?;
+    let mut scratch = std::mem::take(&mut buffers.scratch);
+    reuse_buffer(&mut scratch, scratch_size - 2 * size, source)?;
+    scratch.resize(scratch_size - 2 * size, 0);
+    let capacity = (2 * size).max(ONLINE_SCAN_ROWS * ONLINE_SCAN_ROWS);
+    let mut queries = std::mem::take(&mut buffers.queries);
+    reuse_buffer(&mut queries, capacity, source)?;
+    let mut values = std::mem::take(&mut buffers.values);
+    reuse_buffer(&mut values, capacity, source)?;
+
+    // State used by the algorithm.
+    let mut finished = 0;
+    let mut base = 0;
+    let mut tentative = 0;
+
+    // Shorthand for evaluating a batch of the matrix. We need a
+    // macro here since we don't want to borrow the result vector.
+    macro_rules! batch {
+        ($queries:expr, $out:expr) => {{
+            for &(i, j) in $queries {
+                assert!(i < j, "(i, j) not above diagonal: ({}, {})", i, j);
+                assert!(
+                    i < size && j < size,
+                    "(i, j) out of bounds: ({}, {}), size: {}",
+                    i,
+                    j,
+                    size
+                );
+            }
+            matrix(&result[..finished + 1], $queries, $out)
+        }};
+    }
+
+    // Shorthand for evaluating a single element.
+    macro_rules! m {
+        ($i:expr, $j:expr) => {{
+            let mut out = [initial];
+            batch!(&[($i, $j)], &mut out);
+            out[0]
+        }};
+    }
+
+    // Keep going until we have finished all size columns. Since the
+    // columns are zero-indexed, we're done when finished == size - 1.
+    while finished < size - 1 {
+        // First case: we have already advanced past the previous
+        // tentative value. We make a new tentative value by applying
+        // smawk_inner to the largest square submatrix that fits under
+        // the base.
+        let i = finished + 1;
+        if i > tentative {
+            // Rows which are dominated beyond finished cannot supply
+            // any later column minima, so the base invariant allows
+            // skipping them.
+            while base < finished && hooks.dominated_beyond(base, finished) {
+                base += 1;
+            }
+            rows.clear();
+            rows.extend(base..finished + 1);
+            tentative = std::cmp::min(finished.saturating_add(rows.len()), size - 1);
+            cols.clear();
+            cols.extend(finished + 1..tentative + 1);
+            queries.clear();
+            if rows.len() <= ONLINE_SCAN_ROWS {
+                // Evaluate the whole submatrix in one batch and keep
+                // the minimum of every column in place.
+                for &col in &cols {
+                    queries.extend(rows.iter().map(|&row| (row, col)));
+                }
+                values.clear();
+                values.resize(queries.len(), initial);
+                batch!(&queries, &mut values);
+                for k in 0..cols.len() {
+                    let start = k * rows.len();
+                    let mut best = start;
+                    for idx in start + 1..start + rows.len() {
+                        if values[idx] < values[best] {
+                            best = idx;
+                        }
+                    }
+                    queries[k] = queries[best];
+                    values[k] = values[best];
+                }
+                queries.truncate(cols.len());
+                values.truncate(cols.len());
+            } else {
+                smawk_levels(
+                    &|i, j| m![i, j],
+                    &|queries: &[(usize, usize)], out: &mut [T]| batch!(queries, out),
+                    &rows,
+                    &cols,
+                    &mut minima,
+                    &mut |_, _| {},
+                    &mut NoSkip,
+                    &mut scratch,
+                    &mut queries,
+                    &mut values,
+                );
+                queries.clear();
+                queries.extend(cols.iter().map(|&col| (minima[col], col)));
+                values.clear();
+                values.resize(queries.len(), initial);
+                batch!(&queries, &mut values);
+            }
+            for (&(row, col), &v) in queries.iter().zip(&values) {
+                if col >= result.len() {
+                    result.push((row, v));
+                } else if v < result[col].1 {
+                    result[col] = (row, v);
+                }
+            }
+            finished = i;
+            continue;
+        }
+
+        // The next two cases need the diagonal element and, unless
+        // the diagonal is a new minimum, the element in the tentative
+        // column.
+        if i < tentative {
+            hooks.prefetch(&result[..finished + 1], &[(i - 1, i), (i - 1, tentative)]);
+        } else {
+            hooks.prefetch(&result[..finished + 1], &[(i - 1, i)]);
+        }
+
+        // Second case: the new column minimum is on the diagonal. All
+        // subsequent ones will be at least as low, so we can clear
+        // out all our work from higher rows. As in the fourth case,
+        // the loss of tentative is amortized against the increase in
+        // base. A diagonal which is bounded below by the tentative
+        // minimum of column i is not evaluated.
+        if !hooks.at_least(&result[..finished + 1], i - 1, i, result[i].1) {
+            let diag = m![i - 1, i];
+            if diag < result[i].1 {
+                result[i] = (i - 1, diag);
+                base = i - 1;
+                tentative = i;
+                finished = i;
+                continue;
+            }
+        }
+
+        // Third case: row i-1 does not supply a column minimum in any
+        // column up to tentative. We simply advance finished while
+        // maintaining the invariant. A row dominated beyond i cannot
+        // supply any of them either, and neither can a row which is
+        // bounded below by the tentative minimum.
+        if hooks.dominated_beyond(i - 1, i)
+            || hooks.at_least(
+                &result[..finished + 1],
+                i - 1,
+                tentative,
+                result[tentative].1,
+            )
+            || m![i - 1, tentative] >= result[tentative].1
+        {
+            finished = i;
+            continue;
+        }
+
+        // Fourth and final case: a new column minimum at tentative.
         // This allows us to make progress by incorporating rows prior
         // to finished into the base. The base invariant holds because
         // these rows cannot supply any later column minima. The work
@@ -414,13 +4596,610 @@ pub fn online_column_minima<T: Copy + PartialOrd, M: Fn(&[(usize, T)], usize, us
         tentative = i;
         finished = i;
     }
-
+
+    *buffers = OnlineBuffers {
+        result,
+        rows,
+        cols,
+        minima,
+        scratch,
+        queries,
+        values,
+    };
+    Ok(())
+}
+
+/// Column minimum and optional runner-up, as computed by
+/// [`online_column_minima_2best`].
+pub type TwoBest<T> = ((usize, T), Option<(usize, T)>);
+
+/// Compute upper-right column minima and runner-ups in O(*n* log *n*) time.
+///
+/// This is like [`online_column_minima`], except that each entry in
+/// the result also contains the second smallest value in the column
+/// (above the main diagonal), together with its row. The runner-up
+/// comes from a different row than the minimum, but it can have the
+/// same value in case of ties. Ties are broken in favor of the
+/// smallest row index, just like for the minimum. Columns with fewer
+/// than two candidate rows have no runner-up.
+///
+/// The `matrix` function is given the finished prefix of the result,
+/// including the runner-ups. This allows recurrences which depend on
+/// the two best values of previous columns. The guarantee is the same
+/// as for [`online_column_minima`]: `matrix(i, j)` is only called
+/// after the entry for column `i` has been computed.
+///
+/// The runner-ups do not share the monotone structure of the column
+/// minima: removing the best row from each column removes a
+/// different row per column and the result is no longer totally
+/// monotone. Instead, the runner-up for column `j` is the better of
+/// the minima over the rows above and below the best row. These are
+/// found in a structure of row blocks which is extended as rows are
+/// finished. This costs O(*n* log *n*) additional matrix evaluations
+/// in total.
+///
+/// # Examples
+///
+/// ```
+/// let matrix = vec![vec![0, 2, 3],
+///                   vec![0, 0, 3],
+///                   vec![0, 0, 0]];
+/// let minima = smawk::online_column_minima_2best(0, 3, |_, i, j| matrix[i][j]);
+/// assert_eq!(minima, vec![((0, 0), None),
+///                         ((0, 2), None),
+///                         ((0, 3), Some((1, 3)))]);
+/// ```
+pub fn online_column_minima_2best<T, M>(initial: T, size: usize, matrix: M) -> Vec<TwoBest<T>>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[TwoBest<T>], usize, usize) -> T,
+{
+    /// Finish row `col - 1` and find the best row in column `col`,
+    /// skipping row `best_row`.
+    fn runner_up<T: Copy + PartialOrd>(
+        done: &[TwoBest<T>],
+        matrix: &impl Fn(&[TwoBest<T>], usize, usize) -> T,
+        envelopes: &mut RowEnvelopes,
+        size: usize,
+        best_row: usize,
+        col: usize,
+    ) -> Option<(usize, T)> {
+        let eval = |i, j| matrix(done, i, j);
+        if col > 0 {
+            envelopes.push_row(col - 1, size, &eval);
+        }
+        let above = envelopes.minimum(0, best_row, col, &eval);
+        let below = envelopes.minimum(best_row + 1, col, col, &eval);
+        match (above, below) {
+            (Some(above), Some(below)) if below.1 < above.1 => Some(below),
+            (above, below) => above.or(below),
+        }
+    }
+
+    // The runner-ups are computed lazily: whenever the online
+    // algorithm hands us a longer finished prefix, the newly finished
+    // columns are extended with their runner-ups before evaluating
+    // the matrix.
+    let done = std::cell::RefCell::new(Vec::with_capacity(size));
+    let envelopes = std::cell::RefCell::new(RowEnvelopes { levels: Vec::new() });
+    let extend = |done: &mut Vec<TwoBest<T>>, best: &[(usize, T)]| {
+        let mut envelopes = envelopes.borrow_mut();
+        while done.len() < best.len() {
+            let col = done.len();
+            let second = runner_up(done, &matrix, &mut envelopes, size, best[col].0, col);
+            done.push((best[col], second));
+        }
+    };
+    let minima = online_column_minima(initial, size, |best, i, j| {
+        let mut done = done.borrow_mut();
+        extend(&mut done, best);
+        matrix(&done, i, j)
+    });
+
+    let mut done = done.into_inner();
+    extend(&mut done, &minima);
+    done
+}
+
+/// Lower envelopes of aligned blocks of finished rows.
+///
+/// The block with index `k` on level `h` holds the rows `k·2ʰ` to
+/// `(k+1)·2ʰ - 1`. Its envelope lists the rows which are minimal in
+/// the block, each with the first column from which it is minimal.
+/// A block is added once its last row `x` is finished and the
+/// envelope covers the columns from `x + 1`, which are the only ones
+/// where the block is queried.
+///
+/// In a totally monotone matrix, the columns where the lower half of
+/// a block has a strictly smaller minimum than the upper half form a
+/// suffix. Two envelopes are therefore merged with a binary search,
+/// and finishing a row costs O(log *n*) evaluations in total. A range
+/// of rows is covered by O(log *n*) blocks, each of which costs one
+/// evaluation to query.
+struct RowEnvelopes {
+    levels: Vec<Vec<Vec<(usize, usize)>>>,
+}
+
+impl RowEnvelopes {
+    /// Row of `envelope` which is minimal in column `col`.
+    fn row_at(envelope: &[(usize, usize)], col: usize) -> usize {
+        envelope[envelope.partition_point(|&(start, _)| start <= col) - 1].1
+    }
+
+    /// Add the blocks which end with row `x`. Rows must be pushed in
+    /// order.
+    fn push_row<T: PartialOrd>(
+        &mut self,
+        x: usize,
+        size: usize,
+        eval: &impl Fn(usize, usize) -> T,
+    ) {
+        let start = x + 1;
+        let mut envelope = vec![(start, x)];
+        let mut index = x;
+        for level in 0.. {
+            if self.levels.len() == level {
+                self.levels.push(Vec::new());
+            }
+            debug_assert_eq!(self.levels[level].len(), index);
+            self.levels[level].push(envelope);
+            if index.is_multiple_of(2) {
+                break;
+            }
+
+            let upper = &self.levels[level][index - 1];
+            let lower = &self.levels[level][index];
+            let (mut lo, mut hi) = (start, size);
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                if eval(Self::row_at(lower, mid), mid) < eval(Self::row_at(upper, mid), mid) {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+
+            envelope = Vec::with_capacity(upper.len() + lower.len());
+            if lo > start {
+                envelope.push((start, Self::row_at(upper, start)));
+                envelope.extend(
+                    upper
+                        .iter()
+                        .filter(|&&(first, _)| start < first && first < lo),
+                );
+            }
+            if lo < size {
+                envelope.push((lo, Self::row_at(lower, lo)));
+                envelope.extend(lower.iter().filter(|&&(first, _)| lo < first));
+            }
+            index /= 2;
+        }
+    }
+
+    /// Minimum of column `col` over the rows `lo..hi`, which must all
+    /// be finished before `col`. Ties go to the smallest row.
+    fn minimum<T: PartialOrd>(
+        &self,
+        mut lo: usize,
+        hi: usize,
+        col: usize,
+        eval: &impl Fn(usize, usize) -> T,
+    ) -> Option<(usize, T)> {
+        let mut best: Option<(usize, T)> = None;
+        while lo < hi {
+            let mut level = 0;
+            while lo.is_multiple_of(2 << level) && lo + (2 << level) <= hi {
+                level += 1;
+            }
+            let row = Self::row_at(&self.levels[level][lo >> level], col);
+            let value = eval(row, col);
+            if best.as_ref().is_none_or(|(_, v)| value < *v) {
+                best = Some((row, value));
+            }
+            lo += 1 << level;
+        }
+        best
+    }
+}
+
+/// Compute upper-right column minima within a sliding window of rows.
+///
+/// This is like [`online_column_minima`], except that only the last
+/// `window` rows above the diagonal are candidates in each column:
+///
+/// ```text
+/// v(0) = initial
+/// v(j) = min { M[i, j] | j - window ≤ i < j } for j > 0
+/// ```
+///
+/// This is useful for segmentation problems where a segment can be at
+/// most `window` items long. Entries outside of the window are never
+/// evaluated, so there is no need to encode them as an artificial
+/// infinity in `matrix`.
+///
+/// The matrix must be totally monotone inside the window. Since the
+/// excluded entries form a staircase in the upper-right corner,
+/// treating them as infinite keeps the matrix totally monotone. A
+/// later row which beats an earlier row in some column therefore keeps
+/// doing so in all later columns. The algorithm keeps the candidate
+/// rows in a queue together with the first column where they are
+/// optimal and uses a binary search to find where a new row takes
+/// over. Ties are broken in favor of the smallest row index.
+///
+/// The same guarantee as for [`online_column_minima`] holds: we only
+/// call `matrix(i, j)` after having computed `v(i)`.
+///
+/// Running time: O(*n* log *w*) for `size` *n* and `window` *w*.
+///
+/// # Examples
+///
+/// ```
+/// let matrix = vec![vec![0, 1, 1, 1],
+///                   vec![0, 0, 2, 2],
+///                   vec![0, 0, 0, 3],
+///                   vec![0, 0, 0, 0]];
+/// let minima = smawk::online_column_minima_windowed(0, 4, 1, |_, i, j| matrix[i][j]);
+/// assert_eq!(minima, vec![(0, 0), (0, 1), (1, 2), (2, 3)]);
+/// let minima = smawk::online_column_minima_windowed(0, 4, 2, |_, i, j| matrix[i][j]);
+/// assert_eq!(minima, vec![(0, 0), (0, 1), (0, 1), (1, 2)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this with a `window` of zero.
+pub fn online_column_minima_windowed<T, M>(
+    initial: T,
+    size: usize,
+    window: usize,
+    matrix: M,
+) -> Vec<(usize, T)>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> T,
+{
+    assert!(window > 0, "window must be positive");
+    debug_assert!(
+        initial.partial_cmp(&initial).is_some(),
+        "initial value is not comparable to itself"
+    );
+    if size == 0 {
+        return Vec::new();
+    }
+    let mut result = vec![(0, initial)];
+
+    // Candidate rows with the first column where they are optimal.
+    // The front of the queue covers the smallest columns.
+    let mut candidates = std::collections::VecDeque::new();
+    candidates.push_back((0, 1));
+    for j in 1..size {
+        while candidates.len() > 1 && candidates[1].1 <= j {
+            candidates.pop_front();
+        }
+        let i = candidates[0].0;
+        debug_assert!(j - i <= window, "row {} outside window of column {}", i, j);
+        let value = matrix(&result, i, j);
+        result.push((i, value));
+
+        // Row j strictly beats row a < j in column col if row a has
+        // fallen out of the window or if it has a smaller value.
+        let beats = |result: &[(usize, T)], a: usize, col: usize| {
+            col - a > window || matrix(result, j, col) < matrix(result, a, col)
+        };
+
+        // Row j takes over a suffix of the columns it can reach.
+        let last = std::cmp::min(j + window, size - 1);
+        let mut start = last + 1;
+        while let Some(&(a, first)) = candidates.back() {
+            let first = std::cmp::max(first, j + 1);
+            if first <= last && beats(&result, a, first) {
+                candidates.pop_back();
+                start = first;
+                continue;
+            }
+            let (mut lo, mut hi) = (first, start);
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                if beats(&result, a, mid) {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+            start = lo;
+            break;
+        }
+        if start <= last {
+            candidates.push_back((j, start));
+        }
+    }
+    result
+}
+
+/// Element of an online matrix, optionally with a domination hint.
+///
+/// See [`online_column_minima_hinted`] for how the hints are used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eval<T> {
+    /// The element `M[i, j]`.
+    Value(T),
+    /// The element `M[i, j]` and the promise that row `i` does not
+    /// hold the minimum of any column after `j`.
+    ValueAndDominatedBeyond(T),
+}
+
+impl<T> Eval<T> {
+    /// Return the element, ignoring any hint.
+    pub fn value(self) -> T {
+        match self {
+            Eval::Value(value) | Eval::ValueAndDominatedBeyond(value) => value,
+        }
+    }
+}
+
+/// Online hooks which report the rows marked as dominated by an
+/// [`Eval`] hint. Element `i` holds the first column after which row
+/// `i` is dominated.
+struct Dominance<'a>(&'a std::cell::RefCell<Vec<usize>>);
+
+impl<T> OnlineHooks<T> for Dominance<'_> {
+    fn dominated_beyond(&self, row: usize, column: usize) -> bool {
+        self.0.borrow()[row] <= column
+    }
+}
+
+/// Compute upper-right column minima with domination hints from the
+/// matrix.
+///
+/// This is like [`online_column_minima`], except that `matrix`
+/// returns an [`Eval`]. A matrix can often tell cheaply that a row is
+/// finished, for example because the cost of a line in a paragraph
+/// exceeds every bound once the line is overfull. It then returns
+/// `Eval::ValueAndDominatedBeyond(v)` for element `(i, j)` to promise
+/// that row `i` does not hold the minimum of any column after `j`.
+/// Since ties are broken in favor of the smallest row, a row which
+/// ties with the minimum of an earlier row is dominated too.
+///
+/// The algorithm uses the hints in two places. The rows at the start
+/// of the candidate range which are dominated are dropped before a
+/// new square submatrix is evaluated, and a finished row which is
+/// dominated beyond the next column is not compared against the
+/// tentative minima. Both steps follow from the promise, so truthful
+/// hints give exactly the same result as [`online_column_minima`]
+/// with fewer evaluations. A wrong hint can give wrong minima, use
+/// [`online_column_minima_hinted_checked`] to find it.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::Eval;
+///
+/// let matrix = vec![vec![0, 2, 4],
+///                   vec![0, 0, 1],
+///                   vec![0, 0, 0]];
+/// let minima = smawk::online_column_minima_hinted(0, 3, |_, i, j| {
+///     match (i, j) {
+///         // Row 0 does not hold the minimum of column 2.
+///         (0, 1) => Eval::ValueAndDominatedBeyond(matrix[i][j]),
+///         _ => Eval::Value(matrix[i][j]),
+///     }
+/// });
+/// assert_eq!(minima, vec![(0, 0), (0, 2), (1, 1)]);
+/// ```
+pub fn online_column_minima_hinted<T, M>(initial: T, size: usize, matrix: M) -> Vec<(usize, T)>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> Eval<T>,
+{
+    online_hinted_inner(initial, size, &matrix).0
+}
+
+/// Compute upper-right column minima with domination hints and
+/// verify the hints.
+///
+/// This computes the same minima as [`online_column_minima_hinted`].
+/// Afterwards, every row which was marked as dominated beyond some
+/// column is compared against the minima of all later columns. This
+/// evaluates up to O(*n*²) extra elements, so it is meant as a debug
+/// mode while developing the hints.
+///
+/// # Panics
+///
+/// Panics if a row holds the minimum of a column after the column it
+/// was marked as dominated beyond.
+pub fn online_column_minima_hinted_checked<T, M>(
+    initial: T,
+    size: usize,
+    matrix: M,
+) -> Vec<(usize, T)>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> Eval<T>,
+{
+    let (result, dominated) = online_hinted_inner(initial, size, &matrix);
+    for (row, &column) in dominated.iter().enumerate() {
+        for j in column.saturating_add(1)..size {
+            let value = matrix(&result, row, j).value();
+            let (best_row, best) = result[j];
+            assert!(
+                best < value || (best == value && best_row < row),
+                "wrong hint: row {} is dominated beyond column {}, but holds the minimum of column {}",
+                row,
+                column,
+                j
+            );
+        }
+    }
+    result
+}
+
+/// The online algorithm with domination hints. Returns the minima
+/// and the first column after which each row is dominated.
+fn online_hinted_inner<T, M>(initial: T, size: usize, matrix: &M) -> (Vec<(usize, T)>, Vec<usize>)
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> Eval<T>,
+{
+    let dominated = std::cell::RefCell::new(vec![usize::MAX; size]);
+    let result = online_inner(
+        initial,
+        size,
+        |result: &[(usize, T)], queries: &[(usize, usize)], out: &mut [T]| {
+            for (value, &(i, j)) in out.iter_mut().zip(queries) {
+                *value = match matrix(result, i, j) {
+                    Eval::Value(value) => value,
+                    Eval::ValueAndDominatedBeyond(value) => {
+                        let mut dominated = dominated.borrow_mut();
+                        dominated[i] = dominated[i].min(j);
+                        value
+                    }
+                };
+            }
+        },
+        &Dominance(&dominated),
+        &Infallible,
+    )
+    .expect("allocation is infallible");
+    (result, dominated.into_inner())
+}
+
+/// Online hooks which consult a lower bound for the elements of every
+/// row, and record the elements which the bound saved.
+struct RowBound<'a, B> {
+    row_bound: &'a B,
+    skipped: std::cell::RefCell<Vec<(usize, usize)>>,
+}
+
+impl<T: PartialOrd, B: Fn(&[(usize, T)], usize, usize) -> T> OnlineHooks<T> for RowBound<'_, B> {
+    fn at_least(&self, result: &[(usize, T)], row: usize, column: usize, value: T) -> bool {
+        let bounded = value <= (self.row_bound)(result, row, column);
+        if bounded {
+            self.skipped.borrow_mut().push((row, column));
+        }
+        bounded
+    }
+}
+
+/// Compute upper-right column minima with a lower bound for the
+/// elements of every row.
+///
+/// This is like [`online_column_minima`], except that
+/// `row_bound(result, i, j)` gives a lower bound for `M[i, j]`, for
+/// example the best value which a relaxation allows row `i` to reach
+/// in any column from `j` on. It is given the same finished prefix of
+/// the result as `matrix`. The bound is consulted before an element of
+/// a finished row is compared against a tentative column minimum,
+/// and the element is not evaluated when its bound is not below the
+/// tentative minimum. The new square submatrices are still evaluated
+/// in full.
+///
+/// Truthful bounds give exactly the same result as
+/// [`online_column_minima`] with fewer evaluations of `matrix`. A
+/// bound which is larger than its element can give wrong minima, use
+/// [`online_column_minima_with_row_bound_checked`] to find it.
+///
+/// # Examples
+///
+/// ```
+/// let matrix = vec![vec![0, 2, 4],
+///                   vec![0, 0, 1],
+///                   vec![0, 0, 0]];
+/// let minima = smawk::online_column_minima_with_row_bound(
+///     0,
+///     3,
+///     |_, i, j| matrix[i][j],
+///     // Row 0 never gets below 2.
+///     |_, i, _| if i == 0 { 2 } else { 0 },
+/// );
+/// assert_eq!(minima, vec![(0, 0), (0, 2), (1, 1)]);
+/// ```
+pub fn online_column_minima_with_row_bound<T, M, B>(
+    initial: T,
+    size: usize,
+    matrix: M,
+    row_bound: B,
+) -> Vec<(usize, T)>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> T,
+    B: Fn(&[(usize, T)], usize, usize) -> T,
+{
+    online_row_bound_inner(initial, size, &matrix, &row_bound).0
+}
+
+/// Compute upper-right column minima with a lower bound for every row
+/// and verify the bounds.
+///
+/// This computes the same minima as
+/// [`online_column_minima_with_row_bound`]. Afterwards, every element
+/// which was skipped because of its bound is evaluated and compared
+/// against the bound, so it is meant as a debug mode while developing
+/// the bounds.
+///
+/// # Panics
+///
+/// Panics if a bound which caused an element to be skipped is larger
+/// than the element.
+pub fn online_column_minima_with_row_bound_checked<T, M, B>(
+    initial: T,
+    size: usize,
+    matrix: M,
+    row_bound: B,
+) -> Vec<(usize, T)>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> T,
+    B: Fn(&[(usize, T)], usize, usize) -> T,
+{
+    let (result, skipped) = online_row_bound_inner(initial, size, &matrix, &row_bound);
+    for (row, column) in skipped {
+        assert!(
+            row_bound(&result, row, column) <= matrix(&result, row, column),
+            "wrong bound: row_bound({}, {}) is larger than the element",
+            row,
+            column
+        );
+    }
     result
 }
 
+/// Online column minima and the elements which a row bound saved.
+type BoundedMinima<T> = (Vec<(usize, T)>, Vec<(usize, usize)>);
+
+/// The online algorithm with a row bound.
+fn online_row_bound_inner<T, M, B>(
+    initial: T,
+    size: usize,
+    matrix: &M,
+    row_bound: &B,
+) -> BoundedMinima<T>
+where
+    T: Copy + PartialOrd,
+    M: Fn(&[(usize, T)], usize, usize) -> T,
+    B: Fn(&[(usize, T)], usize, usize) -> T,
+{
+    let hooks = RowBound {
+        row_bound,
+        skipped: std::cell::RefCell::new(Vec::new()),
+    };
+    let result = online_inner(
+        initial,
+        size,
+        |result: &[(usize, T)], queries: &[(usize, usize)], out: &mut [T]| {
+            for (value, &(i, j)) in out.iter_mut().zip(queries) {
+                *value = matrix(result, i, j);
+            }
+        },
+        &hooks,
+        &Infallible,
+    )
+    .expect("allocation is infallible");
+    (result, hooks.skipped.into_inner())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{distance_matrix, sorted_points};
 
     #[test]
     fn smawk_1x1() {
@@ -456,52 +5235,710 @@ mod tests {
         assert_eq!(column_minima(&matrix), vec![1, 1]);
     }
 
-    #[test]
-    fn smawk_3x3() {
-        let matrix = vec![
-            vec![3, 4, 4], //
-            vec![3, 4, 4],
-            vec![2, 3, 3],
-        ];
-        assert_eq!(row_minima(&matrix), vec![0, 0, 0]);
-        assert_eq!(column_minima(&matrix), vec![2, 2, 2]);
+    #[test]
+    fn smawk_3x3() {
+        let matrix = vec![
+            vec![3, 4, 4], //
+            vec![3, 4, 4],
+            vec![2, 3, 3],
+        ];
+        assert_eq!(row_minima(&matrix), vec![0, 0, 0]);
+        assert_eq!(column_minima(&matrix), vec![2, 2, 2]);
+    }
+
+    #[test]
+    fn smawk_4x4() {
+        let matrix = vec![
+            vec![4, 5, 5, 5], //
+            vec![2, 3, 3, 3],
+            vec![2, 3, 3, 3],
+            vec![2, 2, 2, 2],
+        ];
+        assert_eq!(row_minima(&matrix), vec![0, 0, 0, 0]);
+        assert_eq!(column_minima(&matrix), vec![1, 3, 3, 3]);
+    }
+
+    #[test]
+    fn smawk_5x5() {
+        let matrix = vec![
+            vec![3, 2, 4, 5, 6],
+            vec![2, 1, 3, 3, 4],
+            vec![2, 1, 3, 3, 4],
+            vec![3, 2, 4, 3, 4],
+            vec![4, 3, 2, 1, 1],
+        ];
+        assert_eq!(row_minima(&matrix), vec![1, 1, 1, 1, 3]);
+        assert_eq!(column_minima(&matrix), vec![1, 1, 4, 4, 4]);
+    }
+
+    #[test]
+    fn threshold_5x5() {
+        let matrix = vec![
+            vec![3, 2, 4, 5, 6],
+            vec![2, 1, 3, 3, 4],
+            vec![2, 1, 3, 3, 4],
+            vec![3, 2, 4, 3, 4],
+            vec![4, 3, 2, 1, 1],
+        ];
+        for threshold in [0, 25, 26, usize::MAX] {
+            assert_eq!(
+                row_minima_with_threshold(&matrix, threshold),
+                vec![1, 1, 1, 1, 3]
+            );
+            assert_eq!(
+                column_minima_with_threshold(&matrix, threshold),
+                vec![1, 1, 4, 4, 4]
+            );
+        }
+    }
+
+    #[test]
+    fn column_minima_labeled_matches_dense() {
+        let row_labels = [3, 7, 8, 20, 21, 40];
+        let column_labels = [0, 5, 6, 22, 30, 50, 51];
+        let cost = |r: i64, c: i64| (r - c) * (r - c);
+        let matrix = row_labels
+            .iter()
+            .map(|&r| column_labels.iter().map(|&c| cost(r, c)).collect())
+            .collect::<Vec<Vec<_>>>();
+        let dense = column_minima(&matrix)
+            .into_iter()
+            .map(|i| row_labels[i])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            column_minima_labeled(&row_labels, &column_labels, cost),
+            dense
+        );
+        assert_eq!(dense, vec![3, 3, 7, 21, 21, 40, 40]);
+    }
+
+    #[test]
+    fn column_minima_refine_lazy() {
+        let coarse = [
+            vec![3, 2, 4, 5, 6],
+            vec![2, 1, 3, 3, 4],
+            vec![2, 1, 3, 3, 4],
+            vec![3, 2, 4, 3, 4],
+            vec![4, 3, 2, 1, 1],
+        ];
+        let fine = [
+            vec![0, 0, 0, 0, 0],
+            vec![9, 9, 9, 9, 9],
+            vec![1, 1, 1, 1, 1],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+        ];
+        let evaluations = std::cell::RefCell::new(vec![]);
+        let minima = column_minima_refine(
+            5,
+            5,
+            |i, j| coarse[i][j],
+            |i, j| {
+                evaluations.borrow_mut().push((i, j));
+                fine[i][j]
+            },
+        );
+        assert_eq!(minima, vec![2, 2, 4, 4, 4]);
+        // Only the tied rows 1 and 2 in columns 0 and 1 are refined.
+        assert_eq!(
+            evaluations.into_inner(),
+            vec![(1, 0), (2, 0), (1, 1), (2, 1)]
+        );
+    }
+
+    /// Version numbers compared by their components. They only
+    /// implement `PartialOrd`, and all of them are comparable.
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+    struct Version(u32, u32);
+
+    #[test]
+    fn minima_partial_comparable() {
+        let matrix = vec![
+            vec![Version(1, 4), Version(1, 2), Version(2, 0)],
+            vec![Version(1, 5), Version(1, 3), Version(1, 9)],
+            vec![Version(2, 0), Version(1, 3), Version(1, 1)],
+        ];
+        assert_eq!(row_minima_partial(&matrix), vec![1, 1, 2]);
+        assert_eq!(column_minima_partial(&matrix), vec![0, 0, 2]);
+    }
+
+    #[test]
+    fn minima_partial_large() {
+        // Large enough that SMAWK is used instead of a brute force scan.
+        let matrix = (0..30)
+            .map(|i| (0..40).map(|j| (i as f64 - j as f64).powi(2)).collect())
+            .collect::<Vec<Vec<f64>>>();
+        assert_eq!(row_minima_partial(&matrix), row_minima(&matrix));
+        assert_eq!(column_minima_partial(&matrix), column_minima(&matrix));
+    }
+
+    #[test]
+    #[should_panic(expected = "incomparable elements")]
+    fn row_minima_partial_nan() {
+        let matrix = vec![vec![1.0, f64::NAN], vec![2.0, 1.0]];
+        row_minima_partial(&matrix);
+    }
+
+    #[test]
+    #[should_panic(expected = "incomparable elements")]
+    fn column_minima_partial_nan() {
+        let matrix = vec![vec![1.0, 2.0], vec![f64::NAN, 1.0]];
+        column_minima_partial(&matrix);
+    }
+
+    #[test]
+    fn minima_f32_nan_is_infinity() {
+        let (inf, nan) = (f32::INFINITY, f32::NAN);
+        let matrix = vec![
+            vec![nan, inf, nan],
+            vec![inf, nan, 1.0],
+            vec![nan, nan, nan],
+        ];
+        assert_eq!(row_minima_f32(&matrix), vec![0, 2, 0]);
+        assert_eq!(column_minima_f32(&matrix), vec![0, 0, 1]);
+        let matrix = vec![vec![-nan, 1.0], vec![2.0, 3.0]];
+        assert_eq!(row_minima_f32(&matrix), vec![1, 0]);
+        assert_eq!(column_minima_f32(&matrix), vec![1, 0]);
+    }
+
+    #[test]
+    fn minima_f32_negative_zero() {
+        let matrix = vec![vec![0.0, -0.0, 0.0], vec![-0.0, -0.0, 0.0]];
+        assert_eq!(row_minima_f32(&matrix), vec![1, 0]);
+        assert_eq!(column_minima_f32(&matrix), vec![1, 0, 0]);
+    }
+
+    #[test]
+    fn column_minima_closest_to_5x5() {
+        let matrix = vec![
+            vec![1, 2, 3, 4, 5], //
+            vec![1, 2, 3, 4, 5],
+            vec![1, 2, 3, 4, 5],
+            vec![2, 2, 3, 4, 5],
+            vec![3, 3, 3, 4, 5],
+        ];
+        assert_eq!(column_minima(&matrix), vec![0, 0, 0, 0, 0]);
+        assert_eq!(
+            column_minima_closest_to(&matrix, &[4, 3, 3, 9, 0]),
+            vec![2, 3, 3, 4, 0]
+        );
+    }
+
+    #[test]
+    fn column_minima_as_computed_5x5() {
+        let matrix = vec![
+            vec![3, 2, 4, 5, 6],
+            vec![2, 1, 3, 3, 4],
+            vec![2, 1, 3, 3, 4],
+            vec![3, 2, 4, 3, 4],
+            vec![4, 3, 2, 1, 1],
+        ];
+        let mut reported = vec![None; 5];
+        column_minima_as_computed(&matrix, |col, row| {
+            assert_eq!(reported[col], None, "column {} reported twice", col);
+            reported[col] = Some(row);
+        });
+        let minima = reported.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+        assert_eq!(minima, column_minima(&matrix));
+    }
+
+    #[test]
+    fn column_minima_runs_5x5() {
+        let matrix = vec![
+            vec![3, 2, 4, 5, 6],
+            vec![2, 1, 3, 3, 4],
+            vec![2, 1, 3, 3, 4],
+            vec![3, 2, 4, 3, 4],
+            vec![4, 3, 2, 1, 1],
+        ];
+        assert_eq!(column_minima_runs(&matrix), vec![(1, 0..2), (4, 2..5)]);
+    }
+
+    #[test]
+    fn column_minima_by_agrees_with_scan() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (m, n) = (rng.gen_range(1..40), rng.gen_range(1..40));
+            let matrix = distance_matrix(m, n, 50, &mut rng);
+            let scan = |eval: &dyn Fn(usize, usize) -> i64| {
+                (0..n)
+                    .map(|j| {
+                        let i = (0..m).min_by_key(|&i| (eval(i, j), i)).unwrap();
+                        (i, j)
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            let minima = column_minima_by(m, n, |i, j| matrix[i][j], |a, b| a.cmp(b));
+            let rows = minima.iter().map(|&(i, _)| i).collect::<Vec<_>>();
+            assert_eq!(rows, column_minima(&matrix));
+            for ((i, value), (k, j)) in minima.into_iter().zip(scan(&|i, j| matrix[i][j])) {
+                assert_eq!((i, value), (k, matrix[k][j]));
+            }
+
+            // Maxima of the negated matrix with a reversed comparator.
+            let maxima = column_minima_by(m, n, |i, j| -matrix[i][j], |a, b| b.cmp(a));
+            for ((i, value), (k, j)) in maxima.into_iter().zip(scan(&|i, j| matrix[i][j])) {
+                assert_eq!((i, value), (k, -matrix[k][j]));
+            }
+
+            // Square roots keep the order, but are not Monge.
+            let roots = column_minima_by(m, n, |i, j| (matrix[i][j] as f64).sqrt(), f64::total_cmp);
+            for ((i, value), (k, j)) in roots.into_iter().zip(scan(&|i, j| matrix[i][j])) {
+                assert_eq!((i, value), (k, (matrix[k][j] as f64).sqrt()));
+            }
+        }
+    }
+
+    #[test]
+    fn row_threshold_crossings_agree_with_scan() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (m, n) = (rng.gen_range(0..40), rng.gen_range(1..40));
+            let matrix = distance_matrix(m, n, 50, &mut rng);
+
+            // Thresholds below the global minimum and above the maximum.
+            for threshold in [-1, 0, 1, 10, 100, 1000, 2500, 10_000] {
+                let expected = matrix
+                    .iter()
+                    .map(|row| row.iter().position(|&value| value <= threshold))
+                    .collect::<Vec<_>>();
+                assert_eq!(row_threshold_crossings(&matrix, threshold), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn column_minima_counting_agrees_with_scan() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..200 {
+            // Few distinct positions give many ties.
+            let (m, n) = (rng.gen_range(1..30), rng.gen_range(0..30));
+            let (x, y) = sorted_points(m, n, 6, &mut rng);
+            let matrix = x
+                .iter()
+                .map(|&a| y.iter().map(|&b| (a - b).abs()).collect())
+                .collect::<Vec<Vec<i64>>>();
+
+            for modulus in [None, Some(3)] {
+                let expected = (0..n)
+                    .map(|j| {
+                        let value = (0..m).map(|i| matrix[i][j]).min().unwrap();
+                        let first = (0..m).position(|i| matrix[i][j] == value).unwrap();
+                        let count = (0..m).filter(|&i| matrix[i][j] == value).count() as u64;
+                        (first, value, modulus.map_or(count, |p| count % p))
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(column_minima_counting(&matrix, modulus), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn online_column_minima_counting_agrees_with_dp() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..200 {
+            let size = rng.gen_range(0..40);
+            let mut x = (0..size)
+                .map(|_| rng.gen_range(0..10))
+                .collect::<Vec<i64>>();
+            x.sort_unstable();
+            let cost = |minima: &[(usize, i64)], i: usize, j: usize| {
+                let d = x[j] - x[i] - 2;
+                minima[i].1 + d * d
+            };
+
+            for modulus in [None, Some(7)] {
+                let mut expected: Vec<(usize, i64, u64)> = vec![];
+                for j in 0..size {
+                    if j == 0 {
+                        expected.push((0, 0, 1));
+                        continue;
+                    }
+                    let minima = expected.iter().map(|&(r, v, _)| (r, v)).collect::<Vec<_>>();
+                    let value = (0..j).map(|i| cost(&minima, i, j)).min().unwrap();
+                    let first = (0..j).position(|i| cost(&minima, i, j) == value).unwrap();
+                    let count = (0..j)
+                        .filter(|&i| cost(&minima, i, j) == value)
+                        .map(|i| expected[i].2)
+                        .fold(0, |a, b| add_count(a, b, modulus));
+                    expected.push((first, value, count));
+                }
+                assert_eq!(
+                    online_column_minima_counting(0, size, cost, modulus),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn online_column_minima_deamortized_bounded_work() {
+        use rand::{Rng, SeedableRng};
+        use std::cell::RefCell;
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for &size in &[0, 1, 2, 3, 10, 100, 1000] {
+            for _ in 0..10 {
+                // Line breaking with few distinct widths, which gives
+                // many ties.
+                let widths = (0..size).map(|_| rng.gen_range(1..4)).collect::<Vec<i64>>();
+                let mut offsets = vec![0];
+                offsets.extend(widths.iter().scan(0, |sum, w| {
+                    *sum += w + 1;
+                    Some(*sum)
+                }));
+                let cost = |minima: &[(usize, i64)], i: usize, j: usize| {
+                    let gap = 20 - (offsets[j] - offsets[i] - 1);
+                    minima[i].1 + if gap < 0 { 1000 * -gap } else { gap * gap }
+                };
+
+                // Attribute the evaluations to the columns by the
+                // number of finished columns they see.
+                let evaluations = RefCell::new(vec![0; size + 1]);
+                let minima = online_column_minima_deamortized(0, size, |minima, i, j| {
+                    evaluations.borrow_mut()[minima.len()] += 1;
+                    cost(minima, i, j)
+                });
+                assert_eq!(minima, online_column_minima(0, size, cost));
+
+                let bound = 4 * (usize::BITS - size.leading_zeros()) as usize + 1;
+                let most = evaluations.into_inner().into_iter().max().unwrap();
+                assert!(most <= bound, "{} evaluations for size {}", most, size);
+            }
+        }
+    }
+
+    #[test]
+    fn column_minima_rle_round_trip() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (m, n) = (rng.gen_range(1..50), rng.gen_range(0..50));
+            let matrix = distance_matrix(m, n, 100, &mut rng);
+
+            let encoded = column_minima_rle(&matrix);
+            assert!(encoded.len() <= m.min(n));
+            assert_eq!(encoded.iter().map(|&(_, len)| len).sum::<usize>(), n);
+            assert!(encoded.iter().all(|&(_, len)| len > 0));
+            assert_eq!(
+                decode_column_minima_rle(&encoded, n),
+                column_minima(&matrix)
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "run lengths must add up to the number of columns")]
+    fn decode_column_minima_rle_truncated() {
+        decode_column_minima_rle(&[(0, 2), (2, 1)], 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "run lengths must add up to the number of columns")]
+    fn decode_column_minima_rle_huge_runs() {
+        decode_column_minima_rle(&[(0, usize::MAX), (1, usize::MAX), (2, 2)], 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "run lengths must add up to the number of columns")]
+    fn decode_column_minima_rle_too_long() {
+        decode_column_minima_rle(&[(0, usize::MAX / 2)], 4);
+    }
+
+    #[test]
+    fn merge_column_minima_singletons() {
+        use rand::seq::SliceRandom;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..200 {
+            let (m, n) = (rng.gen_range(1..40), rng.gen_range(0..40));
+            let matrix = distance_matrix(m, n, 20, &mut rng);
+
+            let mut groups = (0..m)
+                .map(|i| if n > 0 { vec![(i, 0..n)] } else { vec![] })
+                .collect::<Vec<_>>();
+            while groups.len() > 1 {
+                groups.shuffle(&mut rng);
+                let a = groups.pop().unwrap();
+                let b = groups.pop().unwrap();
+                let merged = merge_column_minima(&matrix, &a, &b);
+                for window in merged.windows(2) {
+                    assert!(window[0].0 < window[1].0, "rows not increasing");
+                }
+                groups.push(merged);
+            }
+            let expanded = groups[0]
+                .iter()
+                .flat_map(|(row, columns)| columns.clone().map(move |_| *row))
+                .collect::<Vec<_>>();
+            let expected = if n > 0 {
+                column_minima(&matrix)
+            } else {
+                vec![]
+            };
+            assert_eq!(expanded, expected, "merged minima differ on {:?}", matrix);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "runs must cover the same columns")]
+    fn merge_column_minima_different_columns() {
+        let matrix = vec![vec![1, 2, 3], vec![3, 2, 1]];
+        merge_column_minima(&matrix, &[(0, 0..3)], &[(1, 0..2)]);
+    }
+
+    #[test]
+    fn leave_one_out_5x5() {
+        let matrix = vec![
+            vec![3, 2, 4, 5, 6],
+            vec![2, 1, 3, 3, 4],
+            vec![2, 1, 3, 3, 4],
+            vec![3, 2, 4, 3, 4],
+            vec![4, 3, 2, 1, 1],
+        ];
+        assert_eq!(
+            leave_one_out_column_minima(&matrix),
+            vec![
+                vec![1, 1, 4, 4, 4],
+                vec![2, 2, 4, 4, 4],
+                vec![1, 1, 4, 4, 4],
+                vec![1, 1, 4, 4, 4],
+                vec![1, 1, 1, 1, 1],
+            ]
+        );
+    }
+
+    #[test]
+    fn column_minima_enabled_5x5() {
+        let matrix = vec![
+            vec![3, 2, 4, 5, 6],
+            vec![2, 1, 3, 3, 4],
+            vec![2, 1, 3, 3, 4],
+            vec![3, 2, 4, 3, 4],
+            vec![4, 3, 2, 1, 1],
+        ];
+        let all = [true; 5];
+        assert_eq!(column_minima_enabled(&matrix, &all), vec![1, 1, 4, 4, 4]);
+        let enabled = [true, false, false, true, false];
+        assert_eq!(
+            column_minima_enabled(&matrix, &enabled),
+            vec![0, 0, 0, 3, 3]
+        );
+        let enabled = [false, false, false, false, true];
+        assert_eq!(column_minima_enabled(&matrix, &enabled), vec![4; 5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "no rows are enabled")]
+    fn column_minima_enabled_none() {
+        let matrix = vec![vec![1, 2], vec![3, 4]];
+        column_minima_enabled(&matrix, &[false, false]);
+    }
+
+    #[test]
+    fn column_minima_counted_matches_external_count() {
+        use std::cell::Cell;
+
+        /// Matrix `(i - j)²` which counts the elements read.
+        struct Squares {
+            size: usize,
+            reads: Cell<usize>,
+        }
+
+        impl Matrix<i64> for Squares {
+            fn nrows(&self) -> usize {
+                self.size
+            }
+            fn ncols(&self) -> usize {
+                self.size
+            }
+            fn index(&self, row: usize, column: usize) -> i64 {
+                self.reads.set(self.reads.get() + 1);
+                (row as i64 - column as i64).pow(2)
+            }
+        }
+
+        let mut previous = None;
+        for size in [1, 10, 20, 100, 200, 400, 800, 1600] {
+            let matrix = Squares {
+                size,
+                reads: Cell::new(0),
+            };
+            let (minima, count) = column_minima_counted(&matrix);
+            assert_eq!(minima, (0..size).collect::<Vec<_>>());
+            assert_eq!(count, matrix.reads.take());
+            assert_eq!(column_minima(&matrix), minima);
+            assert_eq!(count, matrix.reads.take());
+
+            if size >= 100 {
+                let bound = 6 * (size + size);
+                assert!(count <= bound, "{} reads for size {}", count, size);
+            }
+            // Doubling the size at most doubles the count, plus a bit.
+            if let Some((previous_size, previous_count)) = previous {
+                if size == 2 * previous_size && previous_size >= 100 {
+                    assert!(
+                        5 * count <= 11 * previous_count,
+                        "{} vs {}",
+                        count,
+                        previous_count
+                    );
+                }
+            }
+            previous = Some((size, count));
+        }
+    }
+
+    #[test]
+    fn reduce_evaluates_each_element_once() {
+        use rand::{Rng, SeedableRng};
+        use std::cell::RefCell;
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let (m, n) = (rng.gen_range(1..200), rng.gen_range(1..200));
+            let matrix = distance_matrix(m, n, 1000, &mut rng);
+
+            let evaluations = RefCell::new(vec![]);
+            let counting = |i: usize, j: usize| {
+                evaluations.borrow_mut().push((i, j));
+                matrix[i][j]
+            };
+            let rows = (0..m).collect::<Vec<_>>();
+            let cols = (0..n).collect::<Vec<_>>();
+            let mut stack = vec![0; m.min(n)];
+            let len = smawk_reduce(&counting, &rows, &cols, &mut stack, &mut vec![]);
+
+            // The reduced rows still hold every column minimum.
+            let minima = column_minima(&matrix);
+            assert!(minima.iter().all(|i| stack[..len].contains(i)));
+            let mut evaluations = evaluations.into_inner();
+            let count = evaluations.len();
+            assert!(count <= 2 * (m + n), "{} x {}: {}", m, n, count);
+            evaluations.sort_unstable();
+            evaluations.dedup();
+            assert_eq!(evaluations.len(), count);
+        }
+    }
+
+    #[test]
+    fn cached_cmp_saves_comparisons() {
+        use rand::{Rng, SeedableRng};
+        use std::cell::Cell;
+        use std::cmp::Ordering;
+
+        /// Element which compares with a counting comparator and
+        /// without a cache.
+        #[derive(Clone, Copy)]
+        struct Uncached<'a>(i64, &'a Cell<usize>);
+
+        impl PartialEq for Uncached<'_> {
+            fn eq(&self, other: &Self) -> bool {
+                self.partial_cmp(other) == Some(Ordering::Equal)
+            }
+        }
+
+        impl PartialOrd for Uncached<'_> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                self.1.set(self.1.get() + 1);
+                Some(self.0.cmp(&other.0))
+            }
+        }
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        let (mut cached_total, mut uncached_total) = (0, 0);
+        for _ in 0..100 {
+            let (m, n) = (rng.gen_range(1..60), rng.gen_range(1..60));
+            let matrix = distance_matrix(m, n, 20, &mut rng);
+
+            let uncached = Cell::new(0);
+            let wrapped = matrix
+                .iter()
+                .map(|row| row.iter().map(|&v| Uncached(v, &uncached)).collect())
+                .collect::<Vec<Vec<_>>>();
+            let cached = Cell::new(0);
+            let cmp = |a: &i64, b: &i64| {
+                cached.set(cached.get() + 1);
+                a.cmp(b)
+            };
+
+            assert_eq!(
+                column_minima_cached_cmp(&matrix, cmp),
+                column_minima(&matrix)
+            );
+            assert_eq!(column_minima(&wrapped), column_minima(&matrix));
+            assert!(cached.get() <= uncached.get());
+            cached_total += cached.take();
+            uncached_total += uncached.take();
+
+            assert_eq!(row_minima_cached_cmp(&matrix, cmp), row_minima(&matrix));
+            assert_eq!(row_minima(&wrapped), row_minima(&matrix));
+            assert!(cached.get() <= uncached.get());
+            cached_total += cached.take();
+            uncached_total += uncached.take();
+        }
+        assert!(
+            cached_total < uncached_total,
+            "{} cached comparisons compared to {} uncached",
+            cached_total,
+            uncached_total
+        );
     }
 
     #[test]
-    fn smawk_4x4() {
-        let matrix = vec![
-            vec![4, 5, 5, 5], //
-            vec![2, 3, 3, 3],
-            vec![2, 3, 3, 3],
-            vec![2, 2, 2, 2],
-        ];
-        assert_eq!(row_minima(&matrix), vec![0, 0, 0, 0]);
-        assert_eq!(column_minima(&matrix), vec![1, 3, 3, 3]);
+    #[cfg(feature = "fixedbitset")]
+    fn column_minima_chosen_rows_matches_minima() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (m, n) = (rng.gen_range(1..20), rng.gen_range(0..40));
+            let matrix = distance_matrix(m, n, 20, &mut rng);
+            let (minima, chosen) = column_minima_chosen_rows(&matrix);
+            assert_eq!(minima, column_minima(&matrix));
+            assert_eq!(chosen.len(), m);
+            let mut rows = minima.clone();
+            rows.sort_unstable();
+            rows.dedup();
+            assert_eq!(chosen.ones().collect::<Vec<_>>(), rows);
+        }
     }
 
     #[test]
-    fn smawk_5x5() {
-        let matrix = vec![
-            vec![3, 2, 4, 5, 6],
-            vec![2, 1, 3, 3, 4],
-            vec![2, 1, 3, 3, 4],
-            vec![3, 2, 4, 3, 4],
-            vec![4, 3, 2, 1, 1],
-        ];
-        assert_eq!(row_minima(&matrix), vec![1, 1, 1, 1, 3]);
-        assert_eq!(column_minima(&matrix), vec![1, 1, 4, 4, 4]);
+    fn online_0x0() {
+        let minima: Vec<(usize, i32)> = vec![];
+        assert_eq!(online_column_minima(0, 0, |_, _, _| unreachable!()), minima);
+        assert_eq!(
+            online_column_minima_2best(0, 0, |_, _, _| unreachable!()),
+            vec![]
+        );
     }
 
     #[test]
     fn online_1x1() {
-        let matrix = vec![vec![0]];
+        let matrix = [vec![0]];
         let minima = vec![(0, 0)];
         assert_eq!(online_column_minima(0, 1, |_, i, j| matrix[i][j]), minima);
+        assert_eq!(
+            online_column_minima_2best(0, 1, |_, i, j| matrix[i][j]),
+            vec![((0, 0), None)]
+        );
     }
 
     #[test]
     fn online_2x2() {
-        let matrix = vec![
+        let matrix = [
             vec![0, 2], //
             vec![0, 0],
         ];
@@ -511,7 +5948,7 @@ mod tests {
 
     #[test]
     fn online_3x3() {
-        let matrix = vec![
+        let matrix = [
             vec![0, 4, 4], //
             vec![0, 0, 4],
             vec![0, 0, 0],
@@ -522,7 +5959,7 @@ mod tests {
 
     #[test]
     fn online_4x4() {
-        let matrix = vec![
+        let matrix = [
             vec![0, 5, 5, 5], //
             vec![0, 0, 3, 3],
             vec![0, 0, 0, 3],
@@ -534,7 +5971,7 @@ mod tests {
 
     #[test]
     fn online_5x5() {
-        let matrix = vec![
+        let matrix = [
             vec![0, 2, 4, 6, 7],
             vec![0, 0, 3, 4, 5],
             vec![0, 0, 0, 3, 4],
@@ -545,6 +5982,68 @@ mod tests {
         assert_eq!(online_column_minima(0, 5, |_, i, j| matrix[i][j]), minima);
     }
 
+    #[test]
+    fn online_2best_4x4() {
+        let matrix = [
+            vec![0, 5, 5, 5], //
+            vec![0, 0, 3, 3],
+            vec![0, 0, 0, 3],
+            vec![0, 0, 0, 0],
+        ];
+        let minima = vec![
+            ((0, 0), None),
+            ((0, 5), None),
+            ((1, 3), Some((0, 5))),
+            ((1, 3), Some((2, 3))),
+        ];
+        assert_eq!(
+            online_column_minima_2best(0, 4, |_, i, j| matrix[i][j]),
+            minima
+        );
+    }
+
+    #[test]
+    fn online_windowed_5x5() {
+        let matrix = [
+            vec![0, 2, 4, 6, 8], //
+            vec![0, 0, 3, 1, 9],
+            vec![0, 0, 0, 5, 2],
+            vec![0, 0, 0, 0, 7],
+            vec![0, 0, 0, 0, 0],
+        ];
+        let window = |w| online_column_minima_windowed(0, 5, w, |_, i, j| matrix[i][j]);
+        assert_eq!(window(1), vec![(0, 0), (0, 2), (1, 3), (2, 5), (3, 7)]);
+        assert_eq!(window(2), vec![(0, 0), (0, 2), (1, 3), (1, 1), (2, 2)]);
+        assert_eq!(
+            window(5),
+            online_column_minima(0, 5, |_, i, j| matrix[i][j])
+        );
+    }
+
+    #[test]
+    fn online_windowed_0x0() {
+        assert_eq!(online_column_minima_windowed(0, 0, 3, |_, _, _| 0), vec![]);
+    }
+
+    #[test]
+    fn online_2best_sees_runner_ups() {
+        // The value of column j is the sum of the two best values of
+        // the previous column plus one.
+        let minima = online_column_minima_2best(1, 4, |done, i, _| {
+            let ((_, best), second) = done[i];
+            best + second.map_or(0, |(_, v)| v) + 1
+        });
+        assert_eq!(
+            minima,
+            vec![
+                ((0, 1), None),
+                ((0, 2), None),
+                ((0, 2), Some((1, 3))),
+                ((0, 2), Some((1, 3))),
+            ]
+        );
+    }
+
     #[test]
     fn smawk_works_with_partial_ord() {
         let matrix = vec![
@@ -557,7 +6056,7 @@ mod tests {
 
     #[test]
     fn online_works_with_partial_ord() {
-        let matrix = vec![
+        let matrix = [
             vec![0.0, 2.0], //
             vec![0.0, 0.0],
         ];
@@ -567,4 +6066,674 @@ mod tests {
             minima
         );
     }
+
+    /// Line breaking cost for words of random widths where overfull
+    /// lines cost a lot. Row `i` is marked as dominated beyond `j` if
+    /// the line from word `i` to word `j + 1` is overfull: so is every
+    /// longer line, and the line with only the last word is always
+    /// cheaper.
+    fn hinted_line_breaking(
+        offsets: &[i64],
+    ) -> impl Fn(&[(usize, i64)], usize, usize) -> Eval<i64> + '_ {
+        move |minima, i, j| {
+            let width = |j: usize| offsets[j] - offsets[i] - 1;
+            let gap = 40 - width(j);
+            let value = match gap < 0 {
+                true => minima[i].1 + 1_000_000 * -gap,
+                false => minima[i].1 + gap * gap,
+            };
+            if j + 1 < offsets.len() && width(j + 1) > 40 {
+                Eval::ValueAndDominatedBeyond(value)
+            } else {
+                Eval::Value(value)
+            }
+        }
+    }
+
+    #[test]
+    fn online_hinted_saves_evaluations() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for &size in &[1, 2, 10, 100, 1000] {
+            let mut offsets = vec![0];
+            for _ in 0..size {
+                let width: i64 = rng.gen_range(1..10);
+                offsets.push(offsets[offsets.len() - 1] + width + 1);
+            }
+            let matrix = hinted_line_breaking(&offsets);
+
+            let plain = std::cell::Cell::new(0);
+            let expected = online_column_minima(0, size, |minima, i, j| {
+                plain.set(plain.get() + 1);
+                matrix(minima, i, j).value()
+            });
+            let hinted = std::cell::Cell::new(0);
+            let minima = online_column_minima_hinted(0, size, |minima, i, j| {
+                hinted.set(hinted.get() + 1);
+                matrix(minima, i, j)
+            });
+            assert_eq!(minima, expected);
+            assert_eq!(
+                online_column_minima_hinted_checked(0, size, &matrix),
+                expected
+            );
+            if size == 1000 {
+                assert!(
+                    10 * hinted.get() < 9 * plain.get(),
+                    "{} hinted evaluations compared to {} plain",
+                    hinted.get(),
+                    plain.get()
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong hint: row 0 is dominated beyond column 1")]
+    fn online_hinted_checked_wrong_hint() {
+        let matrix = [
+            vec![0, 1, 1, 1], //
+            vec![0, 0, 2, 2],
+            vec![0, 0, 0, 3],
+            vec![0, 0, 0, 0],
+        ];
+        online_column_minima_hinted_checked(0, 4, |_, i, j| {
+            Eval::ValueAndDominatedBeyond(matrix[i][j])
+        });
+    }
+
+    #[test]
+    fn column_minima_with_row_bound_saves_evaluations() {
+        use rand::{Rng, SeedableRng};
+        use std::cell::Cell;
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        let (mut bounded_total, mut plain_total) = (0, 0);
+        for _ in 0..300 {
+            let (m, n) = (rng.gen_range(1..200), rng.gen_range(1..200));
+            let (x, y) = sorted_points(m, n, 1000, &mut rng);
+            // Every row pays a setup cost on top of the distance, and
+            // some rows are much more expensive than others.
+            let setup = (0..m)
+                .map(|_| rng.gen_range(0..1000) * rng.gen_range(1..1000))
+                .collect::<Vec<i64>>();
+            let matrix = x
+                .iter()
+                .zip(&setup)
+                .map(|(&a, &c)| y.iter().map(|&b| c + (a - b) * (a - b)).collect())
+                .collect::<Vec<Vec<i64>>>();
+            // Relaxing the distance to zero leaves only the setup
+            // cost, which is known without evaluating the element.
+            let bound = |i: usize, _: usize| setup[i];
+
+            let (expected, plain) = column_minima_counted(&matrix);
+            let counting = Counted {
+                matrix: &matrix,
+                count: Cell::new(0),
+            };
+            let minima = column_minima_with_row_bound(&counting, bound);
+            assert_eq!(minima, expected, "{:?}", matrix);
+            assert_eq!(
+                column_minima_with_row_bound_checked(&matrix, bound),
+                expected
+            );
+            bounded_total += counting.count.get();
+            plain_total += plain;
+        }
+        assert!(
+            10 * bounded_total < 9 * plain_total,
+            "{} bounded evaluations compared to {} plain",
+            bounded_total,
+            plain_total
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot compute minima with zero rows")]
+    fn column_minima_with_row_bound_zero_rows() {
+        column_minima_with_row_bound(&ColumnCount(&vec![], 3), |_, _| 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong bound: row_bound(1, 0) is larger than the element")]
+    fn column_minima_with_row_bound_checked_wrong_bound() {
+        let matrix = vec![vec![4, 2, 4, 3], vec![3, 3, 5, 3], vec![5, 3, 3, 1]];
+        column_minima_with_row_bound_checked(&matrix, |i, _| if i == 1 { 4 } else { 0 });
+    }
+
+    #[test]
+    fn online_row_bound_saves_evaluations() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for &size in &[1, 2, 10, 100, 1000] {
+            let mut offsets = vec![0];
+            for _ in 0..size {
+                let width: i64 = rng.gen_range(1..10);
+                offsets.push(offsets[offsets.len() - 1] + width + 1);
+            }
+            let penalty = |i: usize, j: usize| {
+                let gap = 40 - (offsets[j] - offsets[i] - 1);
+                gap * gap
+            };
+            let matrix = |minima: &[(usize, i64)], i: usize, j: usize| minima[i].1 + penalty(i, j);
+
+            let plain = std::cell::Cell::new(0);
+            let expected = online_column_minima(0, size, |minima, i, j| {
+                plain.set(plain.get() + 1);
+                matrix(minima, i, j)
+            });
+            let bounded = std::cell::Cell::new(0);
+            let minima = online_column_minima_with_row_bound(
+                0,
+                size,
+                |minima, i, j| {
+                    bounded.set(bounded.get() + 1);
+                    matrix(minima, i, j)
+                },
+                // The penalty is never negative, so the minimum of row
+                // i is a bound which needs no penalty.
+                |minima, i, _| minima[i].1,
+            );
+            assert_eq!(minima, expected);
+            assert_eq!(
+                online_column_minima_with_row_bound_checked(0, size, matrix, |minima, i, _| {
+                    minima[i].1
+                }),
+                expected
+            );
+            if size == 1000 {
+                assert!(
+                    20 * bounded.get() < 19 * plain.get(),
+                    "{} bounded evaluations compared to {} plain",
+                    bounded.get(),
+                    plain.get()
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong bound: row_bound(2, 3) is larger than the element")]
+    fn online_row_bound_checked_wrong_bound() {
+        let matrix = [
+            vec![0, 1, 1, 1], //
+            vec![0, 0, 2, 2],
+            vec![0, 0, 0, 3],
+            vec![0, 0, 0, 0],
+        ];
+        online_column_minima_with_row_bound_checked(0, 4, |_, i, j| matrix[i][j], |_, _, _| 5);
+    }
+
+    #[test]
+    fn online_never_evaluates_column_0() {
+        // Column 0 is (0, initial) no matter what the matrix holds.
+        let matrix = |_: &[(usize, i32)], _: usize, j: usize| {
+            assert_ne!(j, 0, "column 0 evaluated");
+            -100
+        };
+        assert_eq!(online_column_minima(7, 1, matrix), vec![(0, 7)]);
+        assert_eq!(online_column_minima(7, 3, matrix)[0], (0, 7));
+        assert_eq!(online_column_minima_windowed(7, 3, 1, matrix)[0], (0, 7));
+        assert_eq!(
+            online_column_minima_2best(7, 3, |_, _, j| matrix(&[], 0, j))[0],
+            ((0, 7), None)
+        );
+    }
+
+    #[test]
+    fn online_passes_initial_to_matrix() {
+        let minima = online_column_minima(10, 4, |result: &[(usize, i32)], i, j| {
+            assert!(result.len() > i, "row {} used before it was finished", i);
+            result[i].1 + (j - i) as i32
+        });
+        assert_eq!(minima, vec![(0, 10), (0, 11), (0, 12), (0, 13)]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "initial value is not comparable to itself")]
+    fn online_nan_initial() {
+        online_column_minima(f64::NAN, 3, |result: &[(usize, f64)], i, j| {
+            result[i].1 + (j - i) as f64
+        });
+    }
+
+    /// Check column minima of points `x` against points `y` on a
+    /// circle of length `l`, with windows starting half a turn behind
+    /// `x`. Returns the matrix.
+    fn cyclic_points(x: &[i64], y: &[i64], l: i64, f: fn(i64) -> i64) -> Vec<Vec<i64>> {
+        let matrix = x
+            .iter()
+            .map(|&a| {
+                y.iter()
+                    .map(|&b| f((a - b).abs().min(l - (a - b).abs())))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        // Unrolled column k lies at y[k % n] + (k / n) * l, and row i
+        // is read around x[i] + l.
+        let n = y.len();
+        let starts = x
+            .iter()
+            .map(|&a| {
+                (0..3 * n)
+                    .filter(|&k| y[k % n] + (k / n) as i64 * l < a + l - l / 2)
+                    .count()
+            })
+            .collect::<Vec<_>>();
+        let expected = (0..n)
+            .map(|j| (0..x.len()).min_by_key(|&i| (matrix[i][j], i)).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cyclic_column_minima(&matrix, &starts),
+            expected,
+            "{:?}, starts {:?}",
+            matrix,
+            starts
+        );
+        matrix
+    }
+
+    #[test]
+    fn cyclic_column_minima_circulant() {
+        for n in 1..20 {
+            let points = (0..n as i64).collect::<Vec<_>>();
+            let matrix = cyclic_points(&points, &points, n as i64, |d| d * d);
+            // Every row is a rotation of the first row.
+            for (i, row) in matrix.iter().enumerate() {
+                let mut rotated = matrix[0].clone();
+                rotated.rotate_right(i);
+                assert_eq!(row, &rotated);
+            }
+        }
+    }
+
+    #[test]
+    fn cyclic_column_minima_random_points() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..500 {
+            let l = rng.gen_range(1..40);
+            let (m, n) = (rng.gen_range(1..30), rng.gen_range(1..30));
+            let mut x = (0..m).map(|_| rng.gen_range(0..l)).collect::<Vec<_>>();
+            let mut y = (0..n).map(|_| rng.gen_range(0..l)).collect::<Vec<_>>();
+            x.sort_unstable();
+            y.sort_unstable();
+            cyclic_points(&x, &y, l, |d| d * d);
+            cyclic_points(&x, &y, l, |d| d);
+        }
+    }
+
+    /// Buffer source which fails once `remaining` buffers have been
+    /// handed out.
+    struct FailAfter(std::cell::Cell<usize>);
+
+    impl BufferSource for FailAfter {
+        fn with_capacity<U>(&self, len: usize) -> Result<Vec<U>, SmawkError> {
+            match self.0.get() {
+                0 => Err(SmawkError::Alloc),
+                remaining => {
+                    self.0.set(remaining - 1);
+                    Ok(Vec::with_capacity(len))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn try_minima_injected_failures() {
+        let matrix = vec![
+            vec![4, 2, 4, 3, 7],
+            vec![5, 3, 5, 3, 5],
+            vec![5, 3, 3, 1, 2],
+        ];
+        let lookup = |i: usize, j: usize| matrix[i][j];
+        for budget in 0..2 {
+            let source = FailAfter(std::cell::Cell::new(budget));
+            assert_eq!(
+                try_minima_from(&lookup, 3, 5, &source),
+                Err(SmawkError::Alloc)
+            );
+        }
+        let source = FailAfter(std::cell::Cell::new(2));
+        assert_eq!(
+            try_minima_from(&lookup, 3, 5, &source),
+            Ok(column_minima(&matrix))
+        );
+        assert_eq!(try_row_minima(&matrix), Ok(row_minima(&matrix)));
+    }
+
+    #[test]
+    fn try_online_minima_injected_failures() {
+        let matrix = |_: &[(usize, i32)], i: usize, j: usize| ((j - i) as i32 - 3).pow(2);
+        let batched = |result: &[(usize, i32)], queries: &[(usize, usize)], out: &mut [i32]| {
+            for (value, &(i, j)) in out.iter_mut().zip(queries) {
+                *value = matrix(result, i, j);
+            }
+        };
+        for budget in 0..7 {
+            let source = FailAfter(std::cell::Cell::new(budget));
+            assert_eq!(
+                online_inner(0, 20, batched, &NoHooks, &source),
+                Err(SmawkError::Alloc)
+            );
+        }
+        let source = FailAfter(std::cell::Cell::new(7));
+        let expected = online_column_minima(0, 20, matrix);
+        assert_eq!(
+            online_inner(0, 20, batched, &NoHooks, &source),
+            Ok(expected.clone())
+        );
+        assert_eq!(try_online_column_minima(0, 20, matrix), Ok(expected));
+    }
+
+    #[test]
+    fn try_minima_huge_matrix() {
+        /// Implicit matrix with far too many columns to allocate.
+        struct Huge;
+        impl Matrix<u8> for Huge {
+            fn nrows(&self) -> usize {
+                2
+            }
+            fn ncols(&self) -> usize {
+                usize::MAX / 4
+            }
+            fn index(&self, _: usize, _: usize) -> u8 {
+                0
+            }
+        }
+        assert_eq!(try_column_minima(&Huge), Err(SmawkError::Alloc));
+        assert_eq!(try_row_minima(&Huge), Err(SmawkError::Alloc));
+        assert_eq!(
+            try_online_column_minima(0u8, usize::MAX / 4, |_, _, _| 0),
+            Err(SmawkError::Alloc)
+        );
+    }
+
+    #[test]
+    fn column_minima_with_floor_matches_brute_force() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let (m, n) = (rng.gen_range(0..30), rng.gen_range(0..30));
+            let matrix = distance_matrix(m, n, 20, &mut rng);
+            let floor = rng.gen_range(0..10);
+
+            // The floor is an extra candidate which loses ties.
+            let expected = (0..n)
+                .map(|j| {
+                    (0..m)
+                        .map(|i| (matrix[i][j], i))
+                        .chain(std::iter::once((floor, usize::MAX)))
+                        .min()
+                        .map(|(value, row)| (row, value))
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            let actual = column_minima_with_floor(&ColumnCount(&matrix, n), floor);
+            assert_eq!(actual, expected, "{:?}, floor {}", matrix, floor);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn column_minima_with_floor_no_rows_vec() {
+        let matrix: Vec<Vec<i32>> = vec![];
+        column_minima_with_floor(&matrix, 0);
+    }
+
+    #[test]
+    fn column_minima_palette_matches_column_minima() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..500 {
+            let (m, n) = (rng.gen_range(1..30), rng.gen_range(0..30));
+            // A sum of a few weighted blocks of ones in the lower left
+            // and upper right corners is Monge and takes few values.
+            let mut matrix = vec![vec![0i64; n]; m];
+            for _ in 0..rng.gen_range(0..4) {
+                let (a, b, weight) = (
+                    rng.gen_range(0..=m),
+                    rng.gen_range(0..=n),
+                    rng.gen_range(1..4),
+                );
+                let lower_left = rng.gen_bool(0.5);
+                for (i, row) in matrix.iter_mut().enumerate() {
+                    for (j, value) in row.iter_mut().enumerate() {
+                        let inside = match lower_left {
+                            true => i >= a && j < b,
+                            false => i < a && j >= b,
+                        };
+                        if inside {
+                            *value += weight;
+                        }
+                    }
+                }
+            }
+            let mut palette = matrix.concat();
+            palette.sort_unstable();
+            palette.dedup();
+            palette.push(10);
+
+            let matrix = ColumnCount(&matrix, n);
+            assert_eq!(
+                column_minima_palette(&matrix, &palette),
+                column_minima(&matrix),
+                "{:?}",
+                matrix.0
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot compute minima with zero rows")]
+    fn column_minima_palette_zero_rows() {
+        column_minima_palette(&ColumnCount(&vec![], 2), &[0, 1]);
+    }
+
+    #[test]
+    fn upper_triangular_column_minima_matches_brute_force() {
+        use rand::{Rng, SeedableRng};
+
+        /// Matrix which panics when the lower triangle is read.
+        struct UpperOnly(Vec<Vec<i64>>);
+
+        impl Matrix<i64> for UpperOnly {
+            fn nrows(&self) -> usize {
+                self.0.len()
+            }
+            fn ncols(&self) -> usize {
+                self.0[0].len()
+            }
+            fn index(&self, row: usize, column: usize) -> i64 {
+                assert!(row <= column, "read ({}, {})", row, column);
+                self.0[row][column]
+            }
+        }
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let (m, n) = (rng.gen_range(1..40), rng.gen_range(1..40));
+            let (x, y) = sorted_points(m, n, 20, &mut rng);
+            // The lower triangle gets small random garbage.
+            let matrix = (0..m)
+                .map(|i| {
+                    (0..n)
+                        .map(|j| {
+                            if i <= j {
+                                (x[i] - y[j]).pow(2)
+                            } else {
+                                rng.gen_range(-5..5)
+                            }
+                        })
+                        .collect()
+                })
+                .collect::<Vec<Vec<i64>>>();
+
+            let expected = (0..n)
+                .map(|j| {
+                    (0..=j.min(m - 1))
+                        .min_by_key(|&i| (matrix[i][j], i))
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                upper_triangular_column_minima(&UpperOnly(matrix.clone())),
+                expected,
+                "{:?}",
+                matrix
+            );
+        }
+    }
+
+    #[test]
+    fn decreasing_staircase_column_minima_matches_brute_force() {
+        use rand::{Rng, SeedableRng};
+
+        /// Matrix which panics when read below the staircase.
+        struct Staircase<'a>(Vec<Vec<i64>>, &'a [usize]);
+
+        impl Matrix<i64> for Staircase<'_> {
+            fn nrows(&self) -> usize {
+                self.0.len()
+            }
+            fn ncols(&self) -> usize {
+                self.0[0].len()
+            }
+            fn index(&self, row: usize, column: usize) -> i64 {
+                assert!(row <= self.1[column], "read ({}, {})", row, column);
+                self.0[row][column]
+            }
+        }
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..300 {
+            let (m, n) = (rng.gen_range(1..40), rng.gen_range(1..40));
+            let (x, y) = sorted_points(m, n, 20, &mut rng);
+            let mut upper = (0..n).map(|_| rng.gen_range(0..m)).collect::<Vec<usize>>();
+            upper.sort_unstable_by(|a, b| b.cmp(a));
+            // The inverse Monge elements get small random garbage
+            // below the staircase.
+            let matrix = (0..m)
+                .map(|i| {
+                    (0..n)
+                        .map(|j| {
+                            if i <= upper[j] {
+                                -(x[i] - y[j]).pow(2)
+                            } else {
+                                rng.gen_range(-500..-400)
+                            }
+                        })
+                        .collect()
+                })
+                .collect::<Vec<Vec<i64>>>();
+
+            let expected = (0..n)
+                .map(|j| (0..=upper[j]).min_by_key(|&i| (matrix[i][j], i)).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                decreasing_staircase_column_minima(&Staircase(matrix.clone(), &upper), &upper),
+                expected,
+                "{:?} {:?}",
+                matrix,
+                upper
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "upper must be non-increasing")]
+    fn decreasing_staircase_column_minima_increasing() {
+        let matrix = vec![vec![0, 0], vec![0, 0]];
+        decreasing_staircase_column_minima(&matrix, &[0, 1]);
+    }
+
+    /// Matrix which knows its number of columns even without rows.
+    struct ColumnCount<'a>(&'a Vec<Vec<i64>>, usize);
+
+    impl Matrix<i64> for ColumnCount<'_> {
+        fn nrows(&self) -> usize {
+            self.0.len()
+        }
+        fn ncols(&self) -> usize {
+            self.1
+        }
+        fn index(&self, row: usize, column: usize) -> i64 {
+            self.0[row][column]
+        }
+    }
+
+    /// Run a future to completion on the current thread.
+    #[cfg(feature = "async")]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake};
+
+        struct Unpark(std::thread::Thread);
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Arc::new(Unpark(std::thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    /// Future which is pending once before it produces `value`, like
+    /// a value which arrives over the network.
+    #[cfg(feature = "async")]
+    async fn delayed<T>(value: T) -> T {
+        let mut pending = true;
+        std::future::poll_fn(|cx| {
+            if std::mem::take(&mut pending) {
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            } else {
+                std::task::Poll::Ready(())
+            }
+        })
+        .await;
+        value
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn column_minima_async_matches_sync() {
+        use rand::{Rng, SeedableRng};
+        use std::cell::Cell;
+
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (m, n) = (rng.gen_range(1..60), rng.gen_range(0..60));
+            let matrix = distance_matrix(m, n, 30, &mut rng);
+
+            let awaits = Cell::new(0);
+            let minima = block_on(column_minima_async(m, n, |i, j| {
+                awaits.set(awaits.get() + 1);
+                delayed(matrix[i][j])
+            }));
+            assert_eq!(minima, column_minima(&matrix), "{:?}", matrix);
+            assert!(awaits.get() <= 6 * (m + n), "{} awaits", awaits.get());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn column_minima_async_no_columns() {
+        let minima = block_on(column_minima_async(0, 0, |_, _| async { 0 }));
+        assert_eq!(minima, Vec::<usize>::new());
+    }
 }
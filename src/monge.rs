@@ -3,9 +3,11 @@
 //! The functions here are mostly meant to be used for testing
 //! correctness of the SMAWK implementation.
 
-use crate::Matrix;
+use crate::{Infinitary, Interval, Matrix};
+use std::cmp::Ordering::{self, Equal, Greater, Less};
+use std::fmt;
 use std::num::Wrapping;
-use std::ops::Add;
+use std::ops::{Add, Sub};
 
 /// Verify that a matrix is a Monge matrix.
 ///
@@ -20,43 +22,2812 @@ use std::ops::Add;
 /// the sum of the antidiagonal. Checking this condition is done by
 /// checking *n* ✕ *m* submatrices, so the running time is O(*mn*).
 ///
+/// The 2 ✕ 2 submatrices are visited row by row, and the right column
+/// of one submatrix is reused as the left column of the next. Each
+/// element is thus read at most twice.
+///
 /// [Monge matrix]: https://en.wikipedia.org/wiki/Monge_array
-pub fn is_monge<T: Ord + Copy, M: Matrix<T>>(matrix: &M) -> bool
+pub fn is_monge<T: Ord + Copy + Default, M: Matrix<T>>(matrix: &M) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    all_windows(matrix, is_monge_window)
+}
+
+/// Verify that a matrix is an inverse Monge matrix.
+///
+/// An inverse Monge matrix satisfies the Monge inequality the other
+/// way around:
+///
+/// ```text
+/// M[i, j] + M[i', j'] >= M[i, j'] + M[i', j]  for all i < i', j < j'
+/// ```
+///
+/// These are exactly the matrices whose negation is Monge, so an
+/// inverse Monge matrix is totally concave rather than totally
+/// monotone: its column maxima are found by SMAWK, for example with
+/// [`column_minima_by`](crate::column_minima_by) and a reversed
+/// comparator. A matrix can be both Monge and inverse Monge, which
+/// happens when all the inequalities are equalities, for example for
+/// a constant matrix.
+///
+/// The submatrices are visited like in [`is_monge`], and the sums
+/// are compared without overflowing in the same way.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{is_inverse_monge, is_monge};
+/// let matrix = vec![vec![1, 2], vec![2, 3]];
+/// assert!(is_inverse_monge(&matrix));
+/// assert!(!is_inverse_monge(&vec![vec![3, 2], vec![2, 0]]));
+/// assert!(is_inverse_monge(&vec![vec![7, 7], vec![7, 7]]));
+/// assert!(is_monge(&vec![vec![7, 7], vec![7, 7]]));
+/// ```
+pub fn is_inverse_monge<T: Ord + Copy + Default, M: Matrix<T>>(matrix: &M) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    // Swapping the columns of a window swaps its diagonals.
+    all_windows(matrix, |top_left, top_right, bot_left, bot_right| {
+        is_monge_window(top_right, top_left, bot_right, bot_left)
+    })
+}
+
+/// Verify that a matrix of intervals is Monge in both endpoints.
+///
+/// The matrix of lower bounds and the matrix of upper bounds must
+/// both pass [`is_monge`]. This is what
+/// [`column_minima_interval`](crate::column_minima_interval) needs.
+/// The bounds are checked separately, so a matrix of values picked
+/// from the intervals is generally not Monge.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::{monge::is_monge_interval, Interval};
+/// let point = |x| Interval { lo: x, hi: x };
+/// assert!(is_monge_interval(&vec![vec![point(3u32), point(2)],
+///                                  vec![point(2), point(1)]]));
+/// let wide = Interval { lo: 0, hi: 5 };
+/// assert!(!is_monge_interval(&vec![vec![point(3u32), point(2)],
+///                                   vec![point(2), wide]]));
+/// ```
+pub fn is_monge_interval<T: Ord + Copy + Default, M: Matrix<Interval<T>>>(matrix: &M) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    all_windows(matrix, |top_left, top_right, bot_left, bot_right| {
+        is_monge_window(top_left.lo, top_right.lo, bot_left.lo, bot_right.lo)
+            && is_monge_window(top_left.hi, top_right.hi, bot_left.hi, bot_right.hi)
+    })
+}
+
+/// Verify that a matrix with infinite elements is a Monge matrix.
+///
+/// This is [`is_monge`] for [`Infinitary`] elements, with the usual
+/// convention that infinity plus anything is infinity and that
+/// infinity is at most infinity. A 2 ✕ 2 submatrix with an infinite
+/// element on the anti-diagonal always satisfies the inequality. A
+/// submatrix with finite anti-diagonal elements violates it if an
+/// element on the main diagonal is infinite. Otherwise the finite
+/// elements are compared without overflowing like in [`is_monge`].
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::is_monge_infinitary;
+/// use smawk::Infinitary::{Finite, PosInf};
+/// // Forbidden lower-left corner.
+/// assert!(is_monge_infinitary(&vec![vec![Finite(3u32), Finite(2)],
+///                                    vec![PosInf, Finite(1)]]));
+/// // Forbidden element on the main diagonal.
+/// assert!(!is_monge_infinitary(&vec![vec![Finite(3u32), Finite(2)],
+///                                     vec![Finite(2), PosInf]]));
+/// ```
+pub fn is_monge_infinitary<T: Ord + Copy + Default, M: Matrix<Infinitary<T>>>(matrix: &M) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    use Infinitary::Finite;
+    all_windows(matrix, |top_left, top_right, bot_left, bot_right| {
+        match (top_left, top_right, bot_left, bot_right) {
+            (Finite(a), Finite(b), Finite(c), Finite(d)) => is_monge_window(a, b, c, d),
+            (_, Finite(_), Finite(_), _) => false,
+            _ => true,
+        }
+    })
+}
+
+/// Check `window` on every 2 ✕ 2 submatrix of neighboring elements.
+///
+/// The submatrices are visited row by row, and the right column of
+/// one submatrix is reused as the left column of the next.
+fn all_windows<T: Copy, M: Matrix<T>>(matrix: &M, window: impl Fn(T, T, T, T) -> bool) -> bool {
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    if m < 2 || n < 2 {
+        return true;
+    }
+    for row in 0..m - 1 {
+        let mut top_left = matrix.index(row, 0);
+        let mut bot_left = matrix.index(row + 1, 0);
+        for col in 1..n {
+            let top_right = matrix.index(row, col);
+            let bot_right = matrix.index(row + 1, col);
+            if !window(top_left, top_right, bot_left, bot_right) {
+                return false;
+            }
+            top_left = top_right;
+            bot_left = bot_right;
+        }
+    }
+    true
+}
+
+/// Verify that a matrix is a Monge matrix by checking every 2 ✕ 2
+/// submatrix independently.
+///
+/// This gives the same result as [`is_monge`], but reads all four
+/// elements of every submatrix. It is slower and is kept for
+/// comparison in tests and benchmarks.
+pub fn is_monge_by_windows<T: Ord + Copy + Default, M: Matrix<T>>(matrix: &M) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    if matrix.nrows() < 2 || matrix.ncols() < 2 {
+        return true;
+    }
+    (0..matrix.nrows() - 1)
+        .flat_map(|row| (0..matrix.ncols() - 1).map(move |col| (row, col)))
+        .all(|(row, col)| {
+            is_monge_window(
+                matrix.index(row, col),
+                matrix.index(row, col + 1),
+                matrix.index(row + 1, col),
+                matrix.index(row + 1, col + 1),
+            )
+        })
+}
+
+/// Verify that a matrix of unbounded numbers is a Monge matrix.
+///
+/// This is [`is_monge`] for element types whose addition cannot
+/// overflow, such as `num_bigint::BigInt`. Such types are not `Copy`,
+/// so the matrix gives access to its elements by reference, typically
+/// through [`ByRef`](crate::ByRef). The elements are never cloned, but
+/// every 2 ✕ 2 submatrix computes two new sums.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::{monge::is_monge_unbounded, ByRef};
+/// let matrix = vec![vec![3u64, 2], vec![2, 1]];
+/// assert!(is_monge_unbounded::<u64, _>(&ByRef(&matrix)));
+/// ```
+pub fn is_monge_unbounded<'a, T, M>(matrix: &M) -> bool
+where
+    T: Ord + 'a,
+    &'a T: Add<&'a T, Output = T>,
+    M: Matrix<&'a T>,
+{
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    if m < 2 || n < 2 {
+        return true;
+    }
+    for row in 0..m - 1 {
+        let mut top_left = matrix.index(row, 0);
+        let mut bot_left = matrix.index(row + 1, 0);
+        for col in 1..n {
+            let top_right = matrix.index(row, col);
+            let bot_right = matrix.index(row + 1, col);
+            if top_left + bot_right > bot_left + top_right {
+                return false;
+            }
+            top_left = top_right;
+            bot_left = bot_right;
+        }
+    }
+    true
+}
+
+/// Check the Monge inequality on a single 2 ✕ 2 submatrix without
+/// overflowing.
+fn is_monge_window<T: Ord + Copy + Default>(
+    top_left: T,
+    top_right: T,
+    bot_left: T,
+    bot_right: T,
+) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    checked_sum(top_left, bot_right) <= checked_sum(bot_left, top_right)
+}
+
+/// Returns the wrapped sum `a + b` together with the direction of the
+/// overflow: `Less` if the sum is below `T::MIN`, `Greater` if it is
+/// above `T::MAX` and `Equal` if it fits. Sums which overflow in the
+/// same direction wrap by the same amount, so comparing the pairs
+/// compares the exact sums.
+fn checked_sum<T: Ord + Copy + Default>(a: T, b: T) -> (Ordering, T)
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    let sum = (Wrapping(a) + Wrapping(b)).0;
+    let overflow = if b < T::default() {
+        // Only signed integers get here.
+        if sum > a {
+            Less
+        } else {
+            Equal
+        }
+    } else if sum < a {
+        Greater
+    } else {
+        Equal
+    };
+    (overflow, sum)
+}
+
+/// Verify that an `f32` matrix is a Monge matrix up to a tolerance.
+///
+/// This is [`is_monge`] for `f32` elements, where rounding errors can
+/// make a Monge matrix narrowly fail the inequality. Every 2 ✕ 2
+/// submatrix of neighboring elements may violate it by at most
+/// `epsilon`:
+///
+/// ```text
+/// M[i, j] + M[i + 1, j + 1] <= M[i, j + 1] + M[i + 1, j] + epsilon
+/// ```
+///
+/// Every NaN is treated as positive infinity, like in
+/// [`column_minima_f32`](crate::column_minima_f32) and
+/// [`row_minima_f32`](crate::row_minima_f32), so a matrix which
+/// passes this check is one where those functions find the right
+/// minima. Infinity plus anything finite is infinity, which gives the
+/// convention of [`is_monge_infinitary`]: an infinite element on the
+/// anti-diagonal always satisfies the inequality, and an infinite
+/// element on the main diagonal violates it when the anti-diagonal is
+/// finite.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::is_monge_f32;
+/// let matrix = vec![vec![0.1f32, 0.2], vec![0.2, 0.3]];
+/// assert!(is_monge_f32(&matrix, 1e-6));
+/// assert!(!is_monge_f32(&vec![vec![0.0, 0.0], vec![0.0, 0.1]], 1e-6));
+/// // Forbidden lower-left corner.
+/// assert!(is_monge_f32(&vec![vec![3.0, 2.0], vec![f32::NAN, 1.0]], 0.0));
+/// ```
+pub fn is_monge_f32<M: Matrix<f32>>(matrix: &M, epsilon: f32) -> bool {
+    let at = |i, j| match matrix.index(i, j) {
+        x if x.is_nan() => f32::INFINITY,
+        x => x,
+    };
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    (0..m.saturating_sub(1)).all(|i| {
+        (0..n.saturating_sub(1))
+            .all(|j| at(i, j) + at(i + 1, j + 1) <= at(i, j + 1) + at(i + 1, j) + epsilon)
+    })
+}
+
+/// Reason why [`verify_monge_f64_exact`] rejected a matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MongeF64Error {
+    /// The element at `(row, column)` is infinite or NaN.
+    NonFinite {
+        /// Row of the element.
+        row: usize,
+        /// Column of the element.
+        column: usize,
+    },
+    /// The matrix is not a Monge matrix.
+    NotMonge(MongeViolation),
+}
+
+impl fmt::Display for MongeF64Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MongeF64Error::NonFinite { row, column } => {
+                write!(f, "non-finite element at ({}, {})", row, column)
+            }
+            MongeF64Error::NotMonge(violation) => violation.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for MongeF64Error {}
+
+/// Verify exactly that an `f64` matrix is a Monge matrix.
+///
+/// Unlike [`is_monge_f32`], no tolerance is involved: the sign of
+///
+/// ```text
+/// (M[i, j] + M[i + 1, j + 1]) - (M[i, j + 1] + M[i + 1, j])
+/// ```
+///
+/// is computed exactly for every 2 ✕ 2 submatrix of neighboring
+/// elements. The four elements are summed with error-free
+/// transformations: each floating-point addition is paired with the
+/// rounding error it made, which is itself a floating-point number,
+/// and the sign is read off the resulting expansion. This detects a
+/// violation of 1 next to elements of size 10<sup>16</sup>, which a
+/// direct evaluation rounds away, and it is also exact when the sums
+/// would overflow or when the elements are subnormal.
+///
+/// The elements must be finite. The first infinite or NaN element, in
+/// row-major order, is reported as [`MongeF64Error::NonFinite`]
+/// before any submatrix is checked. Otherwise the first violating
+/// submatrix is reported as [`MongeF64Error::NotMonge`].
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{verify_monge_f64_exact, MongeF64Error, MongeViolation};
+/// let matrix = vec![vec![1e16, 1e16], vec![0.0, 1.0]];
+/// // Rounded, both diagonals sum to 1e16.
+/// assert_eq!(1e16 + 1.0, 1e16 + 0.0);
+/// assert_eq!(
+///     verify_monge_f64_exact(&matrix),
+///     Err(MongeF64Error::NotMonge(MongeViolation { row: 0, column: 0 }))
+/// );
+/// ```
+pub fn verify_monge_f64_exact<M: Matrix<f64>>(matrix: &M) -> Result<(), MongeF64Error> {
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    for row in 0..m {
+        for column in 0..n {
+            if !matrix.index(row, column).is_finite() {
+                return Err(MongeF64Error::NonFinite { row, column });
+            }
+        }
+    }
+    for row in 0..m.saturating_sub(1) {
+        for column in 0..n.saturating_sub(1) {
+            let sign = exact_sum_sign([
+                matrix.index(row, column),
+                matrix.index(row + 1, column + 1),
+                -matrix.index(row, column + 1),
+                -matrix.index(row + 1, column),
+            ]);
+            if sign == Greater {
+                return Err(MongeF64Error::NotMonge(MongeViolation { row, column }));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Verify exactly that an `f64` matrix is a Monge matrix.
+///
+/// This is [`verify_monge_f64_exact`] without the reason. A matrix
+/// with an infinite or NaN element is not Monge.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::is_monge_f64_exact;
+/// assert!(is_monge_f64_exact(&vec![vec![0.1, 0.2], vec![0.2, 0.3]]));
+/// assert!(!is_monge_f64_exact(&vec![vec![1e16, 1e16], vec![0.0, 1.0]]));
+/// assert!(!is_monge_f64_exact(&vec![vec![0.0, f64::INFINITY]]));
+/// ```
+pub fn is_monge_f64_exact<M: Matrix<f64>>(matrix: &M) -> bool {
+    verify_monge_f64_exact(matrix).is_ok()
+}
+
+/// Verify exactly that a positive `f64` matrix is a multiplicative
+/// Monge matrix.
+///
+/// A multiplicative Monge matrix has positive elements where
+///
+/// ```text
+/// M[i, j] · M[i', j'] <= M[i, j'] · M[i', j]  for all i < i', j < j'
+/// ```
+///
+/// This is the Monge property of the matrix of logarithms, so the
+/// minima of such a matrix can be found with [`LogDomain`]. Products
+/// of probabilities or of other positive factors give matrices like
+/// this.
+///
+/// Like [`is_monge_f64_exact`], the inequality is checked exactly for
+/// every 2 ✕ 2 submatrix of neighboring elements. The elements are
+/// split into a power of two and a mantissa, so the products neither
+/// overflow nor underflow, and each product of mantissas is paired
+/// with its rounding error. Taking logarithms instead would round
+/// away a violation of a single bit.
+///
+/// A matrix with an element which is not positive and finite is not
+/// multiplicative Monge.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::is_monge_multiplicative;
+/// // (i + j + 1) / 4 is multiplicative Monge.
+/// let matrix = vec![vec![0.25, 0.5, 0.75], vec![0.5, 0.75, 1.0]];
+/// assert!(is_monge_multiplicative(&matrix));
+///
+/// // The main diagonal product is 1 + 2^-51 + 2^-104, which rounds to
+/// // the antidiagonal product 1 + 2^-51.
+/// let (a, b) = (1.0 + f64::EPSILON, 1.0 + 2.0 * f64::EPSILON);
+/// assert_eq!(a * a, b);
+/// assert!(!is_monge_multiplicative(&vec![vec![a, 1.0], vec![b, a]]));
+/// assert!(!is_monge_multiplicative(&vec![vec![1.0, 0.0], vec![1.0, 1.0]]));
+/// ```
+pub fn is_monge_multiplicative<M: Matrix<f64>>(matrix: &M) -> bool {
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    let positive = |value: f64| value > 0.0 && value.is_finite();
+    if !(0..m).all(|i| (0..n).all(|j| positive(matrix.index(i, j)))) {
+        return false;
+    }
+    (0..m.saturating_sub(1)).all(|i| {
+        (0..n.saturating_sub(1)).all(|j| {
+            product_cmp(
+                [matrix.index(i, j), matrix.index(i + 1, j + 1)],
+                [matrix.index(i, j + 1), matrix.index(i + 1, j)],
+            ) != Greater
+        })
+    })
+}
+
+/// Sum two floats, returning the rounded sum and its rounding error.
+///
+/// The error is exact: `a + b == sum + error` in real arithmetic,
+/// provided that the sum does not overflow.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_virtual = sum - a;
+    let a_virtual = sum - b_virtual;
+    (sum, (a - a_virtual) + (b - b_virtual))
+}
+
+/// A sum of up to eight floats, stored without rounding.
+///
+/// The components are non-overlapping and sorted by increasing
+/// magnitude, and zero components are dropped, so the sign of the
+/// sum is the sign of the last component.
+struct Expansion {
+    components: [f64; 8],
+    len: usize,
+}
+
+impl Expansion {
+    fn new() -> Self {
+        Expansion {
+            components: [0.0; 8],
+            len: 0,
+        }
+    }
+
+    /// Add `value` to the expansion (Shewchuk's Grow-Expansion).
+    fn grow(&mut self, value: f64) {
+        let mut carry = value;
+        let mut len = 0;
+        for i in 0..self.len {
+            let (sum, error) = two_sum(carry, self.components[i]);
+            carry = sum;
+            if error != 0.0 {
+                self.components[len] = error;
+                len += 1;
+            }
+        }
+        if carry != 0.0 {
+            self.components[len] = carry;
+            len += 1;
+        }
+        self.len = len;
+    }
+
+    fn components(&self) -> &[f64] {
+        &self.components[..self.len]
+    }
+
+    fn largest(&self) -> f64 {
+        self.components().last().copied().unwrap_or(0.0)
+    }
+}
+
+/// Compute the sign of the sum of four finite floats exactly.
+///
+/// Summing the values directly could overflow, so values of normal
+/// size are first scaled by 1/16, which is exact for them. Subnormal
+/// and other tiny values would lose bits, so they are kept aside: if
+/// the scaled sum is large, they cannot change its sign, and
+/// otherwise the scaled sum is small enough to be scaled back exactly
+/// and combined with them.
+fn exact_sum_sign(values: [f64; 4]) -> std::cmp::Ordering {
+    // 2^-1000, comfortably above the subnormal range after scaling.
+    let tiny = f64::from_bits((1023 - 1000) << 52);
+    let mut scaled = Expansion::new();
+    for &value in &values {
+        if value.abs() >= tiny {
+            scaled.grow(value / 16.0);
+        }
+    }
+    // The tiny values add up to less than 4 * tiny, and the scaled
+    // sum is at least half its largest component.
+    let largest = scaled.largest();
+    if largest.abs() >= tiny {
+        return largest.partial_cmp(&0.0).unwrap();
+    }
+    let mut sum = Expansion::new();
+    for &component in scaled.components() {
+        sum.grow(component * 16.0);
+    }
+    for &value in &values {
+        if value.abs() < tiny {
+            sum.grow(value);
+        }
+    }
+    sum.largest().partial_cmp(&0.0).unwrap()
+}
+
+/// Multiply two floats, returning the rounded product and its
+/// rounding error.
+///
+/// The error is exact: `a * b == product + error` in real arithmetic,
+/// provided that the product neither overflows nor underflows.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let product = a * b;
+    (product, a.mul_add(b, -product))
+}
+
+/// Split a positive finite float into a mantissa in `[1, 2)` and an
+/// exponent.
+fn split_exponent(value: f64) -> (f64, i32) {
+    // Subnormals are scaled by 2^64 to make them normal.
+    let (value, shift) = if value < f64::MIN_POSITIVE {
+        (value * f64::from_bits((1023 + 64) << 52), -64)
+    } else {
+        (value, 0)
+    };
+    let bits = value.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32 - 1023;
+    let mantissa = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+    (mantissa, exponent + shift)
+}
+
+/// Compare the products `a[0] · a[1]` and `b[0] · b[1]` of positive
+/// finite floats exactly.
+fn product_cmp(a: [f64; 2], b: [f64; 2]) -> std::cmp::Ordering {
+    let (a0, a0_exponent) = split_exponent(a[0]);
+    let (a1, a1_exponent) = split_exponent(a[1]);
+    let (b0, b0_exponent) = split_exponent(b[0]);
+    let (b1, b1_exponent) = split_exponent(b[1]);
+    // The products of the mantissas are in [1, 4), so a difference of
+    // two or more in the exponents decides the comparison.
+    let shift = (a0_exponent + a1_exponent) - (b0_exponent + b1_exponent);
+    if shift >= 2 {
+        return Greater;
+    }
+    if shift <= -2 {
+        return std::cmp::Ordering::Less;
+    }
+    let scale = 2f64.powi(shift);
+    let (a_product, a_error) = two_product(a0, a1);
+    let (b_product, b_error) = two_product(b0, b1);
+    exact_sum_sign([a_product * scale, a_error * scale, -b_product, -b_error])
+}
+
+/// Adapter which takes the natural logarithm of every element.
+///
+/// The minima of a positive matrix are the minima of its logarithms,
+/// and products of elements become sums. A [multiplicative Monge
+/// matrix](is_monge_multiplicative) thus becomes a Monge matrix, and
+/// the functions in this crate find its minima through this adapter.
+/// The returned indices refer to the original matrix, and the same
+/// works for dynamic programs over products, such as
+/// [`fused_row_relax`](crate::dp::fused_row_relax) with the
+/// logarithms of the previous layer.
+///
+/// Logarithms are rounded, and the rounding can flip near-ties. Two
+/// products which differ by a relative amount of about 10<sup>-15</sup>
+/// or less can compare either way after taking logarithms, and the
+/// logarithms of an exactly multiplicative Monge matrix can narrowly
+/// violate the Monge inequality. The minima are then still correct up
+/// to such near-ties, but may not be the smallest index among them.
+/// Elements which are zero or negative give infinite or NaN
+/// logarithms, which are not supported.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::LogDomain;
+/// use smawk::Matrix;
+///
+/// // Probabilities (i + j + 1) / 6, scaled per row and column.
+/// let (rows, columns) = ([0.5, 0.2, 1.0], [1.0, 0.1, 0.5, 0.8]);
+/// let matrix = (0..3)
+///     .map(|i| (0..4).map(|j| rows[i] * columns[j] * (i + j + 1) as f64 / 6.0).collect())
+///     .collect::<Vec<Vec<f64>>>();
+/// let log = LogDomain::new(&matrix);
+/// assert_eq!(log.index(0, 0), matrix[0][0].ln());
+/// assert_eq!(smawk::column_minima(&log), vec![1, 1, 1, 1]);
+///
+/// // The best product along two steps of a path through the matrix.
+/// let prev = (0..3).map(|i| matrix[i][0].ln()).collect::<Vec<_>>();
+/// let next = smawk::dp::fused_row_relax(&prev, |i, j| log.index(i, j), 4);
+/// let (row, value) = next[2];
+/// assert_eq!(row, 1);
+/// assert!((value.exp() - matrix[1][0] * matrix[1][2]).abs() < 1e-15);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct LogDomain<'a, M> {
+    matrix: &'a M,
+}
+
+impl<'a, M> LogDomain<'a, M> {
+    /// Take the logarithms of the elements of `matrix`.
+    pub fn new(matrix: &'a M) -> Self {
+        LogDomain { matrix }
+    }
+
+    /// Return the original matrix.
+    pub fn matrix(&self) -> &'a M {
+        self.matrix
+    }
+}
+
+impl<M: Matrix<f64>> Matrix<f64> for LogDomain<'_, M> {
+    fn nrows(&self) -> usize {
+        self.matrix.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.matrix.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> f64 {
+        self.matrix.index(row, column).ln()
+    }
+}
+
+/// Verify that a sequence is convex.
+///
+/// A sequence is convex when the differences `v[i + 1] - v[i]` are
+/// non-decreasing. This is checked as `v[i + 1] + v[i + 1] <= v[i] +
+/// v[i + 2]`, which is the Monge inequality for a 2 ✕ 2 window and
+/// is evaluated without overflow in the same way as in [`is_monge`].
+/// Unlike computing the differences directly, this also works for
+/// decreasing sequences of unsigned numbers.
+///
+/// Sequences with fewer than three elements are convex.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::is_convex_sequence;
+/// assert!(is_convex_sequence(&[9u32, 4, 1, 0, 1, 4]));
+/// assert!(!is_convex_sequence(&[0, 1, 1, 2]));
+/// ```
+pub fn is_convex_sequence<T: Ord + Copy + Default>(values: &[T]) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    values
+        .windows(3)
+        .all(|w| is_monge_window(w[1], w[2], w[0], w[1]))
+}
+
+/// Verify that a sequence is concave.
+///
+/// A sequence is concave when the differences `v[i + 1] - v[i]` are
+/// non-increasing. This is checked as `v[i] + v[i + 2] <= v[i + 1] +
+/// v[i + 1]`, with overflow handled like in [`is_monge`].
+///
+/// Sequences with fewer than three elements are concave.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::is_concave_sequence;
+/// assert!(is_concave_sequence(&[0u32, 5, 8, 9, 9]));
+/// assert!(!is_concave_sequence(&[9u32, 4, 1, 0, 1, 4]));
+/// ```
+pub fn is_concave_sequence<T: Ord + Copy + Default>(values: &[T]) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    values
+        .windows(3)
+        .all(|w| is_monge_window(w[0], w[1], w[1], w[2]))
+}
+
+/// Build the Hankel matrix `M[i, j] = a[i + j]`.
+///
+/// The matrix has `nrows` rows and `a.len() + 1 - nrows` columns, so
+/// every element of `a` is used. The shape must be given, since `a`
+/// is the first row followed by the rest of the last column of a
+/// Hankel matrix of every shape with `a.len() + 1` as the sum of its
+/// dimensions.
+///
+/// Every 2 ✕ 2 window of the matrix holds `a[s]`, `a[s + 1]`, `a[s +
+/// 1]`, and `a[s + 2]`. With at least two rows and two columns, every
+/// `s` occurs, and so the matrix is a Monge matrix exactly when `a` is
+/// concave. This makes it a structured alternative to random Monge
+/// matrices, and it appears in dynamic programs based on (max, +)
+/// convolution.
+///
+/// **Note: this function is only available if you enable the
+/// `ndarray` Cargo feature.**
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{hankel_monge, is_monge};
+///
+/// let matrix = hankel_monge(&[0, 5, 8, 9, 9], 2);
+/// assert_eq!(matrix, ndarray::arr2(&[[0, 5, 8, 9],
+///                                    [5, 8, 9, 9]]));
+/// assert!(is_monge(&matrix));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to pass zero rows or more rows than elements in
+/// `a`.
+#[cfg(feature = "ndarray")]
+pub fn hankel_monge<T: Copy>(a: &[T], nrows: usize) -> ndarray::Array2<T> {
+    assert!(
+        0 < nrows && nrows <= a.len(),
+        "nrows must be between 1 and the length of a"
+    );
+    ndarray::Array2::from_shape_fn((nrows, a.len() + 1 - nrows), |(i, j)| a[i + j])
+}
+
+/// Compute the column minima values and check that they are convex.
+///
+/// This returns the values of the column minima found by
+/// [`column_minima`](crate::column_minima) together with the result
+/// of [`is_convex_sequence`] on them. Many dynamic programs need the
+/// optimal cost to be convex in some parameter, for example to apply
+/// a Lagrangian relaxation. A non-convex result indicates that the
+/// cost was modeled wrongly.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::column_minima_values_convex_check;
+/// let matrix = vec![vec![4, 1, 0, 1],
+///                   vec![5, 2, 1, 0]];
+/// assert_eq!(column_minima_values_convex_check(&matrix), (vec![4, 1, 0, 0], true));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_values_convex_check<T: Ord + Copy + Default, M: Matrix<T>>(
+    matrix: &M,
+) -> (Vec<T>, bool)
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    let values = crate::column_minima(matrix)
+        .into_iter()
+        .enumerate()
+        .map(|(col, row)| matrix.index(row, col))
+        .collect::<Vec<_>>();
+    let convex = is_convex_sequence(&values);
+    (values, convex)
+}
+
+/// Verify that a matrix is totally monotone by brute force.
+///
+/// A matrix is totally monotone when `M[i, j] > M[i, j']` implies
+/// `M[i', j] > M[i', j']` for all `i < i'` and `j < j'`. Unlike the
+/// Monge property, this cannot be checked on adjacent rows and
+/// columns only, so all pairs of rows and columns are checked. The
+/// running time is O(*m*² *n*²), which makes this function useful for
+/// cross-checking small matrices only.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::is_totally_monotone;
+/// // Totally monotone, but not a Monge matrix.
+/// assert!(is_totally_monotone(&vec![vec![1, 2], vec![1, 5]]));
+/// assert!(!is_totally_monotone(&vec![vec![2, 1], vec![1, 2]]));
+/// ```
+pub fn is_totally_monotone<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> bool {
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    (0..m).all(|i| {
+        (i + 1..m).all(|i2| {
+            (0..n).all(|j| {
+                (j + 1..n).all(|j2| {
+                    matrix.index(i, j).partial_cmp(&matrix.index(i, j2)) != Some(Greater)
+                        || matrix.index(i2, j) > matrix.index(i2, j2)
+                })
+            })
+        })
+    })
+}
+
+/// Verify that the row minima of a matrix move right from row to row.
+///
+/// A matrix is monotone when the leftmost minimum of every row is at
+/// or to the right of the leftmost minimum of the row above. This is
+/// weaker than [`is_totally_monotone`], which requires it of every
+/// submatrix. The divide and conquer algorithm of
+/// `recursive::row_minima` only relies on this, while SMAWK needs
+/// total monotonicity. The running time is O(*mn*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{is_monotone_rows, is_totally_monotone};
+/// let matrix = vec![vec![1, 3, 2],
+///                   vec![3, 1, 2]];
+/// assert!(is_monotone_rows(&matrix));
+/// assert!(!is_totally_monotone(&matrix));
+/// assert!(!is_monotone_rows(&vec![vec![2, 1], vec![1, 2]]));
+/// ```
+pub fn is_monotone_rows<T: PartialOrd + Copy, M: Matrix<T>>(matrix: &M) -> bool {
+    let mut previous = 0;
+    for i in 0..matrix.nrows() {
+        let mut minimum = 0;
+        for j in 1..matrix.ncols() {
+            if matrix.index(i, j) < matrix.index(i, minimum) {
+                minimum = j;
+            }
+        }
+        if minimum < previous {
+            return false;
+        }
+        previous = minimum;
+    }
+    true
+}
+
+/// Element-wise minimum of several matrices of the same size.
+///
+/// The minimum is computed on the fly when an element is accessed,
+/// so the combined matrix is never materialized. Ties are broken in
+/// favor of the first matrix.
+///
+/// The element-wise minimum of Monge matrices is generally not Monge,
+/// nor totally monotone. It is Monge in the following nested case:
+/// the minimum of `A` and `B` is Monge if `A` is Monge and the
+/// difference `D = B - A` is a Monge matrix which is either
+/// non-decreasing or non-increasing along both rows and columns. This
+/// holds since `min(A, B) = A + min(D, 0)` where `min(D, 0)` is
+/// Monge. An example is `B = A + x[i] + y[j]` with non-decreasing `x`
+/// and `y`. For more than two matrices, the condition must hold for
+/// each matrix relative to the minimum of the preceding ones. Use
+/// [`is_totally_monotone`] to cross-check other combinations.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::MinOfMatrices;
+/// use smawk::Matrix;
+///
+/// let a = vec![vec![0, 1, 3],
+///              vec![1, 1, 2]];
+/// // Add x = [0, 2] and y = [-2, 0, 1] to every row and column.
+/// let b = vec![vec![-2, 1, 4],
+///              vec![1, 3, 5]];
+/// let matrices = [a, b];
+/// let min = MinOfMatrices::new(&matrices);
+/// assert_eq!(min.index(0, 0), -2);
+/// assert_eq!(smawk::column_minima(&min), vec![0, 0, 1]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct MinOfMatrices<'a, M> {
+    matrices: &'a [M],
+}
+
+impl<'a, M> MinOfMatrices<'a, M> {
+    /// Combine the given matrices.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to pass an empty slice or matrices of different
+    /// sizes.
+    pub fn new<T: Copy>(matrices: &'a [M]) -> Self
+    where
+        M: Matrix<T>,
+    {
+        assert!(!matrices.is_empty(), "expected at least one matrix");
+        let (m, n) = (matrices[0].nrows(), matrices[0].ncols());
+        assert!(
+            matrices.iter().all(|x| x.nrows() == m && x.ncols() == n),
+            "matrices must have the same size"
+        );
+        MinOfMatrices { matrices }
+    }
+
+    /// Return the combined matrices.
+    pub fn matrices(&self) -> &'a [M] {
+        self.matrices
+    }
+}
+
+impl<T: PartialOrd + Copy, M: Matrix<T>> Matrix<T> for MinOfMatrices<'_, M> {
+    fn nrows(&self) -> usize {
+        self.matrices[0].nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.matrices[0].ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> T {
+        let mut min = self.matrices[0].index(row, column);
+        for matrix in &self.matrices[1..] {
+            let value = matrix.index(row, column);
+            if value < min {
+                min = value;
+            }
+        }
+        min
+    }
+}
+
+/// Element-wise maximum of several matrices of the same size.
+///
+/// This is the counterpart of [`MinOfMatrices`]. The maximum is
+/// computed on the fly when an element is accessed, so the combined
+/// matrix is never materialized.
+///
+/// The element-wise maximum of Monge matrices is generally not Monge,
+/// nor totally monotone. It is Monge if `A` is Monge and the
+/// difference `D = B - A` is `x[i] + y[j]` where one of `x` and `y`
+/// is non-decreasing and the other is non-increasing. This holds
+/// since `max(A, B) = A + max(D, 0)`, and `max(D, 0)` is a convex
+/// function of `x[i] - (-y[j])` where both `x` and `-y` are sorted
+/// the same way, which is Monge like `(x[i] - z[j])²`. For more
+/// than two matrices, the condition must hold for each matrix
+/// relative to the maximum of the preceding ones. Use
+/// [`is_totally_monotone`] to cross-check other combinations by brute
+/// force before relying on SMAWK.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{is_totally_monotone, MaxOfMatrices};
+/// use smawk::Matrix;
+///
+/// let a = vec![vec![0, 1, 3],
+///              vec![1, 1, 2]];
+/// // Add x = [0, 2] and y = [1, 0, -2] to every row and column.
+/// let b = vec![vec![1, 1, 1],
+///              vec![4, 3, 2]];
+/// let matrices = [a, b];
+/// let max = MaxOfMatrices::new(&matrices);
+/// assert_eq!(max.index(0, 2), 3);
+/// assert!(is_totally_monotone(&max));
+/// assert_eq!(smawk::column_minima(&max), vec![0, 0, 1]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct MaxOfMatrices<'a, M> {
+    matrices: &'a [M],
+}
+
+impl<'a, M> MaxOfMatrices<'a, M> {
+    /// Combine the given matrices.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to pass an empty slice or matrices of different
+    /// sizes.
+    pub fn new<T: Copy>(matrices: &'a [M]) -> Self
+    where
+        M: Matrix<T>,
+    {
+        assert!(!matrices.is_empty(), "expected at least one matrix");
+        let (m, n) = (matrices[0].nrows(), matrices[0].ncols());
+        assert!(
+            matrices.iter().all(|x| x.nrows() == m && x.ncols() == n),
+            "matrices must have the same size"
+        );
+        MaxOfMatrices { matrices }
+    }
+
+    /// Return the combined matrices.
+    pub fn matrices(&self) -> &'a [M] {
+        self.matrices
+    }
+}
+
+impl<T: PartialOrd + Copy, M: Matrix<T>> Matrix<T> for MaxOfMatrices<'_, M> {
+    fn nrows(&self) -> usize {
+        self.matrices[0].nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.matrices[0].ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> T {
+        let mut max = self.matrices[0].index(row, column);
+        for matrix in &self.matrices[1..] {
+            let value = matrix.index(row, column);
+            if value > max {
+                max = value;
+            }
+        }
+        max
+    }
+}
+
+/// Location and orientation of a 2 ✕ 2 window which violates the
+/// Monge property in a three-dimensional array.
+///
+/// The window lies in the plane spanned by the axes in `axes`. It
+/// starts at `index` and extends one step along each of the two axes.
+///
+/// **Note: this type is only available if you enable the `ndarray`
+/// Cargo feature.**
+#[cfg(feature = "ndarray")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MongeViolation3d {
+    /// The two axes spanning the plane of the window: `(0, 1)`, `(0,
+    /// 2)`, or `(1, 2)`.
+    pub axes: (usize, usize),
+    /// Index of the corner of the window closest to the origin.
+    pub index: [usize; 3],
+}
+
+/// Verify that a three-dimensional array is Monge in every
+/// axis-aligned plane.
+///
+/// An array `A` is Monge in the plane spanned by two axes if every
+/// two-dimensional slice of `A` along these axes is a Monge matrix.
+/// The planes are checked in the order `(0, 1)`, `(0, 2)`, and `(1,
+/// 2)`, and the windows within each plane in index order. The first
+/// violation found is returned. Overflow is handled like in
+/// [`is_monge`].
+///
+/// Running time on an *l* ✕ *m* ✕ *n* array: O(*lmn*).
+///
+/// **Note: this function is only available if you enable the
+/// `ndarray` Cargo feature.**
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{verify_monge_3d, MongeViolation3d};
+///
+/// // Monge in planes (0, 1) and (0, 2), but not in plane (1, 2).
+/// let array = ndarray::Array3::from_shape_fn((2, 2, 2), |(_, j, k)| j * k);
+/// assert_eq!(verify_monge_3d(&array),
+///            Err(MongeViolation3d { axes: (1, 2), index: [0, 0, 0] }));
+/// ```
+#[cfg(feature = "ndarray")]
+pub fn verify_monge_3d<T: Ord + Copy + Default>(
+    array: &ndarray::Array3<T>,
+) -> Result<(), MongeViolation3d>
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    let shape = array.shape();
+    for axes in [(0, 1), (0, 2), (1, 2)] {
+        let mut upper = [shape[0], shape[1], shape[2]];
+        upper[axes.0] = upper[axes.0].saturating_sub(1);
+        upper[axes.1] = upper[axes.1].saturating_sub(1);
+        for i in 0..upper[0] {
+            for j in 0..upper[1] {
+                for k in 0..upper[2] {
+                    let index = [i, j, k];
+                    let mut down = index;
+                    down[axes.0] += 1;
+                    let mut right = index;
+                    right[axes.1] += 1;
+                    let mut diagonal = down;
+                    diagonal[axes.1] += 1;
+                    if !is_monge_window(array[index], array[right], array[down], array[diagonal]) {
+                        return Err(MongeViolation3d { axes, index });
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Verify that a three-dimensional array is Monge in every
+/// axis-aligned plane.
+///
+/// See [`verify_monge_3d`] for details.
+///
+/// **Note: this function is only available if you enable the
+/// `ndarray` Cargo feature.**
+#[cfg(feature = "ndarray")]
+pub fn is_monge_3d<T: Ord + Copy + Default>(array: &ndarray::Array3<T>) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    verify_monge_3d(array).is_ok()
+}
+
+/// Location of a 2 ✕ 2 submatrix which violates the Monge property.
+///
+/// The submatrix consists of rows `row` and `row + 1` and columns
+/// `column` and `column + 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MongeViolation {
+    /// Top row of the offending submatrix.
+    pub row: usize,
+    /// Left column of the offending submatrix.
+    pub column: usize,
+}
+
+impl fmt::Display for MongeViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Monge property violated by the 2 ✕ 2 submatrix at ({}, {})",
+            self.row, self.column
+        )
+    }
+}
+
+impl std::error::Error for MongeViolation {}
+
+/// Integer types with the checked arithmetic used by
+/// [`monge_decompose`] and `MongeMatrix`.
+///
+/// This is implemented for all primitive integer types.
+pub trait CheckedInteger: Ord + Copy {
+    /// The value zero.
+    const ZERO: Self;
+    /// Add two values, returning `None` on overflow.
+    fn checked_add(self, other: Self) -> Option<Self>;
+    /// Subtract two values, returning `None` on overflow.
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    /// Multiply two values, returning `None` on overflow.
+    fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_integer {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedInteger for $t {
+                const ZERO: Self = 0;
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+                fn checked_sub(self, other: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, other)
+                }
+                fn checked_mul(self, other: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Reason why a [`MongeMatrix`] could not be created.
+///
+/// **Note: this type is only available if you enable the `ndarray`
+/// Cargo feature.**
+#[cfg(feature = "ndarray")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MongeMatrixError {
+    /// The matrix is not a Monge matrix.
+    NotMonge(MongeViolation),
+    /// An element, or the sum of two neighboring elements, does not
+    /// fit in the element type.
+    Overflow,
+}
+
+#[cfg(feature = "ndarray")]
+impl fmt::Display for MongeMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MongeMatrixError::NotMonge(violation) => violation.fmt(f),
+            MongeMatrixError::Overflow => write!(f, "arithmetic overflow in Monge matrix"),
+        }
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl std::error::Error for MongeMatrixError {}
+
+/// A matrix which is known to be a Monge matrix.
+///
+/// The matrix is checked once when it is created with
+/// [`MongeMatrix::new`]. The operations below preserve the Monge
+/// property, so they return a `MongeMatrix` directly:
+///
+/// * adding two Monge matrices with `&a + &b`,
+/// * adding a constant with [`MongeMatrix::add_constant`],
+/// * adding row and column potentials with
+///   [`MongeMatrix::add_potentials`],
+/// * scaling by a non-negative factor with [`MongeMatrix::scale`].
+///
+/// The arithmetic is checked. On overflow, the operation returns
+/// [`MongeMatrixError::Overflow`] instead of a matrix which might not
+/// be Monge. Other operations, such as negation or the elementwise
+/// minimum of two matrices, do not preserve the Monge property and
+/// are not provided. Use [`MongeMatrix::into_inner`] to get the plain
+/// array for these.
+///
+/// **Note: this type is only available if you enable the `ndarray`
+/// Cargo feature.**
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use smawk::monge::{MongeMatrix, MongeMatrixError};
+///
+/// let a = MongeMatrix::new(arr2(&[[3, 2], [2, 1]])).unwrap();
+/// let b = MongeMatrix::new(arr2(&[[0, 1], [0, 0]])).unwrap();
+/// let sum = (&a + &b).unwrap();
+/// assert_eq!(sum.as_array(), &arr2(&[[3, 3], [2, 1]]));
+/// assert_eq!(smawk::column_minima(&sum), vec![1, 1]);
+///
+/// assert_eq!(a.scale(i32::MAX), Err(MongeMatrixError::Overflow));
+/// assert!(MongeMatrix::new(arr2(&[[3, 1], [1, 3]])).is_err());
+/// ```
+#[cfg(feature = "ndarray")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MongeMatrix<T> {
+    array: ndarray::Array2<T>,
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: CheckedInteger> MongeMatrix<T> {
+    /// Check that `array` is a Monge matrix.
+    ///
+    /// The sum of two neighboring elements must fit in `T`, otherwise
+    /// [`MongeMatrixError::Overflow`] is returned. The first violating
+    /// 2 ✕ 2 submatrix in row-major order is returned as
+    /// [`MongeMatrixError::NotMonge`].
+    ///
+    /// Running time on an *m* ✕ *n* matrix: O(*mn*).
+    pub fn new(array: ndarray::Array2<T>) -> Result<Self, MongeMatrixError> {
+        for row in 0..array.nrows().saturating_sub(1) {
+            for col in 0..array.ncols().saturating_sub(1) {
+                let main_diagonal = array[[row, col]].checked_add(array[[row + 1, col + 1]]);
+                let anti_diagonal = array[[row, col + 1]].checked_add(array[[row + 1, col]]);
+                match (main_diagonal, anti_diagonal) {
+                    (Some(main), Some(anti)) if main <= anti => {}
+                    (Some(_), Some(_)) => {
+                        return Err(MongeMatrixError::NotMonge(MongeViolation {
+                            row,
+                            column: col,
+                        }))
+                    }
+                    _ => return Err(MongeMatrixError::Overflow),
+                }
+            }
+        }
+        Ok(MongeMatrix { array })
+    }
+
+    /// Return the underlying array.
+    pub fn as_array(&self) -> &ndarray::Array2<T> {
+        &self.array
+    }
+
+    /// Convert into the underlying array.
+    pub fn into_inner(self) -> ndarray::Array2<T> {
+        self.array
+    }
+
+    /// Add `value` to every element.
+    ///
+    /// Running time on an *m* ✕ *n* matrix: O(*mn*), including a
+    /// second pass which validates the result.
+    pub fn add_constant(&self, value: T) -> Result<Self, MongeMatrixError> {
+        self.map_checked(|_, element| element.checked_add(value))
+    }
+
+    /// Add `rows[i] + columns[j]` to the element at `(i, j)`.
+    ///
+    /// Running time on an *m* ✕ *n* matrix: O(*mn*), including a
+    /// second pass which validates the result.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to pass slices whose lengths differ from the
+    /// number of rows and columns.
+    pub fn add_potentials(&self, rows: &[T], columns: &[T]) -> Result<Self, MongeMatrixError> {
+        assert_eq!(
+            rows.len(),
+            self.array.nrows(),
+            "expected a potential per row"
+        );
+        assert_eq!(
+            columns.len(),
+            self.array.ncols(),
+            "expected a potential per column"
+        );
+        self.map_checked(|(i, j), element| element.checked_add(rows[i])?.checked_add(columns[j]))
+    }
+
+    /// Multiply every element by `factor`.
+    ///
+    /// Running time on an *m* ✕ *n* matrix: O(*mn*), including a
+    /// second pass which validates the result.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to pass a negative factor, which would turn the
+    /// matrix into an inverse Monge matrix.
+    pub fn scale(&self, factor: T) -> Result<Self, MongeMatrixError> {
+        assert!(factor >= T::ZERO, "factor must be non-negative");
+        self.map_checked(|_, element| element.checked_mul(factor))
+    }
+
+    /// Apply `f` to every element and check the result for overflow.
+    /// The Monge property is preserved by `f`, so the check can only
+    /// fail with [`MongeMatrixError::Overflow`].
+    ///
+    /// The result is validated again with [`MongeMatrix::new`], which
+    /// costs a second O(*mn*) pass over the matrix. The pass cannot be
+    /// made incremental: every element changes, and the invariant
+    /// that neighboring sums fit in `T` must be rechecked for every
+    /// 2 ✕ 2 submatrix even though the inequality itself still holds.
+    /// Building the new array is O(*mn*) anyway, so this at most
+    /// doubles the cost.
+    fn map_checked<F>(&self, f: F) -> Result<Self, MongeMatrixError>
+    where
+        F: Fn((usize, usize), T) -> Option<T>,
+    {
+        let mut elements = Vec::with_capacity(self.array.len());
+        for (index, &element) in self.array.indexed_iter() {
+            elements.push(f(index, element).ok_or(MongeMatrixError::Overflow)?);
+        }
+        let array = ndarray::Array2::from_shape_vec(self.array.dim(), elements)
+            .expect("one element per index");
+        MongeMatrix::new(array)
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: CheckedInteger> Add for &MongeMatrix<T> {
+    type Output = Result<MongeMatrix<T>, MongeMatrixError>;
+
+    /// Add two Monge matrices of the same shape.
+    ///
+    /// Running time on *m* ✕ *n* matrices: O(*mn*), including a second
+    /// pass which validates the result.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to add matrices of different shapes.
+    fn add(self, other: Self) -> Self::Output {
+        assert_eq!(
+            self.array.dim(),
+            other.array.dim(),
+            "expected matrices of the same shape"
+        );
+        self.map_checked(|index, element| element.checked_add(other.array[index]))
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: Copy> Matrix<T> for MongeMatrix<T> {
+    fn nrows(&self) -> usize {
+        self.array.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.array.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> T {
+        self.array[[row, column]]
+    }
+}
+
+/// Reason why [`monge_decompose`] failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MongeDecomposeError {
+    /// The matrix is not a Monge matrix.
+    NotMonge(MongeViolation),
+    /// The sum of two neighboring elements does not fit in the
+    /// element type.
+    Overflow,
+}
+
+impl fmt::Display for MongeDecomposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MongeDecomposeError::NotMonge(violation) => violation.fmt(f),
+            MongeDecomposeError::Overflow => write!(f, "arithmetic overflow in Monge density"),
+        }
+    }
+}
+
+impl std::error::Error for MongeDecomposeError {}
+
+/// Constructive proof that a matrix is a Monge matrix.
+///
+/// Every Monge matrix `M` can be written as
+///
+/// ```text
+/// M[i, j] = M[i, 0] + M[0, j] - M[0, 0] - P[i, j]
+/// ```
+///
+/// where `P[i, j]` is the sum of the *density* `D[i', j']` over all
+/// `i' < i` and `j' < j`, and the density
+///
+/// ```text
+/// D[i, j] = M[i, j + 1] + M[i + 1, j] - M[i, j] - M[i + 1, j + 1]
+/// ```
+///
+/// is non-negative. A certificate stores the border row and column
+/// and the non-zero density entries, and reconstructs the other
+/// entries on demand. The certificate thus implements the [`Matrix`]
+/// trait itself.
+///
+/// Use [`monge_decompose`] to create a certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MongeCertificate<T> {
+    first_column: Vec<T>,
+    first_row: Vec<T>,
+    density: Vec<(usize, usize, T)>,
+}
+
+impl<T> MongeCertificate<T>
+where
+    T: Copy,
+    Wrapping<T>: Add<Output = Wrapping<T>> + Sub<Output = Wrapping<T>>,
+{
+    /// The first column of the matrix, `M[i, 0]` for all rows `i`.
+    pub fn first_column(&self) -> &[T] {
+        &self.first_column
+    }
+
+    /// The first row of the matrix, `M[0, j]` for all columns `j`.
+    pub fn first_row(&self) -> &[T] {
+        &self.first_row
+    }
+
+    /// The non-zero density entries as `(row, column, value)`
+    /// triples in row-major order.
+    pub fn density(&self) -> &[(usize, usize, T)] {
+        &self.density
+    }
+
+    /// Return the number of non-zero density entries.
+    pub fn nnz(&self) -> usize {
+        self.density.len()
+    }
+
+    /// Reconstruct the matrix entry at `(row, column)`.
+    ///
+    /// The prefix sum of the density can be larger than any matrix
+    /// entry, so the sums wrap around. The entry itself fits in `T`,
+    /// which makes the wrapped result exact.
+    ///
+    /// Running time: O(*nnz*).
+    pub fn entry(&self, row: usize, column: usize) -> T {
+        let prefix = self
+            .density
+            .iter()
+            .take_while(|&&(i, _, _)| i < row)
+            .filter(|&&(_, j, _)| j < column)
+            .fold(Wrapping(self.first_row[0]), |sum, &(_, _, d)| {
+                sum + Wrapping(d)
+            });
+        (Wrapping(self.first_column[row]) + Wrapping(self.first_row[column]) - prefix).0
+    }
+}
+
+impl<T> Matrix<T> for MongeCertificate<T>
+where
+    T: Copy,
+    Wrapping<T>: Add<Output = Wrapping<T>> + Sub<Output = Wrapping<T>>,
+{
+    fn nrows(&self) -> usize {
+        self.first_column.len()
+    }
+    fn ncols(&self) -> usize {
+        self.first_row.len()
+    }
+    fn index(&self, row: usize, column: usize) -> T {
+        self.entry(row, column)
+    }
+}
+
+/// Decompose a Monge matrix into its density representation.
+///
+/// On success, the returned [`MongeCertificate`] is a constructive
+/// proof that `matrix` is a Monge matrix. When the density is sparse,
+/// [`MongeCertificate::density`] is also a compact description of the
+/// matrix.
+///
+/// If `matrix` is not a Monge matrix, the first violating 2 ✕ 2
+/// submatrix (in row-major order) is returned as an error. The
+/// density is computed from the sums of neighboring entries, and
+/// [`MongeDecomposeError::Overflow`] is returned if such a sum does
+/// not fit in `T`.
+///
+/// Running time on an *m* ✕ *n* matrix: O(*mn*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{monge_decompose, MongeDecomposeError, MongeViolation};
+/// use smawk::Matrix;
+///
+/// let matrix = vec![vec![3, 2, 4],
+///                   vec![2, 1, 3],
+///                   vec![4, 2, 2]];
+/// let certificate = monge_decompose(&matrix).unwrap();
+/// assert_eq!(certificate.density(), &[(1, 0, 1), (1, 1, 2)]);
+/// assert_eq!(certificate.index(2, 2), 2);
+///
+/// let matrix = vec![vec![3, 1],
+///                   vec![1, 3]];
+/// assert_eq!(monge_decompose(&matrix),
+///            Err(MongeDecomposeError::NotMonge(MongeViolation { row: 0, column: 0 })));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows or zero
+/// columns.
+pub fn monge_decompose<T, M>(matrix: &M) -> Result<MongeCertificate<T>, MongeDecomposeError>
+where
+    T: CheckedInteger + Default,
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+    M: Matrix<T>,
+{
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    assert!(m > 0 && n > 0, "cannot decompose an empty matrix");
+
+    let mut density = Vec::new();
+    for row in 0..m - 1 {
+        for col in 0..n - 1 {
+            let top_left = matrix.index(row, col);
+            let top_right = matrix.index(row, col + 1);
+            let bot_left = matrix.index(row + 1, col);
+            let bot_right = matrix.index(row + 1, col + 1);
+            if !is_monge_window(top_left, top_right, bot_left, bot_right) {
+                return Err(MongeDecomposeError::NotMonge(MongeViolation {
+                    row,
+                    column: col,
+                }));
+            }
+
+            let d = top_right
+                .checked_add(bot_left)
+                .zip(top_left.checked_add(bot_right))
+                .and_then(|(anti_diagonal, main_diagonal)| anti_diagonal.checked_sub(main_diagonal))
+                .ok_or(MongeDecomposeError::Overflow)?;
+            if d != T::ZERO {
+                density.push((row, col, d));
+            }
+        }
+    }
+
+    Ok(MongeCertificate {
+        first_column: (0..m).map(|row| matrix.index(row, 0)).collect(),
+        first_row: (0..n).map(|col| matrix.index(0, col)).collect(),
+        density,
+    })
+}
+
+/// Element which [`monge_project`] would move too far.
+///
+/// **Note: this type is only available if you enable the `ndarray`
+/// Cargo feature.**
+#[cfg(feature = "ndarray")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MongeProjectionError {
+    /// Row of the element.
+    pub row: usize,
+    /// Column of the element.
+    pub column: usize,
+}
+
+#[cfg(feature = "ndarray")]
+impl fmt::Display for MongeProjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Monge projection moves the element at ({}, {}) by more than epsilon",
+            self.row, self.column
+        )
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl std::error::Error for MongeProjectionError {}
+
+/// Nudge a floating point matrix which is nearly Monge into a Monge
+/// matrix.
+///
+/// Rounding errors can make a float matrix narrowly fail the Monge
+/// inequality, even though the exact values would satisfy it. This
+/// repairs such a matrix by moving its elements by at most `epsilon`.
+///
+/// A matrix is Monge exactly when the differences `M[i, j + 1] - M[i,
+/// j]` are non-increasing down every column. The differences which
+/// break this are replaced by their averages, which is the least
+/// squares fit of a non-increasing sequence. The rows are then summed
+/// up again from their first element and shifted to center the
+/// changes, which keeps the matrix Monge. This takes O(*mn*) time and
+/// the result is Monge up to the rounding of the sums.
+///
+/// This is a heuristic: the result is not necessarily the Monge matrix
+/// closest to `matrix`. An error is returned with the first element
+/// which moves more than `epsilon`. This happens when the violations
+/// are too large to be explained by `epsilon`, but it can also happen
+/// when many small corrections in a row add up. Elements which are NaN
+/// always give an error.
+///
+/// **Note: this function is only available if you enable the
+/// `ndarray` Cargo feature.**
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use smawk::monge::{monge_project, MongeProjectionError};
+///
+/// let matrix = arr2(&[[3.0, 1.0], [1.0, 3.0]]);
+/// assert_eq!(monge_project(&matrix, 1.0), Ok(arr2(&[[2.0, 2.0], [2.0, 2.0]])));
+/// assert_eq!(monge_project(&matrix, 0.5),
+///            Err(MongeProjectionError { row: 0, column: 0 }));
+/// ```
+#[cfg(feature = "ndarray")]
+pub fn monge_project<T>(
+    matrix: &ndarray::Array2<T>,
+    epsilon: T,
+) -> Result<ndarray::Array2<T>, MongeProjectionError>
+where
+    T: Copy
+        + PartialOrd
+        + From<f32>
+        + Add<Output = T>
+        + Sub<Output = T>
+        + std::ops::Div<Output = T>,
+{
+    let (zero, one, two) = (T::from(0.0), T::from(1.0), T::from(2.0));
+    let (m, n) = matrix.dim();
+    let mut result = matrix.clone();
+    if n < 2 {
+        return Ok(result);
+    }
+
+    // Pool adjacent violators on every column of differences. A block
+    // is the sum and the number of the differences it replaces.
+    let mut blocks: Vec<(T, T, usize)> = Vec::with_capacity(m);
+    let mut diffs = ndarray::Array2::from_elem((m, n - 1), zero);
+    for col in 0..n - 1 {
+        blocks.clear();
+        for row in 0..m {
+            let diff = matrix[(row, col + 1)] - matrix[(row, col)];
+            let (mut sum, mut count, mut len) = (diff, one, 1);
+            while let Some(&(prev_sum, prev_count, prev_len)) = blocks.last() {
+                if prev_sum / prev_count >= sum / count {
+                    break;
+                }
+                blocks.pop();
+                sum = sum + prev_sum;
+                count = count + prev_count;
+                len += prev_len;
+            }
+            blocks.push((sum, count, len));
+        }
+        let mut row = 0;
+        for &(sum, count, len) in &blocks {
+            diffs
+                .slice_mut(ndarray::s![row..row + len, col])
+                .fill(sum / count);
+            row += len;
+        }
+    }
+
+    for row in 0..m {
+        for col in 0..n - 1 {
+            result[(row, col + 1)] = result[(row, col)] + diffs[(row, col)];
+        }
+        let changes = (0..n).map(|col| result[(row, col)] - matrix[(row, col)]);
+        let (mut lowest, mut highest) = (zero, zero);
+        for change in changes {
+            if change < lowest {
+                lowest = change;
+            }
+            if change > highest {
+                highest = change;
+            }
+        }
+        let shift = (lowest + highest) / two;
+        for col in 0..n {
+            let value = result[(row, col)] - shift;
+            let change = value - matrix[(row, col)];
+            if !(change <= epsilon && zero - change <= epsilon) {
+                return Err(MongeProjectionError { row, column: col });
+            }
+            result[(row, col)] = value;
+        }
+    }
+    Ok(result)
+}
+
+/// Reason why a matrix is not a Kalmanson matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KalmansonViolation {
+    /// The matrix is not square.
+    NotSquare,
+    /// The element at `(row, column)` differs from the element at
+    /// `(column, row)`, where `row < column`.
+    NotSymmetric {
+        /// Row of the element above the diagonal.
+        row: usize,
+        /// Column of the element above the diagonal.
+        column: usize,
+    },
+    /// The indices `i < j < k < l` violate one of the two Kalmanson
+    /// inequalities.
+    Quadruple([usize; 4]),
+}
+
+impl fmt::Display for KalmansonViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KalmansonViolation::NotSquare => write!(f, "Kalmanson matrix is not square"),
+            KalmansonViolation::NotSymmetric { row, column } => write!(
+                f,
+                "Kalmanson matrix is not symmetric at ({}, {})",
+                row, column
+            ),
+            KalmansonViolation::Quadruple([i, j, k, l]) => write!(
+                f,
+                "Kalmanson inequalities violated by the indices {}, {}, {}, {}",
+                i, j, k, l
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KalmansonViolation {}
+
+/// Verify that a matrix is a Kalmanson matrix.
+///
+/// A Kalmanson matrix is a symmetric matrix where the following
+/// inequalities hold:
+///
+/// ```text
+/// M[i, j] + M[k, l] <= M[i, k] + M[j, l]  for all i < j < k < l
+/// M[i, l] + M[j, k] <= M[i, k] + M[j, l]  for all i < j < k < l
+/// ```
+///
+/// The distances between points in convex position, numbered along
+/// the boundary, form a Kalmanson matrix: the diagonals of a convex
+/// quadrilateral are longer than two opposite sides together. For a
+/// Kalmanson distance matrix, visiting the points in order is an
+/// optimal traveling salesman tour.
+///
+/// It is enough to check the inequalities for quadruples of the form
+/// `(i, i + 1, j, j + 1)` and `(0, i, i + 1, n - 1)`, so the running
+/// time is O(*n*²). The first violation found is returned, with
+/// overflow handled like in [`is_monge`].
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{verify_kalmanson, KalmansonViolation};
+///
+/// // Distances between the corners of a unit square.
+/// let square = vec![vec![0, 10, 14, 10],
+///                   vec![10, 0, 10, 14],
+///                   vec![14, 10, 0, 10],
+///                   vec![10, 14, 10, 0]];
+/// assert_eq!(verify_kalmanson(&square), Ok(()));
+///
+/// // Visiting the corners in a different order crosses the square.
+/// let crossed = vec![vec![0, 14, 10, 10],
+///                    vec![14, 0, 10, 10],
+///                    vec![10, 10, 0, 14],
+///                    vec![10, 10, 14, 0]];
+/// assert_eq!(verify_kalmanson(&crossed),
+///            Err(KalmansonViolation::Quadruple([0, 1, 2, 3])));
+/// ```
+pub fn verify_kalmanson<T: Ord + Copy + Default, M: Matrix<T>>(
+    matrix: &M,
+) -> Result<(), KalmansonViolation>
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    let n = matrix.nrows();
+    if matrix.ncols() != n {
+        return Err(KalmansonViolation::NotSquare);
+    }
+    for row in 0..n {
+        for column in row + 1..n {
+            if matrix.index(row, column) != matrix.index(column, row) {
+                return Err(KalmansonViolation::NotSymmetric { row, column });
+            }
+        }
+    }
+
+    let m = |i, j| matrix.index(i, j);
+    for i in 0..n.saturating_sub(3) {
+        for j in i + 2..n - 1 {
+            // M[i, j + 1] + M[i + 1, j] <= M[i, j] + M[i + 1, j + 1]
+            if !is_monge_window(m(i, j + 1), m(i, j), m(i + 1, j + 1), m(i + 1, j)) {
+                return Err(KalmansonViolation::Quadruple([i, i + 1, j, j + 1]));
+            }
+        }
+    }
+    for i in 1..n.saturating_sub(2) {
+        // M[0, i] + M[i + 1, n - 1] <= M[0, i + 1] + M[i, n - 1]
+        if !is_monge_window(m(0, i), m(0, i + 1), m(i, n - 1), m(i + 1, n - 1)) {
+            return Err(KalmansonViolation::Quadruple([0, i, i + 1, n - 1]));
+        }
+    }
+    Ok(())
+}
+
+/// Verify that a matrix is a Kalmanson matrix.
+///
+/// See [`verify_kalmanson`] for details.
+pub fn is_kalmanson<T: Ord + Copy + Default, M: Matrix<T>>(matrix: &M) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    verify_kalmanson(matrix).is_ok()
+}
+
+/// Matrix with its rows and columns rearranged.
+///
+/// Element `(i, j)` of the permuted matrix is element `(rows[i],
+/// columns[j])` of the wrapped matrix. Nothing is copied, the indices
+/// are translated on every access. Use this with the permutations
+/// found by [`recognize_permuted_monge`] to run SMAWK on a permuted
+/// Monge matrix, and translate the resulting indices back with
+/// [`PermutedMatrix::original_column_minima`] and
+/// [`PermutedMatrix::original_row_minima`].
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{is_monge, PermutedMatrix};
+/// use smawk::Matrix;
+///
+/// let matrix = vec![vec![0, 0], vec![1, 3]];
+/// assert!(!is_monge(&matrix));
+/// let permuted = PermutedMatrix::new(&matrix, &[1, 0], &[0, 1]);
+/// assert_eq!(permuted.index(0, 1), 3);
+/// assert!(is_monge(&permuted));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct PermutedMatrix<'a, M> {
+    matrix: &'a M,
+    rows: &'a [usize],
+    columns: &'a [usize],
+}
+
+/// Reason why [`PermutedMatrix::try_new`] rejected a permutation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermutationError {
+    /// The row permutation does not have one entry per row.
+    RowLength,
+    /// The column permutation does not have one entry per column.
+    ColumnLength,
+    /// The row permutation is out of bounds or repeats this row.
+    Row(usize),
+    /// The column permutation is out of bounds or repeats this column.
+    Column(usize),
+}
+
+impl fmt::Display for PermutationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PermutationError::RowLength => write!(f, "expected one entry per row"),
+            PermutationError::ColumnLength => write!(f, "expected one entry per column"),
+            PermutationError::Row(row) => write!(f, "row {} is not a valid permutation entry", row),
+            PermutationError::Column(column) => {
+                write!(f, "column {} is not a valid permutation entry", column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PermutationError {}
+
+/// Return the first entry which is out of bounds or repeated, or
+/// `None` if `permutation` is a permutation of `0..len`.
+fn find_invalid_entry(permutation: &[usize]) -> Option<usize> {
+    let mut seen = vec![false; permutation.len()];
+    for &entry in permutation {
+        if seen.get(entry).copied().unwrap_or(true) {
+            return Some(entry);
+        }
+        seen[entry] = true;
+    }
+    None
+}
+
+impl<'a, M> PermutedMatrix<'a, M> {
+    /// Rearrange the rows and columns of `matrix`.
+    ///
+    /// The permutations must contain every row and every column
+    /// exactly once. This is not checked, but an index outside the
+    /// matrix panics when it is accessed. Use
+    /// [`PermutedMatrix::try_new`] to check the permutations.
+    pub fn new(matrix: &'a M, rows: &'a [usize], columns: &'a [usize]) -> Self {
+        PermutedMatrix {
+            matrix,
+            rows,
+            columns,
+        }
+    }
+
+    /// Rearrange the rows and columns of `matrix`, checking that
+    /// `rows` and `columns` are permutations of the rows and columns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use smawk::monge::{PermutationError, PermutedMatrix};
+    ///
+    /// let matrix = vec![vec![0, 0], vec![1, 3]];
+    /// assert!(PermutedMatrix::try_new(&matrix, &[1, 0], &[0, 1]).is_ok());
+    /// assert_eq!(PermutedMatrix::try_new(&matrix, &[1, 1], &[0, 1]).err(),
+    ///            Some(PermutationError::Row(1)));
+    /// assert_eq!(PermutedMatrix::try_new(&matrix, &[1, 0], &[0]).err(),
+    ///            Some(PermutationError::ColumnLength));
+    /// ```
+    pub fn try_new<T: Copy>(
+        matrix: &'a M,
+        rows: &'a [usize],
+        columns: &'a [usize],
+    ) -> Result<Self, PermutationError>
+    where
+        M: Matrix<T>,
+    {
+        if rows.len() != matrix.nrows() {
+            return Err(PermutationError::RowLength);
+        }
+        if columns.len() != matrix.ncols() {
+            return Err(PermutationError::ColumnLength);
+        }
+        if let Some(row) = find_invalid_entry(rows) {
+            return Err(PermutationError::Row(row));
+        }
+        if let Some(column) = find_invalid_entry(columns) {
+            return Err(PermutationError::Column(column));
+        }
+        Ok(PermutedMatrix::new(matrix, rows, columns))
+    }
+
+    /// Translate column minima of the permuted matrix back to the
+    /// wrapped matrix.
+    ///
+    /// Entry `j` of `minima` is the permuted row of the minimum in
+    /// permuted column `j`, as returned by
+    /// [`column_minima`](crate::column_minima). The result is indexed
+    /// by the original columns and holds original rows, as if the
+    /// column minima had been computed on the wrapped matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `minima` does not have one entry per column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use smawk::monge::PermutedMatrix;
+    ///
+    /// let matrix = vec![vec![0, 0], vec![1, 3]];
+    /// let permuted = PermutedMatrix::new(&matrix, &[1, 0], &[0, 1]);
+    /// let minima = smawk::column_minima(&permuted);
+    /// assert_eq!(minima, vec![1, 1]);
+    /// assert_eq!(permuted.original_column_minima(&minima), vec![0, 0]);
+    /// ```
+    pub fn original_column_minima(&self, minima: &[usize]) -> Vec<usize> {
+        unpermute(self.rows, self.columns, minima)
+    }
+
+    /// Translate row minima of the permuted matrix back to the wrapped
+    /// matrix.
+    ///
+    /// This is the row counterpart of
+    /// [`PermutedMatrix::original_column_minima`]: the result is
+    /// indexed by the original rows and holds original columns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `minima` does not have one entry per row.
+    pub fn original_row_minima(&self, minima: &[usize]) -> Vec<usize> {
+        unpermute(self.columns, self.rows, minima)
+    }
+}
+
+/// Move `minima[i]`, translated through `values`, to position
+/// `positions[i]`.
+fn unpermute(values: &[usize], positions: &[usize], minima: &[usize]) -> Vec<usize> {
+    assert_eq!(
+        minima.len(),
+        positions.len(),
+        "expected one minimum per line"
+    );
+    let mut original = vec![0; minima.len()];
+    for (&position, &minimum) in positions.iter().zip(minima) {
+        original[position] = values[minimum];
+    }
+    original
+}
+
+impl<T: Copy, M: Matrix<T>> Matrix<T> for PermutedMatrix<'_, M> {
+    fn nrows(&self) -> usize {
+        self.rows.len()
+    }
+    fn ncols(&self) -> usize {
+        self.columns.len()
+    }
+    fn index(&self, row: usize, column: usize) -> T {
+        self.matrix.index(self.rows[row], self.columns[column])
+    }
+}
+
+/// Find permutations of the rows and columns which make a matrix
+/// Monge.
+///
+/// A permuted Monge matrix is a matrix which becomes a Monge matrix
+/// once its rows and columns are rearranged. This returns the row and
+/// column permutations if they exist, in the form expected by
+/// [`PermutedMatrix`]: position `i` of the rows holds the original
+/// row placed at `i`. Otherwise `None` is returned.
+///
+/// The permutations are not unique. Reversing both of them keeps a
+/// Monge matrix Monge, and rows or columns which differ by a constant
+/// can be swapped freely. Any valid pair is returned.
+///
+/// The algorithm tries every column as the first column. Relative to
+/// the first column `c`, the differences `M[i, j] - M[i, c]` decrease
+/// from row to row for every `j`, which orders the rows. The first row
+/// orders the columns in the same way. The permuted matrix is then
+/// checked with the Monge inequality. All sums are compared without
+/// overflowing, like in [`is_monge`].
+///
+/// Running time on an *m* ✕ *n* matrix: O(*mn*² (log *m* + log *n*)).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{is_monge, recognize_permuted_monge, PermutedMatrix};
+///
+/// let matrix = vec![vec![0, 1, 3],
+///                   vec![3, 1, 0],
+///                   vec![1, 0, 1]];
+/// assert!(!is_monge(&matrix));
+/// let (rows, columns) = recognize_permuted_monge(&matrix).unwrap();
+/// assert!(is_monge(&PermutedMatrix::new(&matrix, &rows, &columns)));
+///
+/// let matrix = vec![vec![0, 1, 0],
+///                   vec![1, 0, 0],
+///                   vec![0, 0, 1]];
+/// assert_eq!(recognize_permuted_monge(&matrix), None);
+/// ```
+pub fn recognize_permuted_monge<T, M>(matrix: &M) -> Option<(Vec<usize>, Vec<usize>)>
 where
+    T: Ord + Copy + Default,
     Wrapping<T>: Add<Output = Wrapping<T>>,
+    M: Matrix<T>,
 {
-    /// Returns `Ok(a + b)` if the computation can be done without
-    /// overflow, otherwise `Err(a + b - T::MAX - 1)` is returned.
-    fn checked_add<T: Ord + Copy>(a: Wrapping<T>, b: Wrapping<T>) -> Result<T, T>
-    where
-        Wrapping<T>: Add<Output = Wrapping<T>>,
-    {
-        let sum = a + b;
-        if sum < a {
-            Err(sum.0)
+    let (m, n) = (matrix.nrows(), matrix.ncols());
+    let at = |i, j| matrix.index(i, j);
+
+    for first in 0..n.max(1) {
+        // Rows with larger differences M[i, j] - M[i, first] come
+        // first. The differences are compared without subtracting.
+        let mut rows = (0..m).collect::<Vec<_>>();
+        rows.sort_by(|&a, &b| {
+            lexicographic(n, |j| {
+                checked_sum(at(b, j), at(a, first)).cmp(&checked_sum(at(a, j), at(b, first)))
+            })
+        });
+
+        // Likewise for the columns and the differences M[i, j] -
+        // M[top, j] to the first row.
+        let mut columns = (0..n).collect::<Vec<_>>();
+        if let Some(&top) = rows.first() {
+            columns.sort_by(|&a, &b| {
+                lexicographic(m, |k| {
+                    let i = rows[k];
+                    checked_sum(at(i, b), at(top, a)).cmp(&checked_sum(at(i, a), at(top, b)))
+                })
+            });
+        }
+
+        let permuted = PermutedMatrix::new(matrix, &rows, &columns);
+        let is_monge = (0..m.saturating_sub(1)).all(|i| {
+            (0..n.saturating_sub(1)).all(|j| {
+                is_monge_window(
+                    permuted.index(i, j),
+                    permuted.index(i, j + 1),
+                    permuted.index(i + 1, j),
+                    permuted.index(i + 1, j + 1),
+                )
+            })
+        });
+        if is_monge {
+            return Some((rows, columns));
+        }
+    }
+    None
+}
+
+/// Compare two sequences of length `len` lexicographically, where
+/// `ordering(k)` compares their elements at index `k`.
+fn lexicographic<F>(len: usize, ordering: F) -> std::cmp::Ordering
+where
+    F: FnMut(usize) -> std::cmp::Ordering,
+{
+    (0..len)
+        .map(ordering)
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Two matrices placed next to each other or on top of each other.
+///
+/// This is a view of the concatenated matrix, which is never
+/// materialized. Create it with [`monge_vstack`] or [`monge_hstack`],
+/// which check that the seam between the blocks keeps the Monge
+/// property.
+#[derive(Debug, Clone, Copy)]
+pub struct Stacked<'a, A, B> {
+    first: &'a A,
+    second: &'a B,
+    vertical: bool,
+}
+
+impl<T: Copy, A: Matrix<T>, B: Matrix<T>> Matrix<T> for Stacked<'_, A, B> {
+    fn nrows(&self) -> usize {
+        if self.vertical {
+            self.first.nrows() + self.second.nrows()
+        } else {
+            self.first.nrows()
+        }
+    }
+    fn ncols(&self) -> usize {
+        if self.vertical {
+            self.first.ncols()
+        } else {
+            self.first.ncols() + self.second.ncols()
+        }
+    }
+    fn index(&self, row: usize, column: usize) -> T {
+        if self.vertical && row >= self.first.nrows() {
+            self.second.index(row - self.first.nrows(), column)
+        } else if !self.vertical && column >= self.first.ncols() {
+            self.second.index(row, column - self.first.ncols())
         } else {
-            Ok(sum.0)
+            self.first.index(row, column)
         }
     }
+}
 
-    (0..matrix.nrows() - 1)
-        .flat_map(|row| (0..matrix.ncols() - 1).map(move |col| (row, col)))
-        .all(|(row, col)| {
-            let top_left = Wrapping(matrix.index(row, col));
-            let top_right = Wrapping(matrix.index(row, col + 1));
-            let bot_left = Wrapping(matrix.index(row + 1, col));
-            let bot_right = Wrapping(matrix.index(row + 1, col + 1));
-
-            match (
-                checked_add(top_left, bot_right),
-                checked_add(bot_left, top_right),
+/// Place `top` above `bottom` and check the seam between them.
+///
+/// The Monge property only involves adjacent rows and columns, so the
+/// concatenation of two Monge matrices is Monge if and only if the
+/// 2 ✕ 2 submatrices formed by the last row of `top` and the first
+/// row of `bottom` are Monge. Only these *n* − 1 submatrices are
+/// checked, and the first violating one is returned in the
+/// coordinates of the concatenated matrix. The blocks themselves are
+/// not checked. Use [`is_monge`] on the blocks, or on the result, to
+/// verify them in O(*mn*) time.
+///
+/// Running time: O(*n*) for blocks with *n* columns.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{monge_vstack, MongeViolation};
+/// use smawk::Matrix;
+///
+/// let top = vec![vec![1, 2, 4]];
+/// let bottom = vec![vec![2, 2, 3], vec![4, 3, 3]];
+/// let stacked = monge_vstack(&top, &bottom).unwrap();
+/// assert_eq!(stacked.index(2, 0), 4);
+/// assert_eq!(smawk::column_minima(&stacked), vec![0, 0, 1]);
+///
+/// let bottom = vec![vec![2, 4, 3]];
+/// assert_eq!(monge_vstack(&top, &bottom).err(),
+///            Some(MongeViolation { row: 0, column: 0 }));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to stack matrices with different numbers of
+/// columns.
+pub fn monge_vstack<'a, T, A, B>(
+    top: &'a A,
+    bottom: &'a B,
+) -> Result<Stacked<'a, A, B>, MongeViolation>
+where
+    T: Ord + Copy + Default,
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+    A: Matrix<T>,
+    B: Matrix<T>,
+{
+    assert_eq!(
+        top.ncols(),
+        bottom.ncols(),
+        "blocks must have the same number of columns"
+    );
+    if let (Some(row), true) = (top.nrows().checked_sub(1), bottom.nrows() > 0) {
+        for column in 0..top.ncols().saturating_sub(1) {
+            if !is_monge_window(
+                top.index(row, column),
+                top.index(row, column + 1),
+                bottom.index(0, column),
+                bottom.index(0, column + 1),
             ) {
-                (Ok(a), Ok(b)) => a <= b,   // No overflow.
-                (Err(a), Err(b)) => a <= b, // Double overflow.
-                (Ok(_), Err(_)) => true,    // Anti-diagonal overflow.
-                (Err(_), Ok(_)) => false,   // Main diagonal overflow.
+                return Err(MongeViolation { row, column });
             }
-        })
+        }
+    }
+    Ok(Stacked {
+        first: top,
+        second: bottom,
+        vertical: true,
+    })
+}
+
+/// Place `left` next to `right` and check the seam between them.
+///
+/// This is the counterpart of [`monge_vstack`] for columns: only the
+/// *m* − 1 submatrices formed by the last column of `left` and the
+/// first column of `right` are checked.
+///
+/// Running time: O(*m*) for blocks with *m* rows.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{monge_hstack, MongeViolation};
+///
+/// let left = vec![vec![1], vec![2], vec![4]];
+/// let right = vec![vec![2, 4], vec![2, 3], vec![3, 3]];
+/// let stacked = monge_hstack(&left, &right).unwrap();
+/// assert_eq!(smawk::row_minima(&stacked), vec![0, 0, 1]);
+///
+/// let right = vec![vec![2], vec![4], vec![3]];
+/// assert_eq!(monge_hstack(&left, &right).err(),
+///            Some(MongeViolation { row: 0, column: 0 }));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to stack matrices with different numbers of rows.
+pub fn monge_hstack<'a, T, A, B>(
+    left: &'a A,
+    right: &'a B,
+) -> Result<Stacked<'a, A, B>, MongeViolation>
+where
+    T: Ord + Copy + Default,
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+    A: Matrix<T>,
+    B: Matrix<T>,
+{
+    assert_eq!(
+        left.nrows(),
+        right.nrows(),
+        "blocks must have the same number of rows"
+    );
+    if let (Some(column), true) = (left.ncols().checked_sub(1), right.ncols() > 0) {
+        for row in 0..left.nrows().saturating_sub(1) {
+            if !is_monge_window(
+                left.index(row, column),
+                right.index(row, 0),
+                left.index(row + 1, column),
+                right.index(row + 1, 0),
+            ) {
+                return Err(MongeViolation { row, column });
+            }
+        }
+    }
+    Ok(Stacked {
+        first: left,
+        second: right,
+        vertical: false,
+    })
+}
+
+/// Answer submatrix minimum queries on a Monge matrix.
+///
+/// A query asks for the smallest element in a contiguous range of
+/// rows and columns. The structure keeps a hierarchy of halves of the
+/// rows, and a similar hierarchy of halves of the columns. For each
+/// range of rows in the hierarchy, the minima of all columns form a
+/// staircase with at most one step per row, since the matrix is
+/// Monge. Only the steps are stored, so an *m* ✕ *n* matrix needs
+/// O(*m* log *m* + *n* log *n*) space in addition to the matrix
+/// itself, which must remain available for the queries.
+///
+/// Building the structure evaluates O((*m* log *m* + *n* log *n*)
+/// log(*m* + *n*)) elements and takes O(*m* log *m* log² *n* +
+/// *n* log *n* log *m*) time. A query is answered in O(log *m* (log
+/// *m* + log² *n*)) time, which is O(log³ *n*) for a square matrix.
+/// Use [`ColumnMinimaOracle`](crate::ColumnMinimaOracle) when only
+/// whole columns are queried.
+///
+/// Ties are broken in favor of the smallest row index and then the
+/// smallest column index. The answers are only correct when the
+/// matrix is Monge.
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::MongeRmq;
+/// let matrix = vec![vec![4, 2, 4, 3],
+///                   vec![5, 3, 5, 3],
+///                   vec![5, 3, 3, 1]];
+/// let rmq = MongeRmq::build(&matrix);
+/// assert_eq!(rmq.query(0..3, 0..4), (2, 3, 1));
+/// assert_eq!(rmq.query(0..2, 2..4), (0, 3, 3));
+/// assert_eq!(rmq.query(1..3, 0..3), (1, 1, 3));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MongeRmq<'a, T, M> {
+    matrix: &'a M,
+    rows: Staircases,
+    columns: Staircases,
+    /// For every range of rows, a segment tree over the minima of the
+    /// steps of its staircase.
+    steps: Vec<Vec<Candidate<T>>>,
+}
+
+/// An element ordered by value, row and column.
+type Candidate<T> = (T, usize, usize);
+
+impl<'a, T: Ord + Copy, M: Matrix<T>> MongeRmq<'a, T, M> {
+    /// Build the query structure for a Monge matrix.
+    pub fn build(matrix: &'a M) -> Self {
+        let (m, n) = (matrix.nrows(), matrix.ncols());
+        let columns = Staircases::new(n, m, &|col, row| matrix.index(row, col));
+        let rows = Staircases::new(m, n, &|row, col| matrix.index(row, col));
+        let mut rmq = MongeRmq {
+            matrix,
+            rows,
+            columns,
+            steps: Vec::new(),
+        };
+        rmq.steps = rmq
+            .rows
+            .nodes
+            .iter()
+            .map(|staircase| {
+                let leaves = (0..staircase.len())
+                    .map(|i| {
+                        let (start, row) = staircase[i];
+                        let end = staircase.get(i + 1).map_or(n, |&(end, _)| end);
+                        rmq.row_minimum(row, start..end)
+                    })
+                    .collect::<Vec<_>>();
+                // The leaves go last, the first element is unused.
+                let mut tree = leaves.clone();
+                tree.extend(leaves);
+                for i in (1..staircase.len()).rev() {
+                    tree[i] = std::cmp::min(tree[2 * i], tree[2 * i + 1]);
+                }
+                tree
+            })
+            .collect();
+        rmq
+    }
+
+    /// Return the row, column and value of the smallest element in
+    /// the submatrix given by `rows` and `cols`.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to query an empty range or a range which is
+    /// out of bounds.
+    pub fn query(
+        &self,
+        rows: std::ops::Range<usize>,
+        cols: std::ops::Range<usize>,
+    ) -> (usize, usize, T) {
+        assert!(
+            rows.start < rows.end && rows.end <= self.matrix.nrows(),
+            "row range {:?} is empty or out of bounds",
+            rows
+        );
+        assert!(
+            cols.start < cols.end && cols.end <= self.matrix.ncols(),
+            "column range {:?} is empty or out of bounds",
+            cols
+        );
+        let mut best = None;
+        self.rows.cover(rows, &mut |k| {
+            let staircase = &self.rows.nodes[k];
+            let first = Staircases::step(staircase, cols.start);
+            let last = Staircases::step(staircase, cols.end - 1);
+            if first == last {
+                keep_min(
+                    &mut best,
+                    self.row_minimum(staircase[first].1, cols.clone()),
+                );
+                return;
+            }
+            // The first and last steps may be cut by the query, the
+            // steps in between are covered completely.
+            let first_end = staircase[first + 1].0;
+            keep_min(
+                &mut best,
+                self.row_minimum(staircase[first].1, cols.start..first_end),
+            );
+            let last_start = staircase[last].0;
+            keep_min(
+                &mut best,
+                self.row_minimum(staircase[last].1, last_start..cols.end),
+            );
+            let tree = &self.steps[k];
+            let (mut lo, mut hi) = (first + 1 + staircase.len(), last + staircase.len());
+            while lo < hi {
+                if lo % 2 == 1 {
+                    keep_min(&mut best, tree[lo]);
+                    lo += 1;
+                }
+                if hi % 2 == 1 {
+                    hi -= 1;
+                    keep_min(&mut best, tree[hi]);
+                }
+                lo /= 2;
+                hi /= 2;
+            }
+        });
+        let (value, row, col) = best.expect("query ranges are not empty");
+        (row, col, value)
+    }
+
+    /// Find the smallest element in a range of a single row.
+    fn row_minimum(&self, row: usize, cols: std::ops::Range<usize>) -> Candidate<T> {
+        let mut best = None;
+        self.columns.cover(cols, &mut |k| {
+            let col = self.columns.nodes[k][Staircases::step(&self.columns.nodes[k], row)].1;
+            keep_min(&mut best, (self.matrix.index(row, col), row, col));
+        });
+        best.expect("column range is not empty")
+    }
+}
+
+/// Replace `best` with `candidate` if it is smaller.
+fn keep_min<T: Ord>(best: &mut Option<T>, candidate: T) {
+    if best.as_ref().is_none_or(|best| candidate < *best) {
+        *best = Some(candidate);
+    }
+}
+
+/// Staircases of minima for a hierarchy of halves along one axis of a
+/// Monge matrix.
+///
+/// Node 1 covers all lines along the axis, and the children of node
+/// `k` are nodes `2k` and `2k + 1`, which cover the first and second
+/// half of its lines. For every position along the other axis, the
+/// smallest element among the lines of a node is in a line which is
+/// non-decreasing with the position. A node stores this as steps
+/// `(start, line)` sorted by position.
+#[derive(Debug, Clone)]
+struct Staircases {
+    len: usize,
+    nodes: Vec<Vec<(usize, usize)>>,
+}
+
+impl Staircases {
+    /// Build the staircases for `len` lines of `other` elements each,
+    /// where `value(line, position)` is an element.
+    fn new<T: Ord>(len: usize, other: usize, value: &impl Fn(usize, usize) -> T) -> Self {
+        let mut staircases = Staircases {
+            len,
+            nodes: vec![Vec::new(); 2 * len.next_power_of_two()],
+        };
+        if len > 0 && other > 0 {
+            staircases.build(1, 0..len, other, value);
+        }
+        staircases
+    }
+
+    fn build<T: Ord>(
+        &mut self,
+        k: usize,
+        lines: std::ops::Range<usize>,
+        other: usize,
+        value: &impl Fn(usize, usize) -> T,
+    ) {
+        if lines.len() == 1 {
+            self.nodes[k] = vec![(0, lines.start)];
+            return;
+        }
+        let mid = lines.start + lines.len() / 2;
+        self.build(2 * k, lines.start..mid, other, value);
+        self.build(2 * k + 1, mid..lines.end, other, value);
+        let (first, second) = (&self.nodes[2 * k], &self.nodes[2 * k + 1]);
+        let line = |steps: &[(usize, usize)], pos| steps[Self::step(steps, pos)].1;
+
+        // The second half wins from the first position where it has a
+        // strictly smaller element, ties go to the first half.
+        let (mut lo, mut hi) = (0, other);
+        while lo < hi {
+            let pos = lo + (hi - lo) / 2;
+            if value(line(second, pos), pos) < value(line(first, pos), pos) {
+                hi = pos;
+            } else {
+                lo = pos + 1;
+            }
+        }
+        let mut steps = first
+            .iter()
+            .copied()
+            .take_while(|&(start, _)| start < lo)
+            .collect::<Vec<_>>();
+        if lo < other {
+            let step = Self::step(second, lo);
+            steps.push((lo, second[step].1));
+            steps.extend_from_slice(&second[step + 1..]);
+        }
+        self.nodes[k] = steps;
+    }
+
+    /// Return the index of the step covering position `pos`.
+    fn step(steps: &[(usize, usize)], pos: usize) -> usize {
+        steps.partition_point(|&(start, _)| start <= pos) - 1
+    }
+
+    /// Call `visit` with the nodes which together cover exactly the
+    /// lines in `range`, from first to last.
+    fn cover(&self, range: std::ops::Range<usize>, visit: &mut impl FnMut(usize)) {
+        self.cover_node(1, 0..self.len, &range, visit);
+    }
+
+    fn cover_node(
+        &self,
+        k: usize,
+        lines: std::ops::Range<usize>,
+        range: &std::ops::Range<usize>,
+        visit: &mut impl FnMut(usize),
+    ) {
+        if range.end <= lines.start || lines.end <= range.start {
+            return;
+        }
+        if range.start <= lines.start && lines.end <= range.end {
+            visit(k);
+            return;
+        }
+        let mid = lines.start + lines.len() / 2;
+        self.cover_node(2 * k, lines.start..mid, range, visit);
+        self.cover_node(2 * k + 1, mid..lines.end, range, visit);
+    }
+}
+
+/// Column minima of a Monge matrix which change with the matrix.
+///
+/// The structure owns the matrix and its column minima. The minima
+/// form a staircase: the row of the minimum is non-decreasing from
+/// left to right. The matrix can be changed one element at a time
+/// with [`DynamicMinima::update_entry`], or one row at a time with
+/// [`DynamicMinima::update_row`]. Both updates check the 2 ✕ 2
+/// submatrices around the changed elements. This is enough to keep
+/// the whole matrix Monge. An update which would break the Monge
+/// property is rejected and leaves the matrix unchanged.
+///
+/// After an update, only the minima which can change are repaired.
+/// The staircase bounds the rows which are searched. Updating an
+/// element in column `j` of an *m* ✕ *n* matrix only scans the rows
+/// between the minima of columns `j - 1` and `j + 1`. Updating a row
+/// takes O(*n*) time to check it against its neighbors. The columns
+/// with their minimum in the old row are then searched again with
+/// SMAWK, using only the rows between the neighboring minima.
+///
+/// Ties are broken in favor of the smallest row index, which gives
+/// the same answers as [`column_minima`](crate::column_minima).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::monge::{DynamicMinima, MongeViolation};
+/// let mut minima = DynamicMinima::new(vec![vec![4, 2, 4, 3],
+///                                          vec![5, 3, 5, 3],
+///                                          vec![5, 3, 3, 1]]).unwrap();
+/// assert_eq!(minima.column_minima(), &[0, 0, 2, 2]);
+///
+/// minima.update_entry(2, 2, 4).unwrap();
+/// assert_eq!(minima.column_minima(), &[0, 0, 0, 2]);
+/// minima.update_row(2, &[6, 4, 3, 1]).unwrap();
+/// assert_eq!(minima.column_minima(), &[0, 0, 2, 2]);
+///
+/// // The last column is too small compared to the second last.
+/// assert_eq!(minima.update_entry(0, 3, 0),
+///            Err(MongeViolation { row: 0, column: 2 }));
+/// assert_eq!(minima.as_matrix()[0], vec![4, 2, 4, 3]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicMinima<T> {
+    matrix: Vec<Vec<T>>,
+    minima: Vec<usize>,
+}
+
+impl<T: Ord + Copy + Default> DynamicMinima<T>
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    /// Check that `matrix` is a Monge matrix and compute its column
+    /// minima.
+    ///
+    /// The first violating 2 ✕ 2 submatrix in row-major order is
+    /// returned as an error.
+    ///
+    /// Running time on an *m* ✕ *n* matrix: O(*mn*).
+    ///
+    /// # Panics
+    ///
+    /// It is an error to pass a matrix with zero rows or with rows of
+    /// different lengths.
+    pub fn new(matrix: Vec<Vec<T>>) -> Result<Self, MongeViolation> {
+        assert!(!matrix.is_empty(), "expected at least one row");
+        let ncols = matrix[0].len();
+        assert!(
+            matrix.iter().all(|row| row.len() == ncols),
+            "expected rows of the same length"
+        );
+        let element = |i: usize, j: usize| matrix[i][j];
+        for row in 0..matrix.len() - 1 {
+            for column in 0..ncols.saturating_sub(1) {
+                if !is_monge_window_at(row, column, &element) {
+                    return Err(MongeViolation { row, column });
+                }
+            }
+        }
+        let minima = crate::column_minima(&matrix);
+        Ok(DynamicMinima { matrix, minima })
+    }
+
+    /// Return the row of the minimum in every column.
+    pub fn column_minima(&self) -> &[usize] {
+        &self.minima
+    }
+
+    /// Return the current matrix.
+    pub fn as_matrix(&self) -> &Vec<Vec<T>> {
+        &self.matrix
+    }
+
+    /// Convert into the current matrix.
+    pub fn into_inner(self) -> Vec<Vec<T>> {
+        self.matrix
+    }
+
+    /// Replace the element at `(row, column)` with `value`.
+    ///
+    /// The up to four 2 ✕ 2 submatrices containing the element are
+    /// checked first. The first violating submatrix in row-major
+    /// order is returned as an error, and the matrix is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to update an element which is out of bounds.
+    pub fn update_entry(
+        &mut self,
+        row: usize,
+        column: usize,
+        value: T,
+    ) -> Result<(), MongeViolation> {
+        let (m, n) = (self.matrix.len(), self.matrix[0].len());
+        assert!(
+            row < m && column < n,
+            "element ({}, {}) out of bounds",
+            row,
+            column
+        );
+        let element = |i: usize, j: usize| {
+            if (i, j) == (row, column) {
+                value
+            } else {
+                self.matrix[i][j]
+            }
+        };
+        for i in neighbors(row, m) {
+            for j in neighbors(column, n) {
+                if !is_monge_window_at(i, j, &element) {
+                    return Err(MongeViolation { row: i, column: j });
+                }
+            }
+        }
+        self.matrix[row][column] = value;
+
+        // The other columns are unchanged, so are their minima. They
+        // bound the minimum of this column.
+        let first = column.checked_sub(1).map_or(0, |j| self.minima[j]);
+        let last = self.minima.get(column + 1).copied().unwrap_or(m - 1);
+        if (first..=last).contains(&row) {
+            self.minima[column] = (first..=last)
+                .min_by_key(|&i| self.matrix[i][column])
+                .expect("rows between neighboring minima");
+        }
+        Ok(())
+    }
+
+    /// Replace the elements of `row` with `values`.
+    ///
+    /// The 2 ✕ 2 submatrices between the row and its neighbors are
+    /// checked first. The first violating submatrix in row-major order
+    /// is returned as an error, and the matrix is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to update a row which is out of bounds, or to
+    /// pass a number of values different from the number of columns.
+    pub fn update_row(&mut self, row: usize, values: &[T]) -> Result<(), MongeViolation> {
+        let (m, n) = (self.matrix.len(), self.matrix[0].len());
+        assert!(row < m, "row {} out of bounds", row);
+        assert_eq!(values.len(), n, "expected one value per column");
+        let element = |i: usize, j: usize| {
+            if i == row {
+                values[j]
+            } else {
+                self.matrix[i][j]
+            }
+        };
+        for i in neighbors(row, m) {
+            for j in 0..n.saturating_sub(1) {
+                if !is_monge_window_at(i, j, &element) {
+                    return Err(MongeViolation { row: i, column: j });
+                }
+            }
+        }
+        self.matrix[row].copy_from_slice(values);
+
+        // The smallest element outside of `row` is unchanged, except in
+        // the run of columns which had their minimum in `row`. The
+        // minima of the matrix without `row` are still a staircase, so
+        // the neighbors of the run bound the rows to search.
+        let mut others = self
+            .minima
+            .iter()
+            .map(|&i| (i != row).then_some(i))
+            .collect::<Vec<_>>();
+        if let (Some(start), true) = (self.minima.iter().position(|&i| i == row), m > 1) {
+            let end = start
+                + self.minima[start..]
+                    .iter()
+                    .take_while(|&&i| i == row)
+                    .count();
+            let first = start.checked_sub(1).map_or(0, |j| self.minima[j]);
+            let last = self.minima.get(end).copied().unwrap_or(m - 1);
+            let rows = (first..=last).filter(|&i| i != row).collect::<Vec<_>>();
+            let matrix = &self.matrix;
+            let runners_up = crate::column_minima_by(
+                rows.len(),
+                end - start,
+                |k, j| matrix[rows[k]][start + j],
+                T::cmp,
+            );
+            for (j, (k, _)) in runners_up.into_iter().enumerate() {
+                others[start + j] = Some(rows[k]);
+            }
+        }
+        for (j, other) in others.into_iter().enumerate() {
+            self.minima[j] = match other {
+                Some(i) if (self.matrix[i][j], i) < (values[j], row) => i,
+                _ => row,
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Return the top rows, or left columns, of the 2 ✕ 2 submatrices
+/// containing `index` along an axis of length `len`.
+fn neighbors(index: usize, len: usize) -> std::ops::Range<usize> {
+    index.saturating_sub(1)..std::cmp::min(index + 1, len - 1)
+}
+
+/// Check the Monge inequality on the 2 ✕ 2 submatrix at
+/// `(row, column)`, reading the elements with `element`.
+fn is_monge_window_at<T: Ord + Copy + Default>(
+    row: usize,
+    column: usize,
+    element: &impl Fn(usize, usize) -> T,
+) -> bool
+where
+    Wrapping<T>: Add<Output = Wrapping<T>>,
+{
+    is_monge_window(
+        element(row, column),
+        element(row, column + 1),
+        element(row + 1, column),
+        element(row + 1, column + 1),
+    )
 }
 
 #[cfg(test)]
@@ -75,6 +2846,60 @@ mod tests {
         assert!(is_monge(&matrix));
     }
 
+    #[test]
+    fn convex_sequences() {
+        assert!(is_convex_sequence::<i32>(&[]));
+        assert!(is_convex_sequence(&[5]));
+        assert!(is_convex_sequence(&[5, -3]));
+        assert!(is_convex_sequence(&[3, 1, 0, 0, 1, 3, 6]));
+        assert!(is_convex_sequence(&[1, 2, 3, 4]));
+        assert!(!is_convex_sequence(&[0, 2, 3, 4]));
+        assert!(!is_convex_sequence(&[3, 1, 0, 0, 1, 3, 4]));
+    }
+
+    #[test]
+    fn convex_sequence_handles_overflow() {
+        assert!(is_convex_sequence(&[250u8, 120, 0, 0, 130]));
+        assert!(!is_convex_sequence(&[250u8, 250, 250, 120]));
+        assert!(is_convex_sequence(&[i32::MAX, 0, i32::MAX]));
+        assert!(!is_convex_sequence(&[i32::MIN, i32::MAX, i32::MIN]));
+    }
+
+    #[test]
+    fn convex_check_on_minima() {
+        // The envelope of two parabolas has a kink where they cross.
+        let matrix = vec![
+            vec![0, 1, 4, 9, 16, 25], //
+            vec![9, 4, 1, 0, 1, 4],
+        ];
+        assert_eq!(
+            column_minima_values_convex_check(&matrix),
+            (vec![0, 1, 1, 0, 1, 4], false)
+        );
+    }
+
+    #[test]
+    fn convex_check_on_dp_costs() {
+        // The optimal cost of partitioning a sequence into k groups
+        // with a Monge cost is convex in k.
+        let weights = [3u64, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5];
+        let mut prefix = vec![0];
+        for w in weights {
+            prefix.push(prefix[prefix.len() - 1] + w);
+        }
+        let cost = |k: usize, j: usize| (prefix[j] - prefix[k]).pow(2);
+        let mut layer = vec![u64::MAX / 4; weights.len() + 1];
+        layer[0] = 0;
+        let mut costs = Vec::new();
+        for _ in 0..weights.len() {
+            let next = crate::dp::divide_and_conquer(&layer, cost);
+            layer = next.into_iter().map(|(_, value)| value).collect();
+            costs.push(layer[weights.len()]);
+        }
+        assert!(is_convex_sequence(&costs), "not convex: {:?}", costs);
+        assert!(!is_convex_sequence(&[costs[1], costs[0], costs[2]]));
+    }
+
     #[test]
     fn monge_constant_rows() {
         let matrix = vec![
@@ -118,4 +2943,122 @@ mod tests {
         ];
         assert!(is_monge(&matrix));
     }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn monge_3d_handles_overflow() {
+        let array = ndarray::Array3::<u8>::from_elem((3, 3, 3), 200);
+        assert!(is_monge_3d(&array));
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn monge_3d_single_orientation_violations() {
+        // A function of two axes is Monge in the two planes containing
+        // the third axis, so only the plane of the two axes can fail.
+        let non_monge = [[0, 0, 0], [0, 0, 0], [0, 0, 1]];
+        let array = ndarray::Array3::from_shape_fn((3, 3, 3), |(_, j, k)| non_monge[j][k]);
+        assert_eq!(
+            verify_monge_3d(&array),
+            Err(MongeViolation3d {
+                axes: (1, 2),
+                index: [0, 1, 1]
+            })
+        );
+        let array = ndarray::Array3::from_shape_fn((3, 3, 3), |(i, _, k)| non_monge[i][k]);
+        assert_eq!(
+            verify_monge_3d(&array),
+            Err(MongeViolation3d {
+                axes: (0, 2),
+                index: [1, 0, 1]
+            })
+        );
+        let array = ndarray::Array3::from_shape_fn((3, 3, 3), |(i, j, _)| non_monge[i][j]);
+        assert_eq!(
+            verify_monge_3d(&array),
+            Err(MongeViolation3d {
+                axes: (0, 1),
+                index: [1, 1, 0]
+            })
+        );
+        let monge = [[0, 1, 1], [0, 0, 0], [0, 0, 0]];
+        let array = ndarray::Array3::from_shape_fn((3, 3, 3), |(i, j, _)| monge[i][j]);
+        assert!(is_monge_3d(&array));
+    }
+
+    #[test]
+    fn monge_decompose_round_trip() {
+        let matrix = vec![
+            vec![10, 10, 42, 42, 42],
+            vec![10, 10, 42, 42, 42],
+            vec![5, 5, 10, 10, 10],
+            vec![5, 5, 10, 10, 9],
+        ];
+        let certificate = monge_decompose(&matrix).unwrap();
+        assert_eq!(certificate.density(), &[(1, 1, 27), (2, 3, 1)]);
+        assert_eq!(certificate.nnz(), 2);
+        for (row, values) in matrix.iter().enumerate() {
+            for (col, &value) in values.iter().enumerate() {
+                assert_eq!(certificate.entry(row, col), value);
+            }
+        }
+    }
+
+    #[test]
+    fn monge_decompose_single_row() {
+        let matrix = vec![vec![4u8, 2, 7]];
+        let certificate = monge_decompose(&matrix).unwrap();
+        assert_eq!(certificate.nnz(), 0);
+        assert_eq!(certificate.first_row(), &[4, 2, 7]);
+        assert_eq!(certificate.entry(0, 2), 7);
+    }
+
+    #[test]
+    fn monge_decompose_rejects_non_monge() {
+        let matrix = vec![
+            vec![1, 2, 3], //
+            vec![2, 3, 4],
+            vec![3, 4, 6],
+        ];
+        assert_eq!(
+            monge_decompose(&matrix),
+            Err(MongeDecomposeError::NotMonge(MongeViolation {
+                row: 1,
+                column: 1
+            }))
+        );
+    }
+
+    #[test]
+    fn monge_decompose_large_density_u8() {
+        // Computing the prefix sum at (2, 2) from its neighbors adds
+        // 254 + 254, which does not fit in u8, even though every
+        // entry and every sum of neighboring entries does.
+        let matrix = vec![
+            vec![0u8, 127, 127], //
+            vec![127, 0, 0],
+            vec![127, 0, 0],
+        ];
+        let certificate = monge_decompose(&matrix).unwrap();
+        assert_eq!(certificate.density(), &[(0, 0, 254)]);
+        for (row, values) in matrix.iter().enumerate() {
+            for (col, &value) in values.iter().enumerate() {
+                assert_eq!(certificate.entry(row, col), value);
+            }
+        }
+    }
+
+    #[test]
+    fn monge_decompose_overflow() {
+        let matrix = vec![
+            vec![0u8, 200], //
+            vec![200, 0],
+        ];
+        assert_eq!(monge_decompose(&matrix), Err(MongeDecomposeError::Overflow));
+        let matrix = vec![
+            vec![i8::MIN, i8::MAX], //
+            vec![i8::MAX, i8::MIN],
+        ];
+        assert_eq!(monge_decompose(&matrix), Err(MongeDecomposeError::Overflow));
+    }
 }
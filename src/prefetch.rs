@@ -0,0 +1,220 @@
+//! Online minima for matrices whose elements are slow to evaluate.
+//!
+//! When evaluating a matrix element takes milliseconds, for example
+//! because it queries another service, [`online_column_minima`]
+//! spends nearly all of its time waiting for elements one at a time.
+//! Many of these elements are known a step ahead: the algorithm
+//! evaluates the square submatrices of new tentative minima in
+//! batches, and the following steps need the diagonal element and the
+//! element in the current tentative column.
+//!
+//! A [`PrefetchingMatrix`] uses this to evaluate elements on a thread
+//! pool. The online algorithm hints which elements it is about to
+//! need, and these are evaluated concurrently and cached. Elements
+//! which were not hinted are evaluated synchronously on the calling
+//! thread, just like in the serial algorithm.
+//!
+//! **Note: this module is only available if you enable the `rayon`
+//! Cargo feature.**
+//!
+//! [`online_column_minima`]: crate::online_column_minima
+
+use rayon::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Online matrix which evaluates its elements on a thread pool.
+///
+/// The wrapped function has the same signature as the `matrix`
+/// argument of [`online_column_minima`], but it must be `Sync` since
+/// it is called from several threads at once.
+///
+/// Hinted elements are cached under their `(i, j)` position and the
+/// cache only holds the elements hinted for the current step. The
+/// result is therefore the same as for the serial algorithm, provided
+/// that element `(i, j)` only depends on the finished minima of
+/// columns up to `i`. This is the usual case, such as when the
+/// function looks up `result[i]`.
+///
+/// Speculation costs at most one extra evaluation per column: the
+/// element in the tentative column is wasted when the diagonal element
+/// turns out to be a new minimum. Since the serial algorithm makes at
+/// least one evaluation per column, the number of evaluations is at
+/// most twice that of [`online_column_minima`].
+///
+/// [`online_column_minima`]: crate::online_column_minima
+pub struct PrefetchingMatrix<M> {
+    matrix: M,
+    pool: rayon::ThreadPool,
+}
+
+impl<M> PrefetchingMatrix<M> {
+    /// Wrap `matrix` and evaluate its elements on a new thread pool
+    /// with `threads` threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread pool cannot be created.
+    pub fn new(matrix: M, threads: usize) -> Self {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed to build thread pool");
+        PrefetchingMatrix { matrix, pool }
+    }
+
+    /// Compute upper-right column minima in O(*n*) time.
+    ///
+    /// This computes the same minima as [`online_column_minima`] with
+    /// the wrapped function as the matrix, see the [type-level
+    /// documentation](PrefetchingMatrix) for the requirements on it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use smawk::prefetch::PrefetchingMatrix;
+    ///
+    /// let matrix = vec![vec![0, 2, 4],
+    ///                   vec![0, 0, 1],
+    ///                   vec![0, 0, 0]];
+    /// let lookup = |_: &[(usize, i32)], i: usize, j: usize| matrix[i][j];
+    /// let prefetching = PrefetchingMatrix::new(lookup, 2);
+    /// assert_eq!(prefetching.online_column_minima(0, 3),
+    ///            vec![(0, 0), (0, 2), (1, 1)]);
+    /// ```
+    ///
+    /// [`online_column_minima`]: crate::online_column_minima
+    pub fn online_column_minima<T>(&self, initial: T, size: usize) -> Vec<(usize, T)>
+    where
+        T: Copy + PartialOrd + Send + Sync,
+        M: Fn(&[(usize, T)], usize, usize) -> T + Sync,
+    {
+        let cache = RefCell::new(HashMap::new());
+        crate::online_inner(
+            initial,
+            size,
+            |result: &[(usize, T)], queries: &[(usize, usize)], out: &mut [T]| {
+                let cache = cache.borrow();
+                let mut misses = Vec::new();
+                for (k, query) in queries.iter().enumerate() {
+                    match cache.get(query) {
+                        Some(&value) => out[k] = value,
+                        None => misses.push(k),
+                    }
+                }
+                if let [k] = misses[..] {
+                    let (i, j) = queries[k];
+                    out[k] = (self.matrix)(result, i, j);
+                } else if !misses.is_empty() {
+                    let values = self.evaluate(result, misses.iter().map(|&k| queries[k]));
+                    for (k, value) in misses.into_iter().zip(values) {
+                        out[k] = value;
+                    }
+                }
+            },
+            &Prefetch {
+                owner: self,
+                cache: &cache,
+            },
+            &crate::Infallible,
+        )
+        .expect("allocation is infallible")
+    }
+
+    /// Evaluate the elements at `positions` on the thread pool.
+    fn evaluate<T>(
+        &self,
+        result: &[(usize, T)],
+        positions: impl Iterator<Item = (usize, usize)>,
+    ) -> Vec<T>
+    where
+        T: Copy + Send + Sync,
+        M: Fn(&[(usize, T)], usize, usize) -> T + Sync,
+    {
+        let positions = positions.collect::<Vec<_>>();
+        self.pool.install(|| {
+            positions
+                .par_iter()
+                .map(|&(i, j)| (self.matrix)(result, i, j))
+                .collect()
+        })
+    }
+}
+
+/// Online hooks which evaluate the hinted elements of a step on the
+/// thread pool of `owner` and cache them.
+struct Prefetch<'a, M, T> {
+    owner: &'a PrefetchingMatrix<M>,
+    cache: &'a RefCell<HashMap<(usize, usize), T>>,
+}
+
+impl<M, T> crate::OnlineHooks<T> for Prefetch<'_, M, T>
+where
+    T: Copy + Send + Sync,
+    M: Fn(&[(usize, T)], usize, usize) -> T + Sync,
+{
+    fn prefetch(&self, result: &[(usize, T)], queries: &[(usize, usize)]) {
+        let values = self.owner.evaluate(result, queries.iter().copied());
+        let mut cache = self.cache.borrow_mut();
+        cache.clear();
+        cache.extend(queries.iter().copied().zip(values));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    /// Line breaking cost of a segment from word `i` to word `j`.
+    fn cost(result: &[(usize, i64)], i: usize, j: usize) -> i64 {
+        let offset = |k: usize| (3 * k + k % 4) as i64;
+        let width = offset(j) - offset(i);
+        let gap = 20 - width;
+        result[i].1 + if gap < 0 { 100 * -gap } else { gap * gap }
+    }
+
+    #[test]
+    fn matches_online_column_minima() {
+        for size in 0..50 {
+            let prefetching = PrefetchingMatrix::new(cost, 3);
+            assert_eq!(
+                prefetching.online_column_minima(0, size),
+                crate::online_column_minima(0, size, cost),
+                "size: {}",
+                size
+            );
+        }
+    }
+
+    #[test]
+    fn slow_matrix_is_faster() {
+        let size = 60;
+        let evaluations = AtomicUsize::new(0);
+        let slow = |result: &[(usize, i64)], i, j| {
+            evaluations.fetch_add(1, Ordering::Relaxed);
+            std::thread::sleep(Duration::from_millis(1));
+            cost(result, i, j)
+        };
+
+        let start = Instant::now();
+        let expected = crate::online_column_minima(0, size, slow);
+        let serial_time = start.elapsed();
+        let serial_evaluations = evaluations.swap(0, Ordering::Relaxed);
+
+        let prefetching = PrefetchingMatrix::new(slow, 4);
+        let start = Instant::now();
+        assert_eq!(prefetching.online_column_minima(0, size), expected);
+        let prefetch_time = start.elapsed();
+        let prefetch_evaluations = evaluations.load(Ordering::Relaxed);
+
+        assert!(
+            prefetch_time < serial_time,
+            "{:?} vs {:?}",
+            prefetch_time,
+            serial_time
+        );
+        assert!(prefetch_evaluations <= 2 * serial_evaluations);
+    }
+}
@@ -15,6 +15,9 @@ use ndarray::{s, Array2, ArrayView2, Axis};
 ///
 /// Running time on an *m* ✕ *n* matrix: O(*m* + *n* log *m*).
 ///
+/// The recursion only needs the matrix to be monotone, see
+/// [`is_monotone_rows`](crate::monge::is_monotone_rows).
+///
 /// # Examples
 ///
 /// ```
@@ -60,6 +63,63 @@ pub fn column_minima<T: Ord>(matrix: &Array2<T>) -> Vec<usize> {
     minima
 }
 
+/// Compute row minima in O(*n* + *m* log *m*) time.
+///
+/// This is [`row_minima`] for matrices with many more columns than
+/// rows. The columns are first reduced to at most *m* candidates
+/// with the reduce step of SMAWK, which costs a single pass over the
+/// columns. The recursion then runs on the candidates.
+///
+/// Unlike [`row_minima`], this needs the matrix to be totally
+/// monotone: the reduce step can discard the minimum of a row of a
+/// matrix which is only monotone. Since it shares code with the
+/// SMAWK implementation, it is not an independent check of it.
+///
+/// # Examples
+///
+/// ```
+/// let matrix = ndarray::arr2(&[[4, 2, 4, 3],
+///                              [5, 3, 5, 3],
+///                              [5, 3, 3, 1]]);
+/// assert_eq!(smawk::recursive::row_minima_reduced(&matrix),
+///            vec![1, 1, 3]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero columns.
+pub fn row_minima_reduced<T: Ord>(matrix: &Array2<T>) -> Vec<usize> {
+    let mut minima = vec![0; matrix.nrows()];
+    reduced_inner(matrix.view(), &|| Direction::Row, &mut minima);
+    minima
+}
+
+/// Compute column minima in O(*m* + *n* log *n*) time.
+///
+/// This is [`column_minima`] for matrices with many more rows than
+/// columns. The rows are first reduced to at most *n* candidates, see
+/// [`row_minima_reduced`] for the details and the stronger
+/// precondition.
+///
+/// # Examples
+///
+/// ```
+/// let matrix = ndarray::arr2(&[[4, 2, 4, 3],
+///                              [5, 3, 5, 3],
+///                              [5, 3, 3, 1]]);
+/// assert_eq!(smawk::recursive::column_minima_reduced(&matrix),
+///            vec![0, 0, 2, 2]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix with zero rows.
+pub fn column_minima_reduced<T: Ord>(matrix: &Array2<T>) -> Vec<usize> {
+    let mut minima = vec![0; matrix.ncols()];
+    reduced_inner(matrix.view(), &|| Direction::Column, &mut minima);
+    minima
+}
+
 /// The type of minima (row or column) we compute.
 enum Direction {
     Row,
@@ -107,6 +167,44 @@ fn recursive_inner<T: Ord, F: Fn() -> Direction>(
     recursive_inner(bot_right, dir, offset + min_idx, &mut minima[mid + 1..]);
 }
 
+/// Reduce the long axis to at most one candidate per minimum, then
+/// compute the minima with [`recursive_inner`] on the candidates.
+fn reduced_inner<T: Ord, F: Fn() -> Direction>(
+    matrix: ArrayView2<'_, T>,
+    dir: &F,
+    minima: &mut [usize],
+) {
+    let (m, n) = matrix.dim();
+    let mut stack = vec![0; minima.len()];
+    let len = match dir() {
+        // Row minima of the matrix are column minima of its
+        // transpose, whose rows are the columns here.
+        Direction::Row => crate::smawk_reduce(
+            &|j, i| &matrix[(i, j)],
+            &(0..n).collect::<Vec<_>>(),
+            &(0..m).collect::<Vec<_>>(),
+            &mut stack,
+            &mut Vec::with_capacity(m),
+        ),
+        Direction::Column => crate::smawk_reduce(
+            &|i, j| &matrix[(i, j)],
+            &(0..m).collect::<Vec<_>>(),
+            &(0..n).collect::<Vec<_>>(),
+            &mut stack,
+            &mut Vec::with_capacity(n),
+        ),
+    };
+    let candidates = &stack[..len];
+    let reduced = match dir() {
+        Direction::Row => Array2::from_shape_fn((m, len), |(i, k)| &matrix[(i, candidates[k])]),
+        Direction::Column => Array2::from_shape_fn((len, n), |(k, j)| &matrix[(candidates[k], j)]),
+    };
+    recursive_inner(reduced.view(), dir, 0, minima);
+    for minimum in minima.iter_mut() {
+        *minimum = candidates[*minimum];
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
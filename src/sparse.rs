@@ -0,0 +1,344 @@
+//! Column minima of sparse matrices where missing entries are
+//! infinite.
+//!
+//! A cost matrix is sometimes stored in compressed sparse row (CSR)
+//! format, where an absent entry means that the transition is
+//! forbidden. [`CsrView`] borrows the three arrays of such a matrix.
+//! These are the arrays returned by `indptr().raw_storage()`,
+//! `indices()` and `data()` on a `CsMatView` from the [sprs
+//! crate](https://crates.io/crates/sprs), so no copy is needed. With
+//! the `sprs` Cargo feature, a `CsMatView` converts directly into a
+//! [`CsrView`]. The view itself does not depend on sprs, so other
+//! CSR storage can be used without the dependency.
+//!
+//! The present entries must form a monotone staircase band: the
+//! entries of every row are contiguous, and the first and last
+//! column of the rows are non-decreasing from top to bottom. Rows
+//! without entries are ignored. When the present entries are Monge,
+//! the matrix stays totally monotone with the missing entries treated
+//! as infinite, and [`column_minima`] runs SMAWK in O(*m* + *n*) time
+//! without looking at the missing entries. Checking the band reads
+//! the column index of every present entry once.
+
+use crate::{BandKey, Matrix};
+use std::cmp::Reverse;
+
+/// Borrowed matrix in compressed sparse row format.
+///
+/// The entries of row `i` are stored at positions `indptr[i]` to
+/// `indptr[i + 1]` of `indices` and `data`, where `indices` holds
+/// their columns.
+#[derive(Debug, Clone, Copy)]
+pub struct CsrView<'a, T> {
+    ncols: usize,
+    indptr: &'a [usize],
+    indices: &'a [usize],
+    data: &'a [T],
+}
+
+impl<'a, T: Copy> CsrView<'a, T> {
+    /// Create a view of a sparse matrix with `ncols` columns and
+    /// `indptr.len() - 1` rows.
+    ///
+    /// # Panics
+    ///
+    /// It is an error to pass an empty `indptr`, an `indptr` which is
+    /// not non-decreasing or does not end at the number of entries,
+    /// `indices` and `data` of different lengths, or a column index
+    /// which is out of bounds.
+    pub fn new(ncols: usize, indptr: &'a [usize], indices: &'a [usize], data: &'a [T]) -> Self {
+        assert!(
+            !indptr.is_empty(),
+            "indptr must have an entry per row plus one"
+        );
+        assert!(
+            indptr.windows(2).all(|w| w[0] <= w[1]),
+            "indptr must be non-decreasing"
+        );
+        assert_eq!(indices.len(), data.len(), "expected a column per entry");
+        assert_eq!(
+            indptr[indptr.len() - 1],
+            data.len(),
+            "indptr must end at the number of entries"
+        );
+        assert!(
+            indices.iter().all(|&col| col < ncols),
+            "column index out of bounds"
+        );
+        CsrView {
+            ncols,
+            indptr,
+            indices,
+            data,
+        }
+    }
+
+    /// Return the number of rows.
+    pub fn nrows(&self) -> usize {
+        self.indptr.len() - 1
+    }
+
+    /// Return the number of columns.
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return the entry at `row` and `column`, or `None` if it is
+    /// absent.
+    ///
+    /// Running time: O(log *k*) for a row with *k* entries.
+    pub fn get(&self, row: usize, column: usize) -> Option<T> {
+        let range = self.indptr[row]..self.indptr[row + 1];
+        let pos = self.indices[range.clone()].binary_search(&column).ok()?;
+        Some(self.data[range.start + pos])
+    }
+}
+
+/// View a `CsMatView` from the sprs crate.
+///
+/// **Note: this is only available if you enable the `sprs` Cargo
+/// feature.**
+///
+/// # Panics
+///
+/// It is an error to convert a matrix in compressed sparse column
+/// (CSC) format.
+#[cfg(feature = "sprs")]
+impl<'a, T: Copy> From<sprs::CsMatView<'a, T>> for CsrView<'a, T> {
+    fn from(matrix: sprs::CsMatView<'a, T>) -> Self {
+        assert!(matrix.is_csr(), "expected a matrix in CSR format");
+        let ncols = matrix.cols();
+        let (indptr, indices, data) = matrix.into_raw_storage();
+        CsrView::new(ncols, indptr, indices, data)
+    }
+}
+
+/// The non-empty rows of a [`CsrView`] with their first and last
+/// column, adapted to the `Matrix` trait.
+struct Band<'a, T> {
+    matrix: &'a CsrView<'a, T>,
+    rows: Vec<(usize, usize, usize)>,
+}
+
+impl<T: Copy> Matrix<BandKey<T>> for Band<'_, T> {
+    fn nrows(&self) -> usize {
+        self.rows.len()
+    }
+    fn ncols(&self) -> usize {
+        self.matrix.ncols
+    }
+    fn index(&self, row: usize, column: usize) -> BandKey<T> {
+        let (i, first, last) = self.rows[row];
+        if last < column {
+            BandKey::Above(Reverse(row))
+        } else if column < first {
+            BandKey::Below(row)
+        } else {
+            BandKey::Present(self.matrix.data[self.matrix.indptr[i] + column - first])
+        }
+    }
+}
+
+/// Compute column minima of a sparse matrix with a monotone band of
+/// present entries.
+///
+/// Missing entries are treated as infinite, so the result holds the
+/// row of the smallest present entry in every column, or `None` for
+/// a column without entries. Ties are broken in favor of the smallest
+/// row. The present entries must be Monge, or more generally totally
+/// monotone, and form a band as described in the [module
+/// documentation](self).
+///
+/// The column indices of every row are checked before SMAWK runs,
+/// which reads each of them once. SMAWK itself then evaluates O(*m*
+/// + *n*) entries.
+///
+/// Running time on an *m* ✕ *n* matrix with *nnz* present entries:
+/// O(*m* + *n* + *nnz*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::sparse::{column_minima, CsrView};
+///
+/// // 4 1 . .
+/// // 5 2 3 .
+/// // . . 1 2
+/// let indptr = [0, 2, 5, 7];
+/// let indices = [0, 1, 0, 1, 2, 2, 3];
+/// let data = [4, 1, 5, 2, 3, 1, 2];
+/// let matrix = CsrView::new(4, &indptr, &indices, &data);
+/// assert_eq!(column_minima(&matrix), vec![Some(0), Some(0), Some(2), Some(2)]);
+/// ```
+///
+/// # Panics
+///
+/// It is an error to call this on a matrix where the entries of a row
+/// are not sorted by column or not contiguous, or where the first or
+/// last columns of the non-empty rows decrease.
+pub fn column_minima<T: PartialOrd + Copy>(matrix: &CsrView<'_, T>) -> Vec<Option<usize>> {
+    let mut rows = Vec::with_capacity(matrix.nrows());
+    for i in 0..matrix.nrows() {
+        let indices = &matrix.indices[matrix.indptr[i]..matrix.indptr[i + 1]];
+        assert!(
+            indices.windows(2).all(|w| w[0] < w[1]),
+            "entries of row {} are not sorted by column",
+            i
+        );
+        if let (Some(&first), Some(&last)) = (indices.first(), indices.last()) {
+            assert_eq!(
+                last - first + 1,
+                indices.len(),
+                "entries of row {} are not contiguous",
+                i
+            );
+            rows.push((i, first, last));
+        }
+    }
+    assert!(
+        rows.windows(2)
+            .all(|w| w[0].1 <= w[1].1 && w[0].2 <= w[1].2),
+        "rows must form a monotone staircase band"
+    );
+    if rows.is_empty() {
+        return vec![None; matrix.ncols];
+    }
+
+    let band = Band { matrix, rows };
+    crate::column_minima(&band)
+        .into_iter()
+        .enumerate()
+        .map(|(column, row)| match band.index(row, column) {
+            BandKey::Present(_) => Some(band.rows[row].0),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sorted_points;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    /// Random banded Monge matrix in CSR format, together with the
+    /// dense matrix where missing entries are `None`.
+    #[allow(clippy::type_complexity)]
+    fn random_band(
+        m: usize,
+        n: usize,
+        rng: &mut ChaCha20Rng,
+    ) -> (Vec<usize>, Vec<usize>, Vec<i64>, Vec<Vec<Option<i64>>>) {
+        let (x, y) = sorted_points(m, n, 20, rng);
+        let mut firsts = (0..m).map(|_| rng.gen_range(0..n)).collect::<Vec<_>>();
+        firsts.sort_unstable();
+
+        let (mut indptr, mut indices, mut data) = (vec![0], vec![], vec![]);
+        let mut dense = vec![vec![None; n]; m];
+        let mut last = 0;
+        for i in 0..m {
+            last = (firsts[i] + rng.gen_range(0..4)).max(last).min(n - 1);
+            // Empty rows are ignored and do not count towards the
+            // staircase.
+            if !rng.gen_bool(0.1) {
+                for j in firsts[i]..=last {
+                    let value = (x[i] - y[j]).pow(2);
+                    indices.push(j);
+                    data.push(value);
+                    dense[i][j] = Some(value);
+                }
+            }
+            indptr.push(indices.len());
+        }
+        (indptr, indices, data, dense)
+    }
+
+    /// Smallest present entry per column, preferring the first row.
+    fn dense_minima(dense: &[Vec<Option<i64>>], n: usize) -> Vec<Option<usize>> {
+        (0..n)
+            .map(|j| {
+                (0..dense.len())
+                    .filter_map(|i| dense[i][j].map(|value| (value, i)))
+                    .min()
+                    .map(|(_, i)| i)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn banded_monge_matches_dense() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..500 {
+            let (m, n) = (rng.gen_range(1..40), rng.gen_range(1..40));
+            let (indptr, indices, data, dense) = random_band(m, n, &mut rng);
+            let matrix = CsrView::new(n, &indptr, &indices, &data);
+            for (i, row) in dense.iter().enumerate() {
+                for (j, &value) in row.iter().enumerate() {
+                    assert_eq!(matrix.get(i, j), value);
+                }
+            }
+            assert_eq!(
+                column_minima(&matrix),
+                dense_minima(&dense, n),
+                "{:?}",
+                dense
+            );
+        }
+    }
+
+    #[test]
+    fn no_entries() {
+        let matrix = CsrView::<i32>::new(3, &[0, 0, 0], &[], &[]);
+        assert_eq!(column_minima(&matrix), vec![None, None, None]);
+    }
+
+    #[test]
+    #[should_panic(expected = "entries of row 0 are not contiguous")]
+    fn gap_in_row() {
+        let matrix = CsrView::new(3, &[0, 2], &[0, 2], &[1, 1]);
+        column_minima(&matrix);
+    }
+
+    #[test]
+    #[should_panic(expected = "entries of row 0 are not sorted by column")]
+    fn unsorted_row() {
+        let matrix = CsrView::new(4, &[0, 4], &[0, 2, 1, 3], &[1, 1, 1, 1]);
+        column_minima(&matrix);
+    }
+
+    #[test]
+    #[should_panic(expected = "entries of row 1 are not sorted by column")]
+    fn reversed_row() {
+        let matrix = CsrView::new(2, &[0, 1, 3], &[0, 1, 0], &[1, 1, 1]);
+        column_minima(&matrix);
+    }
+
+    #[test]
+    #[should_panic(expected = "rows must form a monotone staircase band")]
+    fn band_not_monotone() {
+        let matrix = CsrView::new(3, &[0, 1, 2], &[2, 1], &[1, 1]);
+        column_minima(&matrix);
+    }
+
+    #[cfg(feature = "sprs")]
+    #[test]
+    fn sprs_matches_dense() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (m, n) = (rng.gen_range(1..40), rng.gen_range(1..40));
+            let (indptr, indices, data, dense) = random_band(m, n, &mut rng);
+            let csmat = sprs::CsMat::new((m, n), indptr, indices, data);
+            let matrix = CsrView::from(csmat.view());
+            assert_eq!(column_minima(&matrix), dense_minima(&dense, n));
+        }
+    }
+
+    #[cfg(feature = "sprs")]
+    #[test]
+    #[should_panic(expected = "expected a matrix in CSR format")]
+    fn sprs_csc() {
+        let csmat = sprs::CsMat::new_csc((2, 2), vec![0, 1, 2], vec![0, 1], vec![1, 2]);
+        let _ = CsrView::from(csmat.view());
+    }
+}
@@ -0,0 +1,27 @@
+//! Fixtures shared by the unit tests.
+
+use rand::Rng;
+
+/// Random sorted points `x` and `y` with `m` and `n` elements in
+/// `0..max`.
+pub(crate) fn sorted_points<R: Rng>(
+    m: usize,
+    n: usize,
+    max: i64,
+    rng: &mut R,
+) -> (Vec<i64>, Vec<i64>) {
+    let mut x = (0..m).map(|_| rng.gen_range(0..max)).collect::<Vec<_>>();
+    let mut y = (0..n).map(|_| rng.gen_range(0..max)).collect::<Vec<_>>();
+    x.sort_unstable();
+    y.sort_unstable();
+    (x, y)
+}
+
+/// Random Monge matrix `(x[i] - y[j])²` for the [`sorted_points`]
+/// `x` and `y`. A small `max` gives many ties.
+pub(crate) fn distance_matrix<R: Rng>(m: usize, n: usize, max: i64, rng: &mut R) -> Vec<Vec<i64>> {
+    let (x, y) = sorted_points(m, n, max, rng);
+    x.iter()
+        .map(|&a| y.iter().map(|&b| (a - b) * (a - b)).collect())
+        .collect()
+}
@@ -0,0 +1,376 @@
+//! Distance multiplication of unit-Monge matrices.
+//!
+//! A *permutation matrix* `P` of size *n* is an *n* ✕ *n* zero-one
+//! matrix with exactly one non-zero in every row and column. Its
+//! *distribution matrix* is the (*n* + 1) ✕ (*n* + 1) matrix
+//!
+//! ```text
+//! PΣ[i, j] = number of non-zeros P[r, c] with r ≥ i and c < j
+//! ```
+//!
+//! Distribution matrices of permutation matrices are exactly the
+//! *simple unit-Monge* matrices. They are Monge matrices in the sense
+//! of [`crate::monge::is_monge`].
+//!
+//! The *distance product* of two matrices is the min-plus product
+//!
+//! ```text
+//! (A ⊙ B)[i, k] = min { A[i, j] + B[j, k] | all j }
+//! ```
+//!
+//! The distance product of two simple unit-Monge matrices is again
+//! simple unit-Monge, so it can be represented by a permutation. This
+//! is the basis of Tiskin's semi-local string comparison, where
+//! [`distance_multiply`] combines the "seaweed" permutations of two
+//! substrings. Working on the permutations directly, the product is
+//! found in O(*n* log *n*) time instead of the O(*n*³) needed for an
+//! explicit min-plus product.
+//!
+//! The algorithm is from Alexander Tiskin, *Fast distance
+//! multiplication of unit-Monge matrices*, Algorithmica 71, pp.
+//! 859-888 (2015).
+
+use crate::Matrix;
+
+/// A permutation matrix stored as the column of the non-zero in each
+/// row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PermutationMatrix {
+    columns: Vec<usize>,
+}
+
+impl PermutationMatrix {
+    /// Create a permutation matrix with a non-zero at `(row,
+    /// columns[row])` for every row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use smawk::unit_monge::PermutationMatrix;
+    /// let p = PermutationMatrix::new(vec![2, 0, 1]);
+    /// assert_eq!(p.column(0), 2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// It is an error if `columns` is not a permutation of `0..n`
+    /// where *n* = `columns.len()`.
+    pub fn new(columns: Vec<usize>) -> PermutationMatrix {
+        let mut seen = vec![false; columns.len()];
+        for &c in &columns {
+            assert!(
+                c < columns.len() && !seen[c],
+                "not a permutation: {:?}",
+                columns
+            );
+            seen[c] = true;
+        }
+        PermutationMatrix { columns }
+    }
+
+    /// Create the identity permutation matrix of size `n`. This is the
+    /// neutral element of [`distance_multiply`].
+    pub fn identity(n: usize) -> PermutationMatrix {
+        PermutationMatrix {
+            columns: (0..n).collect(),
+        }
+    }
+
+    /// Return the size *n* of the matrix.
+    pub fn size(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Return the column of the non-zero in `row`.
+    pub fn column(&self, row: usize) -> usize {
+        self.columns[row]
+    }
+
+    /// Return the columns of the non-zeros in all rows.
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    /// Compute the explicit (*n* + 1) ✕ (*n* + 1) distribution matrix.
+    ///
+    /// Running time: O(*n*²).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use smawk::unit_monge::PermutationMatrix;
+    /// let p = PermutationMatrix::new(vec![1, 0]);
+    /// assert_eq!(p.to_distribution_matrix(), vec![vec![0, 1, 2],
+    ///                                             vec![0, 1, 1],
+    ///                                             vec![0, 0, 0]]);
+    /// ```
+    pub fn to_distribution_matrix(&self) -> Vec<Vec<usize>> {
+        // Build the rows from the bottom up.
+        let mut row = vec![0; self.size() + 1];
+        let mut matrix = vec![row.clone()];
+        for &c in self.columns.iter().rev() {
+            for value in &mut row[c + 1..] {
+                *value += 1;
+            }
+            matrix.push(row.clone());
+        }
+        matrix.reverse();
+        matrix
+    }
+
+    /// Recover the permutation matrix from an explicit distribution
+    /// matrix.
+    ///
+    /// Returns `None` if `matrix` is not the distribution matrix of a
+    /// permutation matrix.
+    ///
+    /// Running time: O(*n*²).
+    pub fn from_distribution_matrix<M: Matrix<usize>>(matrix: &M) -> Option<PermutationMatrix> {
+        let n = matrix.nrows().checked_sub(1)?;
+        if matrix.ncols() != n + 1 {
+            return None;
+        }
+        // Row i of the distribution matrix exceeds row i + 1 exactly
+        // in the columns after the non-zero in row i.
+        let columns = (0..n)
+            .map(|i| (0..n).find(|&j| matrix.index(i, j + 1) != matrix.index(i + 1, j + 1)))
+            .collect::<Option<Vec<_>>>()?;
+        let mut seen = vec![false; n];
+        for &c in &columns {
+            if seen[c] {
+                return None;
+            }
+            seen[c] = true;
+        }
+
+        let permutation = PermutationMatrix { columns };
+        let expected = permutation.to_distribution_matrix();
+        for (i, row) in expected.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                if matrix.index(i, j) != value {
+                    return None;
+                }
+            }
+        }
+        Some(permutation)
+    }
+}
+
+/// Compute the distance product of two simple unit-Monge matrices.
+///
+/// The matrices are given by their permutation matrices `a` and `b`,
+/// and the result is the permutation matrix `c` such that the
+/// distribution matrix of `c` is the min-plus product of the
+/// distribution matrices of `a` and `b`.
+///
+/// Running time: O(*n* log *n*).
+///
+/// # Examples
+///
+/// ```
+/// use smawk::unit_monge::{distance_multiply, PermutationMatrix};
+/// let a = PermutationMatrix::new(vec![1, 2, 0]);
+/// let b = PermutationMatrix::new(vec![2, 0, 1]);
+/// assert_eq!(distance_multiply(&a, &b), PermutationMatrix::new(vec![2, 1, 0]));
+/// ```
+///
+/// # Panics
+///
+/// It is an error to multiply matrices of different sizes.
+pub fn distance_multiply(a: &PermutationMatrix, b: &PermutationMatrix) -> PermutationMatrix {
+    assert_eq!(a.size(), b.size(), "matrices must have the same size");
+    PermutationMatrix {
+        columns: multiply_inner(&a.columns, &b.columns),
+    }
+}
+
+/// Multiply permutations `p` and `q` given as the column of each row.
+fn multiply_inner(p: &[usize], q: &[usize]) -> Vec<usize> {
+    let n = p.len();
+    if n <= 1 {
+        return p.to_vec();
+    }
+
+    // Split the middle index (columns of p, rows of q) into a low half
+    // 0..h and a high half h..n and multiply the corresponding
+    // subpermutations after compressing away the empty rows and
+    // columns.
+    let h = n / 2;
+    let mut is_lo_column = vec![false; n];
+    for &c in &q[..h] {
+        is_lo_column[c] = true;
+    }
+    let mut rank = vec![0; n];
+    let (mut lo_columns, mut hi_columns) = (Vec::with_capacity(h), Vec::with_capacity(n - h));
+    for c in 0..n {
+        if is_lo_column[c] {
+            rank[c] = lo_columns.len();
+            lo_columns.push(c);
+        } else {
+            rank[c] = hi_columns.len();
+            hi_columns.push(c);
+        }
+    }
+    let (mut lo_rows, mut hi_rows) = (Vec::with_capacity(h), Vec::with_capacity(n - h));
+    let (mut p_lo, mut p_hi) = (Vec::with_capacity(h), Vec::with_capacity(n - h));
+    for (i, &c) in p.iter().enumerate() {
+        if c < h {
+            lo_rows.push(i);
+            p_lo.push(c);
+        } else {
+            hi_rows.push(i);
+            p_hi.push(c - h);
+        }
+    }
+    let q_lo = q[..h].iter().map(|&c| rank[c]).collect::<Vec<_>>();
+    let q_hi = q[h..].iter().map(|&c| rank[c]).collect::<Vec<_>>();
+    let r_lo = multiply_inner(&p_lo, &q_lo);
+    let r_hi = multiply_inner(&p_hi, &q_hi);
+
+    // Combine the two halves into a single colored permutation.
+    let mut column = vec![0; n];
+    let mut row = vec![0; n];
+    let mut is_hi = vec![false; n];
+    for (idx, &i) in lo_rows.iter().enumerate() {
+        column[i] = lo_columns[r_lo[idx]];
+    }
+    for (idx, &i) in hi_rows.iter().enumerate() {
+        column[i] = hi_columns[r_hi[idx]];
+        is_hi[i] = true;
+    }
+    for i in 0..n {
+        row[column[i]] = i;
+    }
+
+    // With hi_above_left(i, k) counting high non-zeros in rows < i
+    // and columns < k, and lo_below_right(i, k) counting low
+    // non-zeros in rows ≥ i and columns ≥ k, the product is
+    //
+    //   PΣ[i, k] = loΣ[i, k] + hiΣ[i, k]
+    //              + min(hi_above_left(i, k), lo_below_right(i, k)).
+    //
+    // The difference delta(i, k) = hi_above_left(i, k) -
+    // lo_below_right(i, k) is non-decreasing in both i and k with
+    // steps of zero or one. Low non-zeros survive where delta ≤ 0,
+    // high non-zeros survive where delta ≥ 0, and a new non-zero
+    // appears in every cell where delta goes from -1 in the top-left
+    // corner to 1 in the bottom-right corner.
+    //
+    // The staircases where delta reaches 0 and 1 are traced from the
+    // bottom-left corner to the top-right corner in O(n) time.
+    let staircase = |threshold: isize| {
+        let mut first = vec![0; n + 1];
+        let (mut k, mut delta) = (0, 0isize);
+        for i in (0..=n).rev() {
+            if i < n {
+                let c = column[i];
+                delta -= (is_hi[i] && c < k || !is_hi[i] && c >= k) as isize;
+            }
+            while k < n && delta < threshold {
+                let r = row[k];
+                delta += (is_hi[r] && r < i || !is_hi[r] && r >= i) as isize;
+                k += 1;
+            }
+            first[i] = if delta >= threshold { k } else { n + 1 };
+        }
+        first
+    };
+    let zero = staircase(0);
+    let one = staircase(1);
+
+    let mut result = vec![0; n];
+    for i in 0..n {
+        let c = column[i];
+        result[i] = if is_hi[i] && c >= zero[i] || !is_hi[i] && c + 1 < one[i + 1] {
+            c
+        } else {
+            debug_assert!(zero[i] >= 1 && zero[i] >= one[i + 1]);
+            zero[i] - 1
+        };
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /// Explicit min-plus product of two square matrices.
+    fn min_plus(a: &[Vec<usize>], b: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let n = a.len();
+        (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|k| (0..n).map(|j| a[i][j] + b[j][k]).min().unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn random_permutation(n: usize, rng: &mut ChaCha20Rng) -> PermutationMatrix {
+        let mut columns = (0..n).collect::<Vec<_>>();
+        columns.shuffle(rng);
+        PermutationMatrix::new(columns)
+    }
+
+    #[test]
+    fn distribution_matrix_is_monge() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for n in 0..10 {
+            let p = random_permutation(n, &mut rng);
+            let matrix = p.to_distribution_matrix();
+            assert!(crate::monge::is_monge(&matrix));
+            assert_eq!(
+                PermutationMatrix::from_distribution_matrix(&matrix),
+                Some(p)
+            );
+        }
+    }
+
+    #[test]
+    fn from_distribution_matrix_rejects_non_unit_monge() {
+        let matrix = vec![vec![0, 1, 2], vec![0, 2, 1], vec![0, 0, 0]];
+        assert_eq!(PermutationMatrix::from_distribution_matrix(&matrix), None);
+        let matrix = vec![vec![0, 1], vec![0, 0], vec![0, 0]];
+        assert_eq!(PermutationMatrix::from_distribution_matrix(&matrix), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a permutation")]
+    fn new_rejects_non_permutation() {
+        PermutationMatrix::new(vec![0, 2, 2]);
+    }
+
+    #[test]
+    fn distance_multiply_identity() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let p = random_permutation(50, &mut rng);
+        let identity = PermutationMatrix::identity(50);
+        assert_eq!(distance_multiply(&p, &identity), p);
+        assert_eq!(distance_multiply(&identity, &p), p);
+    }
+
+    #[test]
+    fn distance_multiply_matches_min_plus() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let sizes = (0..20).chain([31, 64, 100, 200]);
+        for n in sizes {
+            let a = random_permutation(n, &mut rng);
+            let b = random_permutation(n, &mut rng);
+            let c = distance_multiply(&a, &b);
+            // The result must be a permutation.
+            let c = PermutationMatrix::new(c.columns().to_vec());
+            let expected = min_plus(&a.to_distribution_matrix(), &b.to_distribution_matrix());
+            assert_eq!(
+                c.to_distribution_matrix(),
+                expected,
+                "a = {:?}, b = {:?}",
+                a,
+                b
+            );
+        }
+    }
+}
@@ -1,12 +1,20 @@
 #![cfg(feature = "ndarray")]
 
-use ndarray::{s, Array2};
-use rand::SeedableRng;
+use ndarray::{s, Array2, Axis};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
-use smawk::{brute_force, online_column_minima, recursive};
+use smawk::monge::{is_monge_infinitary, is_monotone_rows, is_totally_monotone};
+use smawk::{
+    brute_force, online_column_minima, online_column_minima_2best, online_column_minima_windowed,
+    recursive, CertifiedMin, Infinitary, Interval,
+};
 
 mod random_monge;
-use random_monge::random_monge_matrix;
+use random_monge::{
+    random_monge_matrix, random_monge_matrix_colwise, random_monge_matrix_from,
+    random_monotone_matrix, random_totally_monotone_matrix, MONGE_PRIMITIVES,
+};
 
 /// Check that the brute force, recursive, and SMAWK functions
 /// give identical results on a large number of randomly generated
@@ -18,7 +26,8 @@ fn column_minima_agree() {
     for _ in 0..4 {
         for m in sizes.clone().iter() {
             for n in sizes.clone().iter() {
-                let matrix: Array2<i32> = random_monge_matrix(*m, *n, &mut rng);
+                let matrix: Array2<i32> =
+                    random_monge_matrix_from(*m, *n, &MONGE_PRIMITIVES, &mut rng);
 
                 // Compute and test row minima.
                 let brute_force = brute_force::row_minima(&matrix);
@@ -54,6 +63,777 @@ fn column_minima_agree() {
     }
 }
 
+/// Check that the column minima agree on matrices generated for their
+/// column structure, rather than relying on transposed row cases.
+#[test]
+fn column_minima_colwise_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30, 50];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut staircase_rows = 0;
+    for _ in 0..4 {
+        for &m in &sizes {
+            for &n in &sizes {
+                let matrix: Array2<i32> = random_monge_matrix_colwise(m, n, &mut rng);
+                assert!(smawk::monge::is_monge(&matrix), "not Monge:\n{:?}", matrix);
+
+                let brute_force = brute_force::column_minima(&matrix);
+                let recursive = recursive::column_minima(&matrix);
+                let smawk = smawk::column_minima(&matrix);
+                assert_eq!(
+                    brute_force, recursive,
+                    "recursive and brute force differs on:\n{:?}",
+                    matrix
+                );
+                assert_eq!(
+                    brute_force, smawk,
+                    "SMAWK and brute force differs on:\n{:?}",
+                    matrix
+                );
+
+                let mut rows = brute_force.clone();
+                rows.dedup();
+                staircase_rows += rows.len();
+            }
+        }
+    }
+
+    // The minima should visit more rows than those of a matrix from
+    // the plain generator.
+    let mut plain_rows = 0;
+    for _ in 0..4 {
+        for &m in &sizes {
+            for &n in &sizes {
+                let matrix: Array2<i32> = random_monge_matrix(m, n, &mut rng);
+                let mut rows = brute_force::column_minima(&matrix);
+                rows.dedup();
+                plain_rows += rows.len();
+            }
+        }
+    }
+    assert!(
+        staircase_rows > 2 * plain_rows,
+        "only {} rows, compared to {} for the plain generator",
+        staircase_rows,
+        plain_rows
+    );
+}
+
+/// Check that the recursive functions, with and without the
+/// reduction of the long axis, agree with brute force on skewed
+/// matrices.
+#[test]
+fn recursive_skewed_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..3 {
+        for &short in &[1, 2, 3, 5, 10] {
+            for &long in &[8 * short, 8 * short + 1, 500, 2000] {
+                let matrix: Array2<u8> =
+                    random_monge_matrix_from(short, long, &MONGE_PRIMITIVES, &mut rng);
+                let expected = brute_force::row_minima(&matrix);
+                assert_eq!(
+                    recursive::row_minima(&matrix),
+                    expected,
+                    "recursive and brute force differs on:\n{:?}",
+                    matrix
+                );
+                assert_eq!(
+                    recursive::row_minima_reduced(&matrix),
+                    expected,
+                    "reduced recursive and brute force differs on:\n{:?}",
+                    matrix
+                );
+                let matrix = matrix.reversed_axes();
+                let expected = brute_force::column_minima(&matrix);
+                assert_eq!(
+                    recursive::column_minima(&matrix),
+                    expected,
+                    "recursive and brute force differs on:\n{:?}",
+                    matrix
+                );
+                assert_eq!(
+                    recursive::column_minima_reduced(&matrix),
+                    expected,
+                    "reduced recursive and brute force differs on:\n{:?}",
+                    matrix
+                );
+            }
+        }
+    }
+}
+
+/// Generate a large Monge matrix `(i - y[j])² + a[i] + b[j]` for a
+/// random non-decreasing sequence `y` of row indices. This is much
+/// faster than `random_monge_matrix` for large sizes.
+fn large_monge_matrix(m: usize, n: usize, rng: &mut ChaCha20Rng) -> Array2<i64> {
+    let mut targets = (0..n)
+        .map(|_| rng.gen_range(0..m as i64))
+        .collect::<Vec<_>>();
+    targets.sort_unstable();
+    let a = (0..m).map(|_| rng.gen_range(0..10)).collect::<Vec<i64>>();
+    let b = (0..n).map(|_| rng.gen_range(0..10)).collect::<Vec<i64>>();
+    Array2::from_shape_fn((m, n), |(i, j)| {
+        (i as i64 - targets[j]).pow(2) + a[i] + b[j]
+    })
+}
+
+/// Matrix which counts the batches evaluated through it.
+struct Counting<'a> {
+    matrix: &'a Array2<i64>,
+    batches: std::cell::Cell<usize>,
+    queries: std::cell::Cell<usize>,
+    evaluations: std::cell::Cell<usize>,
+}
+
+impl<'a> Counting<'a> {
+    fn new(matrix: &'a Array2<i64>) -> Self {
+        Counting {
+            matrix,
+            batches: std::cell::Cell::new(0),
+            queries: std::cell::Cell::new(0),
+            evaluations: std::cell::Cell::new(0),
+        }
+    }
+
+    fn average_batch_size(&self) -> usize {
+        self.queries.get() / self.batches.get()
+    }
+}
+
+impl smawk::Matrix<i64> for Counting<'_> {
+    fn nrows(&self) -> usize {
+        self.matrix.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.matrix.ncols()
+    }
+    fn index(&self, row: usize, column: usize) -> i64 {
+        self.evaluations.set(self.evaluations.get() + 1);
+        self.matrix[[row, column]]
+    }
+    fn eval_batch(&self, queries: &[(usize, usize)], out: &mut [i64]) {
+        self.batches.set(self.batches.get() + 1);
+        self.queries.set(self.queries.get() + queries.len());
+        self.evaluations.set(self.evaluations.get() + queries.len());
+        for (value, &(row, column)) in out.iter_mut().zip(queries) {
+            *value = self.matrix[[row, column]];
+        }
+    }
+}
+
+/// Check that batched evaluation gives the same minima as the scalar
+/// path and that wide matrices are evaluated in large batches.
+#[test]
+fn batched_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(m, n) in &[(5, 5000), (20, 2000), (50, 10000)] {
+        let matrix = large_monge_matrix(m, n, &mut rng);
+        let expected = brute_force::column_minima(&matrix);
+        let counting = Counting::new(&matrix);
+        assert_eq!(smawk::column_minima(&counting), expected);
+        assert!(
+            counting.average_batch_size() >= 100,
+            "average batch size {} on {}x{} matrix",
+            counting.average_batch_size(),
+            m,
+            n
+        );
+
+        let matrix = matrix.reversed_axes();
+        let expected = brute_force::row_minima(&matrix);
+        let counting = Counting::new(&matrix);
+        assert_eq!(smawk::row_minima(&counting), expected);
+        assert!(counting.average_batch_size() >= 100);
+    }
+}
+
+/// Check that computing all minima at once agrees with the standalone
+/// functions while evaluating fewer elements.
+#[test]
+fn all_minima_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30, 50, 100];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut standalone_evaluations = 0;
+    let mut shared_evaluations = 0;
+    for &m in &sizes {
+        for &n in &sizes {
+            let matrix =
+                random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng).mapv(|v: i32| v as i64);
+            // Small matrices are scanned by brute force, which shares
+            // nothing, so only larger matrices count towards the savings.
+            let above_threshold = m.saturating_mul(n) >= smawk::BRUTE_FORCE_THRESHOLD;
+            let counting = Counting::new(&matrix);
+            let standalone = (
+                smawk::row_minima(&counting),
+                smawk::column_minima(&counting),
+            );
+            if above_threshold {
+                standalone_evaluations += counting.evaluations.get();
+            }
+
+            let counting = Counting::new(&matrix);
+            assert_eq!(
+                smawk::all_minima(&counting),
+                standalone,
+                "all_minima differs on:\n{:?}",
+                matrix
+            );
+            if above_threshold {
+                shared_evaluations += counting.evaluations.get();
+            }
+        }
+    }
+    assert!(
+        4 * shared_evaluations < 3 * standalone_evaluations,
+        "{} shared evaluations compared to {} standalone",
+        shared_evaluations,
+        standalone_evaluations
+    );
+}
+
+/// Check that the batched online algorithm agrees with the scalar one.
+/// Most evaluations in the online algorithm depend on each other, so
+/// we only check that the tentative phase produces some large batches.
+#[test]
+fn online_batched_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let size = 2000;
+    let near_diagonal = large_monge_matrix(size, size, &mut rng);
+    // Rows with a small index win everywhere, which keeps the base at
+    // the top and lets the tentative squares grow.
+    let far_from_diagonal = Array2::from_shape_fn((size, size), |(i, j)| (i + j % 7) as i64);
+
+    for matrix in [near_diagonal, far_from_diagonal] {
+        let largest = std::cell::Cell::new(0);
+        let batched = smawk::online_column_minima_batched(0, size, |_, batch, out| {
+            largest.set(std::cmp::max(largest.get(), batch.len()));
+            for (value, &(i, j)) in out.iter_mut().zip(batch) {
+                *value = matrix[[i, j]];
+            }
+        });
+        let scalar = online_column_minima(0, size, |_, i, j| matrix[[i, j]]);
+        assert_eq!(batched, scalar);
+        assert!(largest.get() >= 16, "largest batch: {}", largest.get());
+    }
+}
+
+/// Check that leaving out each row agrees with brute force on the
+/// matrix with that row deleted.
+#[test]
+fn leave_one_out_agree() {
+    let sizes = vec![2, 3, 4, 5, 10, 20, 50, 100];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &m in &sizes {
+        for &n in &sizes {
+            let matrix: Array2<i32> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+            let leave_one_out = smawk::leave_one_out_column_minima(&matrix);
+            for (r, minima) in leave_one_out.iter().enumerate() {
+                let rows = (0..m).filter(|&i| i != r).collect::<Vec<_>>();
+                let brute_force = brute_force::column_minima(&matrix.select(Axis(0), &rows))
+                    .into_iter()
+                    .map(|i| rows[i])
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    &brute_force, minima,
+                    "leaving out row {} differs from brute force on:\n{:?}",
+                    r, matrix
+                );
+            }
+        }
+    }
+}
+
+/// Check that the minima agree with brute force on matrices which are
+/// totally monotone, but not Monge. SMAWK only needs total
+/// monotonicity, so none of the algorithms may rely on the stronger
+/// Monge property.
+#[test]
+fn totally_monotone_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30, 50];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut not_monge = 0;
+    for &m in &sizes {
+        for &n in &sizes {
+            let matrix = random_totally_monotone_matrix(m, n, &mut rng);
+            let transposed = matrix.t().to_owned();
+            if m * n <= 400 {
+                assert!(
+                    smawk::monge::is_totally_monotone(&transposed),
+                    "not totally monotone:\n{:?}",
+                    matrix
+                );
+            }
+            if !smawk::monge::is_monge(&matrix) {
+                not_monge += 1;
+            }
+
+            let expected = brute_force::column_minima(&matrix);
+            let mut buffer = vec![0; smawk::scratch_size(m, n)];
+            let mut in_scratch = vec![0; n];
+            smawk::column_minima_in_scratch(
+                &matrix,
+                &mut in_scratch,
+                &mut smawk::Scratch::new(&mut buffer),
+            );
+            let runs = smawk::column_minima_runs(&matrix)
+                .into_iter()
+                .flat_map(|(row, columns)| columns.map(move |_| row))
+                .collect::<Vec<_>>();
+            for (name, minima) in [
+                ("recursive", recursive::column_minima(&matrix)),
+                ("SMAWK", smawk::column_minima(&matrix)),
+                ("partial", smawk::column_minima_partial(&matrix)),
+                ("fallible", smawk::try_column_minima(&matrix).unwrap()),
+                ("counted", smawk::column_minima_counted(&matrix).0),
+                ("fallback", smawk::column_minima_or_fallback(&matrix).0),
+                ("scratch", in_scratch),
+                ("runs", runs),
+            ] {
+                assert_eq!(
+                    expected, minima,
+                    "{} column minima and brute force differ on:\n{:?}",
+                    name, matrix
+                );
+            }
+
+            let expected = brute_force::row_minima(&transposed);
+            for (name, minima) in [
+                ("recursive", recursive::row_minima(&transposed)),
+                ("SMAWK", smawk::row_minima(&transposed)),
+                ("partial", smawk::row_minima_partial(&transposed)),
+                ("fallible", smawk::try_row_minima(&transposed).unwrap()),
+            ] {
+                assert_eq!(
+                    expected, minima,
+                    "{} row minima and brute force differ on:\n{:?}",
+                    name, transposed
+                );
+            }
+        }
+    }
+    assert!(
+        not_monge > sizes.len() * sizes.len() / 2,
+        "only {} matrices are not Monge",
+        not_monge
+    );
+}
+
+/// Check that the runs returned by `column_minima_runs` describe a
+/// monotone staircase which expands to the column minima.
+#[test]
+fn column_minima_runs_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &m in &sizes {
+        for &n in &sizes {
+            let matrix: Array2<i32> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+            let runs = smawk::column_minima_runs(&matrix);
+            for window in runs.windows(2) {
+                assert!(window[0].0 < window[1].0, "rows not increasing: {:?}", runs);
+                assert_eq!(window[0].1.end, window[1].1.start);
+            }
+            let expanded = runs
+                .into_iter()
+                .flat_map(|(row, columns)| columns.map(move |_| row))
+                .collect::<Vec<_>>();
+            assert_eq!(expanded, brute_force::column_minima(&matrix));
+
+            let mut reported = vec![None; n];
+            smawk::column_minima_as_computed(&matrix, |col, row| {
+                assert_eq!(reported[col], None, "column {} reported twice", col);
+                reported[col] = Some(row);
+            });
+            let reported = reported.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+            assert_eq!(reported, brute_force::column_minima(&matrix));
+
+            let expanded = smawk::column_minima_run_iter(&matrix)
+                .flat_map(|(row, columns)| columns.map(move |_| row))
+                .collect::<Vec<_>>();
+            assert_eq!(expanded, smawk::column_minima(&matrix));
+        }
+    }
+}
+
+/// A composite cost compared lexicographically by the derived `Ord`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Cost {
+    primary: i64,
+    tiebreak: i64,
+}
+
+/// Check that SMAWK agrees with brute force on matrices of composite
+/// costs. Comparing `(P, Q)` lexicographically is the same as
+/// comparing `P * K + Q` for a large enough `K`, so two Monge
+/// matrices give a totally monotone matrix of costs.
+#[test]
+fn composite_costs_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &m in &sizes {
+        for &n in &sizes {
+            // The random matrices have many ties in the primary field
+            // which must then be broken by the second field.
+            let primary: Array2<i64> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+            let tiebreak: Array2<i64> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+            let matrix = Array2::from_shape_fn((m, n), |idx| Cost {
+                primary: primary[idx],
+                tiebreak: tiebreak[idx],
+            });
+            assert_eq!(brute_force::row_minima(&matrix), smawk::row_minima(&matrix));
+            assert_eq!(
+                brute_force::column_minima(&matrix),
+                smawk::column_minima(&matrix)
+            );
+        }
+    }
+}
+
+/// Check that refining coarse ties by a fine key agrees with a brute
+/// force lexicographic minimum.
+#[test]
+fn column_minima_refine_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &m in &sizes {
+        for &n in &sizes {
+            let coarse: Array2<i32> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+            let fine = Array2::from_shape_fn((m, n), |_| rng.gen_range(0..3));
+            let brute_force = (0..n)
+                .map(|j| {
+                    (0..m)
+                        .min_by_key(|&i| (coarse[[i, j]], fine[[i, j]], i))
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            let refined =
+                smawk::column_minima_refine(m, n, |i, j| coarse[[i, j]], |i, j| fine[[i, j]]);
+            assert_eq!(
+                brute_force, refined,
+                "refined minima differ on:\n{:?}\nwith fine key:\n{:?}",
+                coarse, fine
+            );
+        }
+    }
+}
+
+/// Check that breaking ties toward target rows agrees with a brute
+/// force enumeration of all minimizing rows.
+#[test]
+fn column_minima_closest_to_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &m in &sizes {
+        for &n in &sizes {
+            let matrix: Array2<i32> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+            let target = (0..n).map(|_| rng.gen_range(0..m + 3)).collect::<Vec<_>>();
+            let brute_force = (0..n)
+                .map(|j| {
+                    let column = matrix.column(j);
+                    let min = *column.iter().min().unwrap();
+                    (0..m)
+                        .filter(|&i| column[i] == min)
+                        .min_by_key(|&i| (i.abs_diff(target[j]), i))
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                brute_force,
+                smawk::column_minima_closest_to(&matrix, &target),
+                "targets {:?} on:\n{:?}",
+                target,
+                matrix
+            );
+        }
+    }
+}
+
+/// Monge matrix split into blocks which counts how often each block
+/// is loaded.
+struct Tiled {
+    matrix: Array2<i64>,
+    block_size: (usize, usize),
+    loads: std::cell::RefCell<std::collections::HashMap<(usize, usize), usize>>,
+}
+
+impl smawk::blocked::BlockMatrix<i64> for Tiled {
+    fn nrows(&self) -> usize {
+        self.matrix.nrows()
+    }
+    fn ncols(&self) -> usize {
+        self.matrix.ncols()
+    }
+    fn block_size(&self) -> (usize, usize) {
+        self.block_size
+    }
+    fn block(&self, bi: usize, bj: usize, mut out: ndarray::ArrayViewMut2<'_, i64>) {
+        *self.loads.borrow_mut().entry((bi, bj)).or_insert(0) += 1;
+        let (bm, bn) = self.block_size;
+        let rows = bi * bm..std::cmp::min((bi + 1) * bm, self.matrix.nrows());
+        let cols = bj * bn..std::cmp::min((bj + 1) * bn, self.matrix.ncols());
+        out.assign(&self.matrix.slice(s![rows, cols]));
+    }
+}
+
+/// Check robust minima on Monge matrices with and without noise.
+#[test]
+fn robust_column_minima_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut improved = 0;
+    for _ in 0..50 {
+        let (m, n) = (rng.gen_range(1..60), rng.gen_range(1..60));
+        let matrix: Array2<i32> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+        let minima = smawk::column_minima(&matrix);
+        assert_eq!(
+            smawk::robust_column_minima(&matrix, 2),
+            (minima, vec![false; n])
+        );
+
+        let noisy = matrix.mapv(|v| v + rng.gen_range(-3..=3));
+        let value = |minima: &[usize]| {
+            minima
+                .iter()
+                .enumerate()
+                .map(|(j, &i)| noisy[[i, j]])
+                .collect::<Vec<_>>()
+        };
+        let smawk = value(&smawk::column_minima(&noisy));
+        let exact = value(&brute_force::column_minima(&noisy));
+        for slack in [0, 1, 3] {
+            let (robust, changed) = smawk::robust_column_minima(&noisy, slack);
+            let robust = value(&robust);
+            for j in 0..n {
+                assert!(exact[j] <= robust[j] && robust[j] <= smawk[j]);
+                assert_eq!(changed[j], robust[j] < smawk[j]);
+            }
+            improved += changed.iter().filter(|&&c| c).count();
+        }
+        // Scanning all rows is exact.
+        let (robust, _) = smawk::robust_column_minima(&noisy, m);
+        assert_eq!(value(&robust), exact);
+    }
+    assert!(improved > 0);
+}
+
+/// Check that querying all columns of an oracle in random order
+/// agrees with SMAWK and evaluates few elements.
+#[test]
+fn column_minima_oracle_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..20 {
+        let (m, n) = (rng.gen_range(1..1000), rng.gen_range(1..1000));
+        let matrix = large_monge_matrix(m, n, &mut rng);
+        let counting = Counting::new(&matrix);
+        let mut oracle = smawk::ColumnMinimaOracle::new(&counting);
+        let mut order = (0..n).collect::<Vec<_>>();
+        order.shuffle(&mut rng);
+        let mut minima = vec![0; n];
+        for &col in &order {
+            minima[col] = oracle.query(col);
+        }
+        assert_eq!(minima, smawk::column_minima(&matrix));
+        assert_eq!(oracle.cached(), n);
+
+        // Every column was scanned exactly once, and the scans on
+        // each level of the binary search overlap in single rows.
+        let levels = (usize::BITS - n.leading_zeros()) as usize;
+        let evaluations = counting.evaluations.get();
+        assert!(
+            evaluations <= levels * (m + n),
+            "{} on {}x{}",
+            evaluations,
+            m,
+            n
+        );
+
+        // Repeated queries are answered from the cache.
+        for &col in &order {
+            assert_eq!(oracle.query(col), minima[col]);
+        }
+        assert_eq!(counting.evaluations.get(), evaluations);
+    }
+}
+
+/// Check that the minima of `column_minima_or_fallback` are either
+/// exact or pass the final certificate check.
+fn assert_exact_or_verified(matrix: &Array2<i64>, minima: Vec<usize>) {
+    if minima == brute_force::column_minima(matrix) {
+        return;
+    }
+    let values = minima
+        .iter()
+        .enumerate()
+        .map(|(col, &row)| matrix[[row, col]])
+        .collect();
+    let certificate = smawk::certificate::Certificate::new(minima, values);
+    assert_eq!(
+        smawk::certificate::verify(matrix, &certificate),
+        Ok(()),
+        "{:?}",
+        matrix
+    );
+}
+
+/// Check that falling back to brute force catches arbitrary matrices,
+/// and that it never happens on Monge matrices. Checking the final
+/// minima reads O(*m* + *n*) elements.
+#[test]
+fn column_minima_or_fallback_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..300 {
+        let (m, n) = (rng.gen_range(1..80), rng.gen_range(1..80));
+        let high = rng.gen_range(1..1000);
+        let matrix = Array2::from_shape_fn((m, n), |_| rng.gen_range(0..high));
+        let (minima, _) = smawk::column_minima_or_fallback(&matrix);
+        assert_exact_or_verified(&matrix, minima);
+    }
+
+    for _ in 0..20 {
+        let (m, n) = (rng.gen_range(1..2000), rng.gen_range(1..2000));
+        let matrix = large_monge_matrix(m, n, &mut rng);
+        let counting = Counting::new(&matrix);
+        let (minima, stats) = smawk::column_minima_or_fallback(&counting);
+        assert_eq!(minima, smawk::column_minima(&matrix));
+        assert_eq!(stats.fallbacks, 0);
+        let evaluations = counting.evaluations.get();
+        assert!(
+            evaluations <= 40 * (m + n),
+            "{} on {}x{}",
+            evaluations,
+            m,
+            n
+        );
+    }
+}
+
+/// Check that a Monge matrix with a few lowered elements gets minima
+/// which are exact or pass the final check, and that some of the
+/// lowered elements are caught by the checks.
+#[test]
+fn column_minima_or_fallback_perturbed_monge() {
+    let mut fallbacks = 0;
+    for i in 0..40 {
+        for j in 0..40 {
+            let mut matrix = Array2::from_shape_fn((40, 40), |(i, j)| {
+                10 * (i as i64 - j as i64) * (i as i64 - j as i64)
+            });
+            matrix[[i, j]] -= 10000;
+            let (minima, stats) = smawk::column_minima_or_fallback(&matrix);
+            assert_exact_or_verified(&matrix, minima);
+            fallbacks += stats.fallbacks;
+        }
+    }
+    assert!(fallbacks > 0);
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..100 {
+        let (m, n) = (rng.gen_range(20..200), rng.gen_range(20..200));
+        let mut matrix = large_monge_matrix(m, n, &mut rng);
+        for _ in 0..rng.gen_range(1..4) {
+            let (i, j) = (rng.gen_range(0..m), rng.gen_range(0..n));
+            matrix[[i, j]] -= rng.gen_range(1..1000);
+        }
+        let (minima, _) = smawk::column_minima_or_fallback(&matrix);
+        assert_exact_or_verified(&matrix, minima);
+    }
+}
+
+/// Check that block-based SMAWK agrees with the cell-based version
+/// and only loads each block a few times.
+#[test]
+fn blocked_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(m, n) in &[
+        (1, 1),
+        (7, 5),
+        (100, 100),
+        (64, 1000),
+        (1000, 64),
+        (500, 500),
+    ] {
+        for &block_size in &[(1, 1), (3, 5), (16, 16), (50, 20)] {
+            let tiled = Tiled {
+                matrix: large_monge_matrix(m, n, &mut rng),
+                block_size,
+                loads: Default::default(),
+            };
+            assert_eq!(
+                smawk::blocked::column_minima(&tiled),
+                smawk::column_minima(&tiled.matrix)
+            );
+            // SMAWK makes about log2(n) passes over the matrix.
+            let passes = (n as f64).log2() as usize + 1;
+            let max = *tiled.loads.borrow().values().max().unwrap();
+            assert!(
+                max <= 4 * passes,
+                "block loaded {} times for {}x{} matrix with block size {:?}",
+                max,
+                m,
+                n,
+                block_size
+            );
+        }
+    }
+}
+
+/// Check that SMAWK gives the same result on both sides of the brute
+/// force threshold.
+#[test]
+fn threshold_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 25, 30];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..4 {
+        for &m in &sizes {
+            for &n in &sizes {
+                let matrix: Array2<i32> =
+                    random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+                let brute_force = brute_force::row_minima(&matrix);
+                assert_eq!(brute_force, smawk::row_minima_with_threshold(&matrix, 0));
+                assert_eq!(
+                    brute_force,
+                    smawk::row_minima_with_threshold(&matrix, usize::MAX)
+                );
+
+                let brute_force = brute_force::column_minima(&matrix);
+                assert_eq!(brute_force, smawk::column_minima_with_threshold(&matrix, 0));
+                assert_eq!(
+                    brute_force,
+                    smawk::column_minima_with_threshold(&matrix, usize::MAX)
+                );
+            }
+        }
+    }
+}
+
+/// Check that SMAWK restricted to a random subset of enabled rows
+/// agrees with brute force on the corresponding submatrix.
+#[test]
+fn column_minima_enabled_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..4 {
+        for &m in &sizes {
+            for &n in &sizes {
+                let matrix: Array2<i32> =
+                    random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+                let mut enabled = (0..m).map(|_| rng.gen::<bool>()).collect::<Vec<_>>();
+                enabled[rng.gen_range(0..m)] = true;
+
+                let rows = (0..m).filter(|&i| enabled[i]).collect::<Vec<_>>();
+                let submatrix = matrix.select(Axis(0), &rows);
+                let brute_force = brute_force::column_minima(&submatrix)
+                    .into_iter()
+                    .map(|i| rows[i])
+                    .collect::<Vec<_>>();
+                let smawk = smawk::column_minima_enabled(&matrix, &enabled);
+                assert_eq!(
+                    brute_force, smawk,
+                    "SMAWK and brute force differs with enabled rows {:?} on:\n{:?}",
+                    enabled, matrix
+                );
+            }
+        }
+    }
+}
+
 /// Check that the brute force and online SMAWK functions give
 /// identical results on a large number of randomly generated
 /// Monge matrices.
@@ -65,7 +845,8 @@ fn online_agree() {
         for &size in &sizes {
             // Random totally monotone square matrix of the
             // desired size.
-            let mut matrix: Array2<i32> = random_monge_matrix(size, size, &mut rng);
+            let mut matrix: Array2<i32> =
+                random_monge_matrix_from(size, size, &MONGE_PRIMITIVES, &mut rng);
 
             // Adjust matrix so the column minima are above the
             // diagonal. The brute_force::column_minima will still
@@ -102,3 +883,266 @@ fn online_agree() {
         }
     }
 }
+
+/// Check that the windowed online algorithm agrees with brute force
+/// restricted to the window.
+#[test]
+fn online_windowed_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30, 50];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..5 {
+        for &size in &sizes {
+            let mut matrix: Array2<i32> =
+                random_monge_matrix_from(size, size, &MONGE_PRIMITIVES, &mut rng);
+            // Mangle the matrix like in online_agree.
+            let max = *matrix.iter().max().unwrap_or(&0);
+            for idx in 0..(size as isize) {
+                matrix.slice_mut(s![idx..idx + 1, ..idx + 1]).fill(max);
+            }
+
+            let initial = 42;
+            for window in [1, 2, 3, 5, size, size + 3] {
+                let brute_force = (0..size)
+                    .map(|j| {
+                        (j.saturating_sub(window)..j)
+                            .map(|i| (i, matrix[[i, j]]))
+                            .min_by_key(|&(i, value)| (value, i))
+                            .unwrap_or((0, initial))
+                    })
+                    .collect::<Vec<_>>();
+                let windowed = online_column_minima_windowed(initial, size, window, |_, i, j| {
+                    assert!(j - i <= window, "({}, {}) outside window {}", i, j, window);
+                    matrix[[i, j]]
+                });
+                assert_eq!(
+                    brute_force, windowed,
+                    "brute force and windowed online differ with window {} on:\n{:3?}",
+                    window, matrix
+                );
+            }
+        }
+    }
+}
+
+/// Check that the runner-ups found by the online 2-best function
+/// agree with a brute force scan of each column.
+#[test]
+fn online_2best_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30, 64, 100];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..5 {
+        for &size in &sizes {
+            // Small values give many ties in the columns.
+            let mut matrix: Array2<i32> =
+                random_monge_matrix_from(size, size, &MONGE_PRIMITIVES, &mut rng);
+            let max = *matrix.iter().max().unwrap_or(&0);
+            for idx in 0..(size as isize) {
+                matrix.slice_mut(s![idx..idx + 1, ..idx + 1]).fill(max);
+            }
+
+            let initial = 42;
+            let mut brute_force = vec![((0, initial), None)];
+            for j in 1..size {
+                let mut candidates = (0..j).map(|i| (matrix[[i, j]], i)).collect::<Vec<_>>();
+                candidates.sort();
+                let best = (candidates[0].1, candidates[0].0);
+                let second = candidates.get(1).map(|&(v, i)| (i, v));
+                brute_force.push((best, second));
+            }
+
+            let online = online_column_minima_2best(initial, size, |_, i, j| matrix[[i, j]]);
+            assert_eq!(
+                brute_force, online,
+                "brute force and online 2-best differ on:\n{:3?}",
+                matrix
+            );
+        }
+    }
+}
+
+/// Check that writing minima into rows and columns of a larger array
+/// agrees with the standalone function.
+#[test]
+fn column_minima_into_view_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(m, n) in &[(1, 1), (1, 7), (4, 4), (5, 30), (40, 200)] {
+        let matrix: Array2<i32> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+        let expected = smawk::column_minima(&matrix);
+
+        let mut layers = Array2::from_elem((3, n), usize::MAX);
+        smawk::column_minima_into_view(&matrix, layers.row_mut(1));
+        assert_eq!(layers.row(1).to_vec(), expected);
+        assert!(layers.row(0).iter().all(|&v| v == usize::MAX));
+        assert!(layers.row(2).iter().all(|&v| v == usize::MAX));
+
+        let mut transposed = Array2::from_elem((n, 3), usize::MAX);
+        smawk::column_minima_into_view(&matrix, transposed.column_mut(2));
+        assert_eq!(transposed.column(2).to_vec(), expected);
+    }
+}
+
+/// Check that certified interval minima agree with a brute force scan
+/// of the intervals, and with the exact minima for zero widths.
+#[test]
+fn column_minima_interval_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 20, 50];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut counts = [0; 2];
+    for &m in &sizes {
+        for &n in &sizes {
+            for &scale in &[0, 1, 5, 50] {
+                let lo: Array2<i32> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+                let width: Array2<i32> =
+                    random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+                let matrix = Array2::from_shape_fn((m, n), |(i, j)| Interval {
+                    lo: lo[[i, j]],
+                    hi: lo[[i, j]] + scale * width[[i, j]],
+                });
+                let expected = (0..n)
+                    .map(|j| {
+                        let column = matrix.column(j);
+                        let bound = column.iter().map(|x| x.hi).min().unwrap();
+                        let best = column.iter().position(|x| x.hi == bound).unwrap();
+                        if (0..m).all(|k| k == best || column[k].lo >= bound) {
+                            CertifiedMin::Certified(best)
+                        } else {
+                            CertifiedMin::Ambiguous(
+                                (0..m).filter(|&k| column[k].lo <= bound).collect(),
+                            )
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let minima = smawk::column_minima_interval(&matrix);
+                assert_eq!(minima, expected, "differs on:\n{:?}", matrix);
+                for minimum in minima {
+                    counts[matches!(minimum, CertifiedMin::Certified(_)) as usize] += 1;
+                }
+
+                if scale == 0 {
+                    let exact = brute_force::column_minima(&lo)
+                        .into_iter()
+                        .map(CertifiedMin::Certified)
+                        .collect::<Vec<_>>();
+                    assert_eq!(smawk::column_minima_interval(&matrix), exact);
+                }
+            }
+        }
+    }
+    assert!(counts.iter().all(|&count| count > 100), "{:?}", counts);
+}
+
+/// Check that the best cell of every column group agrees with a brute
+/// force scan of the group.
+#[test]
+fn column_minima_grouped_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 20, 50];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &m in &sizes {
+        for &n in &sizes {
+            // Small values give many ties between columns.
+            let matrix: Array2<u8> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+            let groups = (0..n).map(|_| rng.gen_range(0..4)).collect::<Vec<u8>>();
+            let best = smawk::column_minima_grouped(&matrix, |j| groups[j]);
+
+            let mut expected = std::collections::HashMap::new();
+            for j in 0..n {
+                for i in 0..m {
+                    let cell = (matrix[[i, j]], j, i);
+                    let entry = expected.entry(groups[j]).or_insert(cell);
+                    *entry = (*entry).min(cell);
+                }
+            }
+            let expected = expected
+                .into_iter()
+                .map(|(g, (value, j, i))| (g, (i, j, value)))
+                .collect();
+            assert_eq!(best, expected, "differs on:\n{:?}", matrix);
+        }
+    }
+}
+
+/// Check the recursive algorithm on matrices which are monotone, but
+/// not totally monotone.
+#[test]
+fn recursive_monotone_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 20, 50, 100];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut not_totally_monotone = 0;
+    for &m in &sizes {
+        for &n in &sizes {
+            let matrix = random_monotone_matrix(m, n, &mut rng);
+            assert!(is_monotone_rows(&matrix));
+            if m <= 10 && n <= 10 && !is_totally_monotone(&matrix) {
+                not_totally_monotone += 1;
+            }
+            assert_eq!(
+                recursive::row_minima(&matrix),
+                brute_force::row_minima(&matrix),
+                "recursive row minima differ on:\n{:?}",
+                matrix
+            );
+        }
+    }
+    assert!(not_totally_monotone > 10, "{}", not_totally_monotone);
+}
+
+/// Check that masking Monge matrices with a staircase of infinite
+/// elements agrees with a brute force masked minimum, including
+/// columns and rows without allowed elements.
+#[test]
+fn infinitary_masks_agree() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 20, 50];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut empty_columns = 0;
+    let mut empty_rows = 0;
+    for &m in &sizes {
+        for &n in &sizes {
+            let values: Array2<u32> = random_monge_matrix_from(m, n, &MONGE_PRIMITIVES, &mut rng);
+            let offset = rng.gen_range(-3..=3);
+            let masked = |allowed: &dyn Fn(usize, usize) -> bool| {
+                Array2::from_shape_fn((m, n), |(i, j)| {
+                    if allowed(i, j) {
+                        Infinitary::Finite(values[[i, j]])
+                    } else {
+                        Infinitary::PosInf
+                    }
+                })
+            };
+            // The first allowed row or column with the smallest
+            // element, or 0 when nothing is allowed.
+            let brute_force = |matrix: &Array2<Infinitary<u32>>, k: usize, by_row: bool| {
+                let len = if by_row { n } else { m };
+                (0..len)
+                    .min_by_key(|&l| {
+                        if by_row {
+                            matrix[[k, l]]
+                        } else {
+                            matrix[[l, k]]
+                        }
+                    })
+                    .unwrap()
+            };
+
+            // Column minima: forbidden lower-left corner.
+            let lower_left = |i: usize, j: usize| j as i64 >= i as i64 - offset;
+            let matrix = masked(&lower_left);
+            assert!(is_monge_infinitary(&matrix));
+            let columns = (0..n)
+                .map(|j| brute_force(&matrix, j, false))
+                .collect::<Vec<_>>();
+            assert_eq!(smawk::column_minima(&matrix), columns, "{:?}", matrix);
+            empty_columns += (0..n).filter(|&j| !lower_left(0, j)).count();
+
+            // Row minima: forbidden upper-right corner.
+            let upper_right = |i: usize, j: usize| j as i64 <= i as i64 + offset;
+            let matrix = masked(&upper_right);
+            assert!(is_monge_infinitary(&matrix));
+            let rows = (0..m)
+                .map(|i| brute_force(&matrix, i, true))
+                .collect::<Vec<_>>();
+            assert_eq!(smawk::row_minima(&matrix), rows, "{:?}", matrix);
+            empty_rows += (0..m).filter(|&i| !upper_right(i, 0)).count();
+        }
+    }
+    assert!(empty_columns > 0 && empty_rows > 0);
+}
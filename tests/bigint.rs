@@ -0,0 +1,82 @@
+#![cfg(feature = "ndarray")]
+
+use ndarray::Array2;
+use num_bigint::BigInt;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use smawk::monge::is_monge_unbounded;
+use smawk::{brute_force, online_column_minima, recursive, ByRef};
+use std::cell::Cell;
+use std::ops::Add;
+
+mod random_monge;
+use random_monge::random_monge_matrix_big;
+
+thread_local! {
+    static CLONES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Big integer which counts how often it is cloned.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Tracked(BigInt);
+
+impl Clone for Tracked {
+    fn clone(&self) -> Self {
+        CLONES.with(|clones| clones.set(clones.get() + 1));
+        Tracked(self.0.clone())
+    }
+}
+
+impl<'a> Add<&'a Tracked> for &'a Tracked {
+    type Output = Tracked;
+    fn add(self, other: &'a Tracked) -> Tracked {
+        Tracked(&self.0 + &other.0)
+    }
+}
+
+/// Check that all algorithms agree on a Monge matrix whose elements
+/// do not fit in any primitive integer type, and that the elements
+/// are never cloned while the minima are computed.
+#[test]
+fn bigint_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let matrix = random_monge_matrix_big(100, 100, 256, &mut rng);
+    assert!(matrix.iter().any(|element| element.bits() > 128));
+    let tracked = matrix.map(|element| Tracked(element.clone()));
+    let by_ref = ByRef(&tracked);
+
+    CLONES.with(|clones| clones.set(0));
+    assert!(is_monge_unbounded::<Tracked, _>(&by_ref));
+
+    let row_minima = smawk::row_minima(&by_ref);
+    assert_eq!(row_minima, brute_force::row_minima(&matrix));
+    assert_eq!(row_minima, recursive::row_minima(&matrix));
+
+    let column_minima = smawk::column_minima(&by_ref);
+    assert_eq!(column_minima, brute_force::column_minima(&matrix));
+    assert_eq!(column_minima, recursive::column_minima(&matrix));
+    assert_eq!(smawk::all_minima(&by_ref), (row_minima, column_minima));
+
+    // The online algorithm only looks above the diagonal.
+    let initial = Tracked(BigInt::from(0));
+    let online = online_column_minima(&initial, 100, |_, i, j| &tracked[[i, j]]);
+    for (j, &(row, value)) in online.iter().enumerate().skip(1) {
+        let expected = (0..j).min_by_key(|&i| (&tracked[[i, j]], i)).unwrap();
+        assert_eq!((row, value), (expected, &tracked[[expected, j]]));
+    }
+    assert_eq!(CLONES.with(Cell::get), 0);
+}
+
+/// Check that a violation of the Monge property is found in a matrix
+/// of big integers.
+#[test]
+fn bigint_not_monge() {
+    let mut rng = ChaCha20Rng::seed_from_u64(1);
+    let mut matrix: Array2<BigInt> = random_monge_matrix_big(10, 10, 200, &mut rng);
+    assert!(is_monge_unbounded::<BigInt, _>(&ByRef(&matrix)));
+    // Raising the top-left corner of a 2 ✕ 2 window above the sum of
+    // its anti-diagonal violates the Monge property.
+    let excess = &matrix[[4, 5]] + &matrix[[5, 4]] + 1;
+    matrix[[4, 4]] += excess;
+    assert!(!is_monge_unbounded::<BigInt, _>(&ByRef(&matrix)));
+}
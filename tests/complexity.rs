@@ -3,10 +3,10 @@
 use ndarray::{Array1, Array2};
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
-use smawk::online_column_minima;
+use smawk::{online_column_minima, online_column_minima_2best};
 
 mod random_monge;
-use random_monge::random_monge_matrix;
+use random_monge::{random_monge_matrix, random_monge_matrix_from, MONGE_PRIMITIVES};
 
 #[derive(Debug)]
 struct LinRegression {
@@ -81,3 +81,77 @@ fn online_linear_complexity() {
         lin_reg
     );
 }
+
+/// Like [`online_linear_complexity`], but with matrices built from all
+/// Monge primitives.
+#[test]
+fn online_linear_complexity_monge_primitives() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut data = vec![];
+
+    for &size in &[1, 2, 3, 4, 5, 10, 15, 20, 30, 40, 50, 60, 70, 80, 90, 100] {
+        // The number of accesses depends on the structure of the
+        // matrix, so it is summed over several matrices.
+        let count = std::cell::RefCell::new(0);
+        for _ in 0..10 {
+            let matrix: Array2<i32> =
+                random_monge_matrix_from(size, size, &MONGE_PRIMITIVES, &mut rng);
+            online_column_minima(0, size, |_, i, j| {
+                *count.borrow_mut() += 1;
+                matrix[[i, j]]
+            });
+        }
+        data.push((size, count.into_inner()));
+    }
+
+    let lin_reg = linear_regression(&data);
+    assert!(
+        lin_reg.r_squared > 0.95,
+        "r² = {:.4} is lower than expected for a linear fit\nData points: {:?}\n{:?}",
+        lin_reg.r_squared,
+        data,
+        lin_reg
+    );
+}
+
+/// Check that finding the runner-ups in `online_column_minima_2best`
+/// costs O(*n* log *n*) matrix accesses on top of the column minima.
+#[test]
+fn online_2best_complexity() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut data = vec![];
+
+    for &size in &[10, 20, 50, 100, 200, 500, 1000] {
+        let matrix: Array2<i32> = random_monge_matrix(size, size, &mut rng);
+        let count = std::cell::RefCell::new(0);
+        online_column_minima_2best(0, size, |_, i, j| {
+            *count.borrow_mut() += 1;
+            matrix[[i, j]]
+        });
+        let extra = count.into_inner();
+        let count = std::cell::RefCell::new(0);
+        online_column_minima(0, size, |_, i, j| {
+            *count.borrow_mut() += 1;
+            matrix[[i, j]]
+        });
+        let extra = extra - count.into_inner();
+
+        let log = (size as f64).log2().ceil() as i32;
+        assert!(
+            extra <= 6 * size as i32 * log,
+            "{} extra accesses for size {}",
+            extra,
+            size
+        );
+        data.push((size, extra / log));
+    }
+
+    let lin_reg = linear_regression(&data);
+    assert!(
+        lin_reg.r_squared > 0.95,
+        "r² = {:.4} is lower than expected for a linear fit\nData points: {:?}\n{:?}",
+        lin_reg.r_squared,
+        data,
+        lin_reg
+    );
+}
@@ -1,12 +1,24 @@
 #![cfg(feature = "ndarray")]
 
-use ndarray::{arr2, Array, Array2};
-use rand::SeedableRng;
+use ndarray::{arr2, s, Array, Array2, Array3};
+use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
-use smawk::monge::is_monge;
+use smawk::monge::{
+    hankel_monge, is_concave_sequence, is_inverse_monge, is_kalmanson, is_monge, is_monge_3d,
+    is_monge_by_windows, is_monge_f32, is_monge_f64_exact, is_monge_infinitary, is_monge_interval,
+    is_monge_multiplicative, is_totally_monotone, monge_decompose, monge_hstack, monge_project,
+    monge_vstack, recognize_permuted_monge, verify_kalmanson, verify_monge_3d,
+    verify_monge_f64_exact, DynamicMinima, KalmansonViolation, LogDomain, MaxOfMatrices,
+    MinOfMatrices, MongeDecomposeError, MongeF64Error, MongeMatrix, MongeMatrixError,
+    MongeProjectionError, MongeRmq, MongeViolation, PermutedMatrix,
+};
+use smawk::{Infinitary, Interval, Matrix};
 
 mod random_monge;
-use random_monge::{random_monge_matrix, MongePrim};
+use random_monge::{
+    analytic_column_minima, random_kalmanson_matrix, random_monge_3d, random_monge_matrix,
+    random_monge_terms, MongePrim, MONGE_PRIMITIVES,
+};
 
 #[test]
 fn random_monge() {
@@ -26,6 +38,231 @@ fn random_monge() {
     );
 }
 
+#[test]
+fn random_monge_small_types() {
+    // Large matrices are built from many primitives and their sum
+    // must not overflow the element type.
+    for seed in 0..4 {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        for &(m, n) in &[(1, 1), (10, 50), (50, 50), (200, 100)] {
+            let matrix: Array2<u8> = random_monge_matrix(m, n, &mut rng);
+            assert!(is_monge(&matrix), "u8 matrix of size {}x{}", m, n);
+            let matrix: Array2<i8> = random_monge_matrix(m, n, &mut rng);
+            assert!(is_monge(&matrix), "i8 matrix of size {}x{}", m, n);
+            let matrix: Array2<u16> = random_monge_matrix(m, n, &mut rng);
+            assert!(is_monge(&matrix), "u16 matrix of size {}x{}", m, n);
+        }
+    }
+}
+
+#[test]
+fn analytic_column_minima_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(m, n) in &[(1, 1), (1, 7), (7, 1), (5, 5), (20, 30), (30, 20)] {
+        for _ in 0..50 {
+            // Single primitives, where the minima are easy to see.
+            for term in random_monge_terms(m, n, 1, 1000, &mut rng) {
+                let matrix: Array2<i32> = term.to_matrix(m, n);
+                assert_eq!(
+                    analytic_column_minima(std::slice::from_ref(&term), m, n),
+                    smawk::brute_force::column_minima(&matrix),
+                    "{:?}",
+                    term
+                );
+            }
+
+            // Sums of primitives.
+            let terms = random_monge_terms(m, n, m + n, 1000, &mut rng);
+            let mut matrix = Array2::<i32>::zeros((m, n));
+            for term in &terms {
+                matrix = matrix + term.to_matrix::<i32>(m, n);
+            }
+            let minima = analytic_column_minima(&terms, m, n);
+            assert_eq!(minima, smawk::brute_force::column_minima(&matrix));
+            assert_eq!(minima, smawk::column_minima(&matrix));
+        }
+    }
+}
+
+#[test]
+fn is_monge_implementations_agree() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut results = [0; 2];
+    for _ in 0..2000 {
+        let (m, n) = (rng.gen_range(0..8), rng.gen_range(0..8));
+        // Large u8 and i8 elements overflow when added, and a small
+        // perturbation of a Monge matrix is sometimes still Monge.
+        let matrix: Array2<u8> = Array2::from_shape_fn((m, n), |_| rng.gen_range(120..=255));
+        let expected = is_monge_by_windows(&matrix);
+        assert_eq!(is_monge(&matrix), expected, "{:?}", matrix);
+        results[expected as usize] += 1;
+
+        let matrix: Array2<i8> = Array2::from_shape_fn((m, n), |_| rng.gen());
+        assert_eq!(
+            is_monge(&matrix),
+            is_monge_by_windows(&matrix),
+            "{:?}",
+            matrix
+        );
+
+        let mut matrix: Array2<u8> = random_monge_matrix(m, n, &mut rng);
+        if m > 0 && n > 0 {
+            let (i, j) = (rng.gen_range(0..m), rng.gen_range(0..n));
+            matrix[[i, j]] = matrix[[i, j]].wrapping_add(rng.gen_range(0..3));
+        }
+        let expected = is_monge_by_windows(&matrix);
+        assert_eq!(is_monge(&matrix), expected, "{:?}", matrix);
+        results[expected as usize] += 1;
+    }
+    assert!(results.iter().all(|&count| count > 100), "{:?}", results);
+}
+
+#[test]
+fn is_monge_negative_values() {
+    assert!(!is_monge(&vec![vec![0i64, -5], vec![-5, 0]]));
+    assert!(is_monge(&vec![vec![-5i64, 0], vec![0, -5]]));
+    // Both sums overflow below i8::MIN, or one overflows in each
+    // direction.
+    assert!(is_monge(&vec![vec![-128i8, -128], vec![-128, -128]]));
+    assert!(is_monge(&vec![vec![-128i8, 127], vec![127, -128]]));
+    assert!(!is_monge(&vec![vec![127i8, -128], vec![-128, 127]]));
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut results = [0; 2];
+    for _ in 0..2000 {
+        let (m, n) = (rng.gen_range(2..4), rng.gen_range(2..4));
+        let matrix: Array2<i8> = Array2::from_shape_fn((m, n), |_| rng.gen());
+        let wide = matrix.mapv(i16::from);
+        let expected = (0..m - 1).all(|i| {
+            (0..n - 1)
+                .all(|j| wide[[i, j]] + wide[[i + 1, j + 1]] <= wide[[i, j + 1]] + wide[[i + 1, j]])
+        });
+        assert_eq!(is_monge(&matrix), expected, "{:?}", matrix);
+        assert_eq!(is_monge_by_windows(&matrix), expected, "{:?}", matrix);
+        results[expected as usize] += 1;
+    }
+    assert!(results.iter().all(|&count| count > 100), "{:?}", results);
+}
+
+#[test]
+fn inverse_monge_of_negated_matrices() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut both = 0;
+    for _ in 0..500 {
+        let (m, n) = (rng.gen_range(1..6), rng.gen_range(1..6));
+        let matrix: Array2<u8> = random_monge_matrix(m, n, &mut rng);
+        // Subtracting from a constant negates the matrix without
+        // leaving the unsigned range.
+        let negated = matrix.mapv(|x| u8::MAX - x);
+        assert!(is_inverse_monge(&negated), "{:?}", negated);
+
+        // A Monge matrix is only inverse Monge when all the
+        // inequalities are equalities.
+        let modular = (0..m - 1).all(|i| {
+            (0..n - 1).all(|j| {
+                let diagonal = u16::from(matrix[[i, j]]) + u16::from(matrix[[i + 1, j + 1]]);
+                diagonal == u16::from(matrix[[i, j + 1]]) + u16::from(matrix[[i + 1, j]])
+            })
+        });
+        assert_eq!(is_inverse_monge(&matrix), modular, "{:?}", matrix);
+        both += modular as usize;
+    }
+    assert!(both > 0);
+    let constant = Array2::from_elem((4, 5), 200u8);
+    assert!(is_monge(&constant) && is_inverse_monge(&constant));
+}
+
+#[test]
+fn inverse_monge_overflow() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut results = [0; 2];
+    for _ in 0..2000 {
+        let (m, n) = (rng.gen_range(0..8), rng.gen_range(0..8));
+        let matrix: Array2<u8> = Array2::from_shape_fn((m, n), |_| rng.gen_range(120..=255));
+        // Reversing the columns turns inverse Monge into Monge.
+        let expected = is_monge_by_windows(&matrix.slice(s![.., ..;-1]).to_owned());
+        assert_eq!(is_inverse_monge(&matrix), expected, "{:?}", matrix);
+        results[expected as usize] += 1;
+    }
+    assert!(results.iter().all(|&count| count > 100), "{:?}", results);
+}
+
+#[test]
+fn monge_interval_endpoints() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..100 {
+        let (m, n) = (rng.gen_range(2..8), rng.gen_range(2..8));
+        let lo: Array2<u32> = random_monge_matrix(m, n, &mut rng);
+        let width: Array2<u32> = random_monge_matrix(m, n, &mut rng);
+        let mut matrix = Array2::from_shape_fn((m, n), |(i, j)| Interval {
+            lo: lo[[i, j]],
+            hi: lo[[i, j]] + width[[i, j]],
+        });
+        assert!(is_monge_interval(&matrix));
+
+        // Raising an element on the main diagonal of the last window
+        // breaks the Monge property of the upper bounds only.
+        let (i, j) = (m - 1, n - 1);
+        matrix[[i, j]].hi = matrix[[i - 1, j]].hi + matrix[[i, j - 1]].hi + 1;
+        assert!(!is_monge_interval(&matrix));
+        assert!(is_monge(&matrix.mapv(|x| x.lo)));
+    }
+}
+
+#[test]
+fn monge_infinitary_masks() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..100 {
+        let (m, n) = (rng.gen_range(2..8), rng.gen_range(2..8));
+        let values: Array2<u32> = random_monge_matrix(m, n, &mut rng);
+        let offset = rng.gen_range(-3..=3);
+        let mut matrix = Array2::from_shape_fn((m, n), |(i, j)| {
+            if j as i64 >= i as i64 - offset {
+                Infinitary::Finite(values[[i, j]])
+            } else {
+                Infinitary::PosInf
+            }
+        });
+        assert!(is_monge_infinitary(&matrix));
+        assert!(is_monge_infinitary(
+            &matrix.mapv(|_| Infinitary::<u32>::PosInf)
+        ));
+
+        // A forbidden element on the main diagonal of a window with
+        // finite anti-diagonal elements breaks the Monge property.
+        let (i, j) = (m - 1, n - 1);
+        matrix[[i - 1, j]] = Infinitary::Finite(0);
+        matrix[[i, j - 1]] = Infinitary::Finite(0);
+        matrix[[i, j]] = Infinitary::PosInf;
+        assert!(!is_monge_infinitary(&matrix));
+    }
+}
+
+#[test]
+fn monge_primitives() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for monge in MONGE_PRIMITIVES {
+        for &(m, n) in &[
+            (0, 0),
+            (0, 5),
+            (5, 0),
+            (1, 1),
+            (1, 5),
+            (5, 1),
+            (5, 4),
+            (30, 20),
+        ] {
+            for _ in 0..20 {
+                let matrix: Array2<u8> = monge.to_matrix(m, n, &mut rng);
+                assert!(is_monge(&matrix), "{:?} {:?}", monge, matrix);
+                let matrix: Array2<i64> = monge.to_matrix(m, n, &mut rng);
+                assert!(is_monge(&matrix), "{:?} {:?}", monge, matrix);
+                assert_eq!(matrix.dim(), (m, n));
+            }
+        }
+    }
+}
+
 #[test]
 fn monge_constant_rows() {
     let mut rng = ChaCha20Rng::seed_from_u64(0);
@@ -81,3 +318,1049 @@ fn monge_lower_left_ones() {
         ])
     );
 }
+
+#[test]
+fn monge_decompose_round_trip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(m, n) in &[(1, 1), (1, 5), (5, 1), (2, 3), (10, 10), (20, 7)] {
+        let matrix: Array2<i32> = random_monge_matrix(m, n, &mut rng);
+        let certificate = monge_decompose(&matrix).unwrap();
+        let reconstructed = Array2::from_shape_fn((m, n), |(i, j)| certificate.entry(i, j));
+        assert_eq!(reconstructed, matrix);
+    }
+}
+
+#[test]
+fn monge_decompose_rejects_perturbed() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..20 {
+        let mut matrix: Array2<i32> = random_monge_matrix(6, 6, &mut rng);
+        // Raising a bottom-right corner beyond its anti-diagonal
+        // breaks the Monge property in the window above-left of it.
+        let slack = matrix[[2, 4]] + matrix[[3, 3]] - matrix[[2, 3]] - matrix[[3, 4]];
+        matrix[[3, 4]] += slack + 1;
+        assert!(!is_monge(&matrix));
+        assert!(matches!(
+            monge_decompose(&matrix),
+            Err(MongeDecomposeError::NotMonge(_))
+        ));
+    }
+}
+
+#[test]
+fn random_monge_3d_is_monge() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(l, m, n) in &[(1, 1, 1), (1, 4, 3), (2, 2, 2), (5, 4, 3), (6, 6, 6)] {
+        let array: Array3<u8> = random_monge_3d(l, m, n, &mut rng);
+        assert!(is_monge_3d(&array), "not Monge:\n{:?}", array);
+    }
+}
+
+#[test]
+fn monge_3d_in_two_orientations() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    // Adding a non-Monge matrix along axes (0, 2) keeps the array
+    // Monge in planes (0, 1) and (1, 2), but breaks plane (0, 2).
+    let mut array: Array3<i32> = random_monge_3d(4, 4, 4, &mut rng);
+    for i in 0..4 {
+        for j in 0..4 {
+            array[[i, j, 3]] += (i == 3) as i32 * 1000;
+        }
+    }
+    assert!(!is_monge_3d(&array));
+    let violation = verify_monge_3d(&array).unwrap_err();
+    assert_eq!(violation.axes, (0, 2));
+    assert_eq!(violation.index, [2, 0, 2]);
+}
+
+#[test]
+fn min_of_nested_matrices() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..20 {
+        let (m, n) = (rng.gen_range(1..15), rng.gen_range(1..15));
+        // Each matrix is the minimum of the preceding ones plus a
+        // monotone Monge difference, which keeps the minimum Monge.
+        let mut matrices = vec![random_monge_matrix::<_, i32>(m, n, &mut rng)];
+        for k in 0..3 {
+            let min = MinOfMatrices::new(&matrices);
+            let mut x = (0..m).map(|_| rng.gen_range(-5..5)).collect::<Vec<i32>>();
+            let mut y = (0..n).map(|_| rng.gen_range(-5..5)).collect::<Vec<i32>>();
+            x.sort_unstable();
+            y.sort_unstable();
+            let sign = if k % 2 == 0 { 1 } else { -1 };
+            let difference = Array2::from_shape_fn((m, n), |(i, j)| sign * (x[i] + y[j]));
+            matrices.push(Array2::from_shape_fn((m, n), |(i, j)| {
+                smawk::Matrix::index(&min, i, j) + difference[[i, j]]
+            }));
+        }
+
+        let min = MinOfMatrices::new(&matrices);
+        let materialized = Array2::from_shape_fn((m, n), |(i, j)| smawk::Matrix::index(&min, i, j));
+        assert!(is_monge(&materialized), "not Monge:\n{:?}", materialized);
+        assert!(is_totally_monotone(&min));
+        assert_eq!(
+            smawk::column_minima(&min),
+            smawk::brute_force::column_minima(&materialized)
+        );
+        assert_eq!(
+            smawk::row_minima(&min),
+            smawk::brute_force::row_minima(&materialized)
+        );
+    }
+}
+
+#[test]
+fn min_of_matrices_not_totally_monotone() {
+    // Two Monge matrices whose minimum is not totally monotone.
+    let a = arr2(&[[9, 0], [9, 0]]);
+    let b = arr2(&[[9, 9], [0, 0]]);
+    assert!(is_monge(&a) && is_monge(&b));
+    let matrices = [a, b];
+    assert!(!is_totally_monotone(&MinOfMatrices::new(&matrices)));
+}
+
+#[test]
+fn max_of_shifted_matrices() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..20 {
+        let (m, n) = (rng.gen_range(1..15), rng.gen_range(1..15));
+        // Each matrix is the maximum of the preceding ones plus a
+        // difference x[i] + y[j] with x and y sorted in opposite
+        // directions, which keeps the maximum Monge.
+        let mut matrices = vec![random_monge_matrix::<_, i32>(m, n, &mut rng)];
+        for k in 0..3 {
+            let max = MaxOfMatrices::new(&matrices);
+            let mut x = (0..m).map(|_| rng.gen_range(-5..5)).collect::<Vec<i32>>();
+            let mut y = (0..n).map(|_| rng.gen_range(-5..5)).collect::<Vec<i32>>();
+            x.sort_unstable();
+            y.sort_unstable();
+            if k % 2 == 0 {
+                y.reverse();
+            } else {
+                x.reverse();
+            }
+            matrices.push(Array2::from_shape_fn((m, n), |(i, j)| {
+                smawk::Matrix::index(&max, i, j) + x[i] + y[j]
+            }));
+        }
+
+        let max = MaxOfMatrices::new(&matrices);
+        let materialized = Array2::from_shape_fn((m, n), |(i, j)| smawk::Matrix::index(&max, i, j));
+        assert!(is_monge(&materialized), "not Monge:\n{:?}", materialized);
+        assert!(is_totally_monotone(&max));
+        assert_eq!(
+            smawk::column_minima(&max),
+            smawk::brute_force::column_minima(&materialized)
+        );
+        assert_eq!(
+            smawk::row_minima(&max),
+            smawk::brute_force::row_minima(&materialized)
+        );
+    }
+}
+
+#[test]
+fn max_of_matrices_not_totally_monotone() {
+    // Two Monge matrices whose maximum is not totally monotone.
+    let a = arr2(&[[0, 0], [1, 1]]);
+    let b = arr2(&[[1, 0], [1, 0]]);
+    assert!(is_monge(&a) && is_monge(&b));
+    let matrices = [a, b];
+    assert!(!is_totally_monotone(&MaxOfMatrices::new(&matrices)));
+}
+
+#[test]
+fn monge_matrix_arithmetic_preserves_monge() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..200 {
+        let (m, n) = (rng.gen_range(1..10), rng.gen_range(1..10));
+        let a = MongeMatrix::new(random_monge_matrix::<_, u16>(m, n, &mut rng)).unwrap();
+        let b = MongeMatrix::new(random_monge_matrix::<_, u16>(m, n, &mut rng)).unwrap();
+        let rows = (0..m).map(|_| rng.gen_range(0..100)).collect::<Vec<u16>>();
+        let columns = (0..n).map(|_| rng.gen_range(0..100)).collect::<Vec<u16>>();
+
+        let results = [
+            &a + &b,
+            a.add_constant(rng.gen_range(0..100)),
+            a.add_potentials(&rows, &columns),
+            a.scale(rng.gen_range(0..20)),
+        ];
+        for result in results {
+            let matrix = result.unwrap();
+            assert!(is_monge(matrix.as_array()), "{:?}", matrix);
+        }
+    }
+}
+
+#[test]
+fn monge_matrix_overflow_is_an_error() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..50 {
+        let matrix: Array2<u8> = random_monge_matrix(4, 4, &mut rng);
+        let max = *matrix.iter().max().unwrap();
+        let a = MongeMatrix::new(matrix).unwrap();
+        assert_eq!(
+            a.add_constant(u8::MAX - max + 1),
+            Err(MongeMatrixError::Overflow)
+        );
+        assert_eq!(a.scale(u8::MAX), Err(MongeMatrixError::Overflow));
+        assert_eq!(
+            a.add_potentials(&[0, 0, 0, u8::MAX], &[0; 4]),
+            Err(MongeMatrixError::Overflow)
+        );
+    }
+    // The sums of neighboring elements must fit as well.
+    let array = arr2(&[[100i8, 100], [100, 100]]);
+    assert_eq!(MongeMatrix::new(array), Err(MongeMatrixError::Overflow));
+    let a = MongeMatrix::new(Array2::from_elem((2, 2), u8::MAX / 2)).unwrap();
+    assert_eq!(&a + &a, Err(MongeMatrixError::Overflow));
+}
+
+#[test]
+fn monge_matrix_rejects_non_monge() {
+    let array = arr2(&[[0i32, 0, 0], [0, 0, 0], [0, 1, 0]]);
+    assert_eq!(
+        MongeMatrix::new(array),
+        Err(MongeMatrixError::NotMonge(smawk::monge::MongeViolation {
+            row: 1,
+            column: 0
+        }))
+    );
+}
+
+#[test]
+fn hankel_monge_exactly_when_concave() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut concave = 0;
+    for _ in 0..2000 {
+        let len = rng.gen_range(3..10);
+        // Sorting decreasing differences gives a concave sequence,
+        // which is sometimes perturbed.
+        let mut differences = (0..len)
+            .map(|_| rng.gen_range(-10..10))
+            .collect::<Vec<i32>>();
+        differences.sort_unstable_by(|a, b| b.cmp(a));
+        let mut a = differences
+            .iter()
+            .scan(1000, |sum, d| {
+                *sum += d;
+                Some(*sum)
+            })
+            .collect::<Vec<_>>();
+        if rng.gen_bool(0.5) {
+            let i = rng.gen_range(0..len);
+            a[i] += rng.gen_range(-2..=2);
+        }
+
+        // Every window of a occurs with two rows and two columns.
+        let matrix = hankel_monge(&a, rng.gen_range(2..len));
+        assert_eq!(is_monge(&matrix), is_concave_sequence(&a), "{:?}", a);
+        concave += is_concave_sequence(&a) as usize;
+    }
+    assert!(
+        (100..1900).contains(&concave),
+        "{} concave sequences",
+        concave
+    );
+}
+
+/// Verify the Monge inequality for a float matrix, allowing each
+/// 2 ✕ 2 submatrix to violate it by at most `tolerance`.
+fn is_monge_with_tolerance(matrix: &Array2<f64>, tolerance: f64) -> bool {
+    let (m, n) = matrix.dim();
+    (0..m.saturating_sub(1)).all(|i| {
+        (0..n.saturating_sub(1)).all(|j| {
+            matrix[(i, j)] + matrix[(i + 1, j + 1)]
+                <= matrix[(i, j + 1)] + matrix[(i + 1, j)] + tolerance
+        })
+    })
+}
+
+#[test]
+fn monge_project_repairs_rounding_errors() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(m, n, noise, epsilon) in &[
+        (1, 1, 1e-4, 1e-3),
+        (1, 10, 1e-4, 1e-3),
+        (10, 1, 1e-4, 1e-3),
+        (2, 2, 1e-4, 1e-3),
+        (10, 10, 1e-4, 1e-3),
+        (50, 30, 1e-4, 1e-3),
+        (200, 200, 1e-9, 1e-6),
+    ] {
+        let monge: Array2<i32> = random_monge_matrix(m, n, &mut rng);
+        let noisy = monge.mapv(|v| v as f64 + rng.gen_range(-noise..noise));
+        if m > 2 && n > 2 {
+            assert!(!is_monge_with_tolerance(&noisy, 0.0), "{}x{}", m, n);
+        }
+
+        let projected = monge_project(&noisy, epsilon).unwrap();
+        assert!(is_monge_with_tolerance(&projected, 1e-9), "{}x{}", m, n);
+        assert!(projected
+            .iter()
+            .zip(&noisy)
+            .all(|(a, b)| (a - b).abs() <= epsilon));
+    }
+}
+
+#[test]
+fn monge_project_leaves_monge_matrices_alone() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let matrix = random_monge_matrix::<_, i32>(20, 20, &mut rng).mapv(f64::from);
+    assert_eq!(monge_project(&matrix, 0.0), Ok(matrix));
+}
+
+#[test]
+fn monge_project_rejects_large_violations() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut matrix = random_monge_matrix::<_, i32>(10, 10, &mut rng).mapv(f64::from);
+    matrix[(4, 6)] += 1.0;
+    assert!(!is_monge_with_tolerance(&matrix, 0.0));
+    assert_eq!(
+        monge_project(&matrix, 0.01),
+        Err(MongeProjectionError { row: 3, column: 0 })
+    );
+    assert!(monge_project(&matrix, 2.0).is_ok());
+
+    matrix[(4, 6)] = f64::NAN;
+    assert!(monge_project(&matrix, 2.0).is_err());
+}
+
+/// Check the Kalmanson inequalities directly on all quadruples.
+fn is_kalmanson_brute_force(matrix: &Array2<i64>) -> bool {
+    let n = matrix.nrows();
+    let m = |i, j| matrix[(i, j)];
+    matrix == matrix.t()
+        && (0..n).all(|i| {
+            (i + 1..n).all(|j| {
+                (j + 1..n).all(|k| {
+                    (k + 1..n).all(|l| {
+                        m(i, j) + m(k, l) <= m(i, k) + m(j, l)
+                            && m(i, l) + m(j, k) <= m(i, k) + m(j, l)
+                    })
+                })
+            })
+        })
+}
+
+#[test]
+fn random_kalmanson_is_kalmanson() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for n in [0, 1, 2, 3, 4, 5, 10, 50, 200] {
+        let matrix = random_kalmanson_matrix(n, &mut rng);
+        assert_eq!(verify_kalmanson(&matrix), Ok(()), "n = {}", n);
+    }
+}
+
+#[test]
+fn kalmanson_agrees_with_brute_force() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut kalmanson = 0;
+    for _ in 0..2000 {
+        let n = rng.gen_range(0..9);
+        let mut matrix = random_kalmanson_matrix(n, &mut rng);
+        if n > 1 && rng.gen::<bool>() {
+            // Swap two points or replace one of the distances.
+            let (i, j) = (rng.gen_range(0..n), rng.gen_range(0..n));
+            if rng.gen::<bool>() {
+                for axis in [ndarray::Axis(0), ndarray::Axis(1)] {
+                    let (a, b) = (
+                        matrix.index_axis(axis, i).to_owned(),
+                        matrix.index_axis(axis, j).to_owned(),
+                    );
+                    matrix.index_axis_mut(axis, i).assign(&b);
+                    matrix.index_axis_mut(axis, j).assign(&a);
+                }
+            } else if i != j {
+                let distance = rng.gen_range(0..2_000_000_000);
+                matrix[(i, j)] = distance;
+                matrix[(j, i)] = distance;
+            }
+        }
+        let expected = is_kalmanson_brute_force(&matrix);
+        assert_eq!(is_kalmanson(&matrix), expected, "{:?}", matrix);
+        kalmanson += expected as usize;
+    }
+    // Both outcomes are well represented.
+    assert!(200 < kalmanson && kalmanson < 1800, "{}", kalmanson);
+}
+
+#[test]
+fn kalmanson_violations() {
+    let matrix = arr2(&[[0, 1, 2], [1, 0, 1], [3, 1, 0]]);
+    assert_eq!(
+        verify_kalmanson(&matrix),
+        Err(KalmansonViolation::NotSymmetric { row: 0, column: 2 })
+    );
+    let matrix = Array2::<i64>::zeros((2, 3));
+    assert_eq!(
+        verify_kalmanson(&matrix),
+        Err(KalmansonViolation::NotSquare)
+    );
+}
+
+#[test]
+fn kalmanson_identity_tour_is_optimal() {
+    // Visiting the points in order is an optimal tour, and pyramidal
+    // tours in particular cannot do better.
+    fn tour_length(matrix: &Array2<i64>, tour: &[usize]) -> i64 {
+        let n = tour.len();
+        (0..n).map(|k| matrix[(tour[k], tour[(k + 1) % n])]).sum()
+    }
+
+    fn permutations(items: &mut Vec<usize>, k: usize, visit: &mut impl FnMut(&[usize])) {
+        if k == items.len() {
+            visit(items);
+        }
+        for i in k..items.len() {
+            items.swap(k, i);
+            permutations(items, k + 1, visit);
+            items.swap(k, i);
+        }
+    }
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for n in 3..8 {
+        for _ in 0..5 {
+            let matrix = random_kalmanson_matrix(n, &mut rng);
+            assert!(is_kalmanson(&matrix));
+            let identity = (0..n).collect::<Vec<_>>();
+            let best = tour_length(&matrix, &identity);
+            permutations(&mut identity.clone(), 1, &mut |tour| {
+                assert!(best <= tour_length(&matrix, tour), "{:?}", tour);
+            });
+        }
+    }
+}
+
+#[test]
+fn kalmanson_blocks_are_monge() {
+    // The distances from the points before a split to the points after
+    // it form a Monge matrix once the columns are reversed, so SMAWK
+    // can search them.
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for n in [2, 5, 20, 100] {
+        let matrix = random_kalmanson_matrix(n, &mut rng);
+        for split in 1..n {
+            let block = matrix.slice(ndarray::s![..split, split..;-1]).to_owned();
+            assert!(is_monge(&block), "n = {}, split = {}", n, split);
+        }
+    }
+}
+
+/// All permutations of `0..n`.
+fn all_permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut result = Vec::new();
+    for permutation in all_permutations(n - 1) {
+        for k in 0..n {
+            let mut longer = permutation.clone();
+            longer.insert(k, n - 1);
+            result.push(longer);
+        }
+    }
+    result
+}
+
+#[test]
+fn recognize_shuffled_monge() {
+    use rand::seq::SliceRandom;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(m, n) in &[
+        (0, 0),
+        (1, 1),
+        (1, 5),
+        (5, 1),
+        (2, 2),
+        (5, 5),
+        (10, 20),
+        (40, 30),
+    ] {
+        let monge: Array2<i32> = random_monge_matrix(m, n, &mut rng);
+        let mut shuffled_rows = (0..m).collect::<Vec<_>>();
+        let mut shuffled_columns = (0..n).collect::<Vec<_>>();
+        shuffled_rows.shuffle(&mut rng);
+        shuffled_columns.shuffle(&mut rng);
+        let matrix = Array2::from_shape_fn((m, n), |(i, j)| {
+            monge[(shuffled_rows[i], shuffled_columns[j])]
+        });
+
+        let (rows, columns) = recognize_permuted_monge(&matrix).expect("permuted Monge matrix");
+        let mut sorted = rows.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..m).collect::<Vec<_>>());
+        let mut sorted = columns.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..n).collect::<Vec<_>>());
+
+        let permuted = PermutedMatrix::new(&matrix, &rows, &columns);
+        assert!(is_monge(&permuted), "{}x{}", m, n);
+        if m > 0 && n > 0 {
+            // The minima found through the permutation are the minima
+            // of the shuffled matrix.
+            for (j, i) in smawk::column_minima(&permuted).into_iter().enumerate() {
+                let column = matrix.column(columns[j]);
+                assert_eq!(matrix[(rows[i], columns[j])], *column.iter().min().unwrap());
+            }
+        }
+    }
+}
+
+#[test]
+fn recognize_permuted_monge_agrees_with_brute_force() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut recognized = 0;
+    for _ in 0..300 {
+        let (m, n) = (rng.gen_range(1..5), rng.gen_range(1..5));
+        let matrix = Array2::from_shape_fn((m, n), |_| rng.gen_range(0..4));
+        let exists = all_permutations(m).iter().any(|rows| {
+            all_permutations(n)
+                .iter()
+                .any(|columns| is_monge(&PermutedMatrix::new(&matrix, rows, columns)))
+        });
+        let found = recognize_permuted_monge(&matrix);
+        assert_eq!(found.is_some(), exists, "{:?}", matrix);
+        if let Some((rows, columns)) = found {
+            assert!(is_monge(&PermutedMatrix::new(&matrix, &rows, &columns)));
+            recognized += 1;
+        }
+    }
+    // Both outcomes are well represented.
+    assert!(50 < recognized && recognized < 250, "{}", recognized);
+}
+
+#[test]
+fn recognize_permuted_monge_extreme_i8() {
+    // Sums of these elements overflow i8 in both directions.
+    let elements = [i8::MIN, i8::MIN + 1, -1, 0, i8::MAX - 1, i8::MAX];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut recognized = 0;
+    for _ in 0..300 {
+        let (m, n) = (rng.gen_range(1..4), rng.gen_range(1..4));
+        let matrix = Array2::from_shape_fn((m, n), |_| elements[rng.gen_range(0..6)]);
+        let exists = all_permutations(m).iter().any(|rows| {
+            all_permutations(n)
+                .iter()
+                .any(|columns| is_monge(&PermutedMatrix::new(&matrix, rows, columns)))
+        });
+        let found = recognize_permuted_monge(&matrix);
+        assert_eq!(found.is_some(), exists, "{:?}", matrix);
+        if let Some((rows, columns)) = found {
+            assert!(is_monge(&PermutedMatrix::new(&matrix, &rows, &columns)));
+            recognized += 1;
+        }
+    }
+    assert!(recognized > 0);
+}
+
+fn inverse_permutation(permutation: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; permutation.len()];
+    for (i, &p) in permutation.iter().enumerate() {
+        inverse[p] = i;
+    }
+    inverse
+}
+
+#[test]
+fn permuted_matrix_minima_map_back() {
+    use rand::seq::SliceRandom;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..100 {
+        let (m, n) = (rng.gen_range(1..20), rng.gen_range(1..20));
+        let monge: Array2<u8> = random_monge_matrix(m, n, &mut rng);
+        let mut shuffled_rows = (0..m).collect::<Vec<_>>();
+        let mut shuffled_columns = (0..n).collect::<Vec<_>>();
+        shuffled_rows.shuffle(&mut rng);
+        shuffled_columns.shuffle(&mut rng);
+        let matrix = Array2::from_shape_fn((m, n), |(i, j)| {
+            monge[(shuffled_rows[i], shuffled_columns[j])]
+        });
+
+        let rows = inverse_permutation(&shuffled_rows);
+        let columns = inverse_permutation(&shuffled_columns);
+        let permuted = PermutedMatrix::try_new(&matrix, &rows, &columns).unwrap();
+        let copy = Array2::from_shape_fn((m, n), |(i, j)| matrix[(rows[i], columns[j])]);
+        assert_eq!(copy, monge);
+
+        let column_minima = smawk::column_minima(&permuted);
+        assert_eq!(column_minima, smawk::column_minima(&copy));
+        let original = permuted.original_column_minima(&column_minima);
+        for (j, &i) in original.iter().enumerate() {
+            assert_eq!(matrix[(i, j)], *matrix.column(j).iter().min().unwrap());
+        }
+
+        let row_minima = smawk::row_minima(&permuted);
+        assert_eq!(row_minima, smawk::row_minima(&copy));
+        let original = permuted.original_row_minima(&row_minima);
+        for (i, &j) in original.iter().enumerate() {
+            assert_eq!(matrix[(i, j)], *matrix.row(i).iter().min().unwrap());
+        }
+
+        // The inverse permutations of the copy give back the
+        // original matrix, and map the minima back where they came
+        // from.
+        let back = PermutedMatrix::try_new(&copy, &shuffled_rows, &shuffled_columns).unwrap();
+        assert!((0..m).all(|i| (0..n).all(|j| back.index(i, j) == matrix[(i, j)])));
+        let original = permuted.original_column_minima(&column_minima);
+        assert_eq!(back.original_column_minima(&original), column_minima);
+        assert_eq!(
+            back.original_row_minima(&permuted.original_row_minima(&row_minima)),
+            row_minima
+        );
+    }
+}
+
+#[test]
+fn permuted_matrix_rejects_invalid_permutations() {
+    use smawk::monge::PermutationError;
+
+    let matrix = vec![vec![0; 3]; 2];
+    assert!(PermutedMatrix::try_new(&matrix, &[1, 0], &[2, 0, 1]).is_ok());
+    assert_eq!(
+        PermutedMatrix::try_new(&matrix, &[0], &[0, 1, 2]).err(),
+        Some(PermutationError::RowLength)
+    );
+    assert_eq!(
+        PermutedMatrix::try_new(&matrix, &[0, 1], &[0, 1, 2, 3]).err(),
+        Some(PermutationError::ColumnLength)
+    );
+    assert_eq!(
+        PermutedMatrix::try_new(&matrix, &[0, 2], &[0, 1, 2]).err(),
+        Some(PermutationError::Row(2))
+    );
+    assert_eq!(
+        PermutedMatrix::try_new(&matrix, &[0, 1], &[2, 0, 2]).err(),
+        Some(PermutationError::Column(2))
+    );
+}
+
+#[test]
+fn monge_f32_matrices() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..20 {
+        let (m, n) = (rng.gen_range(1..30), rng.gen_range(1..30));
+        let matrix: Array2<u16> = random_monge_matrix(m, n, &mut rng);
+        assert!(is_monge(&matrix));
+        // Scaling introduces rounding errors.
+        let scaled = matrix.mapv(|x| f32::from(x) * 0.1);
+        assert!(is_monge_f32(&scaled, 1e-3));
+
+        let rows = smawk::brute_force::row_minima(&matrix);
+        let columns = smawk::brute_force::column_minima(&matrix);
+        assert_eq!(smawk::row_minima_f32(&scaled), rows);
+        assert_eq!(smawk::column_minima_f32(&scaled), columns);
+    }
+}
+
+#[test]
+fn monge_f32_edge_values() {
+    let matrix = arr2(&[[0.0f32, 1.0], [1.0, 2.0 + 1e-4]]);
+    assert!(!is_monge_f32(&matrix, 0.0));
+    assert!(is_monge_f32(&matrix, 1e-3));
+    assert!(is_monge_f32(&arr2(&[[-0.0f32, 0.0], [0.0, -0.0]]), 0.0));
+    assert!(!is_monge_f32(&arr2(&[[0.0, 1.0], [1.0, f32::NAN]]), 1.0));
+    assert!(is_monge_f32(&Array2::<f32>::zeros((0, 3)), 0.0));
+}
+
+#[test]
+fn monge_f32_nan_is_infinite() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..200 {
+        let (m, n) = (rng.gen_range(1..20), rng.gen_range(1..20));
+        let matrix: Array2<u16> = random_monge_matrix(m, n, &mut rng);
+        let mut scaled = matrix.mapv(|x| f32::from(x) * 0.1);
+        // NaN below a staircase in the lower left keeps the matrix
+        // Monge, but NaN anywhere else usually breaks it.
+        let (i, j) = (rng.gen_range(0..m), rng.gen_range(0..n));
+        let staircase = rng.gen_bool(0.5);
+        for ((row, column), x) in scaled.indexed_iter_mut() {
+            if staircase && row >= i && column <= j || !staircase && (row, column) == (i, j) {
+                *x = f32::NAN;
+            }
+        }
+        let infinite = scaled.mapv(|x| if x.is_nan() { f32::INFINITY } else { x });
+        let monge = is_monge_f32(&scaled, 1e-3);
+        assert_eq!(monge, is_monge_f32(&infinite, 1e-3));
+        assert!(monge || !staircase);
+        if monge {
+            // The minima treat NaN as infinity in the same way.
+            let expected = (0..n)
+                .map(|column| {
+                    (0..m)
+                        .min_by(|&a, &b| infinite[(a, column)].total_cmp(&infinite[(b, column)]))
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(smawk::column_minima_f32(&scaled), expected);
+        }
+    }
+}
+
+#[test]
+fn monge_f64_exact_rounding() {
+    let violation = Err(MongeF64Error::NotMonge(MongeViolation {
+        row: 0,
+        column: 0,
+    }));
+    // Each case is [[a, b], [c, d]] with a + d > b + c, but where
+    // the rounded sums are equal.
+    let cases = [
+        // 1e16 + 1 rounds to 1e16.
+        [[1e16, 1e16], [0.0, 1.0]],
+        [[1e100, 1e100], [-1.0, 0.0]],
+        // The violation is smaller than the rounding error of both sums.
+        [[1.0, 1.0], [1e-16, 1.1e-16]],
+        // a + d overflows to infinity, and so does b + c.
+        [[f64::MAX, f64::MAX], [f64::MAX - 2f64.powi(971), f64::MAX]],
+        // The violation is a subnormal next to the largest floats.
+        [[f64::MAX, f64::MAX], [0.0, 5e-324]],
+        [[-f64::MAX, -f64::MAX], [-5e-324, 0.0]],
+    ];
+    for case in cases {
+        let [[a, b], [c, d]] = case;
+        assert!(a + d <= b + c, "{:?} is not adversarial", case);
+        assert_eq!(verify_monge_f64_exact(&arr2(&case)), violation);
+
+        // Swapping the columns turns the violation into a strict
+        // Monge inequality.
+        let swapped = arr2(&[[b, a], [d, c]]);
+        assert_eq!(verify_monge_f64_exact(&swapped), Ok(()), "{:?}", swapped);
+    }
+
+    // Equal sums, which only exact arithmetic can tell apart from
+    // the cases above.
+    assert!(is_monge_f64_exact(&arr2(&[
+        [f64::MAX, f64::MAX],
+        [f64::MAX, f64::MAX]
+    ])));
+    assert!(is_monge_f64_exact(&arr2(&[[1e16, 1e16], [1.0, 1.0]])));
+    assert!(is_monge_f64_exact(&arr2(&[[-0.0, 0.0], [0.0, -0.0]])));
+}
+
+#[test]
+fn monge_f64_exact_non_finite() {
+    let matrix = arr2(&[[0.0, 1.0, 2.0], [1.0, f64::NAN, f64::INFINITY]]);
+    assert_eq!(
+        verify_monge_f64_exact(&matrix),
+        Err(MongeF64Error::NonFinite { row: 1, column: 1 })
+    );
+    assert!(!is_monge_f64_exact(&arr2(&[[f64::NEG_INFINITY]])));
+    assert!(is_monge_f64_exact(&Array2::<f64>::zeros((0, 3))));
+}
+
+#[test]
+fn monge_f64_exact_agrees_with_integers() {
+    // Floats of the form k * 2^e are integers that fit in an i128,
+    // where the Monge inequality can be checked exactly.
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..200 {
+        let (m, n) = (rng.gen_range(1..6), rng.gen_range(1..6));
+        let integers = Array2::from_shape_fn((m, n), |_| {
+            i128::from(rng.gen_range(0i64..16)) << rng.gen_range(50..56)
+        });
+        let floats = integers.mapv(|x| x as f64);
+        assert_eq!(is_monge_f64_exact(&floats), is_monge(&integers));
+    }
+}
+
+/// Return 2^exponent, which may be subnormal.
+fn pow2(exponent: i32) -> f64 {
+    if exponent >= -1022 {
+        f64::from_bits(((exponent + 1023) as u64) << 52)
+    } else {
+        f64::from_bits(1 << (exponent + 1074))
+    }
+}
+
+#[test]
+fn monge_multiplicative_agrees_with_integers() {
+    // Elements k * 2^(e + f) with integers k below 2^30, so their
+    // products do not fit in a float, and powers of two per row and
+    // column which cancel in the inequality, but make the products
+    // overflow or underflow.
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut monge = 0;
+    for _ in 0..200 {
+        let (m, n) = (rng.gen_range(1..8), rng.gen_range(1..8));
+        // Small steps between the rows and columns let the change
+        // below break the Monge property.
+        let step = 1 << rng.gen_range(0..20);
+        let mut a = vec![rng.gen_range(1u64..1 << 20)];
+        let mut b = vec![rng.gen_range(1u64..1 << 20)];
+        for _ in 1..m {
+            a.push(a[a.len() - 1] + rng.gen_range(0..step));
+        }
+        for _ in 1..n {
+            b.push(b[b.len() - 1] + rng.gen_range(0..step));
+        }
+        // (a[i] + b[j]) is multiplicative Monge, and a small change
+        // of one element may break it.
+        let mut integers = Array2::from_shape_fn((m, n), |(i, j)| a[i] + b[j]);
+        let (i, j) = (rng.gen_range(0..m), rng.gen_range(0..n));
+        integers[[i, j]] = integers[[i, j]] + rng.gen_range(0..3) - 1;
+
+        let e = (0..m)
+            .map(|_| rng.gen_range(-535..=495))
+            .collect::<Vec<_>>();
+        let f = (0..n)
+            .map(|_| rng.gen_range(-535..=495))
+            .collect::<Vec<_>>();
+        let floats =
+            Array2::from_shape_fn((m, n), |(i, j)| integers[[i, j]] as f64 * pow2(e[i] + f[j]));
+        let expected = (0..m.saturating_sub(1)).all(|i| {
+            (0..n.saturating_sub(1)).all(|j| {
+                u128::from(integers[[i, j]]) * u128::from(integers[[i + 1, j + 1]])
+                    <= u128::from(integers[[i, j + 1]]) * u128::from(integers[[i + 1, j]])
+            })
+        });
+        assert_eq!(is_monge_multiplicative(&floats), expected, "{:?}", integers);
+        monge += usize::from(expected);
+    }
+    assert!(0 < monge && monge < 200);
+}
+
+#[test]
+fn monge_multiplicative_non_positive() {
+    assert!(is_monge_multiplicative(&arr2(&[[1.0, 2.0], [3.0, 6.0]])));
+    assert!(is_monge_multiplicative(&arr2(&[
+        [5e-324, 1.0],
+        [1.0, f64::MAX]
+    ])));
+    assert!(!is_monge_multiplicative(&arr2(&[[1.0, 2.0], [3.0, -6.0]])));
+    assert!(!is_monge_multiplicative(&arr2(&[[0.0]])));
+    assert!(!is_monge_multiplicative(&arr2(&[[f64::INFINITY]])));
+    assert!(!is_monge_multiplicative(&arr2(&[[f64::NAN]])));
+}
+
+#[test]
+fn log_domain_minima() {
+    // Elements r[i] * c[j] * (a[i] + b[j]) with increasing a and b
+    // are multiplicative Monge. The minima of their logarithms must
+    // match a brute force search in the original domain, except
+    // where two elements are too close for the logarithms to tell
+    // them apart.
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut distinct = 0;
+    for _ in 0..100 {
+        let (m, n) = (rng.gen_range(1..50), rng.gen_range(1..50));
+        let mut a = vec![rng.gen_range(0.1..10.0)];
+        let mut b = vec![rng.gen_range(0.1..10.0)];
+        for _ in 1..m {
+            a.push(a[a.len() - 1] + rng.gen_range(0.0..10.0));
+        }
+        for _ in 1..n {
+            b.push(b[b.len() - 1] + rng.gen_range(0.0..10.0));
+        }
+        let r = (0..m)
+            .map(|_| rng.gen_range(0.01..1.0))
+            .collect::<Vec<f64>>();
+        let c = (0..n)
+            .map(|_| rng.gen_range(0.01..1.0))
+            .collect::<Vec<f64>>();
+        let matrix = Array2::from_shape_fn((m, n), |(i, j)| r[i] * c[j] * (a[i] + b[j]));
+        let log = LogDomain::new(&matrix);
+
+        let minima = smawk::column_minima(&log);
+        for (j, &i) in minima.iter().enumerate() {
+            let column = matrix.column(j);
+            let expected = (0..m).fold(0, |k, i| if column[i] < column[k] { i } else { k });
+            let (min, found) = (column[expected], column[i]);
+            assert!(
+                (found - min) / min <= 1e-12,
+                "column {}: {} vs {}",
+                j,
+                found,
+                min
+            );
+            if (0..m).all(|k| k == expected || column[k] > min * (1.0 + 1e-12)) {
+                assert_eq!(i, expected);
+                distinct += 1;
+            }
+        }
+    }
+    assert!(distinct > 0);
+}
+
+#[test]
+fn monge_stack_valid_seams() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for _ in 0..100 {
+        let (m, n) = (rng.gen_range(2..20), rng.gen_range(2..20));
+        let matrix: Array2<i64> = random_monge_matrix(m, n, &mut rng);
+        let split = rng.gen_range(0..=m);
+        let (top, bottom) = (
+            matrix.slice(ndarray::s![..split, ..]).to_owned(),
+            matrix.slice(ndarray::s![split.., ..]).to_owned(),
+        );
+        let stacked = monge_vstack(&top, &bottom).unwrap();
+        assert!(is_monge(&stacked));
+        assert_eq!(
+            smawk::column_minima(&stacked),
+            smawk::column_minima(&matrix)
+        );
+        assert_eq!(smawk::row_minima(&stacked), smawk::row_minima(&matrix));
+
+        let split = rng.gen_range(0..=n);
+        let (left, right) = (
+            matrix.slice(ndarray::s![.., ..split]).to_owned(),
+            matrix.slice(ndarray::s![.., split..]).to_owned(),
+        );
+        let stacked = monge_hstack(&left, &right).unwrap();
+        assert!(is_monge(&stacked));
+        assert_eq!(
+            smawk::column_minima(&stacked),
+            smawk::column_minima(&matrix)
+        );
+        assert_eq!(smawk::row_minima(&stacked), smawk::row_minima(&matrix));
+    }
+}
+
+#[test]
+fn monge_stack_invalid_seams() {
+    let top = arr2(&[[0, 1, 2], [0, 0, 0]]);
+    let bottom = arr2(&[[1, 1, 4], [1, 0, 2]]);
+    assert!(is_monge(&top) && is_monge(&bottom));
+    assert!(!is_monge(&ndarray::concatenate![
+        ndarray::Axis(0),
+        top,
+        bottom
+    ]));
+    assert_eq!(
+        monge_vstack(&top, &bottom).err(),
+        Some(MongeViolation { row: 1, column: 1 })
+    );
+
+    let left = top.t().to_owned();
+    let right = bottom.t().to_owned();
+    assert!(!is_monge(&ndarray::concatenate![
+        ndarray::Axis(1),
+        left,
+        right
+    ]));
+    assert_eq!(
+        monge_hstack(&left, &right).err(),
+        Some(MongeViolation { row: 1, column: 1 })
+    );
+}
+
+#[test]
+#[should_panic(expected = "blocks must have the same number of columns")]
+fn monge_vstack_mismatched_columns() {
+    let _ = monge_vstack(&arr2(&[[1, 2]]), &arr2(&[[1, 2, 3]]));
+}
+
+#[test]
+fn monge_rmq_agrees_with_brute_force() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(m, n) in &[
+        (1, 1),
+        (1, 7),
+        (7, 1),
+        (2, 3),
+        (10, 10),
+        (13, 40),
+        (50, 9),
+        (64, 64),
+    ] {
+        // Small elements give many ties.
+        let matrix: Array2<u8> = random_monge_matrix(m, n, &mut rng);
+        let rmq = MongeRmq::build(&matrix);
+        for _ in 0..1000 {
+            let (a, b) = (rng.gen_range(0..m), rng.gen_range(0..m));
+            let (c, d) = (rng.gen_range(0..n), rng.gen_range(0..n));
+            let (rows, cols) = (a.min(b)..a.max(b) + 1, c.min(d)..c.max(d) + 1);
+            let (value, row, col) = matrix
+                .slice(s![rows.clone(), cols.clone()])
+                .indexed_iter()
+                .map(|((i, j), &value)| (value, rows.start + i, cols.start + j))
+                .min()
+                .unwrap();
+            assert_eq!(
+                rmq.query(rows.clone(), cols.clone()),
+                (row, col, value),
+                "rows {:?}, columns {:?} of {:?}",
+                rows,
+                cols,
+                matrix
+            );
+        }
+    }
+}
+
+#[test]
+fn dynamic_minima_negative_values() {
+    assert_eq!(
+        DynamicMinima::new(vec![vec![0i64, -5], vec![-5, 0]]).err(),
+        Some(MongeViolation { row: 0, column: 0 })
+    );
+
+    let mut minima = DynamicMinima::new(vec![vec![-5i64, 0], vec![0, -5]]).unwrap();
+    assert_eq!(minima.column_minima(), &[0, 1]);
+    assert_eq!(
+        minima.update_entry(0, 0, 6),
+        Err(MongeViolation { row: 0, column: 0 })
+    );
+    minima.update_entry(1, 1, -10).unwrap();
+    assert_eq!(
+        minima.update_row(0, &[5, -6]),
+        Err(MongeViolation { row: 0, column: 0 })
+    );
+    minima.update_row(1, &[-3, -20]).unwrap();
+    assert_eq!(minima.column_minima(), &[0, 1]);
+    assert_eq!(minima.as_matrix(), &vec![vec![-5, 0], vec![-3, -20]]);
+}
+
+#[test]
+fn dynamic_minima_agree_with_recomputing() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &(m, n) in &[(1, 1), (1, 5), (5, 1), (4, 4), (10, 20), (30, 7)] {
+        let matrix: Array2<i64> = random_monge_matrix(m, n, &mut rng);
+        let mut expected = matrix
+            .outer_iter()
+            .map(|row| row.to_vec())
+            .collect::<Vec<_>>();
+        let mut dynamic = DynamicMinima::new(expected.clone()).unwrap();
+        let mut rejected = 0;
+        for _ in 0..1000 {
+            let i = rng.gen_range(0..m);
+            if rng.gen_bool(0.8) {
+                // A random change, which is rejected if it breaks the
+                // Monge property.
+                let j = rng.gen_range(0..n);
+                let value = expected[i][j] + rng.gen_range(-3..=3);
+                let mut updated = expected.clone();
+                updated[i][j] = value;
+                let result = dynamic.update_entry(i, j, value);
+                assert_eq!(result.is_ok(), is_monge(&updated));
+                match result {
+                    Ok(()) => expected = updated,
+                    Err(_) => rejected += 1,
+                }
+            } else {
+                // The steps between neighboring elements must lie
+                // between the steps of the rows above and below.
+                let mut values = vec![0];
+                for j in 1..n {
+                    let above = i
+                        .checked_sub(1)
+                        .map(|k| expected[k][j] - expected[k][j - 1]);
+                    let below = expected.get(i + 1).map(|row| row[j] - row[j - 1]);
+                    let lo = below.unwrap_or(above.unwrap_or(0) - 5);
+                    let hi = above.unwrap_or(lo + 5);
+                    values.push(values[j - 1] + rng.gen_range(lo..=hi));
+                }
+                let shift = rng.gen_range(-10..10) - values.iter().min().unwrap();
+                let values = values.iter().map(|v| v + shift).collect::<Vec<_>>();
+                dynamic.update_row(i, &values).unwrap();
+                expected[i] = values;
+            }
+            assert_eq!(dynamic.as_matrix(), &expected);
+            assert_eq!(dynamic.column_minima(), smawk::column_minima(&expected));
+        }
+        assert!(m < 2 || n < 2 || rejected > 0);
+    }
+}
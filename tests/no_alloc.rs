@@ -0,0 +1,175 @@
+#![cfg(feature = "ndarray")]
+
+use ndarray::Array2;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+mod random_monge;
+use random_monge::random_monge_matrix;
+
+/// Allocator which counts the allocations made by the current thread.
+///
+/// Panicking inside the allocator would abort the test, so the
+/// allocations are counted and checked afterwards instead.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Check that computing column minima in scratch memory does not
+/// allocate and agrees with the standard function.
+#[test]
+fn column_minima_in_scratch_does_not_allocate() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30, 50];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &m in &sizes {
+        for &n in &sizes {
+            let matrix: Array2<i32> = random_monge_matrix(m, n, &mut rng);
+            let mut buffer = vec![0; smawk::scratch_size(m, n)];
+            let mut minima = vec![0; n];
+
+            let before = ALLOCATIONS.with(Cell::get);
+            let mut scratch = smawk::Scratch::new(&mut buffer);
+            smawk::column_minima_in_scratch(&matrix, &mut minima, &mut scratch);
+            let after = ALLOCATIONS.with(Cell::get);
+
+            assert_eq!(before, after, "allocated on {}x{} matrix", m, n);
+            assert_eq!(minima, smawk::column_minima(&matrix));
+        }
+    }
+}
+
+/// Check that SMAWK allocates a bounded number of buffers per call,
+/// independent of the size, so that many calls on small matrices are
+/// not dominated by allocations.
+#[test]
+fn minima_allocations_per_call() {
+    let sizes = vec![1, 2, 3, 4, 5, 10, 15, 20, 30, 50, 100];
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &m in &sizes {
+        for &n in &sizes {
+            let matrix: Array2<i32> = random_monge_matrix(m, n, &mut rng);
+
+            let before = ALLOCATIONS.with(Cell::get);
+            let minima = smawk::column_minima_with_threshold(&matrix, 0);
+            let after = ALLOCATIONS.with(Cell::get);
+            // The result, the index lists, and the queries and values
+            // of the interpolation steps.
+            assert!(
+                after - before <= 4,
+                "{} allocations on {}x{}",
+                after - before,
+                m,
+                n
+            );
+            assert_eq!(minima, smawk::column_minima(&matrix));
+
+            let before = ALLOCATIONS.with(Cell::get);
+            let minima = smawk::row_minima_with_threshold(&matrix, 0);
+            let after = ALLOCATIONS.with(Cell::get);
+            // One more for the transposed queries.
+            assert!(
+                after - before <= 5,
+                "{} allocations on {}x{}",
+                after - before,
+                m,
+                n
+            );
+            assert_eq!(minima, smawk::row_minima(&matrix));
+        }
+    }
+}
+
+/// Check that the online algorithm allocates a bounded number of
+/// buffers per call, independent of the size, on a line breaking
+/// workload with many short paragraphs.
+#[test]
+fn online_column_minima_allocations_per_call() {
+    use rand::Rng;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    for &size in &[10, 30, 100, 1000] {
+        let mut most = 0;
+        for _ in 0..100 {
+            let widths = (0..size)
+                .map(|_| rng.gen_range(1..10))
+                .collect::<Vec<i64>>();
+            let mut offsets = vec![0];
+            offsets.extend(widths.iter().scan(0, |sum, w| {
+                *sum += w + 1;
+                Some(*sum)
+            }));
+
+            let before = ALLOCATIONS.with(Cell::get);
+            smawk::online_column_minima(0, size, |minima: &[(usize, i64)], i, j| {
+                let gap = 40 - (offsets[j] - offsets[i] - 1);
+                minima[i].1 + if gap < 0 { 1000 * -gap } else { gap * gap }
+            });
+            let after = ALLOCATIONS.with(Cell::get);
+            most = most.max(after - before);
+        }
+        // The result and six buffers for the SMAWK steps.
+        assert!(most <= 7, "{} allocations for size {}", most, size);
+    }
+}
+
+/// Check that a reused online runner stops allocating once its
+/// buffers fit the largest paragraph, and that it agrees with the
+/// standalone function.
+#[test]
+fn online_minima_runner_reuses_buffers() {
+    use rand::Rng;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut runner = smawk::OnlineMinima::new();
+    for round in 0..3 {
+        let mut allocations = 0;
+        for _ in 0..1000 {
+            let size = rng.gen_range(1..=60);
+            let mut offsets = vec![0];
+            for _ in 0..size {
+                let width: i64 = rng.gen_range(1..10);
+                offsets.push(offsets[offsets.len() - 1] + width + 1);
+            }
+            let cost = |minima: &[(usize, i64)], i: usize, j: usize| {
+                let gap = 40 - (offsets[j] - offsets[i] - 1);
+                minima[i].1 + if gap < 0 { 1000 * -gap } else { gap * gap }
+            };
+            let expected = smawk::online_column_minima(0, size, cost);
+
+            let before = ALLOCATIONS.with(Cell::get);
+            let minima = runner.run(0, size, cost);
+            allocations += ALLOCATIONS.with(Cell::get) - before;
+            assert_eq!(minima, &expected[..]);
+        }
+        if round == 0 {
+            // Paragraphs larger than all previous ones grow the buffers.
+            assert!(allocations > 0);
+        } else {
+            assert_eq!(allocations, 0, "allocations in round {}", round);
+        }
+    }
+
+    runner.reset();
+    let before = ALLOCATIONS.with(Cell::get);
+    runner.run(0, 10, |minima: &[(usize, i64)], i, j| {
+        minima[i].1 + (j - i) as i64
+    });
+    assert!(ALLOCATIONS.with(Cell::get) > before);
+}
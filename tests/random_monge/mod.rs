@@ -5,19 +5,54 @@
 // section on "Submodules in Integration Tests" in
 // https://doc.rust-lang.org/book/ch11-03-test-organization.html
 
-use ndarray::{s, Array2};
-use num_traits::PrimInt;
+use ndarray::{s, Array2, Array3, Zip};
+use num_bigint::{BigInt, BigUint};
+use num_traits::{PrimInt, Zero};
 use rand::distributions::{Distribution, Standard};
 use rand::Rng;
 
 /// A Monge matrix can be decomposed into one of these primitive
 /// building blocks.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum MongePrim {
     ConstantRows,
     ConstantCols,
     UpperRightOnes,
     LowerLeftOnes,
+    UpperRightRamp,
+    LowerLeftRamp,
+    DistanceBand,
+    SeparableConvex,
+}
+
+/// The primitives whose elements are all zero or one.
+pub const ZERO_ONE_PRIMITIVES: [MongePrim; 4] = [
+    MongePrim::ConstantRows,
+    MongePrim::ConstantCols,
+    MongePrim::LowerLeftOnes,
+    MongePrim::UpperRightOnes,
+];
+
+/// All primitives.
+pub const MONGE_PRIMITIVES: [MongePrim; 8] = [
+    MongePrim::ConstantRows,
+    MongePrim::ConstantCols,
+    MongePrim::LowerLeftOnes,
+    MongePrim::UpperRightOnes,
+    MongePrim::UpperRightRamp,
+    MongePrim::LowerLeftRamp,
+    MongePrim::DistanceBand,
+    MongePrim::SeparableConvex,
+];
+
+/// Largest element of each of `count` terms whose sum must fit in `T`.
+///
+/// The elements are also limited to (*m* + *n*)², which keeps the
+/// matrices of wide types small enough for further arithmetic.
+fn term_max<T: PrimInt>(m: usize, n: usize, count: usize) -> u64 {
+    let total = T::max_value().to_u64().unwrap_or(u64::MAX);
+    let size = (m + n) as u64;
+    std::cmp::min(total / count.max(1) as u64, size * size).max(1)
 }
 
 impl MongePrim {
@@ -26,58 +61,479 @@ impl MongePrim {
     where
         Standard: Distribution<T>,
     {
-        let mut matrix = Array2::from_elem((m, n), T::zero());
+        self.sample(m, n, term_max::<T>(m, n, 1), rng)
+            .to_matrix(m, n)
+    }
+
+    /// Pick the random parameters of a primitive whose elements are
+    /// at most `max`.
+    pub fn sample<R: Rng>(&self, m: usize, n: usize, max: u64, rng: &mut R) -> MongeTerm {
         // Avoid panic in UpperRightOnes and LowerLeftOnes below.
         if m == 0 || n == 0 {
-            return matrix;
+            return MongeTerm::ConstantRows(vec![false; m]);
         }
 
         match *self {
-            MongePrim::ConstantRows => {
-                for mut row in matrix.rows_mut() {
-                    if rng.gen::<bool>() {
+            MongePrim::ConstantRows => MongeTerm::ConstantRows((0..m).map(|_| rng.gen()).collect()),
+            MongePrim::ConstantCols => MongeTerm::ConstantCols((0..n).map(|_| rng.gen()).collect()),
+            // A count of zero starts from the first row or column,
+            // just like the slice `-0..` does.
+            MongePrim::UpperRightOnes => {
+                let i = rng.gen_range(0..(m + 1) as isize) as usize;
+                let j = rng.gen_range(0..(n + 1) as isize) as usize;
+                MongeTerm::UpperRightOnes(i, if j == 0 { 0 } else { n - j })
+            }
+            MongePrim::LowerLeftOnes => {
+                let i = rng.gen_range(0..(m + 1) as isize) as usize;
+                let j = rng.gen_range(0..(n + 1) as isize) as usize;
+                MongeTerm::LowerLeftOnes(if i == 0 { 0 } else { m - i }, j)
+            }
+            MongePrim::UpperRightRamp => {
+                let height = rng.gen_range(1..=std::cmp::min(m.min(n) as u64, max));
+                MongeTerm::UpperRightRamp {
+                    rows: rng.gen_range(0..=m),
+                    start: rng.gen_range(0..=n),
+                    height: height as usize,
+                    slope: rng.gen_range(1..=max / height),
+                }
+            }
+            MongePrim::LowerLeftRamp => {
+                let height = rng.gen_range(1..=std::cmp::min(m.min(n) as u64, max));
+                MongeTerm::LowerLeftRamp {
+                    start: rng.gen_range(0..=m),
+                    columns: rng.gen_range(0..=n),
+                    height: height as usize,
+                    slope: rng.gen_range(1..=max / height),
+                }
+            }
+            MongePrim::DistanceBand => {
+                let center_offset = rng.gen_range(1 - n as i64..=m as i64 - 1);
+                // The largest distance is in the lower left or the
+                // upper right corner.
+                let reach = std::cmp::max(
+                    (m as i64 - 1 + center_offset).unsigned_abs(),
+                    (center_offset - (n as i64 - 1)).unsigned_abs(),
+                );
+                MongeTerm::DistanceBand {
+                    center_offset,
+                    slope: rng.gen_range(0..=max / reach.max(1)),
+                }
+            }
+            MongePrim::SeparableConvex => {
+                let reach = std::cmp::max(m, n) as u64 - 1;
+                let limit = max.isqrt() / reach.max(1);
+                MongeTerm::SeparableConvex {
+                    a: rng.gen_range(0..=limit),
+                    b: rng.gen_range(0..=limit),
+                }
+            }
+        }
+    }
+}
+
+/// A primitive building block together with its random parameters.
+#[derive(Clone, Debug)]
+pub enum MongeTerm {
+    /// The rows marked `true` are ones.
+    ConstantRows(Vec<bool>),
+    /// The columns marked `true` are ones.
+    ConstantCols(Vec<bool>),
+    /// Ones in the rows before the first index and the columns from
+    /// the second index.
+    UpperRightOnes(usize, usize),
+    /// Ones in the rows from the first index and the columns before
+    /// the second index.
+    LowerLeftOnes(usize, usize),
+    /// `slope * min(height, rows - i, j - start + 1)` in the rows
+    /// before `rows` and the columns from `start`. This is the sum of
+    /// `height` upper right corners of ones, each one row shorter and
+    /// one column narrower than the last.
+    UpperRightRamp {
+        rows: usize,
+        start: usize,
+        height: usize,
+        slope: u64,
+    },
+    /// `slope * min(height, i - start + 1, columns - j)` in the rows
+    /// from `start` and the columns before `columns`, the sum of
+    /// `height` lower left corners of ones.
+    LowerLeftRamp {
+        start: usize,
+        columns: usize,
+        height: usize,
+        slope: u64,
+    },
+    /// `slope * |i - j + center_offset|`, a convex function of the
+    /// distance to a diagonal.
+    DistanceBand { center_offset: i64, slope: u64 },
+    /// `(a * i - b * j)²`, a convex function of `a * i - b * j` with
+    /// non-negative `a` and `b`.
+    SeparableConvex { a: u64, b: u64 },
+}
+
+impl MongeTerm {
+    /// Generate the *m* ✕ *n* matrix of a primitive.
+    pub fn to_matrix<T: PrimInt>(&self, m: usize, n: usize) -> Array2<T> {
+        let mut matrix = Array2::from_elem((m, n), T::zero());
+        match *self {
+            MongeTerm::ConstantRows(ref ones) => {
+                for (mut row, &one) in matrix.rows_mut().into_iter().zip(ones) {
+                    if one {
                         row.fill(T::one())
                     }
                 }
             }
-            MongePrim::ConstantCols => {
-                for mut col in matrix.columns_mut() {
-                    if rng.gen::<bool>() {
+            MongeTerm::ConstantCols(ref ones) => {
+                for (mut col, &one) in matrix.columns_mut().into_iter().zip(ones) {
+                    if one {
                         col.fill(T::one())
                     }
                 }
             }
-            MongePrim::UpperRightOnes => {
-                let i = rng.gen_range(0..(m + 1) as isize);
-                let j = rng.gen_range(0..(n + 1) as isize);
-                matrix.slice_mut(s![..i, -j..]).fill(T::one());
+            MongeTerm::UpperRightOnes(i, j) => {
+                matrix.slice_mut(s![..i, j..]).fill(T::one());
             }
-            MongePrim::LowerLeftOnes => {
-                let i = rng.gen_range(0..(m + 1) as isize);
-                let j = rng.gen_range(0..(n + 1) as isize);
-                matrix.slice_mut(s![-i.., ..j]).fill(T::one());
+            MongeTerm::LowerLeftOnes(i, j) => {
+                matrix.slice_mut(s![i.., ..j]).fill(T::one());
+            }
+            _ => {
+                for ((i, j), element) in matrix.indexed_iter_mut() {
+                    *element = T::from(self.element(i, j)).expect("term does not fit");
+                }
             }
         }
-
         matrix
     }
+
+    /// Element `(i, j)` of the primitive.
+    pub fn element(&self, i: usize, j: usize) -> u64 {
+        match *self {
+            MongeTerm::ConstantRows(ref ones) => u64::from(ones[i]),
+            MongeTerm::ConstantCols(ref ones) => u64::from(ones[j]),
+            MongeTerm::UpperRightOnes(rows, start) => u64::from(i < rows && j >= start),
+            MongeTerm::LowerLeftOnes(start, columns) => u64::from(i >= start && j < columns),
+            MongeTerm::UpperRightRamp {
+                rows,
+                start,
+                height,
+                slope,
+            } => match i < rows && j >= start {
+                true => slope * height.min(rows - i).min(j - start + 1) as u64,
+                false => 0,
+            },
+            MongeTerm::LowerLeftRamp {
+                start,
+                columns,
+                height,
+                slope,
+            } => match i >= start && j < columns {
+                true => slope * height.min(i - start + 1).min(columns - j) as u64,
+                false => 0,
+            },
+            MongeTerm::DistanceBand {
+                center_offset,
+                slope,
+            } => slope * (i as i64 - j as i64 + center_offset).unsigned_abs(),
+            MongeTerm::SeparableConvex { a, b } => {
+                let x = (a * i as u64).abs_diff(b * j as u64);
+                x * x
+            }
+        }
+    }
+}
+
+/// Generate the primitives of a random Monge matrix.
+///
+/// The primitives are picked from all [`MONGE_PRIMITIVES`], and the
+/// matrix is the sum of the `count` of them. The elements of every
+/// primitive are at most `max`.
+#[allow(dead_code)] // Not every integration test uses this.
+pub fn random_monge_terms<R: Rng>(
+    m: usize,
+    n: usize,
+    count: usize,
+    max: u64,
+    rng: &mut R,
+) -> Vec<MongeTerm> {
+    (0..count)
+        .map(|_| {
+            let monge = MONGE_PRIMITIVES[rng.gen_range(0..MONGE_PRIMITIVES.len())];
+            monge.sample(m, n, max, rng)
+        })
+        .collect()
 }
 
-/// Generate a random Monge matrix.
+/// Compute the column minima of the sum of `terms` without looking
+/// at the matrix.
+///
+/// The minima are derived from the parameters of the primitives,
+/// which gives an oracle independent of the minima functions. A
+/// constant column adds the same amount to every row, so it never
+/// moves a minimum. The constant rows add the same amount to every
+/// column. The corner primitives add one to a prefix or a suffix of
+/// the rows in the columns they cover, which is tracked with a
+/// difference array. A ramp is a sum of such corners. The distance
+/// bands and separable convex terms are added to the column directly
+/// from their formulas. Ties are broken in favor of the smallest row.
+#[allow(dead_code)] // Not every integration test uses this.
+pub fn analytic_column_minima(terms: &[MongeTerm], m: usize, n: usize) -> Vec<usize> {
+    let mut rows = vec![0; m];
+    for term in terms {
+        if let MongeTerm::ConstantRows(ones) = term {
+            for (row, &one) in rows.iter_mut().zip(ones) {
+                *row += i64::from(one);
+            }
+        }
+    }
+    (0..n)
+        .map(|col| {
+            // Amount added to row i and below, minus the amount added
+            // to row i + 1 and below.
+            let mut steps = vec![0i64; m + 1];
+            let mut column = rows.clone();
+            for term in terms {
+                match *term {
+                    MongeTerm::UpperRightOnes(i, j) if col >= j => {
+                        steps[0] += 1;
+                        steps[i] -= 1;
+                    }
+                    MongeTerm::LowerLeftOnes(i, j) if col < j => {
+                        steps[i] += 1;
+                        steps[m] -= 1;
+                    }
+                    MongeTerm::UpperRightRamp {
+                        rows,
+                        start,
+                        height,
+                        slope,
+                    } => {
+                        for k in (0..height).filter(|&k| k < rows && col >= start + k) {
+                            steps[0] += slope as i64;
+                            steps[rows - k] -= slope as i64;
+                        }
+                    }
+                    MongeTerm::LowerLeftRamp {
+                        start,
+                        columns,
+                        height,
+                        slope,
+                    } => {
+                        for k in (0..height).filter(|&k| start + k < m && col + k < columns) {
+                            steps[start + k] += slope as i64;
+                            steps[m] -= slope as i64;
+                        }
+                    }
+                    MongeTerm::DistanceBand { .. } | MongeTerm::SeparableConvex { .. } => {
+                        for (i, value) in column.iter_mut().enumerate() {
+                            *value += term.element(i, col) as i64;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let mut best = (i64::MAX, 0);
+            let mut ones = 0;
+            for (i, &row) in column.iter().enumerate() {
+                ones += steps[i];
+                let value = row + ones;
+                if value < best.0 {
+                    best = (value, i);
+                }
+            }
+            best.1
+        })
+        .collect()
+}
+
+/// Generate a random Monge matrix from the [`ZERO_ONE_PRIMITIVES`].
+///
+/// Use [`random_monge_matrix_from`] with [`MONGE_PRIMITIVES`] for
+/// matrices with a richer structure.
+#[allow(dead_code)] // Not every integration test uses this.
 pub fn random_monge_matrix<R: Rng, T: PrimInt>(m: usize, n: usize, rng: &mut R) -> Array2<T>
 where
     Standard: Distribution<T>,
 {
-    let monge_primitives = [
-        MongePrim::ConstantRows,
-        MongePrim::ConstantCols,
-        MongePrim::LowerLeftOnes,
-        MongePrim::UpperRightOnes,
-    ];
+    random_monge_matrix_from(m, n, &ZERO_ONE_PRIMITIVES, rng)
+}
+
+/// Generate a random Monge matrix from the given primitives.
+///
+/// The [`ZERO_ONE_PRIMITIVES`] give matrices where many windows are
+/// tight, that is, where the Monge inequality holds with equality.
+#[allow(dead_code)] // Not every integration test uses this.
+pub fn random_monge_matrix_from<R: Rng, T: PrimInt>(
+    m: usize,
+    n: usize,
+    primitives: &[MongePrim],
+    rng: &mut R,
+) -> Array2<T>
+where
+    Standard: Distribution<T>,
+{
+    // Every primitive adds at most `max` to each element, so limiting
+    // the number of primitives and their size ensures that the sum
+    // fits in T.
+    let count = std::cmp::min(m + n, T::max_value().to_usize().unwrap_or(usize::MAX));
+    let max = term_max::<T>(m, n, count);
     let mut matrix = Array2::from_elem((m, n), T::zero());
+    for _ in 0..count {
+        let monge = primitives[rng.gen_range(0..primitives.len())];
+        matrix = matrix + monge.sample(m, n, max, rng).to_matrix(m, n);
+    }
+    matrix
+}
+
+/// Generate a random Monge matrix of big integers.
+///
+/// Like [`random_monge_matrix`], but every primitive is scaled by a
+/// random amplitude of up to `bits` bits before it is added. A Monge
+/// matrix stays Monge when scaled by a non-negative amplitude, and
+/// the elements grow larger than any primitive integer type.
+#[allow(dead_code)] // Not every integration test uses this.
+pub fn random_monge_matrix_big<R: Rng>(
+    m: usize,
+    n: usize,
+    bits: usize,
+    rng: &mut R,
+) -> Array2<BigInt> {
+    let mut matrix = Array2::from_elem((m, n), BigInt::zero());
     for _ in 0..(m + n) {
-        let monge = monge_primitives[rng.gen_range(0..monge_primitives.len())];
-        matrix = matrix + monge.to_matrix(m, n, rng);
+        let monge = ZERO_ONE_PRIMITIVES[rng.gen_range(0..ZERO_ONE_PRIMITIVES.len())];
+        let digits = (0..bits.div_ceil(32)).map(|_| rng.gen()).collect();
+        let amplitude = BigInt::from(BigUint::new(digits) >> (bits.div_ceil(32) * 32 - bits));
+        let ones: Array2<u8> = monge.to_matrix(m, n, rng);
+        Zip::from(&mut matrix).and(&ones).for_each(|element, &one| {
+            if one == 1 {
+                *element += &amplitude;
+            }
+        });
+    }
+    matrix
+}
+
+/// Generate a random Monge matrix with an interesting column minima
+/// structure.
+///
+/// Matrices from [`random_monge_matrix`] tend to have their column
+/// minima in a few rows only. Here the [`ZERO_ONE_PRIMITIVES`], which
+/// add at most *m* + *n* to an element, are added on top of
+/// `(m + n) * (i - y[j])²` for a random non-decreasing sequence `y` of
+/// row indices. This is a Monge matrix where column `j` has its
+/// minimum at or next to row `y[j]`, so the column minima form a long
+/// staircase through the matrix. The scaled squared distances must fit
+/// in `T`.
+#[allow(dead_code)] // Not every integration test uses this.
+pub fn random_monge_matrix_colwise<R: Rng, T: PrimInt>(m: usize, n: usize, rng: &mut R) -> Array2<T>
+where
+    Standard: Distribution<T>,
+{
+    let mut targets = (0..n)
+        .map(|_| rng.gen_range(0..m.max(1)))
+        .collect::<Vec<_>>();
+    targets.sort_unstable();
+    let distances = Array2::from_shape_fn((m, n), |(i, j)| {
+        let distance = i.abs_diff(targets[j]);
+        T::from((m + n) * distance * distance).expect("squared distance does not fit")
+    });
+    distances + random_monge_matrix_from(m, n, &ZERO_ONE_PRIMITIVES, rng)
+}
+
+/// Generate a random matrix which is totally monotone, but generally
+/// not Monge.
+///
+/// Every column of a matrix from [`random_monge_matrix_colwise`] is
+/// mapped through its own strictly increasing function `a * x^p + b`
+/// with random `a`, `p` and `b`. This keeps the order of the elements
+/// within each column, so the column minima and the total
+/// monotonicity needed to find them are unchanged, but the
+/// differences between columns are no longer additive. The transpose
+/// is totally monotone for row minima.
+#[allow(dead_code)] // Not every integration test uses this.
+pub fn random_totally_monotone_matrix<R: Rng>(m: usize, n: usize, rng: &mut R) -> Array2<i64> {
+    let mut matrix: Array2<i64> = random_monge_matrix_colwise(m, n, rng);
+    for mut column in matrix.columns_mut() {
+        let a = rng.gen_range(1..10);
+        let p = rng.gen_range(1..4);
+        let b = rng.gen_range(-1000..1000);
+        column.mapv_inplace(|x| a * x.pow(p) + b);
     }
     matrix
 }
+
+/// Generate a random matrix whose row minima move right from row to
+/// row, but which is generally not totally monotone.
+///
+/// A non-decreasing column is drawn for the minimum of every row.
+/// The minimum is unique in its row, and the other elements of the
+/// row are random values above it. Nothing relates the rows beyond
+/// the positions of their minima, so submatrices which skip the
+/// minima usually violate total monotonicity.
+#[allow(dead_code)] // Not every integration test uses this.
+pub fn random_monotone_matrix<R: Rng>(m: usize, n: usize, rng: &mut R) -> Array2<i64> {
+    let mut minima = (0..m).map(|_| rng.gen_range(0..n)).collect::<Vec<_>>();
+    minima.sort_unstable();
+    let mut matrix = Array2::zeros((m, n));
+    for (mut row, &minimum) in matrix.rows_mut().into_iter().zip(&minima) {
+        let base = rng.gen_range(-20..20);
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = if j == minimum {
+                base
+            } else {
+                base + rng.gen_range(1..10)
+            };
+        }
+    }
+    matrix
+}
+
+/// Generate a random three-dimensional array which is Monge in every
+/// axis-aligned plane.
+///
+/// The array is a sum of two-dimensional primitives spanning a random
+/// pair of axes, repeated along the third axis. Each such term is
+/// Monge in its own plane and constant along the other two planes.
+#[allow(dead_code)] // Not every integration test uses this.
+pub fn random_monge_3d<R: Rng, T: PrimInt>(l: usize, m: usize, n: usize, rng: &mut R) -> Array3<T>
+where
+    Standard: Distribution<T>,
+{
+    let shape = [l, m, n];
+    let mut array = Array3::from_elem((l, m, n), T::zero());
+    for _ in 0..(l + m + n) {
+        let monge = ZERO_ONE_PRIMITIVES[rng.gen_range(0..ZERO_ONE_PRIMITIVES.len())];
+        let (a, b) = [(0, 1), (0, 2), (1, 2)][rng.gen_range(0..3)];
+        let matrix: Array2<T> = monge.to_matrix(shape[a], shape[b], rng);
+        array = array
+            + Array3::from_shape_fn((l, m, n), |(i, j, k)| {
+                let index = [i, j, k];
+                matrix[[index[a], index[b]]]
+            });
+    }
+    array
+}
+
+/// Generate a random Kalmanson matrix from points in convex position.
+///
+/// The `n` points are picked at random from `4 * n` evenly spaced
+/// points on a circle, and numbered in the order they appear on the
+/// circle. The Euclidean distances between them are rounded to
+/// integers. The circle is large enough for the Kalmanson inequalities
+/// to survive the rounding when `n` is at most a few hundred.
+#[allow(dead_code)] // Not every integration test uses this.
+pub fn random_kalmanson_matrix<R: Rng>(n: usize, rng: &mut R) -> Array2<i64> {
+    let radius = 1e9;
+    let mut positions = rand::seq::index::sample(rng, 4 * n, n).into_vec();
+    positions.sort_unstable();
+    let points = positions
+        .iter()
+        .map(|&p| {
+            let angle = 2.0 * std::f64::consts::PI * p as f64 / (4 * n) as f64;
+            (radius * angle.cos(), radius * angle.sin())
+        })
+        .collect::<Vec<_>>();
+    Array2::from_shape_fn((n, n), |(i, j)| {
+        let (x, y) = (points[i].0 - points[j].0, points[i].1 - points[j].1);
+        x.hypot(y).round() as i64
+    })
+}